@@ -0,0 +1,600 @@
+use {
+    crate::models::error::ConfigError,
+    axum::http::{HeaderName, HeaderValue},
+    reqwest::Url,
+    serde::Deserialize,
+    std::{collections::HashMap, net::IpAddr, path::Path, str::FromStr},
+};
+
+/// Fichero de configuración por defecto si no se indica `CONFIG_FILE`
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Configuración completa del backend.
+/// Se carga desde un fichero TOML (opcional) y después se aplican las variables de entorno,
+/// que siempre tienen prioridad sobre el fichero.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub firebase: FirebaseConfig,
+    pub stripe: StripeConfig,
+    pub resend: ResendConfig,
+    pub mailchimp: MailchimpConfig,
+    pub cal: CalConfig,
+    pub ga: GAConfig,
+    pub rate_limit: RateLimitConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub cors_origins: Vec<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            cors_origins: vec![
+                "http://localhost:4321".to_string(),      // Frontend desarrollo
+                "https://amanahacademia.com".to_string(), // Dominio de producción
+                "https://amanahacademia.vercel.app".to_string(), // Dominio alternativo de producción
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    /// `firebase` o `sqlite`
+    pub backend: String,
+    pub sqlite_path: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            backend: "firebase".to_string(),
+            sqlite_path: "amanahacademia.db".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FirebaseConfig {
+    pub project_id: String,
+    pub api_key: String,
+    pub database_url: Option<String>,
+    pub database_secret: Option<String>,
+    /// `host:puerto` del Firebase Auth Emulator. Si está definido, las llamadas de autenticación
+    /// van al emulador y el middleware acepta sus tokens sin firmar
+    pub auth_emulator_host: Option<String>,
+    pub identity_toolkit_url: String,
+    pub secure_token_url: String,
+    pub public_keys_url: String,
+    /// Cuenta de servicio para la API de administración de Firebase Auth (deshabilitar cuentas...).
+    /// Opcional: sin ella esas operaciones responden 503
+    pub client_email: Option<String>,
+    pub private_key: Option<String>,
+    pub token_url: String,
+    /// OAuth client ID de Google Sign-In: `aud` esperado en los id_token de Google que se
+    /// vinculan a una cuenta. Opcional: sin él la vinculación de Google responde 503
+    pub google_client_id: Option<String>,
+    pub google_tokeninfo_url: String,
+}
+
+impl Default for FirebaseConfig {
+    fn default() -> Self {
+        Self {
+            project_id: String::new(),
+            api_key: String::new(),
+            database_url: None,
+            database_secret: None,
+            auth_emulator_host: None,
+            identity_toolkit_url: "https://identitytoolkit.googleapis.com/v1".to_string(),
+            secure_token_url: "https://securetoken.googleapis.com/v1".to_string(),
+            public_keys_url:
+                "https://www.googleapis.com/robot/v1/metadata/x509/securetoken@system.gserviceaccount.com"
+                    .to_string(),
+            client_email: None,
+            private_key: None,
+            token_url: "https://oauth2.googleapis.com/token".to_string(),
+            google_client_id: None,
+            google_tokeninfo_url: "https://oauth2.googleapis.com/tokeninfo".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StripeConfig {
+    pub api_key: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ResendConfig {
+    pub api_key: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MailchimpConfig {
+    pub api_key: String,
+    pub server_prefix: String,
+    pub list_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CalConfig {
+    pub base_url: String,
+    pub api_key: String,
+    pub team_id: Option<String>,
+    /// Cada cuántos segundos se consulta Cal.com para recuperar bookings perdidos
+    pub polling_interval_secs: u64,
+}
+
+impl Default for CalConfig {
+    fn default() -> Self {
+        Self {
+            base_url: String::new(),
+            api_key: String::new(),
+            team_id: None,
+            polling_interval_secs: 600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GAConfig {
+    pub client_email: String,
+    pub private_key: String,
+    pub property_id: String,
+    pub base_url: String,
+    pub token_url: String,
+}
+
+impl Default for GAConfig {
+    fn default() -> Self {
+        Self {
+            client_email: String::new(),
+            private_key: String::new(),
+            property_id: String::new(),
+            base_url: "https://analyticsdata.googleapis.com/v1beta".to_string(),
+            token_url: "https://oauth2.googleapis.com/token".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Cabecera con la IP real del cliente cuando hay un proxy delante (p. ej. `Fly-Client-IP`).
+    /// Sin ella se usa la IP de la conexión TCP.
+    pub client_ip_header: Option<String>,
+    /// Formulario de contacto (`/email/contact`)
+    pub contact: RateLimitBudget,
+    /// Alta en la newsletter (`/mailchimp/add_contact`)
+    pub newsletter: RateLimitBudget,
+    /// Registro y login (`/users/register`, `/users/login`)
+    pub auth: RateLimitBudget,
+    /// Webhooks de Cal.com (`/webhook/cal`)
+    pub webhook: RateLimitBudget,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            client_ip_header: None,
+            contact: RateLimitBudget::new(5, 2),
+            newsletter: RateLimitBudget::new(5, 2),
+            auth: RateLimitBudget::new(10, 5),
+            webhook: RateLimitBudget::new(120, 60),
+        }
+    }
+}
+
+/// Token bucket: `capacity` peticiones seguidas como máximo y `refill_per_minute` fichas nuevas por minuto
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct RateLimitBudget {
+    pub capacity: u32,
+    pub refill_per_minute: u32,
+}
+
+impl RateLimitBudget {
+    pub const fn new(capacity: u32, refill_per_minute: u32) -> Self {
+        Self {
+            capacity,
+            refill_per_minute,
+        }
+    }
+}
+
+impl Config {
+    /// Carga la configuración del fichero `CONFIG_FILE` (por defecto `config.toml`)
+    /// y de las variables de entorno del proceso
+    pub fn load() -> Result<Self, ConfigError> {
+        let env: HashMap<String, String> = std::env::vars().collect();
+
+        let (path, explicit) = match env.get("CONFIG_FILE") {
+            Some(path) => (path.clone(), true),
+            None => (DEFAULT_CONFIG_FILE.to_string(), false),
+        };
+
+        let contents: Option<String> = match std::fs::read_to_string(Path::new(&path)) {
+            Ok(contents) => Some(contents),
+            // Sin fichero se usa solo el entorno, salvo que se haya pedido uno explícitamente
+            Err(_) if !explicit => None,
+            Err(err) => {
+                return Err(ConfigError {
+                    problems: vec![format!("Cannot read config file '{}': {}", path, err)],
+                });
+            }
+        };
+
+        Self::from_sources(contents.as_deref(), &env)
+    }
+
+    /// Construye la configuración a partir del contenido TOML y un mapa de variables de entorno.
+    /// Devuelve todos los problemas encontrados a la vez, no solo el primero.
+    pub fn from_sources(
+        toml_contents: Option<&str>,
+        env: &HashMap<String, String>,
+    ) -> Result<Self, ConfigError> {
+        let mut config: Config = match toml_contents {
+            Some(contents) => toml::from_str(contents).map_err(|err| ConfigError {
+                problems: vec![format!("Invalid config file: {}", err)],
+            })?,
+            None => Config::default(),
+        };
+
+        let mut problems: Vec<String> = Vec::new();
+        config.apply_env(env, &mut problems);
+        config.apply_auth_emulator();
+        config.validate(&mut problems);
+
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError { problems })
+        }
+    }
+
+    fn apply_env(&mut self, env: &HashMap<String, String>, problems: &mut Vec<String>) {
+        let mut overrides = EnvOverrides { env, problems };
+
+        overrides.string("SERVER_HOST", &mut self.server.host);
+        overrides.parse("PORT", &mut self.server.port);
+        overrides.list("CORS_ORIGINS", &mut self.server.cors_origins);
+
+        overrides.string("DATABASE_BACKEND", &mut self.database.backend);
+        overrides.string("SQLITE_PATH", &mut self.database.sqlite_path);
+
+        overrides.string("FIREBASE_PROJECT_ID", &mut self.firebase.project_id);
+        overrides.string("FIREBASE_API_KEY", &mut self.firebase.api_key);
+        overrides.optional("FIREBASE_DATABASE_URL", &mut self.firebase.database_url);
+        overrides.optional(
+            "FIREBASE_DATABASE_SECRET",
+            &mut self.firebase.database_secret,
+        );
+        overrides.optional(
+            "FIREBASE_AUTH_EMULATOR_HOST",
+            &mut self.firebase.auth_emulator_host,
+        );
+        overrides.string(
+            "FIREBASE_IDENTITY_TOOLKIT_URL",
+            &mut self.firebase.identity_toolkit_url,
+        );
+        overrides.string(
+            "FIREBASE_SECURE_TOKEN_URL",
+            &mut self.firebase.secure_token_url,
+        );
+        overrides.string(
+            "FIREBASE_PUBLIC_KEYS_URL",
+            &mut self.firebase.public_keys_url,
+        );
+        overrides.optional("FIREBASE_CLIENT_EMAIL", &mut self.firebase.client_email);
+        overrides.optional("FIREBASE_PRIVATE_KEY", &mut self.firebase.private_key);
+        overrides.string("FIREBASE_TOKEN_URL", &mut self.firebase.token_url);
+        overrides.optional(
+            "FIREBASE_GOOGLE_CLIENT_ID",
+            &mut self.firebase.google_client_id,
+        );
+        overrides.string(
+            "FIREBASE_GOOGLE_TOKENINFO_URL",
+            &mut self.firebase.google_tokeninfo_url,
+        );
+
+        overrides.string("STRIPE_API_KEY", &mut self.stripe.api_key);
+        overrides.string("RESEND_API_KEY", &mut self.resend.api_key);
+
+        overrides.string("MAILCHIMP_API_KEY", &mut self.mailchimp.api_key);
+        overrides.string("MAILCHIMP_SERVER_PREFIX", &mut self.mailchimp.server_prefix);
+        overrides.string("MAILCHIMP_LIST_ID", &mut self.mailchimp.list_id);
+
+        overrides.string("CAL_BASE_URL", &mut self.cal.base_url);
+        overrides.string("CAL_API_KEY", &mut self.cal.api_key);
+        overrides.optional("TEAM_ID", &mut self.cal.team_id);
+        overrides.parse(
+            "CAL_POLLING_INTERVAL_SECS",
+            &mut self.cal.polling_interval_secs,
+        );
+
+        overrides.string("GA_CLIENT_EMAIL", &mut self.ga.client_email);
+        overrides.string("GA_PRIVATE_KEY", &mut self.ga.private_key);
+        overrides.string("GA_PROPERTY_ID", &mut self.ga.property_id);
+        overrides.string("GA_BASE_URL", &mut self.ga.base_url);
+        overrides.string("GA_TOKEN_URL", &mut self.ga.token_url);
+
+        overrides.parse("RATE_LIMIT_ENABLED", &mut self.rate_limit.enabled);
+        overrides.optional(
+            "RATE_LIMIT_CLIENT_IP_HEADER",
+            &mut self.rate_limit.client_ip_header,
+        );
+    }
+
+    /// En modo emulador las APIs de autenticación se sirven desde el emulador con el mismo path
+    fn apply_auth_emulator(&mut self) {
+        if let Some(host) = &self.firebase.auth_emulator_host {
+            self.firebase.identity_toolkit_url =
+                format!("http://{}/identitytoolkit.googleapis.com/v1", host);
+            self.firebase.secure_token_url =
+                format!("http://{}/securetoken.googleapis.com/v1", host);
+        }
+    }
+
+    fn validate(&self, problems: &mut Vec<String>) {
+        let required: [(&str, &str, &str); 12] = [
+            (
+                "firebase.project_id",
+                "FIREBASE_PROJECT_ID",
+                &self.firebase.project_id,
+            ),
+            (
+                "firebase.api_key",
+                "FIREBASE_API_KEY",
+                &self.firebase.api_key,
+            ),
+            ("stripe.api_key", "STRIPE_API_KEY", &self.stripe.api_key),
+            ("resend.api_key", "RESEND_API_KEY", &self.resend.api_key),
+            (
+                "mailchimp.api_key",
+                "MAILCHIMP_API_KEY",
+                &self.mailchimp.api_key,
+            ),
+            (
+                "mailchimp.server_prefix",
+                "MAILCHIMP_SERVER_PREFIX",
+                &self.mailchimp.server_prefix,
+            ),
+            (
+                "mailchimp.list_id",
+                "MAILCHIMP_LIST_ID",
+                &self.mailchimp.list_id,
+            ),
+            ("cal.base_url", "CAL_BASE_URL", &self.cal.base_url),
+            ("cal.api_key", "CAL_API_KEY", &self.cal.api_key),
+            ("ga.client_email", "GA_CLIENT_EMAIL", &self.ga.client_email),
+            ("ga.private_key", "GA_PRIVATE_KEY", &self.ga.private_key),
+            ("ga.property_id", "GA_PROPERTY_ID", &self.ga.property_id),
+        ];
+        for (key, env_name, value) in required {
+            if value.trim().is_empty() {
+                problems.push(format!("`{}` ({}) is missing", key, env_name));
+            }
+        }
+
+        if IpAddr::from_str(&self.server.host).is_err() {
+            problems.push(format!(
+                "`server.host` (SERVER_HOST) is not a valid IP address: '{}'",
+                self.server.host
+            ));
+        }
+        if self.server.port == 0 {
+            problems.push("`server.port` (PORT) must be greater than 0".to_string());
+        }
+        if self.server.cors_origins.is_empty() {
+            problems.push("`server.cors_origins` (CORS_ORIGINS) must not be empty".to_string());
+        }
+        for origin in &self.server.cors_origins {
+            if !is_http_url(origin) || HeaderValue::from_str(origin).is_err() {
+                problems.push(format!(
+                    "`server.cors_origins` (CORS_ORIGINS) contains an invalid origin: '{}'",
+                    origin
+                ));
+            }
+        }
+
+        match self.database.backend.as_str() {
+            "firebase" => {
+                match self.firebase.database_url.as_deref() {
+                    None | Some("") => problems.push(
+                        "`firebase.database_url` (FIREBASE_DATABASE_URL) is missing".to_string(),
+                    ),
+                    Some(url) => check_url(
+                        problems,
+                        "firebase.database_url",
+                        "FIREBASE_DATABASE_URL",
+                        url,
+                    ),
+                }
+                if self
+                    .firebase
+                    .database_secret
+                    .as_deref()
+                    .is_none_or(|secret| secret.trim().is_empty())
+                {
+                    problems.push(
+                        "`firebase.database_secret` (FIREBASE_DATABASE_SECRET) is missing"
+                            .to_string(),
+                    );
+                }
+            }
+            "sqlite" => {
+                if self.database.sqlite_path.trim().is_empty() {
+                    problems.push("`database.sqlite_path` (SQLITE_PATH) is missing".to_string());
+                }
+            }
+            other => problems.push(format!(
+                "`database.backend` (DATABASE_BACKEND) must be 'firebase' or 'sqlite', got '{}'",
+                other
+            )),
+        }
+
+        let urls: [(&str, &str, &str); 8] = [
+            (
+                "firebase.identity_toolkit_url",
+                "FIREBASE_IDENTITY_TOOLKIT_URL",
+                &self.firebase.identity_toolkit_url,
+            ),
+            (
+                "firebase.secure_token_url",
+                "FIREBASE_SECURE_TOKEN_URL",
+                &self.firebase.secure_token_url,
+            ),
+            (
+                "firebase.public_keys_url",
+                "FIREBASE_PUBLIC_KEYS_URL",
+                &self.firebase.public_keys_url,
+            ),
+            (
+                "firebase.token_url",
+                "FIREBASE_TOKEN_URL",
+                &self.firebase.token_url,
+            ),
+            (
+                "firebase.google_tokeninfo_url",
+                "FIREBASE_GOOGLE_TOKENINFO_URL",
+                &self.firebase.google_tokeninfo_url,
+            ),
+            ("cal.base_url", "CAL_BASE_URL", &self.cal.base_url),
+            ("ga.base_url", "GA_BASE_URL", &self.ga.base_url),
+            ("ga.token_url", "GA_TOKEN_URL", &self.ga.token_url),
+        ];
+        for (key, env_name, value) in urls {
+            // Los vacíos ya se reportan como "missing"
+            if !value.is_empty() {
+                check_url(problems, key, env_name, value);
+            }
+        }
+
+        if let Some(host) = &self.firebase.auth_emulator_host
+            && (host.contains("://") || !is_http_url(&format!("http://{}", host)))
+        {
+            problems.push(format!(
+                "`firebase.auth_emulator_host` (FIREBASE_AUTH_EMULATOR_HOST) must be host:port, got '{}'",
+                host
+            ));
+        }
+
+        if self.firebase.client_email.is_some() != self.firebase.private_key.is_some() {
+            problems.push(
+                "`firebase.client_email` (FIREBASE_CLIENT_EMAIL) and `firebase.private_key` (FIREBASE_PRIVATE_KEY) must be set together"
+                    .to_string(),
+            );
+        }
+
+        if self.cal.polling_interval_secs == 0 {
+            problems.push(
+                "`cal.polling_interval_secs` (CAL_POLLING_INTERVAL_SECS) must be greater than 0"
+                    .to_string(),
+            );
+        }
+
+        if let Some(header) = &self.rate_limit.client_ip_header
+            && HeaderName::from_str(header).is_err()
+        {
+            problems.push(format!(
+                "`rate_limit.client_ip_header` (RATE_LIMIT_CLIENT_IP_HEADER) is not a valid header name: '{}'",
+                header
+            ));
+        }
+        let budgets: [(&str, RateLimitBudget); 4] = [
+            ("rate_limit.contact", self.rate_limit.contact),
+            ("rate_limit.newsletter", self.rate_limit.newsletter),
+            ("rate_limit.auth", self.rate_limit.auth),
+            ("rate_limit.webhook", self.rate_limit.webhook),
+        ];
+        for (key, budget) in budgets {
+            if budget.capacity == 0 || budget.refill_per_minute == 0 {
+                problems.push(format!(
+                    "`{}` must have capacity and refill_per_minute greater than 0",
+                    key
+                ));
+            }
+        }
+    }
+}
+
+/// Aplica las variables de entorno sobre la configuración, acumulando los valores mal formados
+struct EnvOverrides<'a> {
+    env: &'a HashMap<String, String>,
+    problems: &'a mut Vec<String>,
+}
+
+impl EnvOverrides<'_> {
+    fn string(&mut self, name: &str, target: &mut String) {
+        if let Some(value) = self.env.get(name) {
+            *target = value.clone();
+        }
+    }
+
+    fn optional(&mut self, name: &str, target: &mut Option<String>) {
+        if let Some(value) = self.env.get(name) {
+            *target = Some(value.clone()).filter(|value| !value.trim().is_empty());
+        }
+    }
+
+    fn parse<T>(&mut self, name: &str, target: &mut T)
+    where
+        T: FromStr,
+    {
+        if let Some(value) = self.env.get(name) {
+            match value.trim().parse::<T>() {
+                Ok(parsed) => *target = parsed,
+                Err(_) => self
+                    .problems
+                    .push(format!("{} has an invalid value: '{}'", name, value)),
+            }
+        }
+    }
+
+    /// Lista separada por comas
+    fn list(&mut self, name: &str, target: &mut Vec<String>) {
+        if let Some(value) = self.env.get(name) {
+            *target = value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(String::from)
+                .collect();
+        }
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    Url::parse(value).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+fn check_url(problems: &mut Vec<String>, key: &str, env_name: &str, value: &str) {
+    if !is_http_url(value) {
+        problems.push(format!(
+            "`{}` ({}) is not a valid http(s) URL: '{}'",
+            key, env_name, value
+        ));
+    }
+}
+
+#[cfg(test)]
+#[path = "test/config/config.rs"]
+mod extended_tests;
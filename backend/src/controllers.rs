@@ -5,6 +5,7 @@ pub mod mailchimp;
 pub mod metrics;
 pub mod payments;
 pub mod sourvey;
+pub mod subscriptions;
 pub mod teachers;
 pub mod users;
 pub mod webhook;
@@ -1,15 +1,21 @@
 use {
-    crate::models::{
-        cal::{
-            AddGuestsPayload, BookingsQueryParams, CalApiResponse, CalBookingPayload,
-            FetchCalErrors, Schedule, SchedulesQuery, UserCal,
+    crate::{
+        models::{
+            cal::{
+                AddGuestsPayload, BookingsQueryParams, CalApiResponse, CalBookingPayload,
+                FetchCalErrors, Schedule, SchedulesQuery, UserCal,
+            },
+            error::ApiError,
+            firebase::UserAuthentication,
+            response::ResponseAPI,
+            state::AppState,
+            user::UserDB,
+            webhook::{Attendee, BookingChange, CalBookingsResponse},
         },
-        response::ResponseAPI,
-        state::AppState,
-        webhook::{Attendee, BookingChange, CalBookingsResponse},
+        services::subscriptions::check_booking_entitlement,
     },
     axum::{
-        Json, debug_handler,
+        Extension, Json, debug_handler,
         extract::{Path, Query, State},
         http::StatusCode,
         response::{IntoResponse, Response as AxumResponse},
@@ -18,20 +24,13 @@ use {
     reqwest::{Response, Url},
     serde_json::{Value, json},
     std::{collections::HashMap, sync::Arc},
-    tokio::sync::RwLockWriteGuard,
+    tokio::sync::{Mutex, MutexGuard, RwLockWriteGuard},
     tracing::{debug, error, info, instrument},
 };
 
 /// Helper: Maneja errores de red al comunicarse con Cal.com
-fn handle_network_error(error: &reqwest::Error) -> AxumResponse {
-    error!(error = %error, "Failed to communicate with Cal.com");
-    (
-        StatusCode::BAD_GATEWAY,
-        Json(ResponseAPI::<()>::error(
-            "Failed to communicate with Cal.com".to_string(),
-        )),
-    )
-        .into_response()
+fn handle_network_error(error: reqwest::Error) -> AxumResponse {
+    ApiError::from(FetchCalErrors::Network(error)).into_response()
 }
 
 /// Helper: Maneja respuestas de error de Cal.com API
@@ -39,14 +38,27 @@ async fn handle_cal_error_response(response: Response) -> AxumResponse {
     let status = response.status();
     let body = response.text().await.unwrap_or_default();
     error!(status = %status, body = %body, "Cal.com returned error");
-    (
-        StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-        Json(ResponseAPI::<()>::error(format!("Cal.com error: {}", body))),
-    )
-        .into_response()
+    ApiError::from(FetchCalErrors::Api {
+        status: StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        message: body,
+    })
+    .into_response()
 }
 
 /// Confirmar un booking
+#[utoipa::path(
+    post,
+    path = "/cal/bookings/{id}/confirm",
+    tag = "cal",
+    params(
+        ("id" = String, Path, description = "ID de la reserva en Cal.com"),
+    ),
+    responses(
+        (status = 204, description = "Reserva confirmada"),
+        (status = 502, description = "Error al comunicarse con Cal.com", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(skip(state), fields(booking_id = %id))]
 pub async fn confirm_booking(
@@ -67,7 +79,7 @@ pub async fn confirm_booking(
         .await
     {
         Ok(r) => r,
-        Err(ref e) => return handle_network_error(e),
+        Err(e) => return handle_network_error(e),
     };
 
     // Manejamos los posibles errores de la respuesta
@@ -165,6 +177,19 @@ pub async fn fetch_and_detect_changes(state: &AppState) -> Result<Vec<BookingCha
 }
 
 /// Obtener un booking por ID
+#[utoipa::path(
+    get,
+    path = "/cal/bookings/{id}",
+    tag = "cal",
+    params(
+        ("id" = String, Path, description = "ID de la reserva en Cal.com"),
+    ),
+    responses(
+        (status = 200, description = "Reserva encontrada", body = ResponseAPI<CalBookingPayload>),
+        (status = 502, description = "Error al comunicarse con Cal.com", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(skip(state), fields(booking_id = %id))]
 pub async fn get_booking(
@@ -184,7 +209,7 @@ pub async fn get_booking(
         .await
     {
         Ok(response) => response,
-        Err(ref e) => return handle_network_error(e),
+        Err(e) => return handle_network_error(e),
     };
 
     // Manejar errores de la respuesta
@@ -238,6 +263,20 @@ pub async fn get_booking(
 }
 
 /// Agregar invitados/asistentes a un booking existente
+#[utoipa::path(
+    post,
+    path = "/cal/bookings/{id}/guests",
+    tag = "cal",
+    params(
+        ("id" = String, Path, description = "ID de la reserva en Cal.com"),
+    ),
+    request_body = AddGuestsPayload,
+    responses(
+        (status = 200, description = "Invitados añadidos", body = ResponseAPI<CalBookingPayload>),
+        (status = 400, description = "Lista de invitados vacía", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(skip(state, payload), fields(booking_id = %id))]
 pub async fn add_guests_to_booking(
@@ -278,7 +317,7 @@ pub async fn add_guests_to_booking(
         .await
     {
         Ok(r) => r,
-        Err(ref e) => return handle_network_error(e),
+        Err(e) => return handle_network_error(e),
     };
 
     // Verificar el status de la respuesta
@@ -317,8 +356,22 @@ pub async fn add_guests_to_booking(
 }
 
 /// Agregar un nuevo booking
+#[utoipa::path(
+    post,
+    path = "/cal/bookings",
+    tag = "cal",
+    request_body = CalBookingPayload,
+    responses(
+        (status = 201, description = "Reserva creada", body = ResponseAPI<CalBookingPayload>),
+        (status = 400, description = "Payload inválido", body = ResponseAPI<serde_json::Value>),
+        (status = 403, description = "El plan de suscripción no incluye la clase o no quedan clases este mes", body = ResponseAPI<serde_json::Value>),
+        (status = 503, description = "No se pudo comprobar el saldo del plan en Cal.com", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 pub async fn add_booking(
+    Extension(user_claims): Extension<UserAuthentication>,
     State(state): State<Arc<AppState>>,
     Json(flexible_payload): Json<Value>,
 ) -> impl IntoResponse {
@@ -404,6 +457,21 @@ pub async fn add_booking(
             .into_response();
     }
 
+    // Con plan de suscripción la reserva tiene que caber en él; sin plan se paga por clase.
+    // El lock se mantiene hasta que Cal.com responde: dos reservas simultáneas no pueden
+    // gastar la misma clase del plan.
+    let booking_lock: Arc<Mutex<()>> = state.booking_lock(&user_claims.sub).await;
+    let _booking_guard: MutexGuard<()> = booking_lock.lock().await;
+    let user: Option<UserDB> = match state.repositories.users.get(&user_claims.sub).await {
+        Ok(user) => user,
+        Err(e) => return ApiError::from(e).into_response(),
+    };
+    if let Some(user) = &user
+        && let Err(e) = check_booking_entitlement(&state, user, &payload).await
+    {
+        return e.into_response();
+    }
+
     // Construir el body dinámicamente según los campos disponibles
     let mut body: Value = json!({
         "start": start_time,
@@ -518,6 +586,16 @@ pub async fn add_booking(
 }
 
 /// Obtener todos los calendarios del usuario autenticado
+#[utoipa::path(
+    get,
+    path = "/cal/schedules/all",
+    tag = "cal",
+    params(SchedulesQuery),
+    responses(
+        (status = 200, description = "Horarios disponibles", body = ResponseAPI<Vec<Schedule>>),
+        (status = 502, description = "Error al comunicarse con Cal.com", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 #[debug_handler]
 #[instrument(skip(state))]
 pub async fn get_all_schedules(
@@ -571,11 +649,23 @@ pub async fn get_all_schedules(
                 }
             }
         }
-        Err(ref e) => handle_network_error(e),
+        Err(e) => handle_network_error(e),
     }
 }
 
 /// Obtener un calendario específico
+#[utoipa::path(
+    get,
+    path = "/cal/schedule/{id}",
+    tag = "cal",
+    params(
+        ("id" = String, Path, description = "ID del horario en Cal.com"),
+    ),
+    responses(
+        (status = 200, description = "Horario encontrado", body = ResponseAPI<Schedule>),
+        (status = 502, description = "Error al comunicarse con Cal.com", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 #[debug_handler]
 #[instrument(skip(state))]
 pub async fn get_schedule(
@@ -624,11 +714,21 @@ pub async fn get_schedule(
                     .into_response(),
             }
         }
-        Err(ref e) => handle_network_error(e),
+        Err(e) => handle_network_error(e),
     }
 }
 
 /// Obtener todos los bookings
+#[utoipa::path(
+    get,
+    path = "/cal/bookings/all",
+    tag = "cal",
+    params(BookingsQueryParams),
+    responses(
+        (status = 200, description = "Reservas encontradas", body = ResponseAPI<Vec<CalBookingPayload>>),
+        (status = 502, description = "Error al comunicarse con Cal.com", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 #[debug_handler]
 #[instrument(skip(state))]
 pub async fn get_all_bookings(
@@ -719,10 +819,10 @@ pub async fn get_all_bookings(
                 }
             }
         }
-        Err(ref e) => handle_network_error(e),
+        Err(e) => handle_network_error(e),
     }
 }
 
 #[cfg(test)]
 #[path = "../test/controllers/cal.rs"]
-mod tests;
+mod extended_tests;
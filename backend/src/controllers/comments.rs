@@ -1,29 +1,56 @@
 use {
     crate::{
         models::{
-            comments::{Comment, ReplyComment, UpdateComment},
+            comments::{
+                Comment, CommentListQuery, ModerationDecision, ModerationQueue,
+                ModerationQueueQuery, ModerationStatus, RatingSummary, ReplyComment, UpdateComment,
+            },
+            error::{ApiError, RepositoryError},
             firebase::UserAuthentication,
             response::ResponseAPI,
             state::AppState,
         },
-        services::firebase::handle_firebase_response,
+        services::{
+            comments::{
+                list_comments, mark_liked, rating_summary, toggle_like as toggle_user_like,
+                update_comment,
+            },
+            moderation::{
+                edited_status, initial_status, moderation_queue, public_comments, validate_decision,
+            },
+            reviews::{REVIEW_REQUIRES_CLASS, is_review, review_booking},
+        },
     },
     axum::{
         Extension, Json, debug_handler,
-        extract::{Path, State},
+        extract::{Path, Query, State},
         http::StatusCode,
-        response::IntoResponse,
+        response::{IntoResponse, Response},
     },
     chrono::Utc,
+    indexmap::IndexMap,
     std::{collections::HashMap, sync::Arc},
     tracing::instrument,
     uuid::Uuid,
 };
 
 // Crear un comentario
+#[utoipa::path(
+    post,
+    path = "/comments/add",
+    tag = "comments",
+    request_body = Comment,
+    responses(
+        (status = 201, description = "Comentario creado; `moderation_status` indica si queda pendiente de revisión", body = ResponseAPI<HashMap<String, String>>),
+        (status = 403, description = "Reseña con estrellas sin email verificado o sin una clase completada", body = ResponseAPI<serde_json::Value>),
+        (status = 503, description = "No se pudo comprobar la clase en Cal.com", body = ResponseAPI<serde_json::Value>),
+        (status = 500, description = "Error en la base de datos", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, id_token, user_claims, comment),
+    skip(state, user_claims, comment),
     fields(
         user_id = %user_claims.user_id,
         comment_content_length = %comment.content.len(),
@@ -31,17 +58,27 @@ use {
 ))]
 pub async fn add_comment(
     Extension(user_claims): Extension<UserAuthentication>,
-    Extension(id_token): Extension<String>,
     State(state): State<Arc<AppState>>,
     Json(comment): Json<Comment>,
 ) -> impl IntoResponse {
-    // URL de para crear usuario en la DB
-    let url_firebase_db: String = format!(
-        "{}/comments.json?auth={}",
-        state.firebase_options.firebase_database_url, id_token
-    );
+    // Los autores sin contenido aprobado pasan por la cola de moderación
+    let moderation_status: ModerationStatus =
+        match initial_status(&state, &user_claims.user_id, None).await {
+            Ok(status) => status,
+            Err(err) => return repository_error_response(err, "Failed to add comment"),
+        };
+
+    // Las reseñas con estrellas solo se aceptan de alumnos con una clase completada
+    let booking_uid: Option<String> = if is_review(comment.stars) {
+        match review_booking(&state, &user_claims).await {
+            Ok(booking_uid) => Some(booking_uid),
+            Err(err) => return err.into_response(),
+        }
+    } else {
+        None
+    };
 
-    // Creamos el usuario que se va a crear en la DB
+    // Creamos el comentario que se va a guardar en la DB
     let new_comment: Comment = Comment {
         author_uid: Some(user_claims.user_id),
         name: comment.name,
@@ -52,44 +89,62 @@ pub async fn add_comment(
         like: 0,
         reply: Vec::new(),
         users_liked: Vec::new(),
+        moderation_status,
+        moderation_reason: None,
+        verified_student: booking_uid.is_some(),
+        booking_uid,
+        liked: None,
     };
 
     // Enviamos el comentario a la base de datos para su creación
-    match state
-        .firebase_options
-        .firebase_client
-        .post(&url_firebase_db)
-        .json(&new_comment)
-        .send()
-        .await
-    {
-        Ok(response) => match handle_firebase_response::<HashMap<String, String>>(response).await {
-            Ok(data_response) => (
-                StatusCode::CREATED,
-                Json(ResponseAPI::<HashMap<String, String>>::success(
-                    "Comment created successfully".to_string(),
-                    data_response,
-                )),
-            )
-                .into_response(),
-            Err((status, error)) => {
-                return (status, Json(ResponseAPI::<()>::error(error))).into_response();
-            }
-        },
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error(
-                "Failed to add comment".to_string(),
+    match state.repositories.comments.create(&new_comment).await {
+        Ok(comment_id) => (
+            StatusCode::CREATED,
+            Json(ResponseAPI::<HashMap<String, String>>::success(
+                "Comment created successfully".to_string(),
+                HashMap::from([
+                    ("name".to_string(), comment_id),
+                    (
+                        "moderation_status".to_string(),
+                        moderation_status.as_ref().to_string(),
+                    ),
+                ]),
             )),
         )
             .into_response(),
+        Err(err) => {
+            tracing::error!("Failed to add comment: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ResponseAPI::<()>::error(
+                    "Failed to add comment".to_string(),
+                )),
+            )
+                .into_response()
+        }
     }
 }
 
 // Editar un comentario
+#[utoipa::path(
+    put,
+    path = "/comments/edit/{comment_id}",
+    tag = "comments",
+    params(
+        ("comment_id" = String, Path, description = "ID del comentario"),
+    ),
+    request_body = UpdateComment,
+    responses(
+        (status = 200, description = "Comentario actualizado", body = ResponseAPI<Comment>),
+        (status = 403, description = "El comentario no es del usuario, o pasa a reseña sin email verificado o sin una clase completada", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "Comentario no encontrado", body = ResponseAPI<serde_json::Value>),
+        (status = 503, description = "No se pudo comprobar la clase en Cal.com", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, id_token, user_claims, comment),
+    skip(state, user_claims, comment),
     fields(
         comment_id = %comment_id,
         user_id = %user_claims.user_id,
@@ -100,154 +155,171 @@ pub async fn add_comment(
 pub async fn edit_comment(
     Path(comment_id): Path<String>,
     State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
     Extension(user_claims): Extension<UserAuthentication>,
     Json(comment): Json<UpdateComment>,
 ) -> impl IntoResponse {
-    // Obtenemos el ID del comentario a editar
-    let url_firebase_db: String = format!(
-        "{}/comments/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, comment_id, id_token
-    );
+    // El contenido nuevo se vuelve a moderar
+    let initial: ModerationStatus =
+        match initial_status(&state, &user_claims.user_id, Some(&comment_id)).await {
+            Ok(status) => status,
+            Err(err) => return repository_error_response(err, "Failed to update comment"),
+        };
 
-    // Verificar si el comentario existe
-    let existing_comment: Comment = match get_comment_data(&comment_id, &id_token, &state).await {
-        Some(comment) => comment,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(ResponseAPI::<()>::error("Comment not found".to_string())),
-            )
-                .into_response();
+    // Un comentario sin verificar que pasa a tener estrellas necesita una clase que lo respalde
+    let needs_booking: bool = is_review(comment.stars)
+        && matches!(
+            state.repositories.comments.get(&comment_id).await,
+            Ok(Some(existing)) if !existing.verified_student
+                && existing.author_uid.as_ref() == Some(&user_claims.user_id)
+        );
+    let booking_uid: Option<String> = if needs_booking {
+        match review_booking(&state, &user_claims).await {
+            Ok(booking_uid) => Some(booking_uid),
+            Err(err) => return err.into_response(),
         }
+    } else {
+        None
     };
 
-    // Verificamos que el usuario sea el autor del comentario
-    if existing_comment.author_uid != Some(user_claims.user_id) {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(ResponseAPI::<()>::error(
+    // Solo cambiamos el timestamp, contenido y stars sobre la última versión del comentario
+    let updated = update_comment(&state, &comment_id, |existing| {
+        // Verificamos que el usuario sea el autor del comentario
+        if existing.author_uid.as_ref() != Some(&user_claims.user_id) {
+            return Err(ApiError::Forbidden(
                 "You are not authorized to edit this comment".to_string(),
-            )),
-        )
-            .into_response();
-    }
-
-    // Creamos el nuevo comentario con los datos actualizados, solo cambiamos el timestamp contenido y stars
-    let updated_comment: Comment = Comment {
-        timestamp: Utc::now().format("%d/%m/%Y %H:%M").to_string(),
-        content: comment.content,
-        stars: comment.stars,
-        ..existing_comment
-    };
+            ));
+        }
+        if is_review(comment.stars) && !existing.verified_student {
+            let booking_uid: String = booking_uid
+                .clone()
+                .ok_or_else(|| ApiError::Forbidden(REVIEW_REQUIRES_CLASS.to_string()))?;
+            existing.verified_student = true;
+            existing.booking_uid = Some(booking_uid);
+        }
+        existing.timestamp = Utc::now().format("%d/%m/%Y %H:%M").to_string();
+        existing.content = comment.content.clone();
+        existing.stars = comment.stars;
+        existing.moderation_status = edited_status(existing.moderation_status, initial);
+        existing.moderation_reason = None;
+        Ok(())
+    })
+    .await;
 
-    // Intentamos actualizar el comentario en la base de datos
-    match state
-        .firebase_options
-        .firebase_client
-        .put(&url_firebase_db)
-        .json(&updated_comment)
-        .send()
-        .await
-    {
-        Ok(response) => match handle_firebase_response::<Comment>(response).await {
-            Ok(comment) => (
-                StatusCode::OK,
-                Json(ResponseAPI::<Comment>::success(
-                    "Comment updated successfully".to_string(),
-                    Comment {
-                        author_uid: None,
-                        ..comment
-                    },
-                )),
-            )
-                .into_response(),
-            Err((status, error)) => (status, Json(ResponseAPI::<()>::error(error))).into_response(),
-        },
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error(
-                "Failed to update comment".to_string(),
+    match updated {
+        Ok((comment, ())) => (
+            StatusCode::OK,
+            Json(ResponseAPI::<Comment>::success(
+                "Comment updated successfully".to_string(),
+                Comment {
+                    author_uid: None,
+                    ..comment
+                },
             )),
         )
             .into_response(),
+        Err(err) => update_error_response(err, "Failed to update comment"),
     }
 }
 
 // Obtener todos los comentarios
+#[utoipa::path(
+    get,
+    path = "/comments/all",
+    tag = "comments",
+    params(CommentListQuery),
+    responses(
+        (status = 200, description = "Comentarios aprobados indexados por ID, con sus respuestas aprobadas, en el orden pedido", body = ResponseAPI<HashMap<String, Comment>>),
+        (status = 400, description = "Orden o filtro inválidos", body = ResponseAPI<serde_json::Value>),
+        (status = 500, description = "Error en la base de datos", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 #[debug_handler]
 #[instrument(skip(state), fields(operation = "get_all_comments"))]
-pub async fn get_all_comments(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    // URL para obtener todos los comentarios de la DB
-    let url_firebase_db: String = format!(
-        "{}/comments.json",
-        state.firebase_options.firebase_database_url
-    );
-
-    // Realizamos la petición a la base de datos
-    match state
-        .firebase_options
-        .firebase_client
-        .get(&url_firebase_db)
-        .send()
-        .await
+pub async fn get_all_comments(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CommentListQuery>,
+) -> impl IntoResponse {
+    if query
+        .min_stars
+        .is_some_and(|min| !(0.0..=5.0).contains(&min))
     {
-        Ok(response) => {
-            let response_text: String = response.text().await.unwrap_or_default();
+        return ApiError::BadRequest("min_stars must be between 0 and 5".to_string())
+            .into_response();
+    }
 
-            if response_text == "null" || response_text.is_empty() {
-                // No hay comentarios, devolver HashMap vacío
+    // Realizamos la petición a la base de datos
+    match state.repositories.comments.get_all().await {
+        Ok(comments) => {
+            // Solo se publica lo aprobado; el objeto se serializa en el orden pedido
+            let hidden_comments: IndexMap<String, Comment> =
+                list_comments(public_comments(comments), &query);
+            if hidden_comments.is_empty() {
                 return (
                     StatusCode::OK,
-                    Json(ResponseAPI::<HashMap<String, Comment>>::success(
+                    Json(ResponseAPI::<IndexMap<String, Comment>>::success(
                         "No comments found".to_string(),
-                        HashMap::new(),
+                        IndexMap::new(),
                     )),
                 )
                     .into_response();
             }
-
-            match serde_json::from_str::<HashMap<String, Comment>>(&response_text) {
-                Ok(comments) => {
-                    let hidden_comments: HashMap<String, Comment> = comments
-                        .into_iter()
-                        .map(|(id, mut c)| {
-                            c.author_uid = None; // Ocultamos el uid
-                            (id, c)
-                        })
-                        .collect();
-                    (
-                        StatusCode::OK,
-                        Json(ResponseAPI::<HashMap<String, Comment>>::success(
-                            "Comments fetched successfully".to_string(),
-                            hidden_comments,
-                        )),
-                    )
-                        .into_response()
-                }
-                Err(_) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ResponseAPI::<()>::error(
-                        "Error parsing Firebase response".to_string(),
-                    )),
-                )
-                    .into_response(),
-            }
+            (
+                StatusCode::OK,
+                Json(ResponseAPI::<IndexMap<String, Comment>>::success(
+                    "Comments fetched successfully".to_string(),
+                    hidden_comments,
+                )),
+            )
+                .into_response()
         }
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error(
-                "Failed to fetch comments".to_string(),
+        Err(err) => repository_error_response(err, "Failed to fetch comments"),
+    }
+}
+
+// Resumen de valoraciones
+#[utoipa::path(
+    get,
+    path = "/comments/summary",
+    tag = "comments",
+    responses(
+        (status = 200, description = "Media, número de valoraciones, histograma de estrellas y comentarios con más likes (solo aprobados)", body = ResponseAPI<RatingSummary>),
+        (status = 500, description = "Error en la base de datos", body = ResponseAPI<serde_json::Value>),
+    )
+)]
+#[debug_handler]
+#[instrument(skip(state), fields(operation = "get_comments_summary"))]
+pub async fn get_comments_summary(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.repositories.comments.get_all().await {
+        Ok(comments) => (
+            StatusCode::OK,
+            Json(ResponseAPI::<RatingSummary>::success(
+                "Rating summary fetched successfully".to_string(),
+                rating_summary(public_comments(comments)),
             )),
         )
             .into_response(),
+        Err(err) => repository_error_response(err, "Failed to fetch rating summary"),
     }
 }
 
 // Eliminar comentario
+#[utoipa::path(
+    delete,
+    path = "/comments/del/{comment_id}",
+    tag = "comments",
+    params(
+        ("comment_id" = String, Path, description = "ID del comentario"),
+    ),
+    responses(
+        (status = 204, description = "Comentario eliminado"),
+        (status = 403, description = "El comentario no es del usuario", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "Comentario no encontrado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, id_token),
+    skip(state, user_claims),
     fields(
         comment_id = %comment_id,
         operation = "delete_comment"
@@ -255,29 +327,15 @@ pub async fn get_all_comments(State(state): State<Arc<AppState>>) -> impl IntoRe
 )]
 pub async fn delete_comment(
     Path(comment_id): Path<String>,
-    Extension(id_token): Extension<String>,
     Extension(user_claims): Extension<UserAuthentication>,
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    // Obtenemos el ID del comentario a eliminar
-    let url_firebase_db: String = format!(
-        "{}/comments/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, comment_id, id_token
-    );
-
     // Verificar si el comentario existe
-    let comment: Comment = match get_comment_data(&comment_id, &id_token, &state).await {
-        Some(comment) => comment,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(ResponseAPI::<()>::error("Comment not found".to_string())),
-            )
-                .into_response();
-        }
+    let comment: Comment = match get_comment_data(&comment_id, &state).await {
+        Ok(comment) => comment,
+        Err(response) => return response,
     };
 
-    println!("Comment Author UID: {:?}", comment.author_uid);
     if comment.author_uid != Some(user_claims.user_id) {
         return (
             StatusCode::FORBIDDEN,
@@ -289,44 +347,33 @@ pub async fn delete_comment(
     }
 
     // Intentamos eliminar el comentario
-    match state
-        .firebase_options
-        .firebase_client
-        .delete(&url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(response) => {
-            if response.status().is_success() {
-                (
-                    StatusCode::NO_CONTENT,
-                    Json(ResponseAPI::<()>::success_no_data()),
-                )
-                    .into_response()
-            } else {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ResponseAPI::<()>::error(
-                        "Failed to delete comment".to_string(),
-                    )),
-                )
-                    .into_response()
-            }
-        }
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error(
-                "Failed to delete comment".to_string(),
-            )),
+    match state.repositories.comments.delete(&comment_id).await {
+        Ok(_) => (
+            StatusCode::NO_CONTENT,
+            Json(ResponseAPI::<()>::success_no_data()),
         )
             .into_response(),
+        Err(err) => repository_error_response(err, "Failed to delete comment"),
     }
 }
 
 // Añadir o quitar el like
+#[utoipa::path(
+    put,
+    path = "/comments/like/{comment_id}",
+    tag = "comments",
+    params(
+        ("comment_id" = String, Path, description = "ID del comentario"),
+    ),
+    responses(
+        (status = 200, description = "Like añadido o quitado; `liked` indica el estado final", body = ResponseAPI<Comment>),
+        (status = 404, description = "Comentario no encontrado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, id_token, user_claims),
+    skip(state, user_claims),
     fields(
         comment_id = %comment_id,
         user_id = %user_claims.user_id,
@@ -336,91 +383,124 @@ pub async fn delete_comment(
 pub async fn toggle_like(
     Path(comment_id): Path<String>,
     Extension(user_claims): Extension<UserAuthentication>,
-    Extension(id_token): Extension<String>,
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    // Obtenemos el comentario
-    let comment: Comment = match get_comment_data(&comment_id, &id_token, &state).await {
-        Some(comment) => comment,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(ResponseAPI::<()>::error("Comment not found".to_string())),
+    // Añadimos o quitamos el like sobre la última versión del comentario
+    let updated = update_comment(&state, &comment_id, |comment| {
+        ensure_visible(comment, &user_claims.user_id)?;
+        Ok(toggle_user_like(
+            &mut comment.users_liked,
+            &mut comment.like,
+            &user_claims.user_id,
+        ))
+    })
+    .await;
+
+    match updated {
+        Ok((mut comment, _)) => {
+            visible_to(&mut comment, &user_claims.user_id);
+            (
+                StatusCode::OK,
+                Json(ResponseAPI::<Comment>::success(
+                    "Comment updated successfully".to_string(),
+                    Comment {
+                        author_uid: None,
+                        ..comment
+                    },
+                )),
             )
-                .into_response();
+                .into_response()
         }
-    };
-
-    // Actualizamos el estado del "like"
-    let url_firebase_db_like: String = format!(
-        "{}/comments/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, comment_id, id_token
-    );
+        Err(err) => update_error_response(err, "Failed to update comment"),
+    }
+}
 
-    // Comprobamos si ya le habiamos dado like
-    let is_liked: bool = comment.users_liked.contains(&user_claims.user_id);
+// Añadir o quitar el like de una respuesta
+#[utoipa::path(
+    put,
+    path = "/comments/like/{comment_id}/reply/{reply_id}",
+    tag = "comments",
+    params(
+        ("comment_id" = String, Path, description = "ID del comentario"),
+        ("reply_id" = String, Path, description = "ID de la respuesta"),
+    ),
+    responses(
+        (status = 200, description = "Like añadido o quitado; `liked` indica el estado final", body = ResponseAPI<ReplyComment>),
+        (status = 404, description = "Comentario o respuesta no encontrados", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims),
+    fields(
+        comment_id = %comment_id,
+        reply_id = %reply_id,
+        user_id = %user_claims.user_id,
+        operation = "toggle_reply_like"
+    )
+)]
+pub async fn toggle_reply_like(
+    Path((comment_id, reply_id)): Path<(String, String)>,
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let updated = update_comment(&state, &comment_id, |comment| {
+        ensure_visible(comment, &user_claims.user_id)?;
+        // Sin aprobar, la respuesta solo existe para su autor
+        let reply: &mut ReplyComment = comment
+            .reply
+            .iter_mut()
+            .find(|r| {
+                r.id == reply_id
+                    && (r.moderation_status == ModerationStatus::Approved
+                        || r.author_uid == user_claims.user_id)
+            })
+            .ok_or_else(|| ApiError::NotFound("Reply not found".to_string()))?;
 
-    let new_comment: Comment = Comment {
-        like: if is_liked {
-            comment.like.saturating_sub(1)
-        } else {
-            comment.like + 1
-        },
-        users_liked: {
-            if is_liked {
-                comment
-                    .users_liked
-                    .into_iter()
-                    .filter(|uid| uid != &user_claims.user_id)
-                    .collect()
-            } else {
-                let mut users = comment.users_liked.clone();
-                users.push(user_claims.user_id.clone());
-                users
-            }
-        },
-        ..comment
-    };
+        let liked: bool = toggle_user_like(
+            &mut reply.users_liked,
+            &mut reply.like,
+            &user_claims.user_id,
+        );
+        Ok(ReplyComment {
+            liked: Some(liked),
+            ..reply.clone()
+        })
+    })
+    .await;
 
-    // Actualizamos el comentario
-    match state
-        .firebase_options
-        .firebase_client
-        .put(&url_firebase_db_like)
-        .json(&new_comment)
-        .send()
-        .await
-    {
-        Ok(response) => match handle_firebase_response::<Comment>(response).await {
-            Ok(comment) => {
-                (
-                    StatusCode::OK,
-                    Json(ResponseAPI::<Comment>::success(
-                        "Comment updated successfully".to_string(),
-                        Comment {
-                            author_uid: None,
-                            ..comment
-                        },
-                    )),
-                )
-            }
-            .into_response(),
-            Err((status, error)) => (status, Json(ResponseAPI::<()>::error(error))).into_response(),
-        },
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error(
-                "Failed to update comment".to_string(),
+    match updated {
+        Ok((_, reply)) => (
+            StatusCode::OK,
+            Json(ResponseAPI::<ReplyComment>::success(
+                "Reply updated successfully".to_string(),
+                reply,
             )),
         )
             .into_response(),
+        Err(err) => update_error_response(err, "Failed to update reply"),
     }
 }
 
 // Añadir respuesta a un comentario
+#[utoipa::path(
+    post,
+    path = "/comments/reply/{comment_id}",
+    tag = "comments",
+    params(
+        ("comment_id" = String, Path, description = "ID del comentario"),
+    ),
+    request_body = ReplyComment,
+    responses(
+        (status = 201, description = "Respuesta creada", body = ResponseAPI<ReplyComment>),
+        (status = 404, description = "Comentario no encontrado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, id_token, user_claims, reply_comment),
+    skip(state, user_claims, reply_comment),
     fields(
         comment_id = %comment_id,
         user_id = %user_claims.user_id,
@@ -431,110 +511,137 @@ pub async fn toggle_like(
 pub async fn add_reply(
     Path(comment_id): Path<String>,
     State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
     Extension(user_claims): Extension<UserAuthentication>,
     Json(reply_comment): Json<ReplyComment>,
 ) -> impl IntoResponse {
-    // Obtenemos el comentario padre
-    let mut comment: Comment = match get_comment_data(&comment_id, &id_token, &state).await {
-        Some(comment) => comment,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(ResponseAPI::<()>::error("Comment not found".to_string())),
-            )
-                .into_response();
-        }
-    };
-
-    // URL del comentario en Firebase
-    let url_firebase_db_comment = format!(
-        "{}/comments/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, comment_id, id_token
-    );
-
-    // Generar ID único para la nueva reply
-    let reply_id = Uuid::new_v4().to_string();
+    let moderation_status: ModerationStatus =
+        match initial_status(&state, &user_claims.user_id, None).await {
+            Ok(status) => status,
+            Err(err) => return repository_error_response(err, "Failed to add reply"),
+        };
 
-    // Crear la nueva reply con todos los campos
+    // Crear la nueva reply con todos los campos y un ID único
     let new_reply = ReplyComment {
-        id: reply_id,
+        id: Uuid::new_v4().to_string(),
         author_uid: user_claims.user_id.clone(),
-        name: reply_comment.name.clone(),
+        name: reply_comment.name,
         timestamp: Utc::now().format("%d/%m/%Y %H:%M").to_string(),
-        content: reply_comment.content.clone(),
-        url_img: reply_comment.url_img.clone(),
+        content: reply_comment.content,
+        url_img: reply_comment.url_img,
         like: 0,
         users_liked: Vec::new(),
+        moderation_status,
+        moderation_reason: None,
+        liked: None,
     };
 
-    // Agregar la reply al comentario
-    comment.reply.push(new_reply.clone()); // ✅ clonamos para poder devolver luego
-
-    // Guardar el comentario actualizado en Firebase
-    match state
-        .firebase_options
-        .firebase_client
-        .put(&url_firebase_db_comment)
-        .json(&comment)
-        .send()
-        .await
-    {
-        Ok(response) => match handle_firebase_response::<Comment>(response).await {
-            Ok(_) => (
-                StatusCode::CREATED,
-                Json(ResponseAPI::<ReplyComment>::success(
-                    "Reply added successfully".to_string(),
-                    new_reply, // ✅ devolvemos la reply creada
-                )),
-            )
-                .into_response(),
-            Err((status, error)) => (status, Json(ResponseAPI::<()>::error(error))).into_response(),
-        },
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error("Failed to add reply".to_string())),
+    // Agregar la reply a la última versión del comentario
+    let updated = update_comment(&state, &comment_id, |comment| {
+        ensure_visible(comment, &user_claims.user_id)?;
+        comment.reply.push(new_reply.clone());
+        Ok(())
+    })
+    .await;
+
+    match updated {
+        Ok(_) => (
+            StatusCode::CREATED,
+            Json(ResponseAPI::<ReplyComment>::success(
+                "Reply added successfully".to_string(),
+                new_reply,
+            )),
         )
             .into_response(),
+        Err(err) => update_error_response(err, "Failed to add reply"),
     }
 }
 
-// Servicio para obtener un comentario de la base de datos
+// Servicio para obtener un comentario de la base de datos.
+// Devuelve directamente la respuesta de error (404 o 500) si no se puede obtener.
 #[instrument(
-    skip(state, id_token),
+    skip(state),
     fields(
         comment_id = %comment_id,
         operation = "get_comment_data"
     )
 )]
-async fn get_comment_data(
+async fn get_comment_data(comment_id: &str, state: &Arc<AppState>) -> Result<Comment, Response> {
+    match state.repositories.comments.get(comment_id).await {
+        Ok(Some(comment)) => Ok(comment),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ResponseAPI::<()>::error("Comment not found".to_string())),
+        )
+            .into_response()),
+        Err(err) => Err(repository_error_response(err, "Failed to fetch comment")),
+    }
+}
+
+// Lo no aprobado solo existe para su autor
+fn ensure_visible(comment: &Comment, uid: &str) -> Result<(), ApiError> {
+    if comment.moderation_status != ModerationStatus::Approved
+        && comment.author_uid.as_deref() != Some(uid)
+    {
+        return Err(ApiError::NotFound("Comment not found".to_string()));
+    }
+    Ok(())
+}
+
+// Lo que ve `uid` de un comentario: las respuestas sin aprobar y la reserva que respalda
+// la reseña solo las ve su autor
+fn visible_to(comment: &mut Comment, uid: &str) {
+    comment.reply.retain(|reply| {
+        reply.moderation_status == ModerationStatus::Approved || reply.author_uid == uid
+    });
+    if comment.author_uid.as_deref() != Some(uid) {
+        comment.booking_uid = None;
+    }
+    mark_liked(comment, uid);
+}
+
+// Como `get_comment_data`, pero aplicando `ensure_visible`
+async fn get_visible_comment(
     comment_id: &str,
-    id_token: &str,
     state: &Arc<AppState>,
-) -> Option<Comment> {
-    // URL de Firebase Realtime Database para obtener los datos del comentario
-    let url_firebase_db: String = format!(
-        "{}/comments/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, comment_id, id_token
-    );
+    uid: &str,
+) -> Result<Comment, Response> {
+    let comment: Comment = get_comment_data(comment_id, state).await?;
+    ensure_visible(&comment, uid).map_err(IntoResponse::into_response)?;
+    Ok(comment)
+}
 
-    // Realizamos la petición a Firebase Realtime Database
-    match state
-        .firebase_options
-        .firebase_client
-        .get(url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(response) => (handle_firebase_response::<Comment>(response).await).ok(),
-        Err(_) => None,
+// Respuesta genérica para errores de la base de datos
+fn repository_error_response(err: RepositoryError, message: &str) -> Response {
+    tracing::error!("{}: {}", message, err);
+    ApiError::Internal(message.to_string()).into_response()
+}
+
+// Errores de `update_comment`: los de la base de datos se responden como el resto de
+// handlers; 404, 403 y 409 tal cual
+fn update_error_response(err: ApiError, message: &str) -> Response {
+    match err {
+        ApiError::Repository(err) => repository_error_response(err, message),
+        err => err.into_response(),
     }
 }
 
 // Obtener un comentario por id
+#[utoipa::path(
+    get,
+    path = "/comments/{id}",
+    tag = "comments",
+    params(
+        ("id" = String, Path, description = "ID del comentario"),
+    ),
+    responses(
+        (status = 200, description = "Comentario encontrado", body = ResponseAPI<Comment>),
+        (status = 404, description = "Comentario no encontrado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state),
+    skip(state, user_claims),
     fields(
         comment_id = %comment_id,
         operation = "get_comment_by_id"
@@ -542,30 +649,45 @@ async fn get_comment_data(
 )]
 pub async fn get_comment_by_id(
     Path(comment_id): Path<String>,
-    Extension(id_token): Extension<String>,
     State(state): State<Arc<AppState>>,
+    Extension(user_claims): Extension<UserAuthentication>,
 ) -> impl IntoResponse {
-    match get_comment_data(&comment_id, &id_token, &state).await {
-        Some(comment) => (
-            StatusCode::OK,
-            Json(ResponseAPI::<Comment>::success(
-                "Comment fetched successfully".to_string(),
-                comment,
-            )),
-        )
-            .into_response(),
-        None => (
-            StatusCode::NOT_FOUND,
-            Json(ResponseAPI::<()>::error("Comment not found".to_string())),
-        )
-            .into_response(),
+    match get_visible_comment(&comment_id, &state, &user_claims.user_id).await {
+        Ok(mut comment) => {
+            visible_to(&mut comment, &user_claims.user_id);
+            (
+                StatusCode::OK,
+                Json(ResponseAPI::<Comment>::success(
+                    "Comment fetched successfully".to_string(),
+                    comment,
+                )),
+            )
+                .into_response()
+        }
+        Err(response) => response,
     }
 }
 
 // Editar una respuesta específica
+#[utoipa::path(
+    put,
+    path = "/comments/reply/{comment_id}/{reply_id}/edit",
+    tag = "comments",
+    params(
+        ("comment_id" = String, Path, description = "ID del comentario"),
+        ("reply_id" = String, Path, description = "ID de la respuesta"),
+    ),
+    request_body = ReplyComment,
+    responses(
+        (status = 200, description = "Respuesta actualizada", body = ResponseAPI<ReplyComment>),
+        (status = 403, description = "La respuesta no es del usuario", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "Respuesta no encontrada", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, id_token, user_claims, reply_update),
+    skip(state, user_claims, reply_update),
     fields(
         comment_id = %comment_id,
         reply_id = %reply_id,
@@ -576,94 +698,71 @@ pub async fn get_comment_by_id(
 pub async fn edit_reply(
     Path((comment_id, reply_id)): Path<(String, String)>,
     State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
     Extension(user_claims): Extension<UserAuthentication>,
     Json(reply_update): Json<ReplyComment>,
 ) -> impl IntoResponse {
-    // Obtenemos el comentario padre
-    let mut comment: Comment = match get_comment_data(&comment_id, &id_token, &state).await {
-        Some(comment) => comment,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(ResponseAPI::<()>::error("Comment not found".to_string())),
-            )
-                .into_response();
-        }
-    };
-
-    // Limitar scope del mutable borrow
-    let updated_reply = {
-        let reply = comment.reply.iter_mut().find(|r| r.id == reply_id);
-
-        let reply = match reply {
-            Some(r) => r,
-            None => {
-                return (
-                    StatusCode::NOT_FOUND,
-                    Json(ResponseAPI::<()>::error("Reply not found".to_string())),
-                )
-                    .into_response();
-            }
+    // El contenido nuevo se vuelve a moderar
+    let initial: ModerationStatus =
+        match initial_status(&state, &user_claims.user_id, Some(&reply_id)).await {
+            Ok(status) => status,
+            Err(err) => return repository_error_response(err, "Failed to update reply"),
         };
 
+    let updated = update_comment(&state, &comment_id, |comment| {
+        let reply: &mut ReplyComment = comment
+            .reply
+            .iter_mut()
+            .find(|r| r.id == reply_id)
+            .ok_or_else(|| ApiError::NotFound("Reply not found".to_string()))?;
+
         // Verificamos ownership
         if reply.author_uid != user_claims.user_id {
-            return (
-                StatusCode::FORBIDDEN,
-                Json(ResponseAPI::<()>::error(
-                    "You are not authorized to edit this reply".to_string(),
-                )),
-            )
-                .into_response();
+            return Err(ApiError::Forbidden(
+                "You are not authorized to edit this reply".to_string(),
+            ));
         }
 
         // Actualizamos contenido y timestamp
         reply.content = reply_update.content.clone();
         reply.timestamp = Utc::now().format("%d/%m/%Y %H:%M").to_string();
+        reply.moderation_status = edited_status(reply.moderation_status, initial);
+        reply.moderation_reason = None;
+        Ok(reply.clone())
+    })
+    .await;
 
-        reply.clone()
-    }; // <- aquí termina el mutable borrow
-
-    // Guardamos el comentario actualizado en Firebase
-    let url_firebase_db = format!(
-        "{}/comments/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, comment_id, id_token
-    );
-
-    match state
-        .firebase_options
-        .firebase_client
-        .put(&url_firebase_db)
-        .json(&comment)
-        .send()
-        .await
-    {
-        Ok(response) => match handle_firebase_response::<Comment>(response).await {
-            Ok(_) => (
-                StatusCode::OK,
-                Json(ResponseAPI::<ReplyComment>::success(
-                    "Reply updated successfully".to_string(),
-                    updated_reply, // devolvemos la reply editada
-                )),
-            )
-                .into_response(),
-            Err((status, error)) => (status, Json(ResponseAPI::<()>::error(error))).into_response(),
-        },
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error(
-                "Failed to update reply".to_string(),
+    match updated {
+        Ok((_, reply)) => (
+            StatusCode::OK,
+            Json(ResponseAPI::<ReplyComment>::success(
+                "Reply updated successfully".to_string(),
+                reply, // devolvemos la reply editada
             )),
         )
             .into_response(),
+        Err(err) => update_error_response(err, "Failed to update reply"),
     }
 }
 
 // Eliminar una respuesta reply específica
+#[utoipa::path(
+    delete,
+    path = "/comments/del/{comment_id}/reply/{reply_id}",
+    tag = "comments",
+    params(
+        ("comment_id" = String, Path, description = "ID del comentario"),
+        ("reply_id" = String, Path, description = "ID de la respuesta"),
+    ),
+    responses(
+        (status = 204, description = "Respuesta eliminada"),
+        (status = 403, description = "La respuesta no es del usuario", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "Respuesta no encontrada", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, id_token, user_claims),
+    skip(state, user_claims),
     fields(
         comment_id = %comment_id,
         reply_id = %reply_id,
@@ -674,7 +773,6 @@ pub async fn edit_reply(
 pub async fn delete_reply(
     Path((comment_id, reply_id)): Path<(String, String)>,
     State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
     Extension(user_claims): Extension<UserAuthentication>,
 ) -> impl IntoResponse {
     tracing::debug!(
@@ -684,136 +782,59 @@ pub async fn delete_reply(
         user_claims.user_id
     );
 
-    // 1️Obtener el comentario padre
-    let mut comment: Comment = match get_comment_data(&comment_id, &id_token, &state).await {
-        Some(comment) => {
-            tracing::debug!("Found parent comment with {} replies", comment.reply.len());
-            comment
-        }
-        None => {
-            tracing::debug!("Parent comment not found: {}", comment_id);
-            return (
-                StatusCode::NOT_FOUND,
-                Json(ResponseAPI::<()>::error("Comment not found".to_string())),
-            )
-                .into_response();
-        }
-    };
-
-    // Buscar el reply a eliminar
-    let reply_index: Option<usize> = comment.reply.iter().position(|r| r.id == reply_id);
-    tracing::debug!("Computed reply_index: {:?}", reply_index);
-
-    if reply_index.is_none() {
-        tracing::debug!(
-            "Reply not found in comment: {} reply_id: {}",
-            comment_id,
-            reply_id
-        );
-        return (
-            StatusCode::NOT_FOUND,
-            Json(ResponseAPI::<()>::error("Reply not found".to_string())),
-        )
-            .into_response();
-    }
-
-    let index: usize = reply_index.unwrap();
-    let reply: &ReplyComment = &comment.reply[index];
-    tracing::debug!(
-        "Reply at index {} has author_uid={}",
-        index,
-        reply.author_uid
-    );
+    // Quitamos la reply de la última versión del comentario
+    let updated = update_comment(&state, &comment_id, |comment| {
+        let index: usize = comment
+            .reply
+            .iter()
+            .position(|r| r.id == reply_id)
+            .ok_or_else(|| ApiError::NotFound("Reply not found".to_string()))?;
 
-    // Comprobar permisos
-    if reply.author_uid != user_claims.user_id.clone() {
-        tracing::debug!(
-            "Unauthorized delete attempt by user={} for reply author={}",
-            user_claims.user_id,
-            reply.author_uid
-        );
-        return (
-            StatusCode::FORBIDDEN,
-            Json(ResponseAPI::<()>::error(
-                "You are not authorized to delete this reply".to_string(),
-            )),
-        )
-            .into_response();
-    }
-
-    // Eliminar el reply del vector
-    tracing::debug!(
-        "Removing reply at index {} from comment {}",
-        index,
-        comment_id
-    );
-    comment.reply.remove(index);
-
-    // Guardar los cambios en Firebase
-    let url_firebase_db: String = format!(
-        "{}/comments/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, comment_id, id_token
-    );
-
-    tracing::debug!(
-        "Putting updated comment to Firebase URL: {}",
-        url_firebase_db
-    );
-
-    match state
-        .firebase_options
-        .firebase_client
-        .put(&url_firebase_db)
-        .json(&comment)
-        .send()
-        .await
-    {
-        Ok(response) => {
-            let status = response.status();
-            let body_text = response.text().await.unwrap_or_default();
+        // Comprobar permisos
+        if comment.reply[index].author_uid != user_claims.user_id {
             tracing::debug!(
-                "Firebase PUT response status: {}, body: {}",
-                status,
-                body_text
+                "Unauthorized delete attempt by user={} for reply author={}",
+                user_claims.user_id,
+                comment.reply[index].author_uid
             );
-
-            if status.is_success() {
-                (
-                    StatusCode::NO_CONTENT,
-                    Json(ResponseAPI::<()>::success_no_data()),
-                )
-                    .into_response()
-            } else {
-                tracing::error!(
-                    "Failed to delete reply: firebase returned non-success status {}",
-                    status
-                );
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ResponseAPI::<()>::error(
-                        "Failed to delete reply".to_string(),
-                    )),
-                )
-                    .into_response()
-            }
-        }
-        Err(e) => {
-            tracing::error!("Failed to send PUT to Firebase: {:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ResponseAPI::<()>::error(
-                    "Failed to delete reply".to_string(),
-                )),
-            )
-                .into_response()
+            return Err(ApiError::Forbidden(
+                "You are not authorized to delete this reply".to_string(),
+            ));
         }
+
+        comment.reply.remove(index);
+        Ok(())
+    })
+    .await;
+
+    match updated {
+        Ok(_) => (
+            StatusCode::NO_CONTENT,
+            Json(ResponseAPI::<()>::success_no_data()),
+        )
+            .into_response(),
+        Err(err) => update_error_response(err, "Failed to delete reply"),
     }
 }
 
 // Obtener una respuesta específica por id
+#[utoipa::path(
+    get,
+    path = "/comments/{comment_id}/reply/{reply_id}",
+    tag = "comments",
+    params(
+        ("comment_id" = String, Path, description = "ID del comentario"),
+        ("reply_id" = String, Path, description = "ID de la respuesta"),
+    ),
+    responses(
+        (status = 200, description = "Respuesta encontrada", body = ResponseAPI<ReplyComment>),
+        (status = 404, description = "Respuesta no encontrada", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, id_token),
+    skip(state, user_claims),
     fields(
         comment_id = %comment_id,
         reply_id = %reply_id,
@@ -822,28 +843,30 @@ pub async fn delete_reply(
 )]
 pub async fn get_reply_by_id(
     Path((comment_id, reply_id)): Path<(String, String)>,
-    Extension(id_token): Extension<String>,
     State(state): State<Arc<AppState>>,
+    Extension(user_claims): Extension<UserAuthentication>,
 ) -> impl IntoResponse {
     // Obtener el comentario
-    let comment = match get_comment_data(&comment_id, &id_token, &state).await {
-        Some(comment) => comment,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(ResponseAPI::<()>::error("Comment not found".to_string())),
-            )
-                .into_response();
-        }
+    let comment = match get_visible_comment(&comment_id, &state, &user_claims.user_id).await {
+        Ok(comment) => comment,
+        Err(response) => return response,
     };
 
-    // Buscar la reply por ID
-    if let Some(reply) = comment.reply.into_iter().find(|r| r.id == reply_id) {
+    // Buscar la reply por ID; sin aprobar solo la ve su autor
+    if let Some(reply) = comment.reply.into_iter().find(|r| {
+        r.id == reply_id
+            && (r.moderation_status == ModerationStatus::Approved
+                || r.author_uid == user_claims.user_id)
+    }) {
+        let liked: bool = reply.users_liked.contains(&user_claims.user_id);
         return (
             StatusCode::OK,
             Json(ResponseAPI::<ReplyComment>::success(
                 "Reply fetched successfully".to_string(),
-                reply,
+                ReplyComment {
+                    liked: Some(liked),
+                    ..reply
+                },
             )),
         )
             .into_response();
@@ -856,3 +879,148 @@ pub async fn get_reply_by_id(
     )
         .into_response()
 }
+
+// Cola de moderación (solo administradores)
+#[utoipa::path(
+    get,
+    path = "/comments/moderation",
+    tag = "comments",
+    params(ModerationQueueQuery),
+    responses(
+        (status = 200, description = "Comentarios y respuestas con el estado pedido (por defecto, pendientes)", body = ResponseAPI<ModerationQueue>),
+        (status = 403, description = "Solo administradores", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(skip(state, query), fields(operation = "get_moderation_queue"))]
+pub async fn get_moderation_queue(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ModerationQueueQuery>,
+) -> Result<Response, ApiError> {
+    let comments: HashMap<String, Comment> = state.repositories.comments.get_all().await?;
+    let queue: ModerationQueue =
+        moderation_queue(comments, query.status.unwrap_or(ModerationStatus::Pending));
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<ModerationQueue>::success(
+            "Moderation queue fetched successfully".to_string(),
+            queue,
+        )),
+    )
+        .into_response())
+}
+
+// Aprobar, rechazar u ocultar un comentario (solo administradores)
+#[utoipa::path(
+    put,
+    path = "/comments/moderation/{comment_id}",
+    tag = "comments",
+    params(
+        ("comment_id" = String, Path, description = "ID del comentario"),
+    ),
+    request_body = ModerationDecision,
+    responses(
+        (status = 200, description = "Decisión guardada", body = ResponseAPI<Comment>),
+        (status = 400, description = "Estado `pending` o falta el motivo del rechazo", body = ResponseAPI<serde_json::Value>),
+        (status = 403, description = "Solo administradores", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "Comentario no encontrado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims, decision),
+    fields(
+        admin_id = %user_claims.sub,
+        comment_id = %comment_id,
+        operation = "moderate_comment"
+    )
+)]
+pub async fn moderate_comment(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+    Path(comment_id): Path<String>,
+    Json(decision): Json<ModerationDecision>,
+) -> Result<Response, ApiError> {
+    let (status, reason) = validate_decision(decision)?;
+    let (comment, ()) = update_comment(&state, &comment_id, |comment| {
+        comment.moderation_status = status;
+        comment.moderation_reason = reason.clone();
+        Ok(())
+    })
+    .await?;
+    tracing::info!("Comment {} moderated as {}", comment_id, status.as_ref());
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<Comment>::success(
+            "Comment moderated successfully".to_string(),
+            comment,
+        )),
+    )
+        .into_response())
+}
+
+// Aprobar, rechazar u ocultar una respuesta (solo administradores)
+#[utoipa::path(
+    put,
+    path = "/comments/moderation/{comment_id}/reply/{reply_id}",
+    tag = "comments",
+    params(
+        ("comment_id" = String, Path, description = "ID del comentario"),
+        ("reply_id" = String, Path, description = "ID de la respuesta"),
+    ),
+    request_body = ModerationDecision,
+    responses(
+        (status = 200, description = "Decisión guardada", body = ResponseAPI<ReplyComment>),
+        (status = 400, description = "Estado `pending` o falta el motivo del rechazo", body = ResponseAPI<serde_json::Value>),
+        (status = 403, description = "Solo administradores", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "Comentario o respuesta no encontrados", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims, decision),
+    fields(
+        admin_id = %user_claims.sub,
+        comment_id = %comment_id,
+        reply_id = %reply_id,
+        operation = "moderate_reply"
+    )
+)]
+pub async fn moderate_reply(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+    Path((comment_id, reply_id)): Path<(String, String)>,
+    Json(decision): Json<ModerationDecision>,
+) -> Result<Response, ApiError> {
+    let (status, reason) = validate_decision(decision)?;
+    let (_, reply) = update_comment(&state, &comment_id, |comment| {
+        let reply: &mut ReplyComment = comment
+            .reply
+            .iter_mut()
+            .find(|r| r.id == reply_id)
+            .ok_or_else(|| ApiError::NotFound("Reply not found".to_string()))?;
+        reply.moderation_status = status;
+        reply.moderation_reason = reason.clone();
+        Ok(reply.clone())
+    })
+    .await?;
+    tracing::info!("Reply {} moderated as {}", reply_id, status.as_ref());
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<ReplyComment>::success(
+            "Reply moderated successfully".to_string(),
+            reply,
+        )),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+#[path = "../test/controllers/comments.rs"]
+mod extended_tests;
@@ -7,6 +7,17 @@ use {
 };
 
 /// Envía un correo electrónico de contacto utilizando la API de Resend.
+#[utoipa::path(
+    post,
+    path = "/email/contact",
+    tag = "email",
+    request_body = EmailResend,
+    responses(
+        (status = 200, description = "Email enviado", body = ResponseAPI<String>),
+        (status = 429, description = "Demasiadas peticiones", body = ResponseAPI<serde_json::Value>),
+        (status = 500, description = "Error al enviar el email", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 #[debug_handler]
 #[instrument(skip(state, payload))]
 pub async fn send_contact_email(
@@ -15,6 +15,16 @@ use {
     std::sync::Arc,
 };
 
+#[utoipa::path(
+    post,
+    path = "/mailchimp/add_contact",
+    tag = "mailchimp",
+    request_body = Contact,
+    responses(
+        (status = 201, description = "Contacto añadido a la audiencia", body = ResponseAPI<AddContactResponse>),
+        (status = 429, description = "Demasiadas peticiones", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 pub async fn add_contact(
     State(state): State<Arc<AppState>>,
     Json(contact): Json<Contact>,
@@ -54,6 +64,15 @@ pub async fn add_contact(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/mailchimp/get_all_contacts",
+    tag = "mailchimp",
+    responses(
+        (status = 200, description = "Miembros de la audiencia", body = ResponseAPI<MembersResponse>),
+        (status = 500, description = "Error al comunicarse con Mailchimp", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 pub async fn get_all_contacts(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     // URL para obtener todos los miembros de la lista
     let url_mailchimp: String = format!(
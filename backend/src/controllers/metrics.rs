@@ -1,6 +1,7 @@
 use {
     crate::{
         models::{
+            error::ApiError,
             metrics::{GAResponse, GAToken},
             response::ResponseAPI,
             state::AppState,
@@ -40,13 +41,7 @@ async fn get_ga_metrics(
 
     let ga_response: GAResponse = match parse_ga_response(response).await {
         Ok(parsed_data) => parsed_data,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ResponseAPI::<()>::error(e.to_string())),
-            )
-                .into_response();
-        }
+        Err(e) => return ApiError::from(e).into_response(),
     };
 
     (
@@ -60,6 +55,15 @@ async fn get_ga_metrics(
 }
 
 /// Controlador para obtener métricas de usuarios desde Google Analytics
+#[utoipa::path(
+    get,
+    path = "/metrics/users",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Usuarios activos por mes", body = ResponseAPI<GAResponse>),
+        (status = 502, description = "Error al obtener el token de Google Analytics", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 pub async fn get_user_metrics(
     State(state): State<Arc<AppState>>,
     Extension(GAToken(token_ga)): Extension<GAToken>,
@@ -89,6 +93,15 @@ pub async fn get_user_metrics(
 }
 
 /// Controlador para obtener métricas de artículos desde Google Analytics
+#[utoipa::path(
+    get,
+    path = "/metrics/articles",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Eventos de artículos por mes", body = ResponseAPI<GAResponse>),
+        (status = 502, description = "Error al obtener el token de Google Analytics", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 pub async fn get_article_metrics(
     State(state): State<Arc<AppState>>,
     Extension(GAToken(token_ga)): Extension<GAToken>,
@@ -124,6 +137,15 @@ pub async fn get_article_metrics(
 }
 
 /// Controlador para obtener métricas de reservas de clases
+#[utoipa::path(
+    get,
+    path = "/metrics/class",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Eventos de clases por mes", body = ResponseAPI<GAResponse>),
+        (status = 502, description = "Error al obtener el token de Google Analytics", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 pub async fn get_class_metrics(
     State(state): State<Arc<AppState>>,
     Extension(GAToken(token_ga)): Extension<GAToken>,
@@ -158,4 +180,4 @@ pub async fn get_class_metrics(
 
 #[cfg(test)]
 #[path = "../test/controllers/metrics.rs"]
-mod tests;
+mod extended_tests;
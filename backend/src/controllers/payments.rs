@@ -1,11 +1,14 @@
 use {
     crate::{
         models::{
+            error::ApiError,
+            firebase::UserAuthentication,
             response::ResponseAPI,
             state::AppState,
             stripe::{
-                CurrencyMap, PayloadCreacteProduct, PaymentIntentSimplified, PaymentPayload,
-                PaymentResponse, PricePayload, ProductPayload, RelationalCalStripe, StripeRelation,
+                CurrencyMap, PAYMENT_USER_METADATA_KEY, PayloadCreacteProduct,
+                PaymentIntentSimplified, PaymentPayload, PaymentResponse, PricePayload,
+                ProductPayload, RelationalCalStripe, StripeRelation,
             },
         },
         services::payments::insert_options_by_country,
@@ -16,7 +19,6 @@ use {
         http::StatusCode,
         response::IntoResponse,
     },
-    serde_json::json,
     std::{collections::HashMap, str::FromStr, sync::Arc},
     stripe::{
         CreatePaymentIntent, CreatePaymentIntentAutomaticPaymentMethods, CreateProduct,
@@ -28,9 +30,20 @@ use {
 };
 
 /// Comprar precios genericos
+#[utoipa::path(
+    post,
+    path = "/payment/intent",
+    tag = "payments",
+    request_body = PaymentPayload,
+    responses(
+        (status = 200, description = "PaymentIntent creado", body = ResponseAPI<PaymentResponse>),
+        (status = 400, description = "Importe o moneda inválidos", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, payload),
+    skip(state, user_claims, payload),
     fields(
         amount = %payload.amount,
         currency = %payload.currency,
@@ -38,6 +51,7 @@ use {
     )
 )]
 pub async fn payment_intent(
+    Extension(user_claims): Extension<UserAuthentication>,
     State(state): State<Arc<AppState>>,
     Json(payload): Json<PaymentPayload>,
 ) -> impl IntoResponse {
@@ -85,7 +99,11 @@ pub async fn payment_intent(
         expand: &[],
         mandate: None,
         mandate_data: None,
-        metadata: None,
+        // Permite localizar los pagos del usuario (exportación de datos)
+        metadata: Some(HashMap::from([(
+            PAYMENT_USER_METADATA_KEY.to_string(),
+            user_claims.sub.clone(),
+        )])),
         off_session: None,
         on_behalf_of: None,
         payment_method_configuration: None,
@@ -157,6 +175,16 @@ pub async fn payment_intent(
 }
 
 /// Obtener historial de pagos de un usuario
+#[utoipa::path(
+    get,
+    path = "/payment/history",
+    tag = "payments",
+    responses(
+        (status = 200, description = "Historial de pagos", body = ResponseAPI<Vec<PaymentIntentSimplified>>),
+        (status = 500, description = "Error de Stripe", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(skip(state), fields(operation = "get_payment_history"))]
 pub async fn get_payment_history(State(state): State<Arc<AppState>>) -> impl IntoResponse {
@@ -196,6 +224,17 @@ pub async fn get_payment_history(State(state): State<Arc<AppState>>) -> impl Int
 }
 
 /// Crear un producto
+#[utoipa::path(
+    post,
+    path = "/payment/product",
+    tag = "payments",
+    request_body = PayloadCreacteProduct,
+    responses(
+        (status = 201, description = "Producto creado (objeto Product de Stripe)", body = ResponseAPI<serde_json::Value>),
+        (status = 403, description = "Falta el permiso manage_products", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
     skip(state, payload),
@@ -235,22 +274,13 @@ pub async fn create_product(
         currency: match Currency::from_str(&price.currency.to_string()) {
             Ok(currency) => {
                 if currency != Currency::EUR {
-                    return (
-                        StatusCode::BAD_REQUEST,
-                        Json(json!({
-                            "error": "Invalid currency, must be EUR"
-                        })),
-                    );
+                    return ApiError::BadRequest("Invalid currency, must be EUR".to_string())
+                        .into_response();
                 }
                 currency
             }
             Err(_) => {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({
-                        "error": "Invalid currency"
-                    })),
-                );
+                return ApiError::BadRequest("Invalid currency".to_string()).into_response();
             }
         },
         unit_amount: Some(price.unit_amount),
@@ -285,16 +315,27 @@ pub async fn create_product(
     match Product::create(&state.stripe_client, new_product).await {
         Ok(product) => (
             StatusCode::CREATED,
-            Json(json!({ "success": true, "product": product })),
-        ),
-        Err(err) => (
-            StatusCode::BAD_REQUEST,
-            Json(json! ({"success": false, "error": format!("Error creating product: {}", err) })),
-        ),
+            Json(ResponseAPI::<Product>::success(
+                "Product created successfully".to_string(),
+                product,
+            )),
+        )
+            .into_response(),
+        Err(err) => ApiError::from(err).into_response(),
     }
 }
 
 /// Obtener toda la lista de productos
+#[utoipa::path(
+    get,
+    path = "/payment/product/all",
+    tag = "payments",
+    responses(
+        (status = 200, description = "Lista de productos de Stripe", body = ResponseAPI<serde_json::Value>),
+        (status = 500, description = "Error de Stripe", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(skip(state), fields(operation = "get_all_products"))]
 pub async fn get_all_products(State(state): State<Arc<AppState>>) -> impl IntoResponse {
@@ -304,16 +345,27 @@ pub async fn get_all_products(State(state): State<Arc<AppState>>) -> impl IntoRe
     match products {
         Ok(products) => (
             StatusCode::OK,
-            Json(json!({ "success": true, "products": products })),
-        ),
-        Err(_err) => (
-            StatusCode::BAD_REQUEST,
-            Json(json!({"success": false, "error": "Error fetching products" })),
-        ),
+            Json(ResponseAPI::<List<Product>>::success(
+                "Products retrieved successfully".to_string(),
+                products,
+            )),
+        )
+            .into_response(),
+        Err(err) => ApiError::from(err).into_response(),
     }
 }
 
 /// Obtener todos los precios
+#[utoipa::path(
+    get,
+    path = "/payment/price/all",
+    tag = "payments",
+    responses(
+        (status = 200, description = "Lista de precios de Stripe", body = ResponseAPI<serde_json::Value>),
+        (status = 500, description = "Error de Stripe", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(skip(state), fields(operation = "get_all_prices"))]
 pub async fn get_all_prices(State(state): State<Arc<AppState>>) -> impl IntoResponse {
@@ -322,16 +374,31 @@ pub async fn get_all_prices(State(state): State<Arc<AppState>>) -> impl IntoResp
     match prices {
         Ok(prices) => (
             StatusCode::OK,
-            Json(json!({ "success": true, "prices": prices })),
-        ),
-        Err(err) => (
-            StatusCode::BAD_REQUEST,
-            Json(json!({"success": false, "error": format!("Error fetching prices: {}", err) })),
-        ),
+            Json(ResponseAPI::<List<Price>>::success(
+                "Prices retrieved successfully".to_string(),
+                prices,
+            )),
+        )
+            .into_response(),
+        Err(err) => ApiError::from(err).into_response(),
     }
 }
 
 /// Eliminar un producto
+#[utoipa::path(
+    delete,
+    path = "/payment/del/product/{id}",
+    tag = "payments",
+    params(
+        ("id" = String, Path, description = "ID en Stripe"),
+    ),
+    responses(
+        (status = 200, description = "Producto archivado", body = ResponseAPI<serde_json::Value>),
+        (status = 400, description = "ID de producto inválido", body = ResponseAPI<serde_json::Value>),
+        (status = 403, description = "Falta el permiso manage_products", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
     skip(state),
@@ -348,10 +415,7 @@ pub async fn archive_product(
     let product_id: ProductId = match ProductId::from_str(&id) {
         Ok(pid) => pid,
         Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"success": false, "error": "Invalid product ID" })),
-            );
+            return ApiError::BadRequest("Invalid product ID".to_string()).into_response();
         }
     };
 
@@ -359,10 +423,8 @@ pub async fn archive_product(
     let product: Product = match Product::retrieve(&state.stripe_client, &product_id, &[]).await {
         Ok(p) => p,
         Err(err) => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": format!("Product not found: {}", err)})),
-            );
+            tracing::warn!("Product {} not found: {}", product_id, err);
+            return ApiError::NotFound("Product not found".to_string()).into_response();
         }
     };
 
@@ -386,15 +448,10 @@ pub async fn archive_product(
                 };
                 if let Err(e) = Price::update(&state.stripe_client, &price_id, update_price).await {
                     // Maneja el error adecuadamente: quizás loguearlo y continuar o abortar
-                    println!("Failed to archive price {}: {}", price_id, e);
+                    tracing::warn!("Failed to archive price {}: {}", price_id, e);
                 }
             }
-            Err(e) => {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(json!({"error": format!("Failed to remove default price: {}", e)})),
-                );
-            }
+            Err(e) => return ApiError::from(e).into_response(),
         };
     }
 
@@ -404,18 +461,26 @@ pub async fn archive_product(
         ..Default::default()
     };
     match Product::update(&state.stripe_client, &product_id, update_product).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"success": true, "message": "Product archived successfully"})),
-        ),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": format!("Failed to archive product: {}", e)})),
-        ),
+        Ok(_) => (StatusCode::OK, Json(ResponseAPI::<()>::success_no_data())).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
     }
 }
 
 /// Archivar precio
+#[utoipa::path(
+    delete,
+    path = "/payment/del/price/{id}",
+    tag = "payments",
+    params(
+        ("id" = String, Path, description = "ID en Stripe"),
+    ),
+    responses(
+        (status = 200, description = "Precio desactivado", body = ResponseAPI<serde_json::Value>),
+        (status = 400, description = "ID de precio inválido", body = ResponseAPI<serde_json::Value>),
+        (status = 403, description = "Falta el permiso manage_products", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
     skip(state),
@@ -431,24 +496,29 @@ pub async fn delete_price(
     let price_id: PriceId = match PriceId::from_str(&id) {
         Ok(pid) => pid,
         Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"success": false, "error": "Invalid price ID" })),
-            );
+            return ApiError::BadRequest("Invalid price ID".to_string()).into_response();
         }
     };
     let mut params: UpdatePrice<'_> = UpdatePrice::new();
     params.active = Some(false);
     match Price::update(&state.stripe_client, &price_id, params).await {
-        Ok(_) => (StatusCode::OK, Json(json!({ "success": true }))),
-        Err(err) => (
-            StatusCode::BAD_REQUEST,
-            Json(json!({"success": false, "error": format!("Error archiving price: {}", err) })),
-        ),
+        Ok(_) => (StatusCode::OK, Json(ResponseAPI::<()>::success_no_data())).into_response(),
+        Err(err) => ApiError::from(err).into_response(),
     }
 }
 
 /// Recibir la relación entre cal y stripe
+#[utoipa::path(
+    post,
+    path = "/payment/cal/connection",
+    tag = "payments",
+    request_body = RelationalCalStripe,
+    responses(
+        (status = 200, description = "Relación guardada", body = ResponseAPI<HashMap<String, String>>),
+        (status = 500, description = "Error en la base de datos", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
     skip(state),
@@ -460,64 +530,45 @@ pub async fn delete_price(
 )]
 pub async fn archive_cal_connection(
     State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
     Json(payload): Json<RelationalCalStripe>,
 ) -> impl IntoResponse {
-    let url_firebase_db: String = format!(
-        "{}/relation_cal_stripe/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, payload.cal_id, id_token
-    );
-
     tracing::info!(
         "💾 Saving Cal-Stripe relation: {} -> {}",
         payload.cal_id,
         payload.stripe_id
     );
 
+    let relation: StripeRelation = StripeRelation {
+        stripe_id: payload.stripe_id.clone(),
+    };
+
     match state
-        .firebase_options
-        .firebase_client
-        .put(&url_firebase_db)
-        .json(&json!({
-            "stripe_id": payload.stripe_id
-        }))
-        .send()
+        .repositories
+        .cal_stripe
+        .put(&payload.cal_id, &relation)
         .await
     {
-        Ok(response) => {
-            if response.status().is_success() {
-                tracing::info!("Relation saved successfully");
-                let mut data = HashMap::new();
-                data.insert("cal_id".to_string(), payload.cal_id.clone());
-                data.insert("stripe_id".to_string(), payload.stripe_id.clone());
-                (
-                    StatusCode::OK,
-                    Json(ResponseAPI::<HashMap<String, String>>::success(
-                        "Relation saved successfully".to_string(),
-                        data,
-                    )),
-                )
-                    .into_response()
-            } else {
-                let error_text: String = response.text().await.unwrap_or_default();
-                tracing::error!("Firebase error: {}", error_text);
-
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ResponseAPI::<()>::error(
-                        "Failed to save relation".to_string(),
-                    )),
-                )
-                    .into_response()
-            }
+        Ok(_) => {
+            tracing::info!("Relation saved successfully");
+            let mut data = HashMap::new();
+            data.insert("cal_id".to_string(), payload.cal_id.clone());
+            data.insert("stripe_id".to_string(), payload.stripe_id.clone());
+            (
+                StatusCode::OK,
+                Json(ResponseAPI::<HashMap<String, String>>::success(
+                    "Relation saved successfully".to_string(),
+                    data,
+                )),
+            )
+                .into_response()
         }
         Err(e) => {
-            tracing::error!("Request failed: {}", e);
+            tracing::error!("Database error: {}", e);
 
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ResponseAPI::<()>::error(
-                    "Failed to send request".to_string(),
+                    "Failed to save relation".to_string(),
                 )),
             )
                 .into_response()
@@ -526,56 +577,35 @@ pub async fn archive_cal_connection(
 }
 
 /// Obtener todas las reservas pagadas vinculadas a Cal.com
+#[utoipa::path(
+    get,
+    path = "/payment/cal/connection/all",
+    tag = "payments",
+    responses(
+        (status = 200, description = "Relaciones indexadas por ID de Cal.com", body = ResponseAPI<HashMap<String, StripeRelation>>),
+        (status = 500, description = "Error en la base de datos", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(skip(state), fields(operation = "get_all_paid_reservations"))]
-pub async fn get_all_paid_reservations(
-    State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
-) -> impl IntoResponse {
-    let url_firebase_db: String = format!(
-        "{}/relation_cal_stripe.json?auth={}",
-        state.firebase_options.firebase_database_url, id_token
-    );
-
-    match state
-        .firebase_options
-        .firebase_client
-        .get(&url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(response) => {
-            if response.status().is_success() {
-                let data: HashMap<String, StripeRelation> =
-                    response.json().await.unwrap_or_default();
-                (
-                    StatusCode::OK,
-                    Json(ResponseAPI::success(
-                        "Relation retrieved successfully".to_string(),
-                        data,
-                    )),
-                )
-                    .into_response()
-            } else {
-                let error_text: String = response.text().await.unwrap_or_default();
-                tracing::error!("Firebase error: {}", error_text);
-
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ResponseAPI::<()>::error(
-                        "Failed to save relation".to_string(),
-                    )),
-                )
-                    .into_response()
-            }
-        }
+pub async fn get_all_paid_reservations(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.repositories.cal_stripe.get_all().await {
+        Ok(data) => (
+            StatusCode::OK,
+            Json(ResponseAPI::<HashMap<String, StripeRelation>>::success(
+                "Relation retrieved successfully".to_string(),
+                data,
+            )),
+        )
+            .into_response(),
         Err(e) => {
-            tracing::error!("Request failed: {}", e);
+            tracing::error!("Database error: {}", e);
 
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ResponseAPI::<()>::error(
-                    "Failed to send request".to_string(),
+                    "Failed to retrieve relations".to_string(),
                 )),
             )
                 .into_response()
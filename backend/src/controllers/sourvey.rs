@@ -1,10 +1,7 @@
 use {
-    crate::{
-        models::{response::ResponseAPI, sourvey::Survey, state::AppState},
-        services::firebase::handle_firebase_response,
-    },
+    crate::models::{error::ApiError, response::ResponseAPI, sourvey::Survey, state::AppState},
     axum::{
-        Extension, Json, debug_handler,
+        Json, debug_handler,
         extract::{Path, State},
         http::StatusCode,
         response::IntoResponse,
@@ -16,11 +13,21 @@ use {
 };
 
 // Crear encuesta
+#[utoipa::path(
+    post,
+    path = "/surveys",
+    tag = "surveys",
+    request_body = Survey,
+    responses(
+        (status = 201, description = "Encuesta guardada", body = ResponseAPI<Survey>),
+        (status = 500, description = "Error en la base de datos", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
-#[instrument(skip(state, id_token, survey), fields(operation = "create_survey"))]
+#[instrument(skip(state, survey), fields(operation = "create_survey"))]
 pub async fn create_survey(
     State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
     Json(mut survey): Json<Survey>,
 ) -> impl IntoResponse {
     // Si no llega id, generamos uno para mantener consistencia en el modelo
@@ -31,82 +38,43 @@ pub async fn create_survey(
     // Backend is the source of truth for submission timestamp.
     survey.submitted_at = Some(Utc::now().to_rfc3339());
 
-    let url_firebase_db: String = format!(
-        "{}/surveys/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, survey.id, id_token
-    );
-
-    match state
-        .firebase_options
-        .firebase_client
-        .put(&url_firebase_db)
-        .json(&survey)
-        .send()
-        .await
-    {
-        Ok(response) => match handle_firebase_response::<Survey>(response).await {
-            Ok(created) => (
-                StatusCode::CREATED,
-                Json(ResponseAPI::<Survey>::success(
-                    "Survey created successfully".to_string(),
-                    created,
-                )),
-            )
-                .into_response(),
-            Err((status, error)) => (status, Json(ResponseAPI::<()>::error(error))).into_response(),
-        },
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error(
-                "Error connecting to Firebase".to_string(),
+    match state.repositories.surveys.put(&survey).await {
+        Ok(created) => (
+            StatusCode::CREATED,
+            Json(ResponseAPI::<Survey>::success(
+                "Survey created successfully".to_string(),
+                created,
             )),
         )
             .into_response(),
+        Err(err) => ApiError::from(err).into_response(),
     }
 }
 
 // Obtener resultados de encuestas
+#[utoipa::path(
+    get,
+    path = "/surveys/{survey_id}/results",
+    tag = "surveys",
+    params(
+        ("survey_id" = String, Path, description = "ID de la encuesta"),
+    ),
+    responses(
+        (status = 200, description = "Respuestas de la encuesta", body = ResponseAPI<Vec<Survey>>),
+        (status = 500, description = "Error en la base de datos", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
-#[instrument(skip(state, id_token), fields(operation = "get_survey_results", survey_id = %survey_id))]
+#[instrument(skip(state), fields(operation = "get_survey_results", survey_id = %survey_id))]
 pub async fn get_survey_results(
     Path(survey_id): Path<String>,
     State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
 ) -> impl IntoResponse {
-    let url_firebase_db: String = format!(
-        "{}/surveys.json?auth={}",
-        state.firebase_options.firebase_database_url, id_token
-    );
-
-    let surveys_map: HashMap<String, Survey> = match state
-        .firebase_options
-        .firebase_client
-        .get(&url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(response) if response.status().is_success() => match response.text().await {
-            Ok(response_text) => {
-                if response_text.trim().is_empty() || response_text.trim() == "null" {
-                    HashMap::new()
-                } else {
-                    match serde_json::from_str::<HashMap<String, Survey>>(&response_text) {
-                        Ok(value) => value,
-                        Err(_) => {
-                            return (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(ResponseAPI::<()>::error(
-                                    "Error parsing surveys data".to_string(),
-                                )),
-                            )
-                                .into_response();
-                        }
-                    }
-                }
-            }
-            Err(_) => HashMap::new(),
-        },
-        Ok(_) => {
+    let surveys_map: HashMap<String, Survey> = match state.repositories.surveys.get_all().await {
+        Ok(surveys) => surveys,
+        Err(err) => {
+            tracing::error!("Error retrieving surveys: {}", err);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ResponseAPI::<()>::error(
@@ -115,15 +83,6 @@ pub async fn get_survey_results(
             )
                 .into_response();
         }
-        Err(_) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ResponseAPI::<()>::error(
-                    "Error connecting to Firebase".to_string(),
-                )),
-            )
-                .into_response();
-        }
     };
 
     let data: Vec<Survey> = if survey_id == "latest" {
@@ -146,46 +105,23 @@ pub async fn get_survey_results(
 }
 
 // Obtener todos los resultados de encuestas
+#[utoipa::path(
+    get,
+    path = "/surveys/results",
+    tag = "surveys",
+    responses(
+        (status = 200, description = "Todas las respuestas", body = ResponseAPI<Vec<Survey>>),
+        (status = 403, description = "Falta el permiso read_surveys", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
-#[instrument(skip(state, id_token), fields(operation = "get_all_survey_results"))]
-pub async fn get_all_survey_results(
-    State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
-) -> impl IntoResponse {
-    let url_firebase_db: String = format!(
-        "{}/surveys.json?auth={}",
-        state.firebase_options.firebase_database_url, id_token
-    );
-
-    let surveys_map: HashMap<String, Survey> = match state
-        .firebase_options
-        .firebase_client
-        .get(&url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(response) if response.status().is_success() => match response.text().await {
-            Ok(response_text) => {
-                if response_text.trim().is_empty() || response_text.trim() == "null" {
-                    HashMap::new()
-                } else {
-                    match serde_json::from_str::<HashMap<String, Survey>>(&response_text) {
-                        Ok(value) => value,
-                        Err(_) => {
-                            return (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(ResponseAPI::<()>::error(
-                                    "Error parsing surveys data".to_string(),
-                                )),
-                            )
-                                .into_response();
-                        }
-                    }
-                }
-            }
-            Err(_) => HashMap::new(),
-        },
-        Ok(_) => {
+#[instrument(skip(state), fields(operation = "get_all_survey_results"))]
+pub async fn get_all_survey_results(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let surveys_map: HashMap<String, Survey> = match state.repositories.surveys.get_all().await {
+        Ok(surveys) => surveys,
+        Err(err) => {
+            tracing::error!("Error retrieving surveys: {}", err);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ResponseAPI::<()>::error(
@@ -194,15 +130,6 @@ pub async fn get_all_survey_results(
             )
                 .into_response();
         }
-        Err(_) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ResponseAPI::<()>::error(
-                    "Error connecting to Firebase".to_string(),
-                )),
-            )
-                .into_response();
-        }
     };
 
     (
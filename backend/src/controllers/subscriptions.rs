@@ -0,0 +1,210 @@
+use {
+    crate::{
+        models::{
+            error::ApiError,
+            firebase::UserAuthentication,
+            response::ResponseAPI,
+            state::AppState,
+            subscription::{Entitlement, SubscriptionTier},
+            user::UserDB,
+        },
+        services::subscriptions::{entitlement, user_tier, verify_recurring_price},
+        utils::validations::ValidatedJson,
+    },
+    axum::{
+        Extension, Json,
+        body::Body,
+        debug_handler,
+        extract::{Path, State},
+        http::{Response, StatusCode},
+        response::IntoResponse,
+    },
+    chrono::Utc,
+    std::{collections::HashMap, sync::Arc},
+    tracing::instrument,
+};
+
+// Obtener los planes de suscripción
+#[utoipa::path(
+    get,
+    path = "/subscriptions/tiers",
+    tag = "subscriptions",
+    responses(
+        (status = 200, description = "Planes indexados por id", body = ResponseAPI<HashMap<String, SubscriptionTier>>),
+    )
+)]
+#[debug_handler]
+#[instrument(skip(state), fields(operation = "get_subscription_tiers"))]
+pub async fn get_subscription_tiers(
+    State(state): State<Arc<AppState>>,
+) -> Result<Response<Body>, ApiError> {
+    let tiers: HashMap<String, SubscriptionTier> =
+        state.repositories.subscription_tiers.get_all().await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<HashMap<String, SubscriptionTier>>::success(
+            "Subscription tiers retrieved successfully".to_string(),
+            tiers,
+        )),
+    )
+        .into_response())
+}
+
+// Crear o reemplazar un plan de suscripción
+#[utoipa::path(
+    put,
+    path = "/subscriptions/tiers/{id}",
+    tag = "subscriptions",
+    params(
+        ("id" = String, Path, description = "Id del plan (minúsculas, dígitos, `-` y `_`); es el valor de `subscription_tier` del usuario"),
+    ),
+    request_body = SubscriptionTier,
+    responses(
+        (status = 200, description = "Plan guardado", body = ResponseAPI<SubscriptionTier>),
+        (status = 400, description = "Plan inválido o el precio de Stripe no existe o no es recurrente", body = ResponseAPI<serde_json::Value>),
+        (status = 403, description = "Falta el permiso manage_products", body = ResponseAPI<serde_json::Value>),
+        (status = 503, description = "Stripe no responde", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(skip(state, tier), fields(operation = "put_subscription_tier", tier_id = %id))]
+pub async fn put_subscription_tier(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    ValidatedJson(tier): ValidatedJson<SubscriptionTier>,
+) -> Result<Response<Body>, ApiError> {
+    let valid_id: bool = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid_id {
+        return Err(ApiError::BadRequest(
+            "Tier id may only contain lowercase letters, digits, '-' and '_'".to_string(),
+        ));
+    }
+    verify_recurring_price(&state, &tier.stripe_price_id).await?;
+
+    state
+        .repositories
+        .subscription_tiers
+        .put(&id, &tier)
+        .await?;
+    tracing::info!("Subscription tier {} saved", id);
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<SubscriptionTier>::success(
+            "Subscription tier saved successfully".to_string(),
+            tier,
+        )),
+    )
+        .into_response())
+}
+
+// Eliminar un plan de suscripción
+#[utoipa::path(
+    delete,
+    path = "/subscriptions/tiers/{id}",
+    tag = "subscriptions",
+    params(
+        ("id" = String, Path, description = "Id del plan"),
+    ),
+    responses(
+        (status = 200, description = "Plan eliminado", body = ResponseAPI<serde_json::Value>),
+        (status = 403, description = "Falta el permiso manage_products", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "Plan no encontrado", body = ResponseAPI<serde_json::Value>),
+        (status = 409, description = "Hay usuarios con el plan asignado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(skip(state), fields(operation = "delete_subscription_tier", tier_id = %id))]
+pub async fn delete_subscription_tier(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response<Body>, ApiError> {
+    if state
+        .repositories
+        .subscription_tiers
+        .get(&id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::NotFound(format!(
+            "Subscription tier {} not found",
+            id
+        )));
+    }
+
+    // Un usuario con un plan sin definición no podría reservar
+    let users: HashMap<String, UserDB> = state.repositories.users.get_all().await?;
+    let subscribers: usize = users
+        .values()
+        .filter(|user| user.subscription_tier.as_deref() == Some(id.as_str()))
+        .count();
+    if subscribers > 0 {
+        return Err(ApiError::Conflict(format!(
+            "{} users are subscribed to {}",
+            subscribers, id
+        )));
+    }
+
+    state.repositories.subscription_tiers.delete(&id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<()>::success_message(
+            "Subscription tier deleted successfully".to_string(),
+        )),
+    )
+        .into_response())
+}
+
+// Clases disponibles del plan del usuario en el mes actual
+#[utoipa::path(
+    get,
+    path = "/subscriptions/me",
+    tag = "subscriptions",
+    responses(
+        (status = 200, description = "Clases usadas y restantes del mes natural (UTC)", body = ResponseAPI<Entitlement>),
+        (status = 404, description = "El usuario no tiene plan", body = ResponseAPI<serde_json::Value>),
+        (status = 503, description = "Cal.com no responde", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims),
+    fields(
+        user_id = %user_claims.sub,
+        operation = "get_my_entitlement"
+    )
+)]
+pub async fn get_my_entitlement(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response<Body>, ApiError> {
+    let user: UserDB = state
+        .repositories
+        .users
+        .get(&user_claims.sub)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+    let Some((tier_id, tier)) = user_tier(&state, &user).await? else {
+        return Err(ApiError::NotFound("No subscription".to_string()));
+    };
+
+    let entitlement: Entitlement =
+        entitlement(&state, &tier_id, &tier, &user.email, Utc::now()).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<Entitlement>::success(
+            "Entitlement retrieved successfully".to_string(),
+            entitlement,
+        )),
+    )
+        .into_response())
+}
@@ -1,10 +1,7 @@
 use {
-    crate::{
-        models::{response::ResponseAPI, state::AppState, teacher::Teacher},
-        services::firebase::handle_firebase_response,
-    },
+    crate::models::{error::ApiError, response::ResponseAPI, state::AppState, teacher::Teacher},
     axum::{
-        Extension, Json, debug_handler,
+        Json, debug_handler,
         extract::{Path, State},
         http::StatusCode,
         response::IntoResponse,
@@ -14,29 +11,32 @@ use {
 };
 
 // Obtener teacher por ID
+#[utoipa::path(
+    get,
+    path = "/teachers/{id}",
+    tag = "teachers",
+    params(
+        ("id" = String, Path, description = "ID del profesor"),
+    ),
+    responses(
+        (status = 200, description = "Profesor encontrado", body = ResponseAPI<Teacher>),
+        (status = 404, description = "Profesor no encontrado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
-#[instrument(skip(state, id_token))]
+#[instrument(skip(state))]
 pub async fn get_teacher(
     State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
-    // Lógica para obtener la información del profesor por su ID, creamos la url a el recurso y nos autentificamos.
-    let url_firebase_db: String = format!(
-        "{}/teacher_profiles/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, id, id_token
-    );
-
-    // Realizamos la petición a Firebase Realtime Database
-    let teacher: Option<Teacher> = match state
-        .firebase_options
-        .firebase_client
-        .get(url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(response) => (handle_firebase_response::<Teacher>(response).await).ok(),
-        Err(_) => None,
+    // Lógica para obtener la información del profesor por su ID
+    let teacher: Option<Teacher> = match state.repositories.teachers.get(&id).await {
+        Ok(teacher) => teacher,
+        Err(err) => {
+            tracing::error!("Error fetching teacher {}: {}", id, err);
+            None
+        }
     };
 
     match teacher {
@@ -56,146 +56,106 @@ pub async fn get_teacher(
 }
 
 // Crear nuevo teacher
+#[utoipa::path(
+    post,
+    path = "/teachers/add",
+    tag = "teachers",
+    request_body = Teacher,
+    responses(
+        (status = 201, description = "Profesor creado", body = ResponseAPI<HashMap<String, String>>),
+        (status = 403, description = "Falta el permiso manage_teachers", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
-#[instrument(skip(state, id_token))]
+#[instrument(skip(state))]
 pub async fn create_teacher(
     State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
     Json(teacher): Json<Teacher>,
 ) -> impl IntoResponse {
-    // URL de para crear usuario en la DB
-    let url_firebase_db: String = format!(
-        "{}/teacher_profiles.json?auth={}",
-        state.firebase_options.firebase_database_url, id_token
-    );
-
-    // POST:: Crear profesor en FB_DATABASE
-    match state
-        .firebase_options
-        .firebase_client
-        .post(&url_firebase_db)
-        .json(&teacher)
-        .send()
-        .await
-    {
-        Ok(response) => match handle_firebase_response::<HashMap<String, String>>(response).await {
-            Ok(parsed_response) => (
-                StatusCode::CREATED,
-                Json(ResponseAPI::success(
-                    "Teacher created successfully".to_string(),
-                    parsed_response,
-                )),
-            )
-                .into_response(),
-            Err((status, error)) => (status, Json(ResponseAPI::<()>::error(error))).into_response(),
-        },
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error("Error saving profile".to_string())),
+    // Crear profesor en la base de datos
+    match state.repositories.teachers.create(&teacher).await {
+        Ok(teacher_id) => (
+            StatusCode::CREATED,
+            Json(ResponseAPI::success(
+                "Teacher created successfully".to_string(),
+                HashMap::from([("name".to_string(), teacher_id)]),
+            )),
         )
             .into_response(),
+        Err(err) => {
+            tracing::error!("Error saving teacher profile: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ResponseAPI::<()>::error("Error saving profile".to_string())),
+            )
+                .into_response()
+        }
     }
 }
 
 // Mustra todos los profesores
+#[utoipa::path(
+    get,
+    path = "/teachers/all",
+    tag = "teachers",
+    responses(
+        (status = 200, description = "Profesores indexados por ID", body = ResponseAPI<HashMap<String, Teacher>>),
+        (status = 500, description = "Error en la base de datos", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 #[debug_handler]
 #[instrument(skip(state))]
 pub async fn get_all_teachers(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    // URL para obtener todos los usuarios de Firebase Realtime Database
-    let url_firebase_db: String = format!(
-        "{}/teacher_profiles.json",
-        state.firebase_options.firebase_database_url
-    );
-
-    // Realizamos la petición a Firebase Realtime Database para obtener todos los usuarios
-    let user_data_db: HashMap<String, Teacher> = match state
-        .firebase_options
-        .firebase_client
-        .get(&url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(response) if response.status().is_success() => match response.text().await {
-            Ok(response_text) => {
-                if response_text.trim().is_empty() || response_text.trim() == "null" {
-                    HashMap::new()
-                } else {
-                    match serde_json::from_str::<HashMap<String, Teacher>>(&response_text) {
-                        Ok(value) => value,
-                        Err(_) => {
-                            return (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(ResponseAPI::<()>::error(
-                                    "Error parsing database users data".to_string(),
-                                )),
-                            )
-                                .into_response();
-                        }
-                    }
-                }
-            }
-            Err(_) => HashMap::new(),
-        },
-        Ok(_) => {
-            return (
+    match state.repositories.teachers.get_all().await {
+        Ok(teachers) => (
+            StatusCode::OK,
+            Json(ResponseAPI::<HashMap<String, Teacher>>::success(
+                "Users retrieved successfully".to_string(),
+                teachers,
+            )),
+        )
+            .into_response(),
+        Err(err) => {
+            tracing::error!("Error retrieving teachers: {}", err);
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ResponseAPI::<()>::error(
                     "Error retrieving users from database".to_string(),
                 )),
             )
-                .into_response();
-        }
-        Err(_) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ResponseAPI::<()>::error(
-                    "Error connecting to Firebase".to_string(),
-                )),
-            )
-                .into_response();
+                .into_response()
         }
-    };
-
-    (
-        StatusCode::OK,
-        Json(ResponseAPI::<HashMap<String, Teacher>>::success(
-            "Users retrieved successfully".to_string(),
-            user_data_db,
-        )),
-    )
-        .into_response()
+    }
 }
 
 // Elimina un profesor
+#[utoipa::path(
+    delete,
+    path = "/teachers/del/{id}",
+    tag = "teachers",
+    params(
+        ("id" = String, Path, description = "ID del profesor"),
+    ),
+    responses(
+        (status = 204, description = "Profesor eliminado"),
+        (status = 403, description = "Falta el permiso manage_teachers", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
-#[instrument(skip(state, id_token))]
+#[instrument(skip(state))]
 pub async fn delete_teacher(
     State(state): State<Arc<AppState>>,
-    Extension(id_token): Extension<String>,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
     // Lógica para eliminar un profesor por su ID
-    let url_firebase_db: String = format!(
-        "{}/teacher_profiles/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, id, id_token
-    );
-
-    match state
-        .firebase_options
-        .firebase_client
-        .delete(&url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(response) => match handle_firebase_response::<()>(response).await {
-            Ok(_) => (StatusCode::NO_CONTENT, Json(ResponseAPI::success_no_data())),
-            Err((status, error)) => (status, Json(ResponseAPI::<()>::error(error.to_string()))),
-        },
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error(
-                "Error connecting to Firebase".to_string(),
-            )),
-        ),
+    match state.repositories.teachers.delete(&id).await {
+        Ok(_) => (
+            StatusCode::NO_CONTENT,
+            Json(ResponseAPI::<()>::success_no_data()),
+        )
+            .into_response(),
+        Err(err) => ApiError::from(err).into_response(),
     }
 }
@@ -1,30 +1,70 @@
 use {
     crate::{
         models::{
+            error::{ApiError, RepositoryError},
             firebase::{
-                FirebaseAdminLookupResponse, FirebaseAuthResponse, RefreshToken, UserAuth,
+                AccountUpdateResponse, FirebaseAdminLookupResponse, FirebaseAuthResponse,
+                FirebaseUserInfo, GoogleTokenInfo, OobCodeResult, OobRequestType, RefreshToken,
+                SecureTokenResponse, SendOobCodeRequest, SendOobCodeResponse, UserAuth,
                 UserAuthentication, UserMerged,
             },
+            projection::{UserAdminView, UserOwnerView},
             response::ResponseAPI,
+            session::{RevokedSessions, SessionEvent, SessionView},
+            sourvey::Survey,
             state::AppState,
-            user::{Provider, Role, UserDB, UserRequest},
+            user::{
+                AccountDeletionReport, DataExportFormat, DataExportQuery, ExportFormat,
+                LearnerProfile, LearnerProfilePrefillQuery, LinkProviderRequest,
+                LinkedProvidersResponse, PasswordResetConfirm, PasswordResetRequest, Provider,
+                Role, UpdateAccountStatusRequest, UpdatePermissionsRequest, UpdateRoleRequest,
+                UpdateSubscriptionTierRequest, UserDB, UserDataExport, UserDirectoryQuery,
+                UserRequest, VerifyEmailConfirm,
+            },
+        },
+        services::{
+            account_deletion::{delete_account_cascade, is_recent_login},
+            data_export::{collect_user_data, export_to_zip},
+            email::ActionEmail,
+            firebase::{
+                confirm_email_verification, firebase_api_error, handle_firebase_response,
+                link_google_provider, link_password_provider, list_accounts, lookup_account,
+                reset_password, send_oob_code, set_account_disabled,
+                unlink_provider as firebase_unlink_provider, verify_google_id_token,
+            },
+            sessions::{list_sessions, revoke_all_sessions, track_session},
+            users::users_to_csv,
         },
-        services::firebase::handle_firebase_response,
+        utils::validations::{ValidatedJson, ValidatedQuery},
     },
     axum::{
         Extension, Json,
         body::Body,
         debug_handler,
-        extract::{State, rejection::JsonRejection},
-        http::{Response, StatusCode},
+        extract::{Path, Query, State, rejection::JsonRejection},
+        http::{HeaderMap, Response, StatusCode, header},
         response::IntoResponse,
     },
     serde_json::json,
-    std::{collections::HashMap, sync::Arc},
+    std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+    },
     tracing::instrument,
 };
 
 // Creación del usuario conn firebase
+#[utoipa::path(
+    post,
+    path = "/users/register",
+    tag = "users",
+    request_body = UserRequest,
+    responses(
+        (status = 201, description = "Usuario creado; devuelve el idToken", body = ResponseAPI<String>),
+        (status = 400, description = "Payload inválido", body = ResponseAPI<serde_json::Value>),
+        (status = 429, description = "Demasiadas peticiones", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 #[debug_handler]
 #[instrument(
     skip(state, payload),
@@ -76,8 +116,8 @@ pub async fn register_user(
 
     // Obtenemos la URL de registro de usuario con Firebase
     let url_register_auth: String = format!(
-        "https://identitytoolkit.googleapis.com/v1/accounts:signUp?key={}",
-        state.firebase_options.firebase_api_key
+        "{}/accounts:signUp?key={}",
+        state.firebase_options.identity_toolkit_url, state.firebase_options.firebase_api_key
     );
 
     // Creamos el usuario que se va a crear en Firebase Authentication
@@ -124,7 +164,8 @@ pub async fn register_user(
             Some(token) => {
                 // Verify the token by looking up the user
                 let url_firebase_admin = format!(
-                    "https://identitytoolkit.googleapis.com/v1/accounts:lookup?key={}",
+                    "{}/accounts:lookup?key={}",
+                    state.firebase_options.identity_toolkit_url,
                     state.firebase_options.firebase_api_key
                 );
 
@@ -179,6 +220,23 @@ pub async fn register_user(
         }
     };
 
+    // Si la cuenta ya tenía perfil (p. ej. se registró con email y ahora entra con Google,
+    // que Firebase vincula a la misma cuenta) se conserva en lugar de sobrescribirlo
+    if user.provider == Provider::Google
+        && get_user_data_db(&auth_response.local_id, &state)
+            .await
+            .is_some()
+    {
+        return (
+            StatusCode::OK,
+            Json(ResponseAPI::<String>::success(
+                "User already registered".to_string(),
+                auth_response.id_token,
+            )),
+        )
+            .into_response();
+    }
+
     create_user_in_db(
         &state,
         &auth_response.id_token,
@@ -197,12 +255,6 @@ pub async fn create_user_in_db(
     user: &UserRequest,
     email: &str,
 ) -> impl IntoResponse {
-    // URL de para crear usuario en la DB
-    let url_firebase_db: String = format!(
-        "{}/user_profiles/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, user_id, id_token
-    );
-
     // Creamos el usuario que se va a crear en la DB
     let user_db: UserDB = UserDB {
         email: email.to_string(),
@@ -213,18 +265,12 @@ pub async fn create_user_in_db(
         }),
         subscription_tier: user.subscription_tier.clone(),
         permissions: user.permissions.clone(),
+        learner_profile: None,
     };
 
-    // POST:: crear usuario
-    match state
-        .firebase_options
-        .firebase_client
-        .put(&url_firebase_db)
-        .json(&user_db)
-        .send()
-        .await
-    {
-        Ok(response) if response.status().is_success() => (
+    // PUT:: crear usuario
+    match state.repositories.users.put(user_id, &user_db).await {
+        Ok(_) => (
             StatusCode::CREATED,
             Json(ResponseAPI::<String>::success(
                 "User created successfully".to_string(),
@@ -234,7 +280,7 @@ pub async fn create_user_in_db(
             .into_response(),
         // Si algo ha fallado avisamos de que el usuario no se pudo crear en la base de datos pero
         // el usuario fue creado en Firebase Auth
-        Ok(_) => (
+        Err(RepositoryError::Status { .. }) => (
             StatusCode::PARTIAL_CONTENT,
             Json(ResponseAPI::<()>::error(
                 "User created in Firebase Auth but error saving profile in database".to_string(),
@@ -250,6 +296,17 @@ pub async fn create_user_in_db(
 }
 
 // Login de usuario en Firebase
+#[utoipa::path(
+    post,
+    path = "/users/login",
+    tag = "users",
+    request_body = UserRequest,
+    responses(
+        (status = 200, description = "Sesión iniciada; devuelve el idToken", body = ResponseAPI<String>),
+        (status = 401, description = "Credenciales inválidas", body = ResponseAPI<serde_json::Value>),
+        (status = 429, description = "Demasiadas peticiones", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 #[debug_handler]
 #[instrument(
     skip(state, user_request),
@@ -259,14 +316,15 @@ pub async fn create_user_in_db(
 )]
 pub async fn login_user(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(user_request): Json<UserRequest>,
 ) -> impl IntoResponse {
     // Handle different providers
     let auth_response: FirebaseAuthResponse = if user_request.provider == Provider::Email {
         // Construir la URL para la autenticación con email/password
         let url_login_firebase: String = format!(
-            "https://identitytoolkit.googleapis.com/v1/accounts:signInWithPassword?key={}",
-            state.firebase_options.firebase_api_key
+            "{}/accounts:signInWithPassword?key={}",
+            state.firebase_options.identity_toolkit_url, state.firebase_options.firebase_api_key
         );
 
         // Crear el cuerpo de la solicitud para el login de usuario
@@ -310,7 +368,8 @@ pub async fn login_user(
         match &user_request.id_token {
             Some(token) => {
                 let url_firebase_admin = format!(
-                    "https://identitytoolkit.googleapis.com/v1/accounts:lookup?key={}",
+                    "{}/accounts:lookup?key={}",
+                    state.firebase_options.identity_toolkit_url,
                     state.firebase_options.firebase_api_key
                 );
 
@@ -364,28 +423,7 @@ pub async fn login_user(
 
     if user_request.provider == Provider::Google {
         // Verifiamos si existia en la base de datos si existe no hacemos nada y si no existe lo creamos
-        match get_user_data_db(
-            &UserAuthentication {
-                sub: auth_response.local_id.clone(),
-                iss: String::new(),
-                aud: String::new(),
-                iat: 0,
-                exp: 0,
-                email: Some(auth_response.email.clone()),
-                email_verified: None,
-                name: None,
-                picture: None,
-                auth_time: 0,
-                user_id: auth_response.local_id.clone(),
-                firebase: None,
-                phone_number: None,
-                provider_id: None,
-            },
-            &auth_response.id_token,
-            &state,
-        )
-        .await
-        {
+        match get_user_data_db(&auth_response.local_id, &state).await {
             Some(_) => {}
             None => {
                 // Devolbemos un error avisando de que el usuario deveria registrarse
@@ -400,6 +438,14 @@ pub async fn login_user(
         }
     }
 
+    track_session(
+        &state,
+        &auth_response.id_token,
+        user_agent(&headers),
+        SessionEvent::Login,
+    )
+    .await;
+
     (
         StatusCode::OK,
         Json(ResponseAPI::<String>::success(
@@ -410,7 +456,26 @@ pub async fn login_user(
         .into_response()
 }
 
+/// User-Agent de la petición, para que el usuario reconozca sus sesiones
+fn user_agent(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+}
+
 // Actualización de usuario
+#[utoipa::path(
+    put,
+    path = "/users/update/me",
+    tag = "users",
+    request_body = UserRequest,
+    responses(
+        (status = 200, description = "Usuario actualizado", body = ResponseAPI<UserDB>),
+        (status = 403, description = "Intento de cambiar el rol, los permisos o el tier", body = ResponseAPI<serde_json::Value>),
+        (status = 500, description = "Error al actualizar", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
     skip(state, user_claims, id_token, user_request),
@@ -426,10 +491,25 @@ pub async fn update_user(
     State(state): State<Arc<AppState>>,
     Json(user_request): Json<UserRequest>,
 ) -> impl IntoResponse {
+    // El rol, los permisos y el tier solo los cambia un administrador (`/users/admin/:uid/*`)
+    if user_request.role.is_some()
+        || user_request.permissions.is_some()
+        || user_request.subscription_tier.is_some()
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ResponseAPI::<()>::error(
+                "You do not have permission to change your role, permissions or subscription tier"
+                    .to_string(),
+            )),
+        )
+            .into_response();
+    }
+
     // URL para la actualización de usuario en Firebase
     let url_firebase_auth_update: String = format!(
-        "https://identitytoolkit.googleapis.com/v1/accounts:update?key={}",
-        state.firebase_options.firebase_api_key
+        "{}/accounts:update?key={}",
+        state.firebase_options.identity_toolkit_url, state.firebase_options.firebase_api_key
     );
 
     // Cuerpo de la solicitud para la actualización de usuario
@@ -463,14 +543,8 @@ pub async fn update_user(
             .into_response(),
     };
 
-    // URL para la base de datos de Firebase
-    let url_firebase_db: String = format!(
-        "{}/user_profiles/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, user_claims.sub, id_token
-    );
-
     // Obtener los datos del usuario actual
-    let actual_user_db: UserDB = match get_user_data_db(&user_claims, &id_token, &state).await {
+    let actual_user_db: UserDB = match get_user_data_db(&user_claims.sub, &state).await {
         Some(user_db) => user_db,
         None => {
             return (
@@ -487,256 +561,150 @@ pub async fn update_user(
     let user_db: UserDB = UserDB {
         email: user_request.email, // El email es obligatorio darlo en la request
         first_free_class: user_request.first_free_class, // Mantenemos el valor que venga en la request
-        // Preguntamos si en la request se ha dado un nuevo role, si no lo dejamos como estaba
-        role: user_request
-            .role
-            .map(|r| r.to_string())
-            .or_else(|| actual_user_db.role.map(|r| r.to_string())),
-        // Preguntamos si en la request se ha proporcionado un nuevo subscription_tier, si no lo dejamos como estaba
-        subscription_tier: user_request
-            .subscription_tier
-            .or(actual_user_db.subscription_tier),
-        // Preguntamos si en la request se ha dado un nuevo permissions, si no lo dejamos como estaba
-        permissions: match (actual_user_db.permissions, user_request.permissions) {
-            (Some(mut existing), Some(new)) => {
-                existing.extend(new);
-                Some(existing)
-            }
-            (None, Some(new)) => Some(new),
-            (Some(existing), None) => Some(existing),
-            (None, None) => None,
-        },
+        // El rol, el tier, los permisos y el perfil de aprendizaje se conservan tal y como estaban
+        role: actual_user_db.role,
+        subscription_tier: actual_user_db.subscription_tier,
+        permissions: actual_user_db.permissions,
+        learner_profile: actual_user_db.learner_profile,
     };
 
     // Actualizar en la base de datos
     match state
-        .firebase_options
-        .firebase_client
-        .put(&url_firebase_db)
-        .json(&user_db)
-        .send()
+        .repositories
+        .users
+        .put(&user_claims.sub, &user_db)
         .await
     {
-        Ok(response) => match handle_firebase_response::<UserDB>(response).await {
-            Ok(user) => (
-                StatusCode::OK,
-                Json(ResponseAPI::<UserDB>::success(
-                    "User updated successfully".to_string(),
-                    user,
-                )),
-            )
-                .into_response(),
-            Err((status, error)) => (status, Json(error)).into_response(),
-        },
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error(
-                "Error connecting to Firebase".to_string(),
+        Ok(user) => (
+            StatusCode::OK,
+            Json(ResponseAPI::<UserDB>::success(
+                "User updated successfully".to_string(),
+                user,
             )),
         )
             .into_response(),
+        Err(err) => ApiError::from(err).into_response(),
     }
 }
 
 // Obtener todos los usuarios
+#[utoipa::path(
+    get,
+    path = "/users/all",
+    tag = "users",
+    params(UserDirectoryQuery),
+    responses(
+        (status = 200, description = "Usuarios de Firebase Auth combinados con la DB, filtrados y ordenados (vista de administración)",
+            content(
+                (ResponseAPI<Vec<UserAdminView>> = "application/json"),
+                (String = "text/csv"),
+            )
+        ),
+        (status = 400, description = "Parámetros de consulta inválidos", body = ResponseAPI<serde_json::Value>),
+        (status = 403, description = "Solo administradores", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, user_claims, id_token),
+    skip(state, user_claims),
     fields(
         user_id = %user_claims.sub
     )
 )]
 pub async fn get_all_users(
     Extension(user_claims): Extension<UserAuthentication>,
-    Extension(id_token): Extension<String>,
     State(state): State<Arc<AppState>>,
+    ValidatedQuery(query): ValidatedQuery<UserDirectoryQuery>,
 ) -> impl IntoResponse {
-    // Obtenemos de la base de datos el usuario actual
-    let actual_user_db: UserDB = match get_user_data_db(&user_claims, &id_token, &state).await {
-        Some(user_db) => user_db,
-        None => {
+    // El rol de administrador lo garantiza la capa `RequireRole` del router
+    // Obtenemos todos los perfiles de la base de datos
+    let user_data_db: HashMap<String, UserDB> = match state.repositories.users.get_all().await {
+        Ok(users) => users,
+        Err(err) => {
+            tracing::error!("Error retrieving users from database: {}", err);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ResponseAPI::<()>::error(
-                    "Error getting user data".to_string(),
+                    "Error retrieving users from database".to_string(),
                 )),
             )
                 .into_response();
         }
     };
 
-    // Solo podemos obtener todos los usuarios si es administrador
-    if actual_user_db.role != Some("admin".to_string()) {
-        println!("User is not admin, denying access");
+    // Todas las cuentas de Firebase Auth, con credenciales admin (no las del solicitante)
+    let user_data_auth: FirebaseAdminLookupResponse =
+        match list_accounts(&state.firebase_options).await {
+            Ok(users) => users,
+            Err(err) => {
+                tracing::error!("Error listing Firebase accounts: {:?}", err);
+                return firebase_api_error(err).into_response();
+            }
+        };
+
+    let merged_users: Vec<UserMerged> = query.apply(user_data_auth.merge(user_data_db));
+
+    if query.format == ExportFormat::Csv {
         return (
-            StatusCode::FORBIDDEN,
-            Json(ResponseAPI::<()>::error(
-                "You do not have permission to access this resource".to_string(),
-            )),
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"users.csv\"",
+                ),
+            ],
+            users_to_csv(&merged_users),
         )
             .into_response();
-    } else {
-        println!("User is admin, proceeding...");
     }
 
-    // URL para obtener todos los usuarios de Firebase Realtime Database
-    let url_firebase_db: String = format!(
-        "{}/user_profiles.json?auth={}",
-        state.firebase_options.firebase_database_url, id_token
-    );
-
-    // Realizamos la petición a Firebase Realtime Database para obtener todos los usuarios
-    let user_data_db: HashMap<String, UserDB> = match state
-        .firebase_options
-        .firebase_client
-        .get(&url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.text().await {
-                    Ok(response_text) => {
-                        if response_text.trim().is_empty() || response_text.trim() == "null" {
-                            HashMap::new()
-                        } else {
-                            match serde_json::from_str::<HashMap<String, UserDB>>(&response_text) {
-                                Ok(value) => value,
-                                Err(_) => {
-                                    return (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(ResponseAPI::<()>::error(
-                                            "Error parsing database users data".to_string(),
-                                        )),
-                                    )
-                                        .into_response();
-                                }
-                            }
-                        }
-                    }
-                    Err(_) => HashMap::new(),
-                }
-            } else {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ResponseAPI::<()>::error(
-                        "Error retrieving users from database".to_string(),
-                    )),
-                )
-                    .into_response();
-            }
-        }
-        Err(_) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ResponseAPI::<()>::error(
-                    "Error connecting to Firebase".to_string(),
-                )),
-            )
-                .into_response();
-        }
-    };
-
-    // URL de los datos de Firebase Admin
-    let url_firebase_admin: String = format!(
-        "https://identitytoolkit.googleapis.com/v1/accounts:lookup?key={}",
-        state.firebase_options.firebase_api_key
-    );
-
-    // Realizamos la petición a Firebase Admin para obtener la información del usuario
-    let user_data_auth: FirebaseAdminLookupResponse = match state
-        .firebase_options
-        .firebase_client
-        .post(&url_firebase_admin)
-        .json(&json!({ "idToken": id_token }))
-        .send()
-        .await
-    {
-        Ok(response) => {
-            println!("Firebase Admin response status: {}", response.status());
-            match handle_firebase_response::<FirebaseAdminLookupResponse>(response).await {
-                Ok(users) => {
-                    println!("Successfully retrieved auth data");
-                    users
-                }
-                Err((status, error)) => {
-                    println!(
-                        "Error handling Firebase Admin response: {} - {:?}",
-                        status, error
-                    );
-                    return (status, Json(error)).into_response();
-                }
-            }
-        }
-        Err(e) => {
-            println!("Error connecting to Firebase Admin: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ResponseAPI::<()>::error(
-                    "Error connecting to Firebase".to_string(),
-                )),
-            )
-                .into_response();
-        }
-    };
-
-    let merged_users: Vec<UserMerged> = user_data_auth.merge(user_data_db);
     (
         StatusCode::OK,
-        Json(ResponseAPI::<Vec<UserMerged>>::success(
+        Json(ResponseAPI::<Vec<UserAdminView>>::success(
             "Users retrieved successfully".to_string(),
-            merged_users,
+            merged_users.into_iter().map(UserAdminView::from).collect(),
         )),
     )
         .into_response()
 }
 
-// Obtener datos del usuario según su sesión de Firebase DB
-#[instrument(
-    skip(state, id_token),
-    fields(
-        user_id = %user_claims.sub,
-        operation = "get_user_data_db"
-    )
-)]
-pub async fn get_user_data_db(
-    user_claims: &UserAuthentication,
-    id_token: &str,
-    state: &Arc<AppState>,
-) -> Option<UserDB> {
-    // URL de Firebase Realtime Database para obtener los datos del usuario
-    let url_firebase_db: String = format!(
-        "{}/user_profiles/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, user_claims.sub, id_token
-    );
-
-    // Realizamos la petición a Firebase Realtime Database
-    match state
-        .firebase_options
-        .firebase_client
-        .get(url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(response) => {
-            println!("Firebase DB response status: {}", response.status());
-            (handle_firebase_response::<UserDB>(response).await).ok()
+// Obtener datos del usuario según su uid de Firebase
+#[instrument(skip(state), fields(operation = "get_user_data_db"))]
+pub async fn get_user_data_db(user_id: &str, state: &Arc<AppState>) -> Option<UserDB> {
+    match state.repositories.users.get(user_id).await {
+        Ok(user) => user,
+        Err(err) => {
+            tracing::error!("Error getting user data: {}", err);
+            None
         }
-        Err(_) => None,
     }
 }
 
 // Refrescar el token
+#[utoipa::path(
+    put,
+    path = "/users/refresh_token",
+    tag = "users",
+    request_body = RefreshToken,
+    responses(
+        (status = 200, description = "Nuevo idToken", body = ResponseAPI<String>),
+        (status = 401, description = "Refresh token inválido", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(skip(state, refresh_token), fields(operation = "refresh_token"))]
 pub async fn refresh_token(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(refresh_token): Json<RefreshToken>,
 ) -> impl IntoResponse {
     // URL de Firebase para refrescar el token
     let url_firebase_auth_refresh_token: String = format!(
-        "https://securetoken.googleapis.com/v1/token?key={}",
-        state.firebase_options.firebase_api_key
+        "{}/token?key={}",
+        state.firebase_options.secure_token_url, state.firebase_options.firebase_api_key
     );
 
     // Actualizamos el neuvo token, no hace falta devolber nada
@@ -748,15 +716,24 @@ pub async fn refresh_token(
         .send()
         .await
     {
-        Ok(response) => match handle_firebase_response::<FirebaseAuthResponse>(response).await {
-            Ok(auth_response) => (
-                StatusCode::OK,
-                Json(ResponseAPI::<String>::success(
-                    "Token refreshed successfully".to_string(),
-                    auth_response.id_token,
-                )),
-            )
-                .into_response(),
+        Ok(response) => match handle_firebase_response::<SecureTokenResponse>(response).await {
+            Ok(token_response) => {
+                track_session(
+                    &state,
+                    &token_response.id_token,
+                    user_agent(&headers),
+                    SessionEvent::Refresh,
+                )
+                .await;
+                (
+                    StatusCode::OK,
+                    Json(ResponseAPI::<String>::success(
+                        "Token refreshed successfully".to_string(),
+                        token_response.id_token,
+                    )),
+                )
+                    .into_response()
+            }
             Err(_) => (
                 StatusCode::UNAUTHORIZED,
                 Json(ResponseAPI::<()>::error(
@@ -776,49 +753,73 @@ pub async fn refresh_token(
 }
 
 // Obtener el usuario actualmente autentificado
+#[utoipa::path(
+    get,
+    path = "/users/me",
+    tag = "users",
+    responses(
+        (status = 200, description = "Perfil del usuario combinado con su cuenta de Firebase Auth en la vista del propio usuario (incluye los métodos de acceso vinculados)", body = ResponseAPI<UserOwnerView>),
+        (status = 401, description = "No autenticado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_user_me(
     State(state): State<Arc<AppState>>,
     Extension(user_claims): Extension<UserAuthentication>,
     Extension(id_token): Extension<String>,
-) -> impl IntoResponse {
+) -> Result<Response<Body>, ApiError> {
     // Obtener el usuario actualmente autentificado
-    let user: UserDB = match get_user_data_db(&user_claims, &id_token, &state).await {
-        Some(user) => user,
-        None => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ResponseAPI::<()>::error("User not found".to_string())),
-            )
-                .into_response();
-        }
+    let Some(user) = get_user_data_db(&user_claims.sub, &state).await else {
+        return Err(ApiError::Unauthorized("User not found".to_string()));
     };
+    let auth: FirebaseAdminLookupResponse = lookup_account(&state.firebase_options, &id_token)
+        .await
+        .map_err(firebase_api_error)?;
 
-    (
+    // Mismo merge que el directorio de administración, restringido al perfil propio
+    let Some(merged) = auth
+        .merge(HashMap::from([(user_claims.sub.clone(), user)]))
+        .into_iter()
+        .find(|merged| merged.local_id == user_claims.sub)
+    else {
+        return Err(ApiError::Unauthorized("User not found".to_string()));
+    };
+
+    Ok((
         StatusCode::OK,
-        Json(ResponseAPI::<UserDB>::success(
+        Json(ResponseAPI::<UserOwnerView>::success(
             "User retrieved successfully".to_string(),
-            user,
+            UserOwnerView::from(merged),
         )),
     )
-        .into_response()
+        .into_response())
 }
 
 // Verificar si el usuario actual es admin
+#[utoipa::path(
+    get,
+    path = "/users/admin_check",
+    tag = "users",
+    responses(
+        (status = 200, description = "Indica si el usuario es administrador", body = ResponseAPI<bool>),
+        (status = 500, description = "Error en la base de datos", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, user_claims, id_token),
+    skip(state, user_claims),
     fields(
         user_id = %user_claims.sub,
         operation = "admin_check"
     )
 )]
 pub async fn get_user_admin_check(
-    Extension(id_token): Extension<String>,
     Extension(user_claims): Extension<UserAuthentication>,
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
     // Obtenemos de la base de datos el usuario actual
-    let actual_user_db: UserDB = match get_user_data_db(&user_claims, &id_token, &state).await {
+    let actual_user_db: UserDB = match get_user_data_db(&user_claims.sub, &state).await {
         Some(user_db) => user_db,
         None => {
             return (
@@ -844,189 +845,1071 @@ pub async fn get_user_admin_check(
         .into_response()
 }
 
-// Eliminar el usuario actualmente autentificado
+// Eliminar el usuario actualmente autentificado y, en cascada, todo lo asociado a él
+#[utoipa::path(
+    delete,
+    path = "/users/del/me",
+    tag = "users",
+    responses(
+        (status = 200, description = "Cuenta eliminada con el informe de la cascada", body = ResponseAPI<AccountDeletionReport>),
+        (status = 401, description = "El login no es reciente; hay que volver a iniciar sesión", body = ResponseAPI<serde_json::Value>),
+        (status = 503, description = "Algún paso falló tras los reintentos; la cuenta se conserva y la petición se puede repetir", body = ResponseAPI<AccountDeletionReport>),
+    ),
+    security(("bearer_auth" = []))
+)]
 #[debug_handler]
 #[instrument(
-    skip(state, user_claims, id_token),
+    skip(state, user_claims),
     fields(
         user_id = %user_claims.sub,
         operation = "delete_me"
     )
 )]
 pub async fn delete_me(
-    Extension(id_token): Extension<String>,
     Extension(user_claims): Extension<UserAuthentication>,
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    // URL para eliminar el usuario especificado en Firebase Authentication
-    let url_firebase_auth: String = format!(
-        "https://identitytoolkit.googleapis.com/v1/accounts:delete?key={}",
-        state.firebase_options.firebase_api_key
-    );
-
-    // Obtenemos el usuario de las claims y lo borramos
-    // Si falló la eliminación de Auth, retornar error inmediatamente
-    let _: Response<Body> = match state
-        .firebase_options
-        .firebase_client
-        .post(url_firebase_auth) // Fb utiliza POST para eliminar usuarios
-        .json(&json!({ "idToken": id_token }))
-        .send()
-        .await
-    {
-        Ok(response) => match handle_firebase_response::<FirebaseAuthResponse>(response).await {
-            Ok(_) => (StatusCode::NO_CONTENT).into_response(),
-            Err((status, error)) => (status, Json(error)).into_response(),
-        },
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error(
-                "Error connecting to Firebase".to_string(),
-            )),
-        )
-            .into_response(),
-    };
+) -> Result<Response<Body>, ApiError> {
+    // Se comprueba antes de la cascada para no anonimizar nada de una cuenta que no se borrará
+    if !is_recent_login(user_claims.auth_time, chrono::Utc::now()) {
+        return Err(ApiError::Unauthorized(
+            "Recent login required to delete the account".to_string(),
+        ));
+    }
 
-    // URL de para eliminar usuario en la DB
-    let url_firebase_db: String = format!(
-        "{}/user_profiles/{}.json?auth={}",
-        state.firebase_options.firebase_database_url, user_claims.sub, id_token
-    );
+    // Sin email verificado no se toca nada buscado por email: podría ser de otra persona
+    let report: AccountDeletionReport =
+        delete_account_cascade(&state, &user_claims.sub, user_claims.verified_email()).await;
 
-    // Eliminamos el usuario de Firebase Realtime Database
-    match state
-        .firebase_options
-        .firebase_client
-        .delete(url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(response) if response.status().is_success() => (StatusCode::NO_CONTENT).into_response(),
-        Ok(_) => (
-            StatusCode::BAD_REQUEST,
-            Json(ResponseAPI::<()>::error("Invalid credential".to_string())),
+    if report.account_deleted {
+        Ok((
+            StatusCode::OK,
+            Json(ResponseAPI::success("Account deleted".to_string(), report)),
         )
-            .into_response(),
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseAPI::<()>::error(
-                "Error connecting to Firebase".to_string(),
+            .into_response())
+    } else {
+        Ok((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ResponseAPI::error_with_data(
+                "deletion_incomplete",
+                "Account deletion incomplete, retry the request".to_string(),
+                report,
             )),
         )
-            .into_response(),
+            .into_response())
     }
 }
 
-/// Obtener el user por el email
-/// Obtener el usuario por email desde Firebase Realtime Database
-/// Retorna Some(UserDB) si lo encuentra, None si no existe o hay error
-pub async fn get_user_by_email_db(state: &AppState, email: &str) -> Option<UserDB> {
-    // URL de Firebase DB con query por email y autenticación admin
-    let url_firebase_db = format!(
-        "{}/user_profiles.json?orderBy=\"email\"&equalTo=\"{}\",&auth={}",
-        state.firebase_options.firebase_database_url,
-        email,
-        state.firebase_options.firebase_database_secret
+// Exportar todos los datos del usuario autenticado (derecho de acceso del RGPD)
+#[utoipa::path(
+    get,
+    path = "/users/me/export",
+    tag = "users",
+    params(DataExportQuery),
+    responses(
+        (status = 200, description = "Fichero con todos los datos del usuario; `unavailable_sources` indica lo que no se pudo consultar",
+            content(
+                (UserDataExport = "application/json"),
+                (Vec<u8> = "application/zip"),
+            )
+        ),
+        (status = 401, description = "No autenticado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims, id_token, query),
+    fields(
+        user_id = %user_claims.sub,
+        operation = "export_me"
+    )
+)]
+pub async fn export_me(
+    Extension(user_claims): Extension<UserAuthentication>,
+    Extension(id_token): Extension<String>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DataExportQuery>,
+) -> Result<Response<Body>, ApiError> {
+    let export: UserDataExport = collect_user_data(&state, &user_claims, &id_token).await;
+    tracing::info!(
+        "Data export generated ({} unavailable sources)",
+        export.unavailable_sources.len()
     );
 
-    tracing::debug!("🔍 Buscando usuario en Firebase: {}", email);
+    let (content_type, extension, body): (&str, &str, Vec<u8>) = match query.format {
+        DataExportFormat::Json => (
+            "application/json",
+            "json",
+            serde_json::to_vec_pretty(&export)
+                .map_err(|e| ApiError::Internal(format!("Error serializing export: {}", e)))?,
+        ),
+        DataExportFormat::Zip => (
+            "application/zip",
+            "zip",
+            export_to_zip(&export)
+                .map_err(|e| ApiError::Internal(format!("Error building export ZIP: {}", e)))?,
+        ),
+    };
 
-    // Realizar petición a Firebase
-    let response = match state
-        .firebase_options
-        .firebase_client
-        .get(&url_firebase_db)
-        .send()
-        .await
-    {
-        Ok(resp) => resp,
-        Err(e) => {
-            tracing::error!("❌ Error conectando a Firebase DB: {}", e);
-            return None;
-        }
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"amanahacademia-export.{}\"",
+                    extension
+                ),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+// Vincular un método de acceso (email/contraseña o Google) a la cuenta autenticada
+#[utoipa::path(
+    post,
+    path = "/users/me/providers",
+    tag = "users",
+    request_body = LinkProviderRequest,
+    responses(
+        (status = 200, description = "Proveedor vinculado; devuelve los métodos de acceso de la cuenta", body = ResponseAPI<LinkedProvidersResponse>),
+        (status = 401, description = "Token de Google inválido o sesión demasiado antigua", body = ResponseAPI<serde_json::Value>),
+        (status = 409, description = "El proveedor ya está vinculado a esta u otra cuenta", body = ResponseAPI<serde_json::Value>),
+        (status = 503, description = "Vinculación de Google no configurada", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims, id_token, payload),
+    fields(
+        user_id = %user_claims.sub,
+        operation = "link_provider"
+    )
+)]
+pub async fn link_provider(
+    Extension(user_claims): Extension<UserAuthentication>,
+    Extension(id_token): Extension<String>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<LinkProviderRequest>,
+) -> Result<Response<Body>, ApiError> {
+    let account: FirebaseUserInfo = current_account(&state, &id_token).await?;
+    let provider: Provider = match &payload {
+        LinkProviderRequest::Email { .. } => Provider::Email,
+        LinkProviderRequest::Google { .. } => Provider::Google,
     };
+    if has_provider(&account, &provider) {
+        return Err(ApiError::Conflict(format!(
+            "Provider '{}' is already linked",
+            provider.provider_id()
+        )));
+    }
 
-    tracing::debug!("Firebase DB response status: {}", response.status());
+    let linked: LinkedProvidersResponse = match payload {
+        LinkProviderRequest::Email { password } => {
+            let email: String = account.email.clone().ok_or_else(|| {
+                ApiError::BadRequest("The account has no email address".to_string())
+            })?;
+            let updated: AccountUpdateResponse =
+                link_password_provider(&state.firebase_options, &id_token, &email, &password)
+                    .await
+                    .map_err(provider_error)?;
 
-    // Manejar respuesta de Firebase
-    let users = match handle_firebase_response::<HashMap<String, UserDB>>(response).await {
-        Ok(users) => users,
-        Err((status, error)) => {
-            tracing::error!(
-                "Error parseando respuesta de Firebase: {} - {:?}",
-                status,
-                error
-            );
-            return None;
+            LinkedProvidersResponse {
+                providers: updated.provider_user_info,
+                id_token: updated.id_token,
+                refresh_token: updated.refresh_token,
+            }
+        }
+        LinkProviderRequest::Google {
+            id_token: google_id_token,
+        } => {
+            let google: GoogleTokenInfo =
+                verify_google_id_token(&state.firebase_options, &google_id_token)
+                    .await
+                    .map_err(firebase_api_error)?;
+            link_google_provider(&state.firebase_options, &user_claims.sub, &google)
+                .await
+                .map_err(provider_error)?;
+
+            LinkedProvidersResponse {
+                providers: current_account(&state, &id_token)
+                    .await?
+                    .provider_user_info
+                    .unwrap_or_default(),
+                id_token: None,
+                refresh_token: None,
+            }
         }
     };
+    tracing::info!("Provider '{}' linked", provider.provider_id());
 
-    // Firebase devuelve un HashMap, extraer el primer (y único) usuario
-    let user = users.into_iter().next().map(|(_, user_data)| user_data);
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<LinkedProvidersResponse>::success(
+            "Provider linked successfully".to_string(),
+            linked,
+        )),
+    )
+        .into_response())
+}
 
-    match &user {
-        Some(_) => tracing::info!("✅ Usuario encontrado: ({})", email),
-        None => tracing::warn!("Usuario no encontrado: {}", email),
+// Desvincular un método de acceso de la cuenta autenticada
+#[utoipa::path(
+    delete,
+    path = "/users/me/providers/{provider}",
+    tag = "users",
+    params(("provider" = Provider, Path, description = "Proveedor a desvincular")),
+    responses(
+        (status = 200, description = "Proveedor desvinculado; devuelve los métodos de acceso restantes", body = ResponseAPI<LinkedProvidersResponse>),
+        (status = 404, description = "El proveedor no está vinculado", body = ResponseAPI<serde_json::Value>),
+        (status = 409, description = "Es el único método de acceso de la cuenta", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims, id_token),
+    fields(
+        user_id = %user_claims.sub,
+        operation = "unlink_provider"
+    )
+)]
+pub async fn unlink_provider(
+    Extension(user_claims): Extension<UserAuthentication>,
+    Extension(id_token): Extension<String>,
+    State(state): State<Arc<AppState>>,
+    Path(provider): Path<Provider>,
+) -> Result<Response<Body>, ApiError> {
+    let account: FirebaseUserInfo = current_account(&state, &id_token).await?;
+    if !has_provider(&account, &provider) {
+        return Err(ApiError::NotFound(format!(
+            "Provider '{}' is not linked",
+            provider.provider_id()
+        )));
+    }
+    // Sin otro proveedor la cuenta quedaría sin forma de iniciar sesión
+    if account.provider_user_info.as_ref().map_or(0, Vec::len) <= 1 {
+        return Err(ApiError::Conflict(
+            "Cannot unlink the only sign-in method of the account".to_string(),
+        ));
     }
 
-    user
+    let updated: AccountUpdateResponse =
+        firebase_unlink_provider(&state.firebase_options, &id_token, provider.provider_id())
+            .await
+            .map_err(provider_error)?;
+    tracing::info!("Provider '{}' unlinked", provider.provider_id());
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<LinkedProvidersResponse>::success(
+            "Provider unlinked successfully".to_string(),
+            LinkedProvidersResponse {
+                providers: updated.provider_user_info,
+                id_token: updated.id_token,
+                refresh_token: updated.refresh_token,
+            },
+        )),
+    )
+        .into_response())
 }
 
-/// Actualizar el campo `first_free_class` de un usuario en Firebase Realtime Database
-pub async fn update_first_free_class(state: &AppState, email: &str) -> Result<(), String> {
-    tracing::debug!("🔄 Actualizando first_free_class=true para: {}", email);
+/// Cuenta de Firebase Auth dueña del idToken
+async fn current_account(state: &AppState, id_token: &str) -> Result<FirebaseUserInfo, ApiError> {
+    lookup_account(&state.firebase_options, id_token)
+        .await
+        .map_err(firebase_api_error)?
+        .users
+        .into_iter()
+        .next()
+        .ok_or_else(|| ApiError::Unauthorized("User not found".to_string()))
+}
 
-    // Obtener UID del usuario con autenticación admin
-    let url_query: String = format!(
-        "{}/user_profiles.json?orderBy=\"email\"&equalTo=\"{}\",&auth={}",
-        state.firebase_options.firebase_database_url,
-        email,
-        state.firebase_options.firebase_database_secret
-    );
+fn has_provider(account: &FirebaseUserInfo, provider: &Provider) -> bool {
+    account
+        .provider_user_info
+        .iter()
+        .flatten()
+        .any(|info| info.provider_id == provider.provider_id())
+}
 
-    let response = state
-        .firebase_options
-        .firebase_client
-        .get(&url_query)
-        .send()
-        .await
-        .map_err(|e| format!("Error conectando a Firebase: {}", e))?;
+/// Errores de Identity Toolkit al vincular/desvincular con su código HTTP propio
+fn provider_error((status, message): (StatusCode, String)) -> ApiError {
+    if message.starts_with("FEDERATED_USER_ID_ALREADY_LINKED")
+        || message.starts_with("EMAIL_EXISTS")
+    {
+        ApiError::Conflict("This sign-in method belongs to another account".to_string())
+    } else if message.starts_with("CREDENTIAL_TOO_OLD_LOGIN_AGAIN") {
+        ApiError::Unauthorized("Recent sign-in required, log in again".to_string())
+    } else {
+        firebase_api_error((status, message))
+    }
+}
 
-    let users: HashMap<String, UserDB> = handle_firebase_response(response)
-        .await
-        .map_err(|(_, error)| format!("Error parseando respuesta: {}", error))?;
+// Listar las sesiones abiertas del usuario autenticado
+#[utoipa::path(
+    get,
+    path = "/users/me/sessions",
+    tag = "users",
+    responses(
+        (status = 200, description = "Sesiones registradas en login y refresco; `current` marca la de esta petición", body = ResponseAPI<Vec<SessionView>>),
+        (status = 401, description = "No autenticado o sesión revocada", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims),
+    fields(
+        user_id = %user_claims.sub,
+        operation = "list_my_sessions"
+    )
+)]
+pub async fn list_my_sessions(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response<Body>, ApiError> {
+    let sessions: Vec<SessionView> = list_sessions(&state, &user_claims).await?;
 
-    let (user_uid, _) = users
-        .into_iter()
-        .next()
-        .ok_or_else(|| format!("Usuario {} no encontrado", email))?;
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<Vec<SessionView>>::success(
+            "Sessions retrieved successfully".to_string(),
+            sessions,
+        )),
+    )
+        .into_response())
+}
+
+// Cerrar la sesión en todos los dispositivos (incluido el actual)
+#[utoipa::path(
+    delete,
+    path = "/users/me/sessions",
+    tag = "users",
+    responses(
+        (status = 200, description = "Sesiones revocadas; hay que volver a iniciar sesión", body = ResponseAPI<RevokedSessions>),
+        (status = 503, description = "Faltan las credenciales de administración de Firebase", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims),
+    fields(
+        user_id = %user_claims.sub,
+        operation = "revoke_my_sessions"
+    )
+)]
+pub async fn revoke_my_sessions(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response<Body>, ApiError> {
+    let revoked: RevokedSessions = revoke_all_sessions(&state, &user_claims.sub).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<RevokedSessions>::success(
+            "All sessions revoked".to_string(),
+            revoked,
+        )),
+    )
+        .into_response())
+}
+
+// Obtener el perfil de aprendizaje del alumno
+#[utoipa::path(
+    get,
+    path = "/users/me/profile",
+    tag = "users",
+    responses(
+        (status = 200, description = "Perfil de aprendizaje (vacío si aún no lo ha rellenado)", body = ResponseAPI<LearnerProfile>),
+        (status = 404, description = "El usuario no tiene perfil en la DB", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims),
+    fields(
+        user_id = %user_claims.sub,
+        operation = "get_my_learner_profile"
+    )
+)]
+pub async fn get_my_learner_profile(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response<Body>, ApiError> {
+    let user: UserDB = managed_user(&state, &user_claims.sub).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<LearnerProfile>::success(
+            "Learner profile retrieved successfully".to_string(),
+            user.learner_profile.unwrap_or_default(),
+        )),
+    )
+        .into_response())
+}
+
+// Reemplazar el perfil de aprendizaje del alumno
+#[utoipa::path(
+    put,
+    path = "/users/me/profile",
+    tag = "users",
+    request_body = LearnerProfile,
+    responses(
+        (status = 200, description = "Perfil de aprendizaje guardado", body = ResponseAPI<LearnerProfile>),
+        (status = 400, description = "Nivel, zona horaria, áreas de enfoque o franjas horarias inválidas", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "El usuario no tiene perfil en la DB", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims, profile),
+    fields(
+        user_id = %user_claims.sub,
+        operation = "update_my_learner_profile"
+    )
+)]
+pub async fn update_my_learner_profile(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(profile): ValidatedJson<LearnerProfile>,
+) -> Result<Response<Body>, ApiError> {
+    let user: UserDB = managed_user(&state, &user_claims.sub).await?;
+
+    save_learner_profile(
+        &state,
+        &user_claims.sub,
+        user,
+        profile,
+        "Learner profile updated",
+    )
+    .await
+}
+
+// Precargar el perfil de aprendizaje desde la encuesta de nivel
+#[utoipa::path(
+    post,
+    path = "/users/me/profile/prefill",
+    tag = "users",
+    params(LearnerProfilePrefillQuery),
+    responses(
+        (status = 200, description = "Perfil completado con las respuestas de la encuesta; los campos ya rellenos no se modifican", body = ResponseAPI<LearnerProfile>),
+        (status = 404, description = "No hay encuesta enviada con el email verificado del usuario", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims),
+    fields(
+        user_id = %user_claims.sub,
+        operation = "prefill_my_learner_profile"
+    )
+)]
+pub async fn prefill_my_learner_profile(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LearnerProfilePrefillQuery>,
+) -> Result<Response<Body>, ApiError> {
+    let user: UserDB = managed_user(&state, &user_claims.sub).await?;
+    // El email del perfil es editable: las encuestas se buscan por el verificado del token
+    let survey: Survey = learner_survey(
+        &state,
+        user_claims.verified_email(),
+        query.survey_id.as_deref(),
+    )
+    .await?;
+
+    let mut profile: LearnerProfile = user.learner_profile.clone().unwrap_or_default();
+    profile.fill_missing(LearnerProfile::from_survey(&survey));
+
+    save_learner_profile(
+        &state,
+        &user_claims.sub,
+        user,
+        profile,
+        "Learner profile prefilled from survey",
+    )
+    .await
+}
+
+/// Encuesta enviada con el email del alumno: la indicada o, si no, la más reciente.
+/// Una encuesta de otro usuario se trata como inexistente, igual que todas sin email verificado.
+async fn learner_survey(
+    state: &AppState,
+    verified_email: Option<&str>,
+    survey_id: Option<&str>,
+) -> Result<Survey, ApiError> {
+    let Some(email) = verified_email else {
+        return Err(ApiError::NotFound("Survey not found".to_string()));
+    };
+    let surveys: HashMap<String, Survey> = state.repositories.surveys.get_all().await?;
+
+    surveys
+        .into_values()
+        .filter(|survey| survey.user_email.eq_ignore_ascii_case(email))
+        .filter(|survey| survey_id.is_none_or(|id| survey.id == id))
+        .max_by(|a, b| a.submitted_at.cmp(&b.submitted_at))
+        .ok_or_else(|| ApiError::NotFound("Survey not found".to_string()))
+}
+
+/// Guarda el perfil de aprendizaje con la fecha de actualización y responde con él
+async fn save_learner_profile(
+    state: &AppState,
+    uid: &str,
+    mut user: UserDB,
+    mut profile: LearnerProfile,
+    message: &str,
+) -> Result<Response<Body>, ApiError> {
+    profile.updated_at = Some(chrono::Utc::now().to_rfc3339());
+    user.learner_profile = Some(profile);
+
+    let saved: UserDB = state.repositories.users.put(uid, &user).await?;
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<LearnerProfile>::success(
+            message.to_string(),
+            saved.learner_profile.unwrap_or_default(),
+        )),
+    )
+        .into_response())
+}
+
+// Enviar el email de verificación al usuario autenticado
+#[utoipa::path(
+    post,
+    path = "/users/verify-email",
+    tag = "users",
+    responses(
+        (status = 200, description = "Email de verificación enviado", body = ResponseAPI<serde_json::Value>),
+        (status = 409, description = "El email ya está verificado", body = ResponseAPI<serde_json::Value>),
+        (status = 429, description = "Demasiadas peticiones", body = ResponseAPI<serde_json::Value>),
+        (status = 503, description = "No se pudo enviar el email", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims),
+    fields(
+        user_id = %user_claims.sub,
+        operation = "send_verification_email"
+    )
+)]
+pub async fn send_verification_email(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response<Body>, ApiError> {
+    if user_claims.email_verified == Some(true) {
+        return Err(ApiError::Conflict("Email already verified".to_string()));
+    }
+    let email: String = user_claims
+        .email
+        .clone()
+        .ok_or_else(|| ApiError::BadRequest("The account has no email address".to_string()))?;
+
+    let oob: SendOobCodeResponse = send_oob_code(
+        &state.firebase_options,
+        &SendOobCodeRequest {
+            request_type: OobRequestType::VerifyEmail,
+            email: email.clone(),
+            return_oob_link: true,
+        },
+    )
+    .await
+    .map_err(firebase_api_error)?;
+
+    // Sin enlace Firebase ya ha enviado su email genérico
+    if let Some(link) = oob.oob_link {
+        ActionEmail {
+            subject: "Verifica tu email en Amanah Academia",
+            title: "Confirma tu dirección de email",
+            intro: "Gracias por registrarte en Amanah Academia. Confirma tu email para poder reservar clases.",
+            action_label: "Verificar email",
+            action_url: &link,
+            outro: "Si no has creado una cuenta en Amanah Academia puedes ignorar este mensaje.",
+        }
+        .send(&state.resend_client, &email)
+        .await?;
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<()>::success_message(
+            "Verification email sent".to_string(),
+        )),
+    )
+        .into_response())
+}
+
+// Confirmar el email con el código del enlace de verificación
+#[utoipa::path(
+    post,
+    path = "/users/verify-email/confirm",
+    tag = "users",
+    request_body = VerifyEmailConfirm,
+    responses(
+        (status = 200, description = "Email verificado; devuelve el email", body = ResponseAPI<String>),
+        (status = 400, description = "Código inválido o caducado", body = ResponseAPI<serde_json::Value>),
+    )
+)]
+#[debug_handler]
+#[instrument(skip(state, payload), fields(operation = "confirm_verification_email"))]
+pub async fn confirm_verification_email(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(payload): ValidatedJson<VerifyEmailConfirm>,
+) -> Result<Response<Body>, ApiError> {
+    let result: OobCodeResult =
+        confirm_email_verification(&state.firebase_options, &payload.oob_code)
+            .await
+            .map_err(firebase_api_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<String>::success(
+            "Email verified successfully".to_string(),
+            result.email.unwrap_or_default(),
+        )),
+    )
+        .into_response())
+}
 
-    // Actualizar solo el campo específico con autenticación admin
-    let url_update = format!(
-        "{}/user_profiles/{}.json?auth={}",
-        state.firebase_options.firebase_database_url,
-        user_uid,
-        state.firebase_options.firebase_database_secret
+// Pedir el email de restablecimiento de contraseña
+#[utoipa::path(
+    post,
+    path = "/users/password-reset/request",
+    tag = "users",
+    request_body = PasswordResetRequest,
+    responses(
+        (status = 200, description = "Si el email existe se ha enviado el enlace", body = ResponseAPI<serde_json::Value>),
+        (status = 400, description = "Email inválido", body = ResponseAPI<serde_json::Value>),
+        (status = 429, description = "Demasiadas peticiones", body = ResponseAPI<serde_json::Value>),
+    )
+)]
+#[debug_handler]
+#[instrument(skip(state, payload), fields(operation = "request_password_reset"))]
+pub async fn request_password_reset(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(payload): ValidatedJson<PasswordResetRequest>,
+) -> Result<Response<Body>, ApiError> {
+    // Misma respuesta exista o no la cuenta, para no revelar qué emails están registrados
+    let response = (
+        StatusCode::OK,
+        Json(ResponseAPI::<()>::success_message(
+            "If the email is registered, a password reset link has been sent".to_string(),
+        )),
     );
 
-    let update_data = serde_json::json!({
-        "first_free_class": true
-    });
+    let oob: SendOobCodeResponse = match send_oob_code(
+        &state.firebase_options,
+        &SendOobCodeRequest {
+            request_type: OobRequestType::PasswordReset,
+            email: payload.email.clone(),
+            return_oob_link: true,
+        },
+    )
+    .await
+    {
+        Ok(oob) => oob,
+        Err((_, message)) if message.starts_with("EMAIL_NOT_FOUND") => {
+            tracing::info!("Password reset requested for unknown email");
+            return Ok(response.into_response());
+        }
+        Err(err) => return Err(firebase_api_error(err)),
+    };
 
-    let response = state
-        .firebase_options
-        .firebase_client
-        .patch(&url_update)
-        .json(&update_data)
-        .send()
+    if let Some(link) = oob.oob_link {
+        ActionEmail {
+            subject: "Restablece tu contraseña de Amanah Academia",
+            title: "Restablecer contraseña",
+            intro: "Hemos recibido una solicitud para cambiar la contraseña de tu cuenta.",
+            action_label: "Elegir nueva contraseña",
+            action_url: &link,
+            outro: "Si no has pedido el cambio puedes ignorar este mensaje; tu contraseña sigue siendo la misma.",
+        }
+        .send(&state.resend_client, &payload.email)
+        .await?;
+    }
+
+    Ok(response.into_response())
+}
+
+// Fijar la nueva contraseña con el código del email de restablecimiento
+#[utoipa::path(
+    post,
+    path = "/users/password-reset/confirm",
+    tag = "users",
+    request_body = PasswordResetConfirm,
+    responses(
+        (status = 200, description = "Contraseña cambiada; devuelve el email", body = ResponseAPI<String>),
+        (status = 400, description = "Código inválido o caducado, o contraseña débil", body = ResponseAPI<serde_json::Value>),
+        (status = 429, description = "Demasiadas peticiones", body = ResponseAPI<serde_json::Value>),
+    )
+)]
+#[debug_handler]
+#[instrument(skip(state, payload), fields(operation = "confirm_password_reset"))]
+pub async fn confirm_password_reset(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(payload): ValidatedJson<PasswordResetConfirm>,
+) -> Result<Response<Body>, ApiError> {
+    let result: OobCodeResult = reset_password(
+        &state.firebase_options,
+        &payload.oob_code,
+        &payload.new_password,
+    )
+    .await
+    .map_err(firebase_api_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<String>::success(
+            "Password updated successfully".to_string(),
+            result.email.unwrap_or_default(),
+        )),
+    )
+        .into_response())
+}
+
+/// Perfil de la DB de un usuario (gestionado por un administrador o el propio); 404 si no existe
+async fn managed_user(state: &AppState, uid: &str) -> Result<UserDB, ApiError> {
+    state
+        .repositories
+        .users
+        .get(uid)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", uid)))
+}
+
+/// Guarda el perfil modificado y responde con él
+async fn save_managed_user(
+    state: &AppState,
+    uid: &str,
+    user: &UserDB,
+    message: &str,
+) -> Result<Response<Body>, ApiError> {
+    let saved: UserDB = state.repositories.users.put(uid, user).await?;
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<UserDB>::success(message.to_string(), saved)),
+    )
+        .into_response())
+}
+
+// Cambiar el rol de un usuario (solo administradores)
+#[utoipa::path(
+    put,
+    path = "/users/admin/{uid}/role",
+    tag = "users",
+    params(
+        ("uid" = String, Path, description = "UID de Firebase del usuario"),
+    ),
+    request_body = UpdateRoleRequest,
+    responses(
+        (status = 200, description = "Rol actualizado", body = ResponseAPI<UserDB>),
+        (status = 403, description = "Solo administradores; no se puede cambiar el rol propio", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "Usuario no encontrado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims, payload),
+    fields(
+        admin_id = %user_claims.sub,
+        target_uid = %uid,
+        operation = "admin_set_role"
+    )
+)]
+pub async fn admin_set_role(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+    Path(uid): Path<String>,
+    Json(payload): Json<UpdateRoleRequest>,
+) -> Result<Response<Body>, ApiError> {
+    // Evita que el último administrador se quite el rol a sí mismo por error
+    if uid == user_claims.sub {
+        return Err(ApiError::Forbidden(
+            "Administrators cannot change their own role".to_string(),
+        ));
+    }
+
+    let mut user: UserDB = managed_user(&state, &uid).await?;
+    user.role = Some(payload.role.to_string());
+    tracing::info!("Role of {} set to {}", uid, payload.role);
+
+    save_managed_user(&state, &uid, &user, "Role updated successfully").await
+}
+
+// Conceder y revocar permisos de un usuario (solo administradores)
+#[utoipa::path(
+    put,
+    path = "/users/admin/{uid}/permissions",
+    tag = "users",
+    params(
+        ("uid" = String, Path, description = "UID de Firebase del usuario"),
+    ),
+    request_body = UpdatePermissionsRequest,
+    responses(
+        (status = 200, description = "Permisos actualizados", body = ResponseAPI<UserDB>),
+        (status = 403, description = "Solo administradores", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "Usuario no encontrado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims, payload),
+    fields(
+        admin_id = %user_claims.sub,
+        target_uid = %uid,
+        operation = "admin_update_permissions"
+    )
+)]
+pub async fn admin_update_permissions(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+    Path(uid): Path<String>,
+    Json(payload): Json<UpdatePermissionsRequest>,
+) -> Result<Response<Body>, ApiError> {
+    let mut user: UserDB = managed_user(&state, &uid).await?;
+
+    let mut permissions: HashSet<String> = user.permissions.take().unwrap_or_default();
+    permissions.extend(payload.grant.iter().map(|p| p.to_string()));
+    for permission in &payload.revoke {
+        permissions.remove(permission.as_ref());
+    }
+    // Sin permisos el campo se elimina en lugar de guardar un conjunto vacío
+    user.permissions = (!permissions.is_empty()).then_some(permissions);
+
+    save_managed_user(&state, &uid, &user, "Permissions updated successfully").await
+}
+
+// Fijar o quitar el tier de suscripción de un usuario (solo administradores)
+#[utoipa::path(
+    put,
+    path = "/users/admin/{uid}/tier",
+    tag = "users",
+    params(
+        ("uid" = String, Path, description = "UID de Firebase del usuario"),
+    ),
+    request_body = UpdateSubscriptionTierRequest,
+    responses(
+        (status = 200, description = "Tier actualizado", body = ResponseAPI<UserDB>),
+        (status = 400, description = "Tier vacío o sin definir en /subscriptions/tiers", body = ResponseAPI<serde_json::Value>),
+        (status = 403, description = "Solo administradores", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "Usuario no encontrado", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims, payload),
+    fields(
+        admin_id = %user_claims.sub,
+        target_uid = %uid,
+        operation = "admin_set_subscription_tier"
+    )
+)]
+pub async fn admin_set_subscription_tier(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+    Path(uid): Path<String>,
+    Json(payload): Json<UpdateSubscriptionTierRequest>,
+) -> Result<Response<Body>, ApiError> {
+    let tier: Option<String> = match payload.subscription_tier {
+        Some(tier) if tier.trim().is_empty() => {
+            return Err(ApiError::BadRequest(
+                "subscription_tier must not be empty; use null to remove it".to_string(),
+            ));
+        }
+        tier => tier.map(|t| t.trim().to_string()),
+    };
+    if let Some(tier) = &tier
+        && state
+            .repositories
+            .subscription_tiers
+            .get(tier)
+            .await?
+            .is_none()
+    {
+        return Err(ApiError::BadRequest(format!(
+            "Subscription tier {} is not defined",
+            tier
+        )));
+    }
+
+    let mut user: UserDB = managed_user(&state, &uid).await?;
+    user.subscription_tier = tier;
+
+    save_managed_user(
+        &state,
+        &uid,
+        &user,
+        "Subscription tier updated successfully",
+    )
+    .await
+}
+
+// Deshabilitar o rehabilitar la cuenta de Firebase Auth de un usuario (solo administradores)
+#[utoipa::path(
+    put,
+    path = "/users/admin/{uid}/status",
+    tag = "users",
+    params(
+        ("uid" = String, Path, description = "UID de Firebase del usuario"),
+    ),
+    request_body = UpdateAccountStatusRequest,
+    responses(
+        (status = 200, description = "Estado de la cuenta actualizado", body = ResponseAPI<serde_json::Value>),
+        (status = 403, description = "Solo administradores; no se puede deshabilitar la cuenta propia", body = ResponseAPI<serde_json::Value>),
+        (status = 404, description = "Usuario no encontrado", body = ResponseAPI<serde_json::Value>),
+        (status = 503, description = "Credenciales de administración de Firebase no configuradas", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims, payload),
+    fields(
+        admin_id = %user_claims.sub,
+        target_uid = %uid,
+        operation = "admin_set_account_status"
+    )
+)]
+pub async fn admin_set_account_status(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+    Path(uid): Path<String>,
+    Json(payload): Json<UpdateAccountStatusRequest>,
+) -> Result<Response<Body>, ApiError> {
+    if uid == user_claims.sub && payload.disabled {
+        return Err(ApiError::Forbidden(
+            "Administrators cannot disable their own account".to_string(),
+        ));
+    }
+
+    set_account_disabled(&state.firebase_options, &uid, payload.disabled)
         .await
-        .map_err(|e| format!("Error actualizando Firebase: {}", e))?;
+        .map_err(|(status, message)| match status {
+            // Identity Toolkit responde 400 USER_NOT_FOUND para uids desconocidos
+            StatusCode::BAD_REQUEST if message.starts_with("USER_NOT_FOUND") => {
+                ApiError::NotFound(format!("User {} not found", uid))
+            }
+            _ => firebase_api_error((status, message)),
+        })?;
+    tracing::info!(
+        "Account {} {}",
+        uid,
+        if payload.disabled {
+            "disabled"
+        } else {
+            "enabled"
+        }
+    );
 
-    if !response.status().is_success() {
-        let error_text: String = response.text().await.unwrap_or_default();
-        return Err(format!("Firebase update failed: {}", error_text));
+    let message: &str = if payload.disabled {
+        "Account disabled successfully"
+    } else {
+        "Account enabled successfully"
+    };
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<()>::success_message(message.to_string())),
+    )
+        .into_response())
+}
+
+// Cerrar todas las sesiones de un usuario (cuenta comprometida, dispositivo perdido...)
+#[utoipa::path(
+    delete,
+    path = "/users/admin/{uid}/sessions",
+    tag = "users",
+    params(("uid" = String, Path, description = "uid de Firebase del usuario")),
+    responses(
+        (status = 200, description = "Sesiones revocadas", body = ResponseAPI<RevokedSessions>),
+        (status = 404, description = "Usuario no encontrado", body = ResponseAPI<serde_json::Value>),
+        (status = 503, description = "Faltan las credenciales de administración de Firebase", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+#[instrument(
+    skip(state, user_claims),
+    fields(
+        admin_id = %user_claims.sub,
+        operation = "admin_revoke_sessions"
+    )
+)]
+pub async fn admin_revoke_sessions(
+    Extension(user_claims): Extension<UserAuthentication>,
+    State(state): State<Arc<AppState>>,
+    Path(uid): Path<String>,
+) -> Result<Response<Body>, ApiError> {
+    managed_user(&state, &uid).await?;
+    let revoked: RevokedSessions = revoke_all_sessions(&state, &uid).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ResponseAPI::<RevokedSessions>::success(
+            "All sessions revoked".to_string(),
+            revoked,
+        )),
+    )
+        .into_response())
+}
+
+/// Obtener el usuario por email desde la base de datos
+/// Retorna Some(UserDB) si lo encuentra, None si no existe o hay error
+pub async fn get_user_by_email_db(state: &AppState, email: &str) -> Option<UserDB> {
+    tracing::debug!("🔍 Buscando usuario en la base de datos: {}", email);
+
+    let user = match state.repositories.users.find_by_email(email).await {
+        Ok(user) => user.map(|(_, user_data)| user_data),
+        Err(err) => {
+            tracing::error!("❌ Error buscando usuario por email: {}", err);
+            return None;
+        }
+    };
+
+    match &user {
+        Some(_) => tracing::info!("✅ Usuario encontrado: ({})", email),
+        None => tracing::warn!("Usuario no encontrado: {}", email),
     }
 
+    user
+}
+
+/// Actualizar el campo `first_free_class` de un usuario en la base de datos
+pub async fn update_first_free_class(state: &AppState, email: &str) -> Result<(), String> {
+    tracing::debug!("🔄 Actualizando first_free_class=true para: {}", email);
+
+    let (user_uid, _) = state
+        .repositories
+        .users
+        .find_by_email(email)
+        .await
+        .map_err(|e| format!("Error buscando usuario: {}", e))?
+        .ok_or_else(|| format!("Usuario {} no encontrado", email))?;
+
+    // Actualizar solo el campo específico
+    state
+        .repositories
+        .users
+        .set_first_free_class(&user_uid, true)
+        .await
+        .map_err(|e| format!("Firebase update failed: {}", e))?;
+
     tracing::info!("✅ first_free_class actualizado para: {}", email);
     Ok(())
 }
+
+#[cfg(test)]
+#[path = "../test/controllers/users.rs"]
+mod extended_tests;
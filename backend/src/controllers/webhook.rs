@@ -8,22 +8,54 @@ use {
             cal::BookingStatus,
             response::ResponseAPI,
             state::AppState,
-            stripe::RelationalCalStripe,
             user::UserDB,
             webhook::{Attendee, BookingChange, CalWebhookEvent, RefundResponse, WebhookTrigger},
         },
+        services::{
+            payments::refund_payment_intent,
+            supervisor::{ShutdownSignal, TaskStatus},
+        },
     },
-    axum::{Json, extract::State, http::StatusCode, response::IntoResponse},
+    axum::{Json, debug_handler, extract::State, http::StatusCode, response::IntoResponse},
     std::sync::Arc,
-    stripe::{CreateRefund, PaymentIntentId, Refund},
     tokio::time::{Interval, MissedTickBehavior},
 };
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/webhook/health",
+    tag = "webhook",
+    responses(
+        (status = 200, description = "El servidor está vivo", body = String),
+    )
+)]
 pub async fn health_check() -> &'static str {
     "OK"
 }
 
+/// Estado de las tareas en segundo plano (solo administradores)
+#[utoipa::path(
+    get,
+    path = "/webhook/health/tasks",
+    tag = "webhook",
+    responses(
+        (status = 200, description = "Estado de las tareas en segundo plano", body = ResponseAPI<Vec<TaskStatus>>),
+        (status = 403, description = "Solo administradores", body = ResponseAPI<serde_json::Value>),
+    ),
+    security(("bearer_auth" = []))
+)]
+#[debug_handler]
+pub async fn tasks_health(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        Json(ResponseAPI::<Vec<TaskStatus>>::success(
+            "Background tasks status".to_string(),
+            state.tasks.statuses(),
+        )),
+    )
+}
+
 /// Procesa el reembolso de un booking cancelado
 /// Retorna Ok(RefundResponse) si el reembolso fue exitoso
 async fn process_refund(state: &AppState, booking_id: &String) -> Result<RefundResponse, String> {
@@ -42,80 +74,25 @@ async fn process_refund(state: &AppState, booking_id: &String) -> Result<RefundR
         return Err("ID de reserva inválido".to_string());
     }
 
-    let url_firebase_db_relationship: String = format!(
-        "{}/relation_cal_stripe/{}.json",
-        state.firebase_options.firebase_database_url, booking_id
-    );
-
-    // Obtener relación booking <-> Stripe desde Firebase
-    let stripe_id: String = match state
-        .firebase_options
-        .firebase_client
-        .get(&url_firebase_db_relationship)
-        .send()
-        .await
-    {
-        Ok(res) => match res.json::<Option<RelationalCalStripe>>().await {
-            Ok(Some(val)) => val.stripe_id,
-            Ok(None) => {
-                let msg = format!(
-                    "No se encontró relación de Firebase para booking_id: {}",
-                    booking_id
-                );
-                tracing::error!("{}", msg);
-                return Err(msg);
-            }
-            Err(e) => {
-                let msg = format!("Error parseando respuesta de Firebase: {}", e);
-                tracing::error!("{}", msg);
-                return Err(msg);
-            }
-        },
-        Err(e) => {
-            let msg = format!("Error obteniendo relación de Firebase: {}", e);
+    // Obtener relación booking <-> Stripe desde la base de datos
+    let stripe_id: String = match state.repositories.cal_stripe.get(booking_id).await {
+        Ok(Some(relation)) => relation.stripe_id,
+        Ok(None) => {
+            let msg = format!(
+                "No se encontró relación de Firebase para booking_id: {}",
+                booking_id
+            );
             tracing::error!("{}", msg);
             return Err(msg);
         }
-    };
-
-    // Convertir a PaymentIntentId
-    let pi_id: PaymentIntentId = match stripe_id.parse() {
-        Ok(id) => id,
         Err(e) => {
-            let msg = format!("Invalid PaymentIntent ID: {}", e);
+            let msg = format!("Error obteniendo relación de Firebase: {}", e);
             tracing::error!("{}", msg);
             return Err(msg);
         }
     };
 
-    // Crear refund en Stripe
-    match Refund::create(
-        &state.stripe_client,
-        CreateRefund {
-            payment_intent: Some(pi_id),
-            ..Default::default()
-        },
-    )
-    .await
-    {
-        Ok(refund) => {
-            tracing::info!("Reembolso creado en Stripe: {:?}", refund);
-
-            let response = RefundResponse {
-                id: refund.id.to_string(),
-                amount: refund.amount,
-                currency: refund.currency.to_string(),
-                status: refund.status.clone(),
-                created: refund.created,
-            };
-            Ok(response)
-        }
-        Err(e) => {
-            let msg = format!("Error creando reembolso: {:?}", e);
-            tracing::error!("{}", msg);
-            Err(msg)
-        }
-    }
+    refund_payment_intent(state, &stripe_id).await
 }
 
 /// Procesa un booking creado de tipo "free-class"
@@ -161,6 +138,17 @@ async fn process_created_free(
 }
 
 /// Obtener webhooks de cal
+#[utoipa::path(
+    post,
+    path = "/webhook/cal",
+    tag = "webhook",
+    request_body = CalWebhookEvent,
+    responses(
+        (status = 200, description = "Evento procesado", body = ResponseAPI<RefundResponse>),
+        (status = 400, description = "Evento inválido", body = ResponseAPI<serde_json::Value>),
+        (status = 429, description = "Demasiadas peticiones", body = ResponseAPI<serde_json::Value>),
+    )
+)]
 pub async fn handle_cal_webhook(
     State(state): State<Arc<AppState>>,
     Json(event): Json<CalWebhookEvent>,
@@ -273,9 +261,10 @@ pub async fn handle_cal_webhook(
         .into_response()
 }
 
-/// Tarea de polling para detectar cambios en bookings de Cal.com
-pub async fn polling_task(state: Arc<AppState>) {
-    let poll_interval_secs: u64 = 600; // 10 minutos
+/// Tarea de polling para detectar cambios en bookings de Cal.com.
+/// Al pedir el apagado termina la iteración en curso (reembolsos incluidos) y vuelve.
+pub async fn polling_task(state: Arc<AppState>, mut shutdown: ShutdownSignal) {
+    let poll_interval_secs: u64 = state.cal_options.polling_interval_secs;
     let mut interval: Interval =
         tokio::time::interval(std::time::Duration::from_secs(poll_interval_secs));
 
@@ -289,7 +278,13 @@ pub async fn polling_task(state: Arc<AppState>) {
 
     loop {
         // Esperar hasta el próximo tick (primera iteración es inmediata)
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.wait() => {
+                tracing::info!("Cal.com polling task stopped");
+                return;
+            }
+        }
 
         match fetch_and_detect_changes(&state).await {
             Ok(changes) if !changes.is_empty() => {
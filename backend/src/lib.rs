@@ -1,6 +1,8 @@
+pub mod config;
 pub mod controllers;
 pub mod middleware;
 pub mod models;
+pub mod repositories;
 pub mod routes;
 pub mod services;
 pub mod utils;
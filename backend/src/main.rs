@@ -1,30 +1,37 @@
 use {
     amanahacademia::{
+        config::Config,
         controllers,
         models::{
             metrics::ServiceAccount,
+            rate_limit::RateLimiter,
             state::{AppState, CalOptions, CustomFirebase, GAOptions, KeyCache, MailchimpOptions},
         },
+        repositories::Repositories,
         routes,
+        services::{
+            self,
+            firebase_keys::fetch_firebase_keys,
+            supervisor::{TaskSupervisor, wait_for_os_shutdown},
+        },
     },
     axum::{
         Router,
         http::{
-            Method,
+            HeaderValue, Method,
             header::{AUTHORIZATION, CONTENT_TYPE},
         },
     },
     reqwest::Client as HttpClient,
     resend_rs::Resend,
-    serde_json::Value,
-    std::{collections::HashMap, env, net::SocketAddr, sync::Arc, time::SystemTime},
+    std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration},
     stripe::Client as StripeClient,
     tokio::{net::TcpListener, sync::RwLock},
     tower_http::{
         cors::{AllowOrigin, CorsLayer},
         trace::TraceLayer,
     },
-    tracing::{error, info},
+    tracing::{error, info, warn},
     tracing_subscriber::{
         EnvFilter,
         fmt::{self},
@@ -33,6 +40,10 @@ use {
     },
 };
 
+/// Tiempo máximo para que las tareas en segundo plano terminen al apagar
+/// (por debajo del `kill_timeout` de fly.toml)
+const TASKS_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(20);
+
 #[tokio::main]
 async fn main() {
     // Cargar variables de entorno
@@ -71,60 +82,99 @@ async fn main() {
         info!("🚀 Modo producción - debug_assertions desactivado");
     }
 
-    // Las keys públicas de Firebase son necesarias para validar JWTs en cada request
-    let initial_keys = fetch_firebase_keys()
-        .await
-        .expect("Failed to fetch initial Firebase keys");
-    let firebase_keys = Arc::new(RwLock::new(KeyCache {
-        keys: initial_keys,
-        fetched_at: SystemTime::now(),
-    }));
-
-    async fn fetch_firebase_keys() -> Result<Value, Box<dyn std::error::Error>> {
-        let response = reqwest::get(
-        "https://www.googleapis.com/robot/v1/metadata/x509/securetoken@system.gserviceaccount.com",
-    )
-    .await?;
-
-        if !response.status().is_success() {
-            return Err("Firebase keys endpoint returned error".into());
+    // Cargar y validar toda la configuración antes de arrancar nada
+    let config: Config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            error!("{}", err);
+            std::process::exit(1);
         }
+    };
 
-        let keys: Value = response.json().await?;
-
-        if !keys.is_object() || keys.as_object().is_none_or(|m| m.is_empty()) {
-            return Err("Firebase public keys are empty".into());
+    // Las keys públicas de Firebase son necesarias para validar JWTs en cada request.
+    // Con el emulador los tokens no van firmados, así que no hacen falta.
+    let emulator_mode: bool = config.firebase.auth_emulator_host.is_some();
+    let initial_keys: KeyCache = match &config.firebase.auth_emulator_host {
+        Some(host) => {
+            warn!(
+                "🧪 Firebase Auth Emulator activo en {}: los ID tokens NO se verifican criptográficamente",
+                host
+            );
+            KeyCache::empty()
         }
-
-        info!("Firebase public keys fetched successfully");
-        Ok(keys)
-    }
+        // Si Google no responde arrancamos igualmente: las rutas protegidas devuelven 503
+        // hasta que la tarea de refresco consiga las claves
+        None => {
+            match fetch_firebase_keys(&HttpClient::new(), &config.firebase.public_keys_url).await {
+                Ok(fetched) => {
+                    info!(
+                        "Firebase public keys fetched successfully (max-age {:?})",
+                        fetched.max_age
+                    );
+                    KeyCache::new(fetched.keys, fetched.max_age)
+                }
+                Err(err) => {
+                    error!(
+                        "Failed to fetch initial Firebase keys ({}), starting in degraded mode",
+                        err
+                    );
+                    KeyCache::empty()
+                }
+            }
+        }
+    };
+    let firebase_keys = Arc::new(RwLock::new(initial_keys));
 
     let firebase_options: CustomFirebase = CustomFirebase {
         firebase_keys,
-        firebase_project_id: env::var("FIREBASE_PROJECT_ID")
-            .expect("FIREBASE_PROJECT_ID must be set"),
-        firebase_api_key: env::var("FIREBASE_API_KEY").expect("FIREBASE_API_KEY must be set"),
-        firebase_database_url: env::var("FIREBASE_DATABASE_URL")
-            .expect("FIREBASE_DATABASE_URL must be set"),
-        firebase_database_secret: env::var("FIREBASE_DATABASE_SECRET")
-            .expect("FIREBASE_DATABASE_SECRET must be set"),
+        firebase_project_id: config.firebase.project_id.clone(),
+        firebase_api_key: config.firebase.api_key.clone(),
         firebase_client: HttpClient::new(),
+        auth_emulator_host: config.firebase.auth_emulator_host.clone(),
+        identity_toolkit_url: config.firebase.identity_toolkit_url.clone(),
+        secure_token_url: config.firebase.secure_token_url.clone(),
+        public_keys_url: config.firebase.public_keys_url.clone(),
+        service_account: config
+            .firebase
+            .client_email
+            .clone()
+            .zip(config.firebase.private_key.clone())
+            .map(|(client_email, private_key)| ServiceAccount {
+                client_email,
+                private_key,
+            }),
+        token_url: config.firebase.token_url.clone(),
+        admin_token_cache: Default::default(),
+        google_client_id: config.firebase.google_client_id.clone(),
+        google_tokeninfo_url: config.firebase.google_tokeninfo_url.clone(),
     };
 
-    let stripe_client: StripeClient =
-        StripeClient::new(env::var("STRIPE_API_KEY").expect("STRIPE_API_KEY must be set"));
+    // Backend de persistencia: Firebase Realtime Database (por defecto) o SQLite local
+    let repositories: Repositories = match config.database.backend.as_str() {
+        "sqlite" => {
+            info!(
+                "Usando SQLite como base de datos: {}",
+                config.database.sqlite_path
+            );
+            Repositories::sqlite(&config.database.sqlite_path)
+                .expect("Failed to open SQLite database")
+        }
+        // La validación de Config garantiza que la URL y el secreto existen
+        _ => Repositories::firebase(
+            firebase_options.firebase_client.clone(),
+            config.firebase.database_url.clone().unwrap_or_default(),
+            config.firebase.database_secret.clone().unwrap_or_default(),
+        ),
+    };
 
-    let resend_client: Resend = Resend::new(
-        env::var("RESEND_API_KEY")
-            .expect("RESEND_API_KEY must be set")
-            .as_str(),
-    );
+    let stripe_client: StripeClient = StripeClient::new(config.stripe.api_key.clone());
+
+    let resend_client: Resend = Resend::new(&config.resend.api_key);
 
     let mailchimp_client: MailchimpOptions = MailchimpOptions::new(
-        env::var("MAILCHIMP_API_KEY").expect("MAILCHIMP_API_KEY must be set"),
-        env::var("MAILCHIMP_SERVER_PREFIX").expect("MAILCHIMP_SERVER_PREFIX must be set"),
-        env::var("MAILCHIMP_LIST_ID").expect("MAILCHIMP_LIST_ID must be set"),
+        config.mailchimp.api_key.clone(),
+        config.mailchimp.server_prefix.clone(),
+        config.mailchimp.list_id.clone(),
     );
 
     // Configurar cliente HTTP con timeouts mayores para Cal.com
@@ -139,9 +189,10 @@ async fn main() {
 
     let cal_options: CalOptions = CalOptions {
         client: cal_client,
-        base_url: env::var("CAL_BASE_URL").expect("CAL_BASE_URL must be set"),
-        api_key: env::var("CAL_API_KEY").expect("CAL_API_KEY must be set"),
-        team_id: env::var("TEAM_ID").ok(),
+        base_url: config.cal.base_url.clone(),
+        api_key: config.cal.api_key.clone(),
+        team_id: config.cal.team_id.clone(),
+        polling_interval_secs: config.cal.polling_interval_secs,
         booking_cache: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
         recent_changes: Arc::new(tokio::sync::RwLock::new(Vec::new())),
     };
@@ -150,13 +201,17 @@ async fn main() {
     let ga_options: GAOptions = GAOptions {
         client: HttpClient::new(),
         service_account: ServiceAccount {
-            client_email: env::var("GA_CLIENT_EMAIL").expect("GA_CLIENT_EMAIL must be set"),
-            private_key: env::var("GA_PRIVATE_KEY").expect("GA_PRIVATE_KEY must be set"),
+            client_email: config.ga.client_email.clone(),
+            private_key: config.ga.private_key.clone(),
         },
-        base_url: "https://analyticsdata.googleapis.com/v1beta".to_string(),
-        property_id: env::var("GA_PROPERTY_ID").expect("GA_PROPERTY_ID must be set"),
+        base_url: config.ga.base_url.clone(),
+        token_url: config.ga.token_url.clone(),
+        property_id: config.ga.property_id.clone(),
+        token_cache: Default::default(),
     };
 
+    let tasks: TaskSupervisor = TaskSupervisor::new();
+
     // Inicializar el estado de la aplicación y el enrutador
     let state: Arc<AppState> = Arc::new(AppState {
         firebase_options,
@@ -165,27 +220,40 @@ async fn main() {
         mailchimp_client,
         cal_options,
         ga_options,
+        repositories,
+        rate_limiter: RateLimiter::new(config.rate_limit.clone()),
+        tasks: tasks.clone(),
+        session_revocations: Default::default(),
+        booking_locks: Default::default(),
     });
 
-    // Configuración de CORS (Cross-Origin Resource Sharing)
+    // Configuración de CORS (Cross-Origin Resource Sharing), orígenes ya validados en Config
     let cors: CorsLayer = CorsLayer::new()
-        .allow_origin(AllowOrigin::list(vec![
-            "http://localhost:4321".parse().unwrap(), // Frontend desarrollo
-            "https://amanahacademia.com".parse().unwrap(), // Dominio de producción
-            "https://amanahacademia.vercel.app".parse().unwrap(), // Dominio alternativo de producción
-        ]))
+        .allow_origin(AllowOrigin::list(
+            config
+                .server
+                .cors_origins
+                .iter()
+                .filter_map(|origin| origin.parse::<HeaderValue>().ok()),
+        ))
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
         .allow_headers([CONTENT_TYPE, AUTHORIZATION]);
 
-    // Polling cada 5 minutos para recuperar bookings que no llegaron vía webhook.
-    // Se spawnea antes de `with_state` porque necesita ownership del clone.
+    // Tareas en segundo plano supervisadas: se reinician con backoff si terminan o hacen panic.
+    // Polling periódico (cal.polling_interval_secs) para recuperar bookings que no llegaron vía webhook
     let state_for_polling: Arc<AppState> = state.clone();
-    tokio::spawn(async move {
-        info!("Iniciando tarea de polling de Cal.com");
-        controllers::webhook::polling_task(state_for_polling).await;
-        error!("La tarea de polling ha terminado inesperadamente");
+    tasks.spawn("cal_polling", move |shutdown| {
+        controllers::webhook::polling_task(state_for_polling.clone(), shutdown)
     });
 
+    // Rotación de las claves públicas de Firebase según su Cache-Control
+    if !emulator_mode {
+        let state_for_keys: Arc<AppState> = state.clone();
+        tasks.spawn("firebase_keys", move |shutdown| {
+            services::firebase_keys::key_refresh_task(state_for_keys.clone(), shutdown)
+        });
+    }
+
     // Configurar el enrutador de la aplicación
     let app: Router = Router::new()
         .nest("/users", routes::users::router(state.clone()))
@@ -193,22 +261,52 @@ async fn main() {
         .nest("/payment", routes::payments::router(state.clone()))
         .nest("/teachers", routes::teachers::router(state.clone()))
         .nest("/surveys", routes::sourvey::router(state.clone()))
+        .nest(
+            "/subscriptions",
+            routes::subscriptions::router(state.clone()),
+        )
         .nest("/email", routes::email::router(state.clone()))
         .nest("/mailchimp", routes::mailchimp::router(state.clone()))
         .nest("/cal", routes::cal::router(state.clone()))
         .nest("/metrics", routes::metrics::router(state.clone()))
         .nest("/webhook", routes::webhooks::router(state.clone()))
+        .nest("/docs", routes::docs::router(state.clone()))
         .layer(cors)
         .layer(TraceLayer::new_for_http()) // Logging de requests para debugging
         .with_state(state); // Estado compartido
 
     // Inicializar el listener TCP y arrancar el servidor
-    let addr: SocketAddr = SocketAddr::from(([0, 0, 0, 0], 3000));
-    let listener: TcpListener = TcpListener::bind("0.0.0.0:3000").await.unwrap();
+    let addr: SocketAddr = SocketAddr::new(
+        config
+            .server
+            .host
+            .parse()
+            .expect("HOST validated in Config"),
+        config.server.port,
+    );
+    let listener: TcpListener = TcpListener::bind(addr).await.unwrap();
 
     info!("Server listening on http://{}", addr);
-    match axum::serve(listener, app).await {
+    // Al recibir SIGTERM/SIGINT se deja de aceptar conexiones, se terminan las peticiones en curso
+    // y las tareas en segundo plano acaban su iteración (p. ej. reembolsos a medio procesar)
+    let tasks_for_signal: TaskSupervisor = tasks.clone();
+    let shutdown_signal = async move {
+        wait_for_os_shutdown().await;
+        tasks_for_signal.begin_shutdown();
+    };
+
+    // ConnectInfo da al rate limiter la IP del cliente cuando no hay proxy delante
+    match axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal)
+    .await
+    {
         Ok(_) => info!("Server finalized"),
         Err(err) => error!("Error in server: {}", err),
     };
+
+    tasks.shutdown(TASKS_SHUTDOWN_TIMEOUT).await;
+    info!("Background tasks stopped, bye");
 }
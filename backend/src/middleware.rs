@@ -1 +1,3 @@
 pub mod auth;
+pub mod authorization;
+pub mod rate_limit;
@@ -1,24 +1,28 @@
 use {
-    crate::models::{
-        error::AuthError,
-        firebase::UserAuthentication,
-        metrics::{ClaimsGA, ServiceAccount, TokenResponse},
-        state::AppState,
+    crate::{
+        models::{
+            error::{ApiError, AuthError, MetricsError},
+            firebase::UserAuthentication,
+            metrics::{CachedAccessToken, TokenResponse},
+            state::AppState,
+        },
+        services::{
+            firebase_keys::force_refresh_firebase_keys,
+            google_oauth::request_service_account_token, sessions::session_revoked_at,
+        },
     },
     axum::{
         extract::{Request, State},
-        http::{HeaderValue, StatusCode},
+        http::HeaderValue,
         middleware::Next,
         response::Response,
     },
-    jsonwebtoken::{
-        Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation, decode, encode,
-    },
-    reqwest::Client as HttpClient,
+    base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD},
+    jsonwebtoken::{Algorithm, DecodingKey, Header, TokenData, Validation, decode},
     serde_json::Value,
     std::{
         sync::Arc,
-        time::{Duration, SystemTime, UNIX_EPOCH},
+        time::{SystemTime, UNIX_EPOCH},
     },
     tracing::{error, info, instrument, warn},
 };
@@ -28,35 +32,57 @@ pub async fn firebase_auth_middleware(
     State(state): State<Arc<AppState>>,
     mut request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
+) -> Result<Response, ApiError> {
     // Extract and validate the token
     let token: String = {
         let token_str: &str = extract_bearer_token(&request)?;
         token_str.to_string() // Convertimos a `String` para evitar problemas de lifetime
     };
 
-    // Obtener claves (con refresh si necesario)
-    let firebase_keys = get_or_refresh_keys(&state)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let project_id: &str = &state.firebase_options.firebase_project_id;
 
     // Verificar el token y obtener los claims del usuario
-    let user_claims: TokenData<UserAuthentication> = verify_firebase_token(
-        &token,
-        &firebase_keys,
-        &state.firebase_options.firebase_project_id,
-    )
+    let user_claims: UserAuthentication = if state.firebase_options.auth_emulator_host.is_some() {
+        // El emulador emite tokens sin firmar, no hay claves que consultar
+        verify_emulator_token(&token, project_id)
+    } else {
+        let firebase_keys: Value = current_firebase_keys(&state).await?;
+
+        match verify_firebase_token(&token, &firebase_keys, project_id) {
+            // Google puede haber rotado las claves antes de que venza nuestra caché
+            Err(AuthError::NoMatchingKey) => {
+                match force_refresh_firebase_keys(&state.firebase_options).await {
+                    Ok(Some(keys)) => verify_firebase_token(&token, &keys, project_id),
+                    Ok(None) => Err(AuthError::NoMatchingKey),
+                    Err(err) => {
+                        warn!("Failed to refresh Firebase keys for unknown kid: {}", err);
+                        Err(AuthError::NoMatchingKey)
+                    }
+                }
+            }
+            result => result,
+        }
+        .map(|data| data.claims)
+    }
     .map_err(|err| {
         warn!("Auth failed: {}", err); // ← Solo warn, no error
-        StatusCode::from(err)
+        ApiError::from(err)
     })?;
 
     // Loguear el user_id para trazabilidad
-    tracing::Span::current().record("user_id", &user_claims.claims.user_id);
+    tracing::Span::current().record("user_id", &user_claims.user_id);
+
+    // Tokens emitidos antes de cerrar todas las sesiones ya no valen, aunque no hayan caducado
+    if let Some(revoked_at) = session_revoked_at(&state, &user_claims.sub).await?
+        && user_claims.auth_time < revoked_at
+    {
+        warn!("Auth failed: session revoked");
+        return Err(AuthError::SessionRevoked.into());
+    }
 
     // Agregar los claims del usuario a las extensiones del request
     // para que puedan ser utilizados en los handlers
-    request.extensions_mut().insert(user_claims.claims);
+    request.extensions_mut().insert(user_claims);
 
     // Agregar el token original a las extensiones del request
     request.extensions_mut().insert(token.to_string());
@@ -107,8 +133,8 @@ pub fn verify_firebase_token(
 
     // Configura la validación del token
     let mut validation: Validation = Validation::new(Algorithm::RS256);
-    validation.set_audience(&["amanahacademia"]); // Reemplaza con tu project ID
-    validation.set_issuer(&[&"https://securetoken.google.com/amanahacademia".to_string()]); // Reemplaza con tu project ID
+    validation.set_audience(&[project_id]);
+    validation.set_issuer(&[firebase_issuer(project_id)]);
 
     // Verifica el token
     let decoding_key = DecodingKey::from_rsa_pem(public_key_pem.as_bytes())
@@ -120,55 +146,79 @@ pub fn verify_firebase_token(
     Ok(token_data)
 }
 
-/// Función que maneja el refresh
-async fn get_or_refresh_keys(
-    state: &Arc<AppState>,
-) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-    // Leer caché
-    {
-        let cache = state.firebase_options.firebase_keys.read().await;
-        if !cache.is_expired() {
-            return Ok(cache.keys.clone());
-        }
-    }
+/// Issuer de los ID tokens de Firebase para un proyecto
+fn firebase_issuer(project_id: &str) -> String {
+    format!("https://securetoken.google.com/{}", project_id)
+}
 
-    // Caché expirado, refrescar
-    warn!("Firebase keys expired, refreshing...");
+// Verificamos un token del Firebase Auth Emulator. Vienen sin firmar (`alg: none`),
+// así que solo se comprueban los claims: audiencia, issuer y expiración.
+#[instrument(skip(token))]
+pub fn verify_emulator_token(
+    token: &str,
+    project_id: &str,
+) -> Result<UserAuthentication, AuthError> {
+    let claims: UserAuthentication = decode_token_claims(token)?;
 
-    let new_keys = fetch_firebase_keys_internal(&state.firebase_options.firebase_client).await?;
+    if claims.aud != project_id {
+        return Err(AuthError::TokenVerification("InvalidAudience".to_string()));
+    }
+    if claims.iss != firebase_issuer(project_id) {
+        return Err(AuthError::TokenVerification("InvalidIssuer".to_string()));
+    }
 
-    // Actualizar caché
-    {
-        let mut cache = state.firebase_options.firebase_keys.write().await;
-        cache.keys = new_keys.clone();
-        cache.fetched_at = SystemTime::now();
+    let now: i64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    if claims.exp < now {
+        return Err(AuthError::TokenVerification("ExpiredSignature".to_string()));
     }
 
-    info!("Firebase keys refreshed successfully");
-    Ok(new_keys)
+    Ok(claims)
 }
 
-/// Función interna para obtener las claves públicas de Firebase
-async fn fetch_firebase_keys_internal(
-    client: &HttpClient,
-) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-    let response = client
-        .get("https://www.googleapis.com/robot/v1/metadata/x509/securetoken@system.gserviceaccount.com")
-        .timeout(Duration::from_secs(10))
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
-        return Err("Firebase keys endpoint error".into());
-    }
+/// Lee los claims de un ID token SIN verificar la firma. Solo para tokens que acabamos de
+/// recibir de Firebase (login, refresco) o que ya se han verificado por otra vía.
+pub fn decode_token_claims(token: &str) -> Result<UserAuthentication, AuthError> {
+    let payload: &str = token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| AuthError::TokenVerification("Malformed token".to_string()))?;
 
-    let keys: Value = response.json().await?;
+    let decoded: Vec<u8> = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|e| AuthError::TokenVerification(e.to_string()))?;
+
+    serde_json::from_slice(&decoded).map_err(|e| AuthError::TokenVerification(e.to_string()))
+}
 
-    if !keys.is_object() || keys.as_object().is_none_or(|m| m.is_empty()) {
-        return Err("Empty Firebase keys".into());
+/// Claves públicas en caché. Aunque hayan vencido se sirven igualmente: las revalida
+/// `key_refresh_task` en segundo plano. Solo si aún no hay ninguna (arranque degradado)
+/// se intenta una descarga en el momento.
+async fn current_firebase_keys(state: &Arc<AppState>) -> Result<Value, ApiError> {
+    {
+        let cache = state.firebase_options.firebase_keys.read().await;
+        if !cache.is_empty() {
+            if cache.is_expired() {
+                warn!("Serving expired Firebase keys while they are revalidated");
+            }
+            return Ok(cache.keys.clone());
+        }
     }
 
-    Ok(keys)
+    match force_refresh_firebase_keys(&state.firebase_options).await {
+        Ok(Some(keys)) => Ok(keys),
+        Ok(None) => Err(ApiError::Unavailable(
+            "Authentication is temporarily unavailable".to_string(),
+        )),
+        Err(err) => {
+            error!("Failed to get Firebase keys: {}", err);
+            Err(ApiError::Unavailable(
+                "Authentication is temporarily unavailable".to_string(),
+            ))
+        }
+    }
 }
 
 /// Midelware para obtener el token de Goolge Analytics sin verificar usuario
@@ -176,12 +226,9 @@ pub async fn public_ga_auth_middleware(
     State(state): State<Arc<AppState>>,
     mut request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
+) -> Result<Response, ApiError> {
     // Solo obtener el token de Google Analytics (sin verificar usuario)
-    let ga_token: String = get_ga_token(&state).await.map_err(|e| {
-        error!("Failed to get GA token: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    let ga_token: String = get_ga_token(&state).await?;
 
     // Agregar el token de GA a las extensiones del request
     request
@@ -191,37 +238,38 @@ pub async fn public_ga_auth_middleware(
     Ok(next.run(request).await)
 }
 
-/// Función auxiliar para obtener el token de Google Analytics
-async fn get_ga_token(state: &Arc<AppState>) -> Result<String, Box<dyn std::error::Error>> {
-    let now: u64 = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-    let service_account: &ServiceAccount = &state.ga_options.service_account;
-
-    let claims: ClaimsGA = ClaimsGA {
-        iss: service_account.client_email.clone(),
-        scope: "https://www.googleapis.com/auth/analytics.readonly".to_string(),
-        aud: "https://oauth2.googleapis.com/token".to_string(),
-        exp: now + 3600,
-        iat: now,
-    };
+/// Devuelve el access token de Google Analytics, reutilizando el de la caché mientras no esté
+/// a punto de caducar. El lock se mantiene durante el refresco para que solo una petición
+/// vaya al endpoint de tokens y el resto reciba el mismo token.
+async fn get_ga_token(state: &Arc<AppState>) -> Result<String, MetricsError> {
+    let mut cache = state.ga_options.token_cache.lock().await;
+
+    if let Some(cached) = cache.as_ref()
+        && cached.is_usable()
+    {
+        return Ok(cached.access_token.clone());
+    }
 
-    let encoding_key: EncodingKey =
-        EncodingKey::from_rsa_pem(service_account.private_key.as_bytes())?;
-    let jwt: String = encode(&Header::new(Algorithm::RS256), &claims, &encoding_key)?;
-
-    let response: TokenResponse = state
-        .ga_options
-        .client
-        .post("https://oauth2.googleapis.com/token")
-        .form(&[
-            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
-            ("assertion", &jwt),
-        ])
-        .send()
-        .await?
-        .json()
-        .await?;
-
-    Ok(response.access_token)
+    let token: TokenResponse = request_ga_token(state).await?;
+    info!(
+        "Google Analytics access token refreshed (expires in {}s)",
+        token.expires_in
+    );
+
+    let access_token: String = token.access_token.clone();
+    *cache = Some(CachedAccessToken::new(token.access_token, token.expires_in));
+    Ok(access_token)
+}
+
+/// Firma el JWT de la cuenta de servicio y lo intercambia por un access token
+async fn request_ga_token(state: &Arc<AppState>) -> Result<TokenResponse, MetricsError> {
+    Ok(request_service_account_token(
+        &state.ga_options.client,
+        &state.ga_options.service_account,
+        "https://www.googleapis.com/auth/analytics.readonly",
+        &state.ga_options.token_url,
+    )
+    .await?)
 }
 
 #[cfg(test)]
@@ -0,0 +1,116 @@
+use {
+    crate::models::{
+        error::ApiError,
+        firebase::UserAuthentication,
+        state::AppState,
+        user::{Permission, Role, UserDB},
+    },
+    axum::{
+        extract::{Request, State},
+        middleware::Next,
+        response::Response,
+    },
+    std::sync::Arc,
+    tracing::{instrument, warn},
+};
+
+/// Requisito de autorización que se comprueba contra el perfil del usuario en la base de datos
+pub trait Authorization: Clone + Send + Sync + 'static {
+    fn is_authorized(&self, user: &UserDB) -> bool;
+}
+
+/// Exige que el usuario tenga un rol concreto
+#[derive(Debug, Clone)]
+pub struct RequireRole(pub Role);
+
+impl Authorization for RequireRole {
+    fn is_authorized(&self, user: &UserDB) -> bool {
+        user.role.as_deref() == Some(self.0.as_ref())
+    }
+}
+
+/// Exige que el usuario tenga un permiso concreto (los administradores los tienen todos)
+#[derive(Debug, Clone, Copy)]
+pub struct RequirePermission(pub Permission);
+
+impl Authorization for RequirePermission {
+    fn is_authorized(&self, user: &UserDB) -> bool {
+        RequireRole(Role::Admin).is_authorized(user)
+            || user
+                .permissions
+                .as_ref()
+                .is_some_and(|permissions| permissions.contains(self.0.as_ref()))
+    }
+}
+
+/// Middleware de autorización. Debe ir detrás de `firebase_auth_middleware`:
+///
+/// ```ignore
+/// .route_layer(middleware::from_fn_with_state(
+///     (state.clone(), RequireRole(Role::Admin)),
+///     require_authorization::<RequireRole>,
+/// ))
+/// ```
+///
+/// Si el usuario está autorizado, su `UserDB` queda en las extensiones del request.
+#[instrument(skip(state, requirement, request, next))]
+pub async fn require_authorization<A>(
+    State((state, requirement)): State<(Arc<AppState>, A)>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError>
+where
+    A: Authorization + std::fmt::Debug,
+{
+    let user_id: String = request
+        .extensions()
+        .get::<UserAuthentication>()
+        .map(|claims| claims.user_id.clone())
+        .ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+
+    // Sin perfil en la base de datos no hay rol ni permisos
+    let user: Option<UserDB> = state.repositories.users.get(&user_id).await?;
+
+    match user {
+        Some(user) if requirement.is_authorized(&user) => {
+            request.extensions_mut().insert(user);
+            Ok(next.run(request).await)
+        }
+        _ => {
+            warn!(user_id = %user_id, requirement = ?requirement, "Access denied");
+            Err(ApiError::Forbidden(
+                "You do not have permission to access this resource".to_string(),
+            ))
+        }
+    }
+}
+
+/// Exige que el email del usuario esté verificado (claim `email_verified` del ID token).
+/// El estado es la acción protegida, que aparece en el mensaje de error:
+///
+/// ```ignore
+/// .route_layer(middleware::from_fn_with_state("booking", require_verified_email))
+/// ```
+///
+/// Tras verificar el email el frontend debe refrescar el token para que el claim cambie.
+pub async fn require_verified_email(
+    State(action): State<&'static str>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let claims: &UserAuthentication = request
+        .extensions()
+        .get::<UserAuthentication>()
+        .ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+
+    if claims.email_verified != Some(true) {
+        warn!(user_id = %claims.user_id, action, "Email not verified");
+        return Err(ApiError::EmailNotVerified(action));
+    }
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+#[path = "../test/middleware/authorization.rs"]
+mod extended_tests;
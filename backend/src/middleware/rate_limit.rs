@@ -0,0 +1,80 @@
+use {
+    crate::models::{
+        error::ApiError,
+        firebase::UserAuthentication,
+        rate_limit::{RateLimitDecision, RateLimitGroup},
+        state::AppState,
+    },
+    axum::{
+        extract::{ConnectInfo, Request, State},
+        middleware::Next,
+        response::Response,
+    },
+    std::{net::SocketAddr, sync::Arc},
+    tracing::{instrument, warn},
+};
+
+/// Middleware de rate limiting por grupo de rutas:
+///
+/// ```ignore
+/// .route_layer(middleware::from_fn_with_state(
+///     (state.clone(), RateLimitGroup::Auth),
+///     rate_limit,
+/// ))
+/// ```
+///
+/// Si la ruta está autenticada (va detrás de `firebase_auth_middleware`) el bucket es por uid,
+/// si no por IP del cliente. Al agotarse el presupuesto responde 429 con `Retry-After`.
+#[instrument(skip(state, request, next))]
+pub async fn rate_limit(
+    State((state, group)): State<(Arc<AppState>, RateLimitGroup)>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let subject: String = rate_limit_subject(&state, &request);
+
+    match state.rate_limiter.check(group, &subject).await {
+        RateLimitDecision::Allowed { .. } => Ok(next.run(request).await),
+        RateLimitDecision::Limited { retry_after } => {
+            warn!(subject = %subject, group = group.as_ref(), "Rate limit exceeded");
+            Err(ApiError::RateLimited {
+                // Redondeamos hacia arriba para no invitar a reintentar antes de tiempo
+                retry_after_secs: retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0),
+            })
+        }
+    }
+}
+
+/// Identifica a quién se le cobra la petición: uid si está autenticado, si no la IP
+fn rate_limit_subject(state: &AppState, request: &Request) -> String {
+    if let Some(claims) = request.extensions().get::<UserAuthentication>() {
+        return format!("uid:{}", claims.user_id);
+    }
+
+    // Solo se confía en la cabecera si se ha configurado (proxy propio delante)
+    let from_header: Option<String> = state
+        .rate_limiter
+        .config
+        .client_ip_header
+        .as_deref()
+        .and_then(|header| request.headers().get(header))
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty());
+
+    let ip: String = from_header
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    format!("ip:{}", ip)
+}
+
+#[cfg(test)]
+#[path = "../test/middleware/rate_limit.rs"]
+mod extended_tests;
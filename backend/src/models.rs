@@ -5,10 +5,14 @@ pub mod error;
 pub mod firebase;
 pub mod mailchimp;
 pub mod metrics;
+pub mod projection;
+pub mod rate_limit;
 pub mod response;
+pub mod session;
 pub mod sourvey;
 pub mod state;
 pub mod stripe;
+pub mod subscription;
 pub mod teacher;
 pub mod user;
 pub mod webhook;
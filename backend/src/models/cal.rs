@@ -1,11 +1,13 @@
+use axum::http::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use thiserror::Error;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::models::webhook::{Attendee, Organizer};
 
 /// Estado de los bookings de cal
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default, ToSchema)]
 /// Cal.com API v2 envía valores en UPPERCASE o lowercase según el endpoint
 /// Usamos alias para compatibilidad con ambos formatos
 #[serde(rename_all = "lowercase")]
@@ -30,7 +32,7 @@ pub struct CalApiResponse<T> {
     pub data: T,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Availability {
     pub days: Vec<String>,
@@ -38,7 +40,7 @@ pub struct Availability {
     pub end_time: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Schedule {
     pub id: u64,
@@ -50,7 +52,8 @@ pub struct Schedule {
     pub overrides: Vec<serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct BookingsQueryParams {
     #[serde(rename = "eventTypeId")]
     pub event_type_id: Option<String>,
@@ -79,7 +82,7 @@ pub struct BookingsQueryParams {
     pub sort_start: Option<String>, // "asc" | "desc"
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct UserCal {
     #[serde(default)]
     pub id: i64,
@@ -91,7 +94,7 @@ pub struct UserCal {
     pub time_zone: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct EventTypeCal {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub id: Option<i64>,
@@ -103,7 +106,7 @@ pub struct EventTypeCal {
 
 /// Estructura de los datos de la reserva incluidos en el payload del webhook de Cal.com
 /// Compatible con Cal.com API v2 (webhooks y endpoints REST)
-#[derive(Deserialize, Debug, Serialize, Clone)]
+#[derive(Deserialize, Debug, Serialize, Clone, ToSchema)]
 pub struct CalBookingPayload {
     /// ID numérico directo de Cal.com (cuando viene como "id" en respuestas)
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -279,13 +282,13 @@ pub struct CalBookingPayload {
     pub guests: Option<Vec<String>>,
 }
 /// Estructura para agregar invitados a un booking
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct AddGuestsPayload {
     pub guests: Vec<GuestInput>,
 }
 
 /// Estructura de un invitado para agregar a un booking
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GuestInput {
     pub email: String,
@@ -302,9 +305,13 @@ pub enum FetchCalErrors {
 
     #[error("Failed to parse Cal.com response: {0}")]
     ParseError(reqwest::Error),
+
+    #[error("Cal.com error: {message}")]
+    Api { status: StatusCode, message: String },
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct SchedulesQuery {
     #[serde(default)]
     pub team: bool,
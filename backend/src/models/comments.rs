@@ -1,7 +1,35 @@
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use utoipa::{IntoParams, ToSchema};
+
+/// Estado de moderación de comentarios y respuestas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationStatus {
+    /// En cola, solo lo ve su autor
+    Pending,
+    /// Publicado. Es el valor por defecto porque lo anterior a la moderación ya era público
+    #[default]
+    Approved,
+    Rejected,
+    /// Retirado después de haberse publicado
+    Hidden,
+}
+
+impl AsRef<str> for ModerationStatus {
+    fn as_ref(&self) -> &str {
+        match self {
+            ModerationStatus::Pending => "pending",
+            ModerationStatus::Approved => "approved",
+            ModerationStatus::Rejected => "rejected",
+            ModerationStatus::Hidden => "hidden",
+        }
+    }
+}
 
 /// Comentario en Firebase DB
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Comment {
     pub author_uid: Option<String>, // Usuario que comento
     pub name: String,               // Nombre del usuario
@@ -16,10 +44,21 @@ pub struct Comment {
     pub reply: Vec<ReplyComment>, // Respuestas al comentario
     #[serde(default)]
     pub users_liked: Vec<String>, // Usuarios que le dieron like
+    #[serde(default)]
+    pub moderation_status: ModerationStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moderation_reason: Option<String>, // Motivo del rechazo u ocultación
+    #[serde(default)]
+    pub verified_student: bool, // Reseña de un alumno con una clase completada
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub booking_uid: Option<String>, // Reserva de Cal.com que respalda la reseña
+    /// Si quien hace la petición le ha dado like. Solo se rellena en respuestas, no se guarda
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub liked: Option<bool>,
 }
 
 /// Constestación de comentarios
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ReplyComment {
     pub id: String,
     pub author_uid: String, // Usuario que comento
@@ -32,11 +71,83 @@ pub struct ReplyComment {
     pub like: u32, // Likes del comentario
     #[serde(default)]
     pub users_liked: Vec<String>, // Usuarios que le dieron like
+    #[serde(default)]
+    pub moderation_status: ModerationStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moderation_reason: Option<String>, // Motivo del rechazo u ocultación
+    /// Si quien hace la petición le ha dado like. Solo se rellena en respuestas, no se guarda
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub liked: Option<bool>,
 }
 
 /// Actualización típica de comentario
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateComment {
     pub content: String,
     pub stars: f32,
 }
+
+/// Decisión de un administrador sobre un comentario o respuesta
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ModerationDecision {
+    pub status: ModerationStatus,
+    /// Obligatorio al rechazar u ocultar
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Filtro de la cola de moderación (por defecto, pendientes)
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+pub struct ModerationQueueQuery {
+    pub status: Option<ModerationStatus>,
+}
+
+/// Respuesta en la cola de moderación junto al comentario al que pertenece
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QueuedReply {
+    pub comment_id: String,
+    pub reply: ReplyComment,
+}
+
+/// Comentarios y respuestas con el estado pedido
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ModerationQueue {
+    pub comments: HashMap<String, Comment>,
+    pub replies: Vec<QueuedReply>,
+}
+
+/// Orden del listado público de comentarios
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentSort {
+    #[default]
+    Newest,
+    MostLiked,
+    HighestStars,
+    LowestStars,
+}
+
+/// Orden y filtros de `/comments/all`
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+pub struct CommentListQuery {
+    /// Por defecto, los más recientes primero
+    pub sort: Option<CommentSort>,
+    /// Estrellas mínimas (0-5)
+    pub min_stars: Option<f32>,
+    /// `true` solo con respuestas, `false` solo sin respuestas
+    pub has_replies: Option<bool>,
+}
+
+/// Resumen de las valoraciones publicadas
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RatingSummary {
+    /// Media de estrellas, redondeada a un decimal; 0 si no hay reseñas
+    pub average: f32,
+    /// Número de reseñas (comentarios con estrellas)
+    pub count: usize,
+    /// Número de reseñas por estrellas (1-5, redondeando las medias estrellas)
+    pub histogram: BTreeMap<u8, usize>,
+    /// Reseñas con más likes, en orden
+    #[schema(value_type = HashMap<String, Comment>)]
+    pub most_liked: IndexMap<String, Comment>,
+}
@@ -1,6 +1,11 @@
 use {
-    crate::models::{metrics::GAErrorResponse, response::ResponseAPI},
-    axum::{Json, http::StatusCode},
+    crate::models::{cal::FetchCalErrors, metrics::GAErrorResponse, response::ResponseAPI},
+    axum::{
+        Json,
+        http::{HeaderValue, StatusCode, header::RETRY_AFTER},
+        response::{IntoResponse, Response},
+    },
+    stripe::StripeError,
 };
 
 /// Errores relacionados con la autenticación de Firebase
@@ -18,12 +23,16 @@ pub enum AuthError {
     NoMatchingKey,
     #[error("Invalid key format")]
     InvalidKeyFormat,
+    #[error("Session revoked, sign in again")]
+    SessionRevoked,
 }
 
 impl From<AuthError> for StatusCode {
     fn from(err: AuthError) -> Self {
         match err {
-            AuthError::MissingHeader | AuthError::InvalidHeaderFormat => StatusCode::UNAUTHORIZED,
+            AuthError::MissingHeader
+            | AuthError::InvalidHeaderFormat
+            | AuthError::SessionRevoked => StatusCode::UNAUTHORIZED,
             AuthError::TokenVerification(_)
             | AuthError::MissingKid
             | AuthError::NoMatchingKey
@@ -32,6 +41,31 @@ impl From<AuthError> for StatusCode {
     }
 }
 
+/// Errores al descargar las claves públicas de Firebase
+#[derive(Debug, thiserror::Error)]
+pub enum FirebaseKeysError {
+    #[error("Firebase keys request failed: {0}")]
+    Network(#[from] reqwest::Error),
+    #[error("Firebase keys endpoint returned {0}")]
+    Status(StatusCode),
+    #[error("Firebase public keys are empty")]
+    Empty,
+}
+
+/// Errores al pedir un access token de Google con una cuenta de servicio
+#[derive(Debug, thiserror::Error)]
+pub enum ServiceAccountError {
+    #[error("Invalid service account credentials: {0}")]
+    Credentials(String),
+
+    #[error("Token request failed: {0}")]
+    Network(#[from] reqwest::Error),
+
+    /// El cuerpo de la respuesta solo se registra en el log al recibirla
+    #[error("Token endpoint rejected the request ({status})")]
+    Token { status: StatusCode },
+}
+
 /// Errores relacionados con las metricas de Google Analitics
 #[derive(Debug, thiserror::Error)]
 pub enum MetricsError {
@@ -46,24 +80,21 @@ pub enum MetricsError {
 
     #[error("Failed to parse response: {0}")]
     Parse(#[from] serde_json::Error),
+
+    #[error("Invalid Google Analytics service account credentials: {0}")]
+    Credentials(String),
+
+    #[error("Google Analytics token endpoint rejected the request ({status})")]
+    Token { status: StatusCode },
 }
 
-impl From<MetricsError> for (StatusCode, Json<ResponseAPI<()>>) {
-    fn from(err: MetricsError) -> Self {
-        let (status, message) = match &err {
-            MetricsError::Network(_) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to fetch metrics: {}", err),
-            ),
-            MetricsError::Api { message, status } => (
-                StatusCode::BAD_REQUEST,
-                format!("Google Analytics API error: {} ({})", message, status),
-            ),
-            MetricsError::ApiText(_) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{}", err)),
-            MetricsError::Parse(_) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{}", err)),
-        };
-
-        (status, Json(ResponseAPI::error(message)))
+impl From<ServiceAccountError> for MetricsError {
+    fn from(err: ServiceAccountError) -> Self {
+        match err {
+            ServiceAccountError::Credentials(message) => MetricsError::Credentials(message),
+            ServiceAccountError::Network(err) => MetricsError::Network(err),
+            ServiceAccountError::Token { status } => MetricsError::Token { status },
+        }
     }
 }
 
@@ -75,3 +106,200 @@ impl From<GAErrorResponse> for MetricsError {
         }
     }
 }
+
+/// Errores de la capa de persistencia (Firebase Realtime Database o SQLite)
+#[derive(Debug, thiserror::Error)]
+pub enum RepositoryError {
+    #[error("Database connection error: {0}")]
+    Network(#[from] reqwest::Error),
+
+    #[error("Database returned error: {message} ({status})")]
+    Status { status: StatusCode, message: String },
+
+    #[error("Failed to parse database data: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    /// Escritura condicional rechazada: otro escritor cambió el registro desde la lectura
+    #[error("Record was modified concurrently")]
+    Conflict,
+}
+
+/// Errores de configuración detectados al arrancar. Se acumulan todos para mostrar un único informe.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid configuration:\n  - {}", .problems.join("\n  - "))]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+/// Error común de la API. Todos los handlers acaban devolviendo este tipo (o convirtiéndose a él)
+/// para que las respuestas de error tengan siempre el mismo sobre `ResponseAPI` con un `code` estable.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+
+    #[error(transparent)]
+    Metrics(#[from] MetricsError),
+
+    #[error(transparent)]
+    Cal(#[from] FetchCalErrors),
+
+    #[error("Stripe error: {0}")]
+    Stripe(#[from] StripeError),
+
+    #[error("Upstream request failed: {0}")]
+    Network(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+
+    #[error("Invalid JSON: {0}")]
+    InvalidJson(String),
+
+    #[error("Validation error: {0}")]
+    Validation(String),
+
+    #[error("{0}")]
+    BadRequest(String),
+
+    #[error("{0}")]
+    Unauthorized(String),
+
+    #[error("{0}")]
+    Forbidden(String),
+
+    #[error("Email address must be verified before {0}")]
+    EmailNotVerified(&'static str),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("{0}")]
+    Unavailable(String),
+
+    #[error("Too many requests, retry in {retry_after_secs} seconds")]
+    RateLimited { retry_after_secs: u64 },
+
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl ApiError {
+    /// Código estable que consume el frontend. No cambiar los valores existentes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::Auth(AuthError::MissingHeader) => "auth_missing_header",
+            ApiError::Auth(AuthError::InvalidHeaderFormat) => "auth_invalid_header",
+            ApiError::Auth(AuthError::SessionRevoked) => "auth_session_revoked",
+            ApiError::Auth(_) => "auth_invalid_token",
+            ApiError::Metrics(MetricsError::Api { .. }) => "metrics_api_error",
+            ApiError::Metrics(MetricsError::Credentials(_) | MetricsError::Token { .. }) => {
+                "metrics_auth_failed"
+            }
+            ApiError::Metrics(_) => "metrics_unavailable",
+            ApiError::Cal(FetchCalErrors::Api { .. }) => "cal_api_error",
+            ApiError::Cal(_) => "cal_unavailable",
+            ApiError::Stripe(_) => "stripe_error",
+            ApiError::Network(_) => "upstream_unavailable",
+            ApiError::Repository(RepositoryError::Conflict) => "conflict",
+            ApiError::Repository(_) => "database_error",
+            ApiError::InvalidJson(_) => "invalid_json",
+            ApiError::Validation(_) => "validation_failed",
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::EmailNotVerified(_) => "email_not_verified",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Unavailable(_) => "service_unavailable",
+            ApiError::RateLimited { .. } => "rate_limited",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::Auth(
+                AuthError::MissingHeader
+                | AuthError::InvalidHeaderFormat
+                | AuthError::SessionRevoked,
+            ) => StatusCode::UNAUTHORIZED,
+            ApiError::Auth(_) => StatusCode::FORBIDDEN,
+            ApiError::Metrics(MetricsError::Api { .. }) => StatusCode::BAD_REQUEST,
+            ApiError::Metrics(MetricsError::Token { .. }) => StatusCode::BAD_GATEWAY,
+            ApiError::Metrics(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Cal(FetchCalErrors::Api { status, .. }) => *status,
+            ApiError::Cal(_) | ApiError::Network(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Stripe(StripeError::Stripe(err)) => match err.http_status {
+                404 => StatusCode::NOT_FOUND,
+                400..=499 => StatusCode::BAD_REQUEST,
+                _ => StatusCode::BAD_GATEWAY,
+            },
+            ApiError::Stripe(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Repository(RepositoryError::Conflict) => StatusCode::CONFLICT,
+            ApiError::Repository(RepositoryError::Status { status, .. })
+                if status.is_client_error() =>
+            {
+                *status
+            }
+            ApiError::Repository(_) | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::InvalidJson(_) | ApiError::Validation(_) | ApiError::BadRequest(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) | ApiError::EmailNotVerified(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    /// Mensaje que recibe el cliente. Los errores 5xx de la base de datos y de servicios
+    /// externos pueden incluir URLs o datos internos, así que el detalle solo va al log.
+    pub fn public_message(&self) -> String {
+        if !self.status().is_server_error() {
+            return self.to_string();
+        }
+        match self {
+            ApiError::Repository(_) => "Database error".to_string(),
+            ApiError::Network(_) => "Upstream service unavailable".to_string(),
+            ApiError::Stripe(_) => "Payment provider error".to_string(),
+            ApiError::Internal(_) => "Internal server error".to_string(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        if status.is_server_error() {
+            tracing::error!(code = self.code(), "{}", self);
+        } else {
+            tracing::warn!(code = self.code(), "{}", self);
+        }
+
+        let mut response: Response = (
+            status,
+            Json(ResponseAPI::<()>::error_with_code(
+                self.code(),
+                self.public_message(),
+            )),
+        )
+            .into_response();
+
+        if let ApiError::RateLimited { retry_after_secs } = self {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
+    }
+}
@@ -2,15 +2,25 @@ use {
     crate::models::user::UserDB,
     serde::{Deserialize, Serialize},
     std::collections::HashMap,
+    utoipa::ToSchema,
 };
 
 /// Token usado para refrescar la sesión cuando el idToken de Firebase expira
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct RefreshToken {
     pub grant_type: String,
     pub refresh_token: String,
 }
 
+/// Respuesta de Secure Token al refrescar la sesión (en snake_case, a diferencia de Identity Toolkit)
+#[derive(Debug, Deserialize)]
+pub struct SecureTokenResponse {
+    pub id_token: String,
+    pub refresh_token: String,
+    pub expires_in: String,
+    pub user_id: String,
+}
+
 /// Payload para autenticación de usuarios con email/password en Firebase
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct UserAuth {
@@ -24,6 +34,43 @@ pub struct UserAuth {
     pub display_name: Option<String>,
 }
 
+/// Tipos de código de un solo uso (oobCode) que genera Identity Toolkit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OobRequestType {
+    VerifyEmail,
+    PasswordReset,
+}
+
+/// Petición a `projects/{id}/accounts:sendOobCode`. Con `return_oob_link` Firebase devuelve el
+/// enlace en vez de mandar su email genérico, y así enviamos el nuestro con Resend. Google solo
+/// lo respeta con credenciales de administrador, que también permiten identificar la cuenta por
+/// email en los dos tipos de código.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendOobCodeRequest {
+    pub request_type: OobRequestType,
+    pub email: String,
+    pub return_oob_link: bool,
+}
+
+/// Respuesta de `accounts:sendOobCode`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendOobCodeResponse {
+    pub email: Option<String>,
+    /// Solo llega si Firebase acepta `returnOobLink`; si no, ya ha enviado él el email
+    pub oob_link: Option<String>,
+}
+
+/// Respuesta de `accounts:resetPassword` y de `accounts:update` al aplicar un oobCode
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OobCodeResult {
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+}
+
 /// Claims (declaraciones) del JWT de Firebase después de autenticar un usuario
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UserAuthentication {
@@ -52,6 +99,17 @@ pub struct UserAuthentication {
     pub provider_id: Option<String>,
 }
 
+impl UserAuthentication {
+    /// Email del token solo si Firebase lo ha verificado. Los datos que se buscan por email
+    /// (reservas, encuestas, newsletter) solo pertenecen al usuario con un email verificado.
+    pub fn verified_email(&self) -> Option<&str> {
+        match self.email.as_deref() {
+            Some(email) if self.email_verified == Some(true) => Some(email),
+            _ => None,
+        }
+    }
+}
+
 /// Respuesta de Firebase Auth tras login/signup exitoso
 #[derive(Debug, Deserialize, Serialize)]
 pub struct FirebaseAuthResponse {
@@ -74,7 +132,7 @@ pub struct FirebaseAuthResponse {
 }
 
 /// Información completa de un usuario obtenida desde Firebase Admin API
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct FirebaseUserInfo {
     #[serde(rename = "localId")]
     pub local_id: String,
@@ -112,7 +170,7 @@ pub struct FirebaseUserInfo {
 }
 
 /// Información del proveedor de autenticación (Google, Password, etc.)
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct ProviderUserInfo {
     /// Tipo de proveedor: "password", "google.com", "facebook.com", etc.
     #[serde(rename = "providerId")]
@@ -129,6 +187,34 @@ pub struct ProviderUserInfo {
     pub raw_id: Option<String>,
 }
 
+/// Respuesta de `accounts:update` (vincular contraseña o desvincular proveedores)
+#[derive(Debug, Deserialize)]
+pub struct AccountUpdateResponse {
+    #[serde(rename = "localId")]
+    pub local_id: String,
+    #[serde(rename = "providerUserInfo", default)]
+    pub provider_user_info: Vec<ProviderUserInfo>,
+    /// Solo cuando se cambia la contraseña: Firebase emite tokens nuevos
+    #[serde(rename = "idToken")]
+    pub id_token: Option<String>,
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: Option<String>,
+}
+
+/// Claims de un id_token de Google Sign-In devueltas por el endpoint `tokeninfo`
+#[derive(Debug, Deserialize)]
+pub struct GoogleTokenInfo {
+    /// ID de la cuenta de Google (`rawId` del proveedor en Firebase)
+    pub sub: String,
+    /// OAuth client ID para el que se emitió el token
+    pub aud: String,
+    pub email: Option<String>,
+    /// `tokeninfo` lo devuelve como string ("true"/"false")
+    pub email_verified: Option<String>,
+    pub name: Option<String>,
+    pub picture: Option<String>,
+}
+
 /// Información adicional de Firebase en el token JWT
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FirebaseInfo {
@@ -144,8 +230,17 @@ pub struct FirebaseAdminLookupResponse {
     pub users: Vec<FirebaseUserInfo>,
 }
 
+/// Página de cuentas de Firebase Admin API (`accounts:batchGet`)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FirebaseAccountsPage {
+    #[serde(default)]
+    pub users: Vec<FirebaseUserInfo>,
+    pub next_page_token: Option<String>,
+}
+
 /// Usuario combinado con datos de Firebase Auth y nuestra base de datos
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct UserMerged {
     pub local_id: String,
     /// Indica si el usuario ya usó su clase gratuita de prueba
@@ -173,7 +268,8 @@ pub struct UserMerged {
     pub permissions: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub provider_user_info: Option<Vec<ProviderUserInfo>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Nunca se serializa: no sale del servidor sea cual sea la vista
+    #[serde(skip_serializing)]
     pub password_hash: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub password_updated_at: Option<f64>,
@@ -196,10 +292,11 @@ impl FirebaseAdminLookupResponse {
             .map(|auth_user| {
                 // Intentar encontrar usuario en nuestra DB por ID o email
                 let db_user = db_users.get(&auth_user.local_id).or_else(|| {
-                    auth_user
-                        .email
-                        .as_ref()
-                        .and_then(|email| db_users.values().find(|db| db.email == *email))
+                    auth_user.email.as_ref().and_then(|email| {
+                        db_users
+                            .values()
+                            .find(|db| db.email.eq_ignore_ascii_case(email))
+                    })
                 });
 
                 UserMerged {
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Contact {
     pub email_address: String,
     /// Estado del contacto: "subscribed", "unsubscribed", "cleaned", "pending"
@@ -10,7 +11,7 @@ pub struct Contact {
     pub merge_fields: Option<MergeFields>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
 pub struct MergeFields {
     /// Nombre del contacto
     #[serde(rename = "FNAME")]
@@ -20,7 +21,7 @@ pub struct MergeFields {
     pub lname: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AddContactResponse {
     /// ID único del contacto en Mailchimp
     pub id: String,
@@ -56,7 +57,7 @@ pub struct ErrorMailchimp {
     pub instance: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct MembersResponse {
     pub members: Vec<Member>,
     pub list_id: String,
@@ -66,7 +67,7 @@ pub struct MembersResponse {
     pub _links: Vec<Link>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Member {
     /// MD5 hash del email en lowercase
     pub id: String,
@@ -128,7 +129,7 @@ pub struct Member {
     pub _links: Vec<Link>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct MemberStats {
     /// Tasa promedio de apertura de emails (0.0 - 1.0)
     pub avg_open_rate: Option<f64>,
@@ -138,7 +139,7 @@ pub struct MemberStats {
     pub ecommerce_data: Option<EcommerceData>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct EcommerceData {
     /// Ingresos totales generados por este cliente
     pub total_revenue: Option<f64>,
@@ -146,7 +147,7 @@ pub struct EcommerceData {
     pub currency_code: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Location {
     pub latitude: Option<f64>,
     pub longitude: Option<f64>,
@@ -161,7 +162,7 @@ pub struct Location {
     pub region: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct MarketingPermission {
     pub marketing_permission_id: String,
     /// Descripción del permiso (ej: "Email Marketing")
@@ -170,7 +171,7 @@ pub struct MarketingPermission {
     pub enabled: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LastNote {
     pub note_id: i64,
     pub created_at: String,
@@ -178,14 +179,14 @@ pub struct LastNote {
     pub note: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Tag {
     pub id: i64,
     pub name: String,
 }
 
 /// Link HATEOAS para navegación de la API REST
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Link {
     /// Relación del link (ej: "self", "parent", "update")
     pub rel: String,
@@ -196,3 +197,29 @@ pub struct Link {
     /// JSON Schema del recurso (si está disponible)
     pub schema: Option<String>,
 }
+
+/// Respuesta de `GET /search-members`
+#[derive(Debug, Deserialize)]
+pub struct SearchMembersResponse {
+    pub exact_matches: SearchMembersMatches,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchMembersMatches {
+    #[serde(default)]
+    pub members: Vec<MembershipStatus>,
+}
+
+/// Estado de un email en una audiencia (lo que se incluye en la exportación de datos)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MembershipStatus {
+    /// Hash MD5 del email; identifica al miembro en `/lists/{list_id}/members/{id}`
+    pub id: String,
+    pub email_address: String,
+    /// "subscribed", "unsubscribed", "cleaned", "pending"
+    pub status: String,
+    pub list_id: String,
+    pub timestamp_signup: Option<String>,
+    pub timestamp_opt: Option<String>,
+    pub last_changed: Option<String>,
+}
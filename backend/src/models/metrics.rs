@@ -1,4 +1,11 @@
-use serde::{Deserialize, Serialize};
+use {
+    serde::{Deserialize, Serialize},
+    std::time::{Duration, Instant},
+    utoipa::ToSchema,
+};
+
+/// Antelación con la que se renueva un access token de Google antes de que caduque
+pub const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
 
 // Wrapper para el token de Google Analytics en las extensiones
 #[derive(Clone)]
@@ -7,15 +14,43 @@ pub struct GAToken(pub String);
 #[derive(Deserialize)]
 pub struct TokenResponse {
     pub access_token: String,
+    /// Segundos de validez del token (Google suele devolver 3599)
+    #[serde(default = "default_token_expires_in")]
+    pub expires_in: u64,
 }
 
-#[derive(Deserialize)]
+fn default_token_expires_in() -> u64 {
+    3600
+}
+
+/// Access token de Google (Analytics o administración de Firebase) en caché, compartido entre peticiones
+#[derive(Debug, Clone)]
+pub struct CachedAccessToken {
+    pub access_token: String,
+    pub expires_at: Instant,
+}
+
+impl CachedAccessToken {
+    pub fn new(access_token: String, expires_in: u64) -> Self {
+        Self {
+            access_token,
+            expires_at: Instant::now() + Duration::from_secs(expires_in),
+        }
+    }
+
+    /// Se puede reutilizar si aún le queda más que el margen de renovación
+    pub fn is_usable(&self) -> bool {
+        Instant::now() + TOKEN_REFRESH_MARGIN < self.expires_at
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct ServiceAccount {
     pub client_email: String,
     pub private_key: String,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct GAResponse {
     #[serde(default)]
     pub rows: Vec<MetricData>,
@@ -35,7 +70,7 @@ pub struct GAError {
     pub status: String,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct MetricData {
     #[serde(rename = "dimensionValues")]
     dimension_values: Vec<DimensionValue>,
@@ -43,12 +78,12 @@ pub struct MetricData {
     metric_values: Vec<MetricValue>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 struct DimensionValue {
     value: String, // "202410" formato YYYYMM
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 struct MetricValue {
     value: String, // "1234" como string
 }
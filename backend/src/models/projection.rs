@@ -0,0 +1,111 @@
+use {
+    crate::models::firebase::{ProviderUserInfo, UserMerged},
+    serde::Serialize,
+    utoipa::ToSchema,
+};
+
+// Vistas de un usuario. Cada endpoint responde con la suya y solo se serializan sus campos;
+// `UserMerged` es la vista interna y no se devuelve tal cual en respuestas HTTP. Los secretos
+// (hash de contraseña, `valid_since`...) no forman parte de ninguna vista.
+
+/// El propio usuario (`/users/me`): incluye los métodos de acceso vinculados
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UserOwnerView {
+    pub local_id: String,
+    pub first_free_class: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub photo_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_tier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider_user_info: Option<Vec<ProviderUserInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_login_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+/// Directorio de administración (`/users/all`): incluye si la cuenta está deshabilitada
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UserAdminView {
+    pub local_id: String,
+    pub first_free_class: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub photo_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_tier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_login_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+impl From<UserMerged> for UserOwnerView {
+    fn from(user: UserMerged) -> Self {
+        Self {
+            local_id: user.local_id,
+            first_free_class: user.first_free_class,
+            email: user.email,
+            email_verified: user.email_verified,
+            display_name: user.display_name,
+            photo_url: user.photo_url,
+            phone_number: user.phone_number,
+            role: user.role,
+            subscription_tier: user.subscription_tier,
+            permissions: user.permissions,
+            provider_user_info: user.provider_user_info,
+            last_login_at: user.last_login_at,
+            created_at: user.created_at,
+        }
+    }
+}
+
+impl From<UserMerged> for UserAdminView {
+    fn from(user: UserMerged) -> Self {
+        Self {
+            local_id: user.local_id,
+            first_free_class: user.first_free_class,
+            email: user.email,
+            email_verified: user.email_verified,
+            display_name: user.display_name,
+            photo_url: user.photo_url,
+            phone_number: user.phone_number,
+            disabled: user.disabled,
+            role: user.role,
+            subscription_tier: user.subscription_tier,
+            permissions: user.permissions,
+            last_login_at: user.last_login_at,
+            created_at: user.created_at,
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/models/projection.rs"]
+mod extended_tests;
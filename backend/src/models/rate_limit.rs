@@ -0,0 +1,166 @@
+use {
+    crate::config::{RateLimitBudget, RateLimitConfig},
+    async_trait::async_trait,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// Número de claves a partir del cual el store en memoria purga los buckets ya llenos
+const IN_MEMORY_MAX_KEYS: usize = 10_000;
+
+/// Grupos de rutas con presupuesto propio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitGroup {
+    Contact,
+    Newsletter,
+    Auth,
+    Webhook,
+}
+
+impl AsRef<str> for RateLimitGroup {
+    fn as_ref(&self) -> &str {
+        match self {
+            RateLimitGroup::Contact => "contact",
+            RateLimitGroup::Newsletter => "newsletter",
+            RateLimitGroup::Auth => "auth",
+            RateLimitGroup::Webhook => "webhook",
+        }
+    }
+}
+
+/// Resultado de consumir una ficha del bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitDecision {
+    Allowed { remaining: u32 },
+    Limited { retry_after: Duration },
+}
+
+/// Estado de un token bucket. Las fichas se reponen de forma continua según el presupuesto.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn full(budget: RateLimitBudget, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(budget.capacity),
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, budget: RateLimitBudget, now: Instant) {
+        let per_second: f64 = f64::from(budget.refill_per_minute) / 60.0;
+        let elapsed: f64 = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(f64::from(budget.capacity));
+        self.updated_at = now;
+    }
+
+    /// Intenta consumir una ficha en el instante `now`
+    pub fn take(&mut self, budget: RateLimitBudget, now: Instant) -> RateLimitDecision {
+        self.refill(budget, now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            RateLimitDecision::Allowed {
+                remaining: self.tokens.floor() as u32,
+            }
+        } else {
+            let per_second: f64 = f64::from(budget.refill_per_minute) / 60.0;
+            RateLimitDecision::Limited {
+                retry_after: Duration::from_secs_f64((1.0 - self.tokens) / per_second),
+            }
+        }
+    }
+
+    fn is_full(&self, budget: RateLimitBudget, now: Instant) -> bool {
+        let mut bucket = *self;
+        bucket.refill(budget, now);
+        bucket.tokens >= f64::from(budget.capacity)
+    }
+}
+
+/// Almacenamiento de los buckets. La implementación por defecto vive en memoria del proceso;
+/// con varias instancias se puede sustituir por una compartida (Redis, etc.).
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    async fn take(&self, key: &str, budget: RateLimitBudget) -> RateLimitDecision;
+}
+
+#[derive(Default)]
+pub struct InMemoryRateLimitStore {
+    buckets: Mutex<HashMap<String, (TokenBucket, RateLimitBudget)>>,
+}
+
+impl InMemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn take(&self, key: &str, budget: RateLimitBudget) -> RateLimitDecision {
+        let now: Instant = Instant::now();
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        // Un bucket lleno equivale a no tenerlo, así que se puede descartar sin perder estado
+        if buckets.len() >= IN_MEMORY_MAX_KEYS {
+            buckets.retain(|_, (bucket, budget)| !bucket.is_full(*budget, now));
+        }
+
+        let (bucket, _) = buckets
+            .entry(key.to_string())
+            .or_insert_with(|| (TokenBucket::full(budget, now), budget));
+        bucket.take(budget, now)
+    }
+}
+
+/// Rate limiter compartido en `AppState`
+pub struct RateLimiter {
+    pub config: RateLimitConfig,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimiter {
+    /// Rate limiter con el store en memoria
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self::with_store(config, Arc::new(InMemoryRateLimitStore::new()))
+    }
+
+    pub fn with_store(config: RateLimitConfig, store: Arc<dyn RateLimitStore>) -> Self {
+        Self { config, store }
+    }
+
+    pub fn budget(&self, group: RateLimitGroup) -> RateLimitBudget {
+        match group {
+            RateLimitGroup::Contact => self.config.contact,
+            RateLimitGroup::Newsletter => self.config.newsletter,
+            RateLimitGroup::Auth => self.config.auth,
+            RateLimitGroup::Webhook => self.config.webhook,
+        }
+    }
+
+    /// Consume una ficha del bucket de `subject` (IP o uid) dentro del grupo
+    pub async fn check(&self, group: RateLimitGroup, subject: &str) -> RateLimitDecision {
+        if !self.config.enabled {
+            return RateLimitDecision::Allowed {
+                remaining: self.budget(group).capacity,
+            };
+        }
+
+        let key: String = format!("{}:{}", group.as_ref(), subject);
+        self.store.take(&key, self.budget(group)).await
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/models/rate_limit.rs"]
+mod extended_tests;
@@ -1,7 +1,8 @@
 use serde::Serialize;
+use utoipa::ToSchema;
 
 /// Estructura genérica para respuestas de la API
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ResponseAPI<T>
 where
     T: Serialize,
@@ -13,6 +14,9 @@ where
     pub data: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Código estable y legible por máquina para que el frontend distinga errores
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
 }
 
 impl<T> ResponseAPI<T>
@@ -25,6 +29,7 @@ where
             message: Some(message),
             data: Some(data),
             error: None,
+            code: None,
         }
     }
 
@@ -34,6 +39,39 @@ where
             message: None,
             data: None,
             error: Some(error),
+            code: None,
+        }
+    }
+
+    pub fn error_with_code(code: &str, error: String) -> Self {
+        ResponseAPI {
+            success: false,
+            message: None,
+            data: None,
+            error: Some(error),
+            code: Some(code.to_string()),
+        }
+    }
+
+    /// Error que acompaña datos útiles para el cliente (p. ej. un informe parcial)
+    pub fn error_with_data(code: &str, error: String, data: T) -> Self {
+        ResponseAPI {
+            success: false,
+            message: None,
+            data: Some(data),
+            error: Some(error),
+            code: Some(code.to_string()),
+        }
+    }
+
+    /// Respuesta correcta con un mensaje propio y sin datos
+    pub fn success_message(message: String) -> Self {
+        ResponseAPI {
+            success: true,
+            message: Some(message),
+            data: None,
+            error: None,
+            code: None,
         }
     }
 
@@ -43,6 +81,7 @@ where
             message: Some("Operation successful".to_string()),
             data: None,
             error: None,
+            code: None,
         }
     }
 }
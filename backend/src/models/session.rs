@@ -0,0 +1,64 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::{collections::HashMap, time::Instant},
+    utoipa::ToSchema,
+};
+
+/// Sesión abierta por un login. Los refrescos del token mantienen el mismo `auth_time`,
+/// así que sirve para encontrar la sesión al refrescar sin guardar el refresh token.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Session {
+    /// `auth_time` del ID token (segundos Unix)
+    pub auth_time: i64,
+    pub created_at: String,
+    /// Último login o refresco visto con esta sesión
+    pub last_seen_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sign_in_provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+}
+
+/// Documento de sesiones de un usuario (`user_sessions/{uid}`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserSessions {
+    /// Sesiones indexadas por un id aleatorio asignado en el login: dos logins en el mismo
+    /// segundo comparten `auth_time` pero son sesiones distintas
+    #[serde(default)]
+    pub sessions: HashMap<String, Session>,
+    /// Los ID tokens con `auth_time` anterior se rechazan (mismo valor que `validSince` en Firebase)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<i64>,
+}
+
+/// Origen del ID token que se registra como sesión
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// Login nuevo: siempre abre una sesión
+    Login,
+    /// Refresco del token: actualiza la sesión de su login
+    Refresh,
+}
+
+/// Sesión tal y como se muestra al usuario
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionView {
+    #[serde(flatten)]
+    pub session: Session,
+    /// Es la sesión del token con el que se hace la petición
+    pub current: bool,
+}
+
+/// Resultado de cerrar todas las sesiones
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RevokedSessions {
+    pub revoked_at: i64,
+    pub sessions_revoked: usize,
+}
+
+/// Instante de revocación leído de la base de datos, para no consultarla en cada petición
+#[derive(Debug, Clone, Copy)]
+pub struct CachedRevocation {
+    pub revoked_at: Option<i64>,
+    pub fetched_at: Instant,
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 /// Nivel de espanol del usuario. En frontend se permite un string libre,
 /// por eso en backend se modela como alias para mantener flexibilidad.
@@ -7,7 +8,7 @@ pub type SpanishLevel = String;
 /// Area de enfoque de la encuesta. Se permite string libre.
 pub type FocusArea = String;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum QuestionType {
     Text,
@@ -17,7 +18,7 @@ pub enum QuestionType {
     Textarea,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Question {
     pub id: String,
     pub label: String,
@@ -28,7 +29,7 @@ pub struct Question {
     pub answer: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Survey {
     pub id: String,
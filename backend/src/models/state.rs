@@ -1,10 +1,24 @@
 use {
-    crate::models::{cal::CalBookingPayload, metrics::ServiceAccount, webhook::BookingChange},
+    crate::{
+        models::{
+            cal::CalBookingPayload,
+            metrics::{CachedAccessToken, ServiceAccount},
+            rate_limit::RateLimiter,
+            session::CachedRevocation,
+            webhook::BookingChange,
+        },
+        repositories::Repositories,
+        services::supervisor::TaskSupervisor,
+    },
     reqwest::Client as HttpClient,
     resend_rs::Resend,
-    std::{collections::HashMap, sync::Arc, time::SystemTime},
+    std::{
+        collections::HashMap,
+        sync::Arc,
+        time::{Duration, SystemTime},
+    },
     stripe::Client as StripeClient,
-    tokio::sync::RwLock,
+    tokio::sync::{Mutex, RwLock},
 };
 
 // Estado global de la aplicación
@@ -15,6 +29,25 @@ pub struct AppState {
     pub mailchimp_client: MailchimpOptions,
     pub cal_options: CalOptions,
     pub ga_options: GAOptions,
+    pub repositories: Repositories,
+    pub rate_limiter: RateLimiter,
+    pub tasks: TaskSupervisor,
+    /// Revocaciones de sesión por uid, cacheadas para el middleware de autenticación
+    pub session_revocations: Arc<RwLock<HashMap<String, CachedRevocation>>>,
+    /// Un mutex por uid para que la comprobación del saldo del plan y la creación de la
+    /// reserva en Cal.com no se intercalen entre peticiones del mismo usuario
+    pub booking_locks: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
+}
+
+impl AppState {
+    /// Mutex de reservas del usuario. Solo serializa dentro de esta instancia del servidor.
+    pub async fn booking_lock(&self, uid: &str) -> Arc<Mutex<()>> {
+        let mut locks = self.booking_locks.lock().await;
+        // Se aprovecha para quitar los mutex que ya no usa nadie (solo los referencia el
+        // mapa), así no crece con cada usuario que ha reservado alguna vez
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(uid.to_string()).or_default().clone()
+    }
 }
 
 /// Configuración para interactuar con la API de Google Analytics
@@ -22,7 +55,11 @@ pub struct GAOptions {
     pub client: HttpClient,
     pub service_account: ServiceAccount,
     pub base_url: String,
+    pub token_url: String,
     pub property_id: String,
+    /// Access token en caché. El Mutex asíncrono hace que las peticiones concurrentes
+    /// esperen a un único refresco en lugar de pedir cada una su token.
+    pub token_cache: Arc<Mutex<Option<CachedAccessToken>>>,
 }
 
 /// Configuración para interactuar con la API de Cal.com
@@ -31,6 +68,7 @@ pub struct CalOptions {
     pub base_url: String,
     pub api_key: String,
     pub team_id: Option<String>,
+    pub polling_interval_secs: u64,
     pub booking_cache: Arc<RwLock<HashMap<String, CalBookingPayload>>>,
     pub recent_changes: Arc<RwLock<Vec<BookingChange>>>,
 }
@@ -41,20 +79,70 @@ pub struct CustomFirebase {
     pub firebase_keys: Arc<RwLock<KeyCache>>,
     pub firebase_project_id: String,
     pub firebase_api_key: String,
-    pub firebase_database_url: String,
-    pub firebase_database_secret: String,
     pub firebase_client: HttpClient,
+    /// `host:puerto` del Firebase Auth Emulator si está activo
+    pub auth_emulator_host: Option<String>,
+    /// Base de la API de Identity Toolkit (`accounts:*`)
+    pub identity_toolkit_url: String,
+    /// Base de la API de Secure Token (refresh de tokens)
+    pub secure_token_url: String,
+    /// URL de las claves públicas para verificar los ID tokens
+    pub public_keys_url: String,
+    /// Cuenta de servicio para las operaciones de administración de Identity Toolkit
+    pub service_account: Option<ServiceAccount>,
+    pub token_url: String,
+    /// Access token de administración en caché (mismo esquema que el de Google Analytics)
+    pub admin_token_cache: Arc<Mutex<Option<CachedAccessToken>>>,
+    /// `aud` esperado en los id_token de Google que se vinculan a una cuenta
+    pub google_client_id: Option<String>,
+    /// Endpoint de Google que valida esos id_token
+    pub google_tokeninfo_url: String,
 }
-/// Estructura con TTL
+/// Vigencia de las claves si la respuesta de Google no trae `Cache-Control: max-age`
+pub const DEFAULT_KEYS_MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// Caché de las claves públicas de Firebase. La vigencia la marca el `max-age` de la respuesta;
+/// una vez vencida se siguen sirviendo mientras el refresco en segundo plano las revalida.
 pub struct KeyCache {
     pub keys: serde_json::Value,
     pub fetched_at: SystemTime,
+    pub max_age: Duration,
+    /// Último refresco forzado por un `kid` desconocido (para limitarlos)
+    pub last_forced_refresh: Option<SystemTime>,
 }
 impl KeyCache {
+    pub fn new(keys: serde_json::Value, max_age: Duration) -> Self {
+        Self {
+            keys,
+            fetched_at: SystemTime::now(),
+            max_age,
+            last_forced_refresh: None,
+        }
+    }
+
+    /// Caché sin claves: el servidor arranca en modo degradado hasta el primer refresco correcto
+    pub fn empty() -> Self {
+        Self {
+            keys: serde_json::Value::Object(Default::default()),
+            fetched_at: SystemTime::UNIX_EPOCH,
+            max_age: Duration::ZERO,
+            last_forced_refresh: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.as_object().is_none_or(|keys| keys.is_empty())
+    }
+
     pub fn is_expired(&self) -> bool {
+        self.expires_in().is_zero()
+    }
+
+    /// Tiempo que queda hasta que venzan las claves (cero si ya vencieron)
+    pub fn expires_in(&self) -> Duration {
         match SystemTime::now().duration_since(self.fetched_at) {
-            Ok(duration) => duration.as_secs() > 3600,
-            Err(_) => true,
+            Ok(elapsed) => self.max_age.saturating_sub(elapsed),
+            Err(_) => Duration::ZERO,
         }
     }
 }
@@ -66,12 +154,15 @@ pub struct MailchimpOptions {
     pub list_id: String,
     /// Prefijo del datacenter (ej: "us1", "us19") extraído del API key
     pub datacenter: String,
+    /// Base de la API; se deriva del datacenter (los tests la apuntan a un servidor local)
+    pub base_url: String,
     pub client: reqwest::Client,
 }
 impl MailchimpOptions {
     pub fn new(api_key: String, datacenter: String, list_id: String) -> Self {
         Self {
             api_key,
+            base_url: format!("https://{}.api.mailchimp.com/3.0", datacenter),
             datacenter,
             list_id,
             client: reqwest::Client::new(),
@@ -79,6 +170,6 @@ impl MailchimpOptions {
     }
 
     pub fn get_base_url(&self) -> String {
-        format!("https://{}.api.mailchimp.com/3.0", self.datacenter)
+        self.base_url.clone()
     }
 }
@@ -2,10 +2,14 @@ use {
     serde::{Deserialize, Serialize},
     std::collections::HashMap,
     stripe::{CreateProductDefaultPriceDataRecurring, Currency, PaymentIntent},
+    utoipa::ToSchema,
 };
 
+/// Clave de metadata de los PaymentIntent con el uid de Firebase de quien paga
+pub const PAYMENT_USER_METADATA_KEY: &str = "firebase_uid";
+
 /// Payload para crear un PaymentIntent (pago único)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct PaymentPayload {
     /// Cantidad en la unidad más pequeña de la moneda (céntimos para USD/EUR)
     pub amount: i64,
@@ -14,7 +18,7 @@ pub struct PaymentPayload {
 }
 
 /// Respuesta tras crear o consultar un PaymentIntent
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PaymentResponse {
     /// Secret usado por Stripe.js en el frontend para confirmar el pago
     pub client_secret: Option<String>,
@@ -25,7 +29,7 @@ pub struct PaymentResponse {
 }
 
 /// Payload para crear un producto en Stripe
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ProductPayload {
     pub name: String,
     pub description: String,
@@ -38,18 +42,19 @@ pub struct ProductPayload {
 }
 
 /// Payload para crear el precio de un producto
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PricePayload {
     /// Código ISO de moneda en minúsculas
     pub currency: String,
     /// Precio en la unidad más pequeña (céntimos). Ej: 1000 = $10.00
     pub unit_amount: i64,
     /// Si es Some, el precio será recurrente (suscripción). Si None, pago único.
+    #[schema(value_type = Option<Object>)]
     pub recurring: Option<CreateProductDefaultPriceDataRecurring>,
 }
 
 /// Payload completo para crear un producto con su precio en una sola operación
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PayloadCreacteProduct {
     pub product: ProductPayload,
     pub price: PricePayload,
@@ -59,7 +64,7 @@ pub struct PayloadCreacteProduct {
 pub type CurrencyMap<T> = HashMap<Currency, T>;
 
 /// Relación entre un evento de Cal.com y un producto/precio de Stripe
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct RelationalCalStripe {
     /// ID del evento en Cal.com
     pub cal_id: String,
@@ -68,12 +73,12 @@ pub struct RelationalCalStripe {
 }
 
 /// Relación entre un evento de Cal.com y un producto/precio de Stripe
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct StripeRelation {
     pub stripe_id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PaymentIntentSimplified {
     pub id: String,
     pub amount: i64,
@@ -0,0 +1,51 @@
+use {
+    crate::utils::validations::validate_non_whitespace,
+    serde::{Deserialize, Serialize},
+    utoipa::ToSchema,
+    validator::Validate,
+};
+
+/// Plan de suscripción (`subscription_tiers/{id}`). El id es el valor que se guarda en
+/// `UserDB::subscription_tier`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate, ToSchema)]
+pub struct SubscriptionTier {
+    #[validate(length(max = 60), custom = "validate_non_whitespace")]
+    pub name: String,
+    /// Clases incluidas por mes natural (UTC); `None` = ilimitadas
+    pub monthly_class_allowance: Option<u32>,
+    /// Tipos de evento de Cal.com reservables con el plan (slug o id numérico); vacío = todos
+    #[serde(default)]
+    pub allowed_event_types: Vec<String>,
+    /// Descuento sobre las clases que se pagan aparte
+    #[serde(default)]
+    #[validate(range(max = 100))]
+    pub discount_percentage: u8,
+    /// Precio recurrente de Stripe con el que se cobra el plan
+    #[validate(custom = "validate_non_whitespace")]
+    pub stripe_price_id: String,
+}
+
+impl SubscriptionTier {
+    /// Si el plan permite reservar el tipo de evento indicado por slug o id
+    pub fn allows_event_type(&self, slug: Option<&str>, id: Option<i64>) -> bool {
+        self.allowed_event_types.is_empty()
+            || self.allowed_event_types.iter().any(|allowed| {
+                slug.is_some_and(|slug| allowed == slug)
+                    || id.is_some_and(|id| *allowed == id.to_string())
+            })
+    }
+}
+
+/// Clases disponibles del plan en el periodo actual
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Entitlement {
+    pub tier: String,
+    pub period_start: String,
+    pub period_end: String,
+    /// `None` = ilimitadas
+    pub allowance: Option<u32>,
+    pub used: u32,
+    /// `None` = ilimitadas
+    pub remaining: Option<u32>,
+    pub discount_percentage: u8,
+}
@@ -1,10 +1,29 @@
 use {
+    crate::{
+        models::{
+            cal::CalBookingPayload,
+            comments::{Comment, ReplyComment},
+            firebase::{FirebaseUserInfo, ProviderUserInfo, UserMerged},
+            mailchimp::MembershipStatus,
+            sourvey::{FocusArea, SpanishLevel, Survey},
+            stripe::PaymentIntentSimplified,
+        },
+        utils::validations::{
+            validate_class_times, validate_focus_areas, validate_non_whitespace,
+            validate_spanish_level, validate_timezone,
+        },
+    },
     serde::{Deserialize, Serialize},
-    std::collections::HashSet,
+    std::{
+        cmp::Ordering,
+        collections::{HashMap, HashSet},
+    },
+    utoipa::{IntoParams, ToSchema},
+    validator::Validate,
 };
 
 /// Estructura de los datos que se pasan desde el frontend para los enpoints de users
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UserRequest {
     // Datos obligatorios requeridos por Firebase Auth
     pub email: String,
@@ -24,16 +43,47 @@ pub struct UserRequest {
 }
 
 /// Proveedor de autenticación usado por el usuario
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum Provider {
     Email,
     Google,
 }
 
+impl Provider {
+    /// `providerId` con el que Firebase Auth identifica al proveedor
+    pub fn provider_id(&self) -> &'static str {
+        match self {
+            Provider::Email => "password",
+            Provider::Google => "google.com",
+        }
+    }
+}
+
+/// Credencial del método de acceso que se quiere vincular a la cuenta actual
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "provider", rename_all = "lowercase")]
+pub enum LinkProviderRequest {
+    /// Añade contraseña a una cuenta creada con Google (con el email de la cuenta)
+    Email { password: String },
+    /// id_token de Google Sign-In (no el de Firebase) de la cuenta de Google a vincular
+    Google { id_token: String },
+}
+
+/// Métodos de acceso de la cuenta tras vincular o desvincular uno
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LinkedProvidersResponse {
+    pub providers: Vec<ProviderUserInfo>,
+    /// Firebase renueva la sesión al añadir contraseña; el cliente debe usar estos tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+}
+
 /// Roles posibles de un usuario en la aplicación
 /// Roles posibles de un usuario en la aplicación
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum Role {
     Admin,
@@ -62,12 +112,393 @@ impl AsRef<str> for Role {
     }
 }
 
+/// Permisos granulares que se guardan en `UserDB.permissions`.
+/// Los administradores los tienen todos implícitamente.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    ManageProducts,
+    ManageTeachers,
+    ReadSurveys,
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+impl AsRef<str> for Permission {
+    fn as_ref(&self) -> &str {
+        match self {
+            Permission::ManageProducts => "manage_products",
+            Permission::ManageTeachers => "manage_teachers",
+            Permission::ReadSurveys => "read_surveys",
+        }
+    }
+}
+
 /// Usuario de la base de datos
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
 pub struct UserDB {
     pub email: String,
     pub first_free_class: bool,
     pub role: Option<String>,
     pub subscription_tier: Option<String>,
     pub permissions: Option<HashSet<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub learner_profile: Option<LearnerProfile>,
+}
+
+/// Niveles de español aceptados en el perfil de aprendizaje (MCER más principiante absoluto)
+pub const SPANISH_LEVELS: [&str; 7] = ["beginner", "A1", "A2", "B1", "B2", "C1", "C2"];
+
+/// Perfil de aprendizaje del alumno, para que el profesor sepa a quién da clase.
+/// Se puede precargar desde la encuesta de nivel que rellenó el alumno.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Validate, ToSchema)]
+pub struct LearnerProfile {
+    #[validate(length(max = 60), custom = "validate_non_whitespace")]
+    pub native_language: Option<String>,
+    /// Uno de `SPANISH_LEVELS`
+    #[validate(custom = "validate_spanish_level")]
+    pub spanish_level: Option<SpanishLevel>,
+    #[serde(default)]
+    #[validate(custom = "validate_focus_areas")]
+    pub focus_areas: Vec<FocusArea>,
+    /// Zona horaria IANA (p. ej. `Europe/Madrid`); las franjas horarias se interpretan en ella
+    #[validate(custom = "validate_timezone")]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    #[validate(custom = "validate_class_times")]
+    pub preferred_class_times: Vec<ClassTimeSlot>,
+    #[validate(length(max = 1000))]
+    pub learning_goals: Option<String>,
+    /// Lo fija el servidor al guardar
+    pub updated_at: Option<String>,
+}
+
+/// Franja horaria en la que el alumno prefiere dar clase
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ClassTimeSlot {
+    pub weekday: Weekday,
+    /// Hora de inicio `HH:MM`
+    pub start: String,
+    /// Hora de fin `HH:MM`, posterior al inicio
+    pub end: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl LearnerProfile {
+    /// Perfil deducido de las respuestas de la encuesta de nivel. Las respuestas libres
+    /// que no pasan la validación (p. ej. un nivel "other") se descartan.
+    pub fn from_survey(survey: &Survey) -> Self {
+        let answer = |id: &str| -> Option<String> {
+            survey
+                .questions
+                .iter()
+                .find(|question| question.id == id)
+                .and_then(|question| question.answer.as_deref())
+                .map(str::trim)
+                .filter(|answer| !answer.is_empty())
+                .map(str::to_string)
+        };
+
+        LearnerProfile {
+            native_language: answer("native-language").filter(|language| language.len() <= 60),
+            spanish_level: answer("spanish-level").and_then(|level| {
+                SPANISH_LEVELS
+                    .iter()
+                    .find(|known| known.eq_ignore_ascii_case(&level))
+                    .map(|known| known.to_string())
+            }),
+            focus_areas: answer("focus")
+                .map(|focus| {
+                    focus
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|area| !area.is_empty())
+                        .map(str::to_string)
+                        .collect::<Vec<String>>()
+                })
+                .filter(|areas| validate_focus_areas(areas).is_ok())
+                .unwrap_or_default(),
+            learning_goals: answer("extra-notes").filter(|goals| goals.len() <= 1000),
+            ..Default::default()
+        }
+    }
+
+    /// Completa los campos vacíos con los de `other` sin tocar lo que el alumno ya rellenó
+    pub fn fill_missing(&mut self, other: LearnerProfile) {
+        self.native_language = self.native_language.take().or(other.native_language);
+        self.spanish_level = self.spanish_level.take().or(other.spanish_level);
+        if self.focus_areas.is_empty() {
+            self.focus_areas = other.focus_areas;
+        }
+        self.timezone = self.timezone.take().or(other.timezone);
+        if self.preferred_class_times.is_empty() {
+            self.preferred_class_times = other.preferred_class_times;
+        }
+        self.learning_goals = self.learning_goals.take().or(other.learning_goals);
+    }
+}
+
+/// Encuesta desde la que precargar el perfil de aprendizaje
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LearnerProfilePrefillQuery {
+    /// Si no se indica se usa la última encuesta enviada por el alumno
+    pub survey_id: Option<String>,
+}
+
+/// Payload para pedir el email de restablecimiento de contraseña
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct PasswordResetRequest {
+    #[validate(email)]
+    pub email: String,
+}
+
+/// Payload para fijar la nueva contraseña con el código (oobCode) recibido por email
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct PasswordResetConfirm {
+    #[validate(custom = "validate_non_whitespace")]
+    pub oob_code: String,
+    /// Firebase exige un mínimo de 6 caracteres
+    #[validate(length(min = 6))]
+    pub new_password: String,
+}
+
+/// Payload para confirmar el email con el código (oobCode) del enlace de verificación
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct VerifyEmailConfirm {
+    #[validate(custom = "validate_non_whitespace")]
+    pub oob_code: String,
+}
+
+/// Payload de administración para cambiar el rol de un usuario
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateRoleRequest {
+    pub role: Role,
+}
+
+/// Payload de administración para conceder y revocar permisos en una sola operación.
+/// Si un permiso aparece en ambas listas prevalece la revocación.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct UpdatePermissionsRequest {
+    #[serde(default)]
+    pub grant: HashSet<Permission>,
+    #[serde(default)]
+    pub revoke: HashSet<Permission>,
+}
+
+/// Payload de administración para fijar (o quitar con `null`) el tier de suscripción
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSubscriptionTierRequest {
+    pub subscription_tier: Option<String>,
+}
+
+/// Payload de administración para deshabilitar o rehabilitar la cuenta de Firebase Auth
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateAccountStatusRequest {
+    pub disabled: bool,
+}
+
+/// Campo por el que se ordena el directorio de usuarios
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSortField {
+    CreatedAt,
+    LastLoginAt,
+}
+
+/// Sentido de la ordenación
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Formato de salida del directorio de usuarios
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// Filtros, orden y formato de `GET /users/all`. Todos son opcionales y se combinan con AND.
+#[derive(Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct UserDirectoryQuery {
+    pub role: Option<Role>,
+    /// Tier de suscripción (sin distinguir mayúsculas)
+    pub subscription_tier: Option<String>,
+    /// `false` devuelve los alumnos que aún no han usado su clase gratuita
+    pub first_free_class: Option<bool>,
+    pub email_verified: Option<bool>,
+    /// Texto libre que se busca en el email y el nombre
+    pub search: Option<String>,
+    pub sort: Option<UserSortField>,
+    /// `desc` por defecto
+    #[serde(default)]
+    pub order: SortOrder,
+    /// `csv` descarga el resultado filtrado como fichero
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+impl UserDirectoryQuery {
+    /// Indica si el usuario cumple todos los filtros de la consulta
+    pub fn matches(&self, user: &UserMerged) -> bool {
+        if let Some(role) = &self.role
+            && user.role.as_deref() != Some(role.as_ref())
+        {
+            return false;
+        }
+        if let Some(tier) = &self.subscription_tier
+            && !user
+                .subscription_tier
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case(tier.trim()))
+        {
+            return false;
+        }
+        if let Some(first_free_class) = self.first_free_class
+            && user.first_free_class != first_free_class
+        {
+            return false;
+        }
+        if let Some(verified) = self.email_verified
+            && user.email_verified.unwrap_or(false) != verified
+        {
+            return false;
+        }
+        match self.search.as_deref().map(str::trim) {
+            Some(search) if !search.is_empty() => {
+                let search: String = search.to_lowercase();
+                [&user.email, &user.display_name]
+                    .into_iter()
+                    .flatten()
+                    .any(|value| value.to_lowercase().contains(&search))
+            }
+            _ => true,
+        }
+    }
+
+    /// Filtra y ordena los usuarios. Los que no tienen la fecha de orden van siempre al final.
+    pub fn apply(&self, users: Vec<UserMerged>) -> Vec<UserMerged> {
+        let mut users: Vec<UserMerged> = users.into_iter().filter(|u| self.matches(u)).collect();
+
+        if let Some(field) = self.sort {
+            let key = |user: &UserMerged| -> Option<i64> {
+                match field {
+                    UserSortField::CreatedAt => user.created_at.as_deref(),
+                    UserSortField::LastLoginAt => user.last_login_at.as_deref(),
+                }
+                .and_then(|ms| ms.parse().ok())
+            };
+            users.sort_by(|a, b| match (key(a), key(b)) {
+                (Some(a), Some(b)) => match self.order {
+                    SortOrder::Asc => a.cmp(&b),
+                    SortOrder::Desc => b.cmp(&a),
+                },
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+        }
+
+        users
+    }
+}
+
+/// Formato de descarga de `GET /users/me/export`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DataExportFormat {
+    #[default]
+    Json,
+    /// Un fichero JSON por cada sección del export
+    Zip,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DataExportQuery {
+    #[serde(default)]
+    pub format: DataExportFormat,
+}
+
+/// Respuesta escrita por el usuario, con el comentario al que pertenece
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ExportedReply {
+    pub comment_id: String,
+    pub reply: ReplyComment,
+}
+
+/// Todo lo que guardamos de un usuario (derecho de acceso del RGPD)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserDataExport {
+    pub uid: String,
+    /// Fecha de generación (RFC 3339)
+    pub generated_at: String,
+    pub profile: Option<UserDB>,
+    /// Metadatos de Firebase Auth (sin el hash de la contraseña)
+    pub auth: Option<FirebaseUserInfo>,
+    pub comments: HashMap<String, Comment>,
+    pub replies: Vec<ExportedReply>,
+    pub surveys: Vec<Survey>,
+    /// Reservas de Cal.com en las que el usuario es asistente
+    pub bookings: Vec<CalBookingPayload>,
+    pub payments: Vec<PaymentIntentSimplified>,
+    pub newsletter: Option<MembershipStatus>,
+    /// Fuentes que no se pudieron consultar (o `payments (truncated)` si hay más pagos de los
+    /// exportados); sus secciones van vacías o incompletas
+    pub unavailable_sources: Vec<String>,
+}
+
+/// Reserva futura cancelada al borrar la cuenta
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CancelledBooking {
+    pub uid: String,
+    pub start_time: Option<String>,
+    /// Solo se reembolsan las reservas pagadas canceladas con la antelación mínima
+    pub refunded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refund_id: Option<String>,
+}
+
+/// Paso de la cascada de borrado que siguió fallando tras los reintentos
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FailedDeletionStep {
+    pub step: String,
+    pub attempts: u32,
+    pub error: String,
+}
+
+/// Informe de `DELETE /users/del/me`: qué se ha anonimizado, cancelado o borrado
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct AccountDeletionReport {
+    pub comments_anonymized: usize,
+    pub replies_anonymized: usize,
+    pub likes_removed: usize,
+    pub surveys_anonymized: usize,
+    pub newsletter_archived: bool,
+    pub bookings_cancelled: Vec<CancelledBooking>,
+    /// Si no está vacío la cuenta se conserva para poder repetir el borrado
+    pub failed_steps: Vec<FailedDeletionStep>,
+    pub account_deleted: bool,
 }
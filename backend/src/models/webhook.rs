@@ -4,10 +4,11 @@ use {
     core::fmt,
     serde::{Deserialize, Serialize},
     serde_json::Value,
+    utoipa::ToSchema,
 };
 
 /// Tipos de eventos que pueden activar un webhook de Cal.com
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum WebhookTrigger {
     BookingCreated,
@@ -27,7 +28,7 @@ impl fmt::Display for WebhookTrigger {
 }
 
 /// Estructura del evento recibido desde el webhook de Cal.com
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct CalWebhookEvent {
     #[serde(rename = "triggerEvent")]
     pub trigger_event: WebhookTrigger,
@@ -38,7 +39,7 @@ pub struct CalWebhookEvent {
 }
 
 /// Estructura que representa a un asistente en una reserva de Cal.com
-#[derive(Deserialize, Debug, Serialize, Clone)]
+#[derive(Deserialize, Debug, Serialize, Clone, ToSchema)]
 pub struct Attendee {
     pub email: String,
     pub name: String,
@@ -51,7 +52,7 @@ pub struct Attendee {
 }
 
 /// Estructura que representa el idioma preferido de un asistente
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, ToSchema)]
 pub struct Language {
     pub locale: String,
 }
@@ -80,7 +81,7 @@ where
 }
 
 /// Estructura que representa al organizador (profesor) de una reserva de Cal.com
-#[derive(Deserialize, Debug, Serialize, Clone)]
+#[derive(Deserialize, Debug, Serialize, Clone, ToSchema)]
 pub struct Organizer {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub id: Option<i64>,
@@ -95,7 +96,7 @@ pub struct Organizer {
 }
 
 /// Estructura de la respuesta al crear un reembolso en Cal.com
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RefundResponse {
     pub id: String,
     pub amount: i64,
@@ -0,0 +1,162 @@
+pub mod firebase;
+pub mod sqlite;
+
+use {
+    crate::models::{
+        comments::Comment,
+        error::RepositoryError,
+        session::{Session, UserSessions},
+        sourvey::Survey,
+        stripe::StripeRelation,
+        subscription::SubscriptionTier,
+        teacher::Teacher,
+        user::UserDB,
+    },
+    async_trait::async_trait,
+    firebase::FirebaseDatabase,
+    reqwest::Client as HttpClient,
+    sqlite::SqliteDatabase,
+    std::{collections::HashMap, path::Path, sync::Arc},
+};
+
+/// Acceso a los perfiles de usuario (`user_profiles`)
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn get(&self, uid: &str) -> Result<Option<UserDB>, RepositoryError>;
+    async fn get_all(&self) -> Result<HashMap<String, UserDB>, RepositoryError>;
+    /// Devuelve el uid y el perfil del usuario con ese email
+    async fn find_by_email(&self, email: &str)
+    -> Result<Option<(String, UserDB)>, RepositoryError>;
+    async fn put(&self, uid: &str, user: &UserDB) -> Result<UserDB, RepositoryError>;
+    async fn set_first_free_class(&self, uid: &str, value: bool) -> Result<(), RepositoryError>;
+    async fn delete(&self, uid: &str) -> Result<(), RepositoryError>;
+}
+
+/// Acceso a los comentarios (`comments`)
+#[async_trait]
+pub trait CommentRepository: Send + Sync {
+    /// Crea el comentario y devuelve el id generado
+    async fn create(&self, comment: &Comment) -> Result<String, RepositoryError>;
+    async fn get(&self, id: &str) -> Result<Option<Comment>, RepositoryError>;
+    async fn get_all(&self) -> Result<HashMap<String, Comment>, RepositoryError>;
+    async fn put(&self, id: &str, comment: &Comment) -> Result<Comment, RepositoryError>;
+    async fn delete(&self, id: &str) -> Result<(), RepositoryError>;
+    /// Comentario junto a su versión, para escribirlo después con `put_if_version`
+    async fn get_versioned(&self, id: &str) -> Result<Option<(Comment, String)>, RepositoryError>;
+    /// Guarda el comentario solo si sigue en la versión leída. Si otro escritor se adelantó
+    /// (o el comentario ya no existe) devuelve `RepositoryError::Conflict` sin escribir.
+    async fn put_if_version(
+        &self,
+        id: &str,
+        comment: &Comment,
+        version: &str,
+    ) -> Result<(), RepositoryError>;
+}
+
+/// Acceso a los perfiles de profesores (`teacher_profiles`)
+#[async_trait]
+pub trait TeacherRepository: Send + Sync {
+    /// Crea el profesor y devuelve el id generado
+    async fn create(&self, teacher: &Teacher) -> Result<String, RepositoryError>;
+    async fn get(&self, id: &str) -> Result<Option<Teacher>, RepositoryError>;
+    async fn get_all(&self) -> Result<HashMap<String, Teacher>, RepositoryError>;
+    async fn delete(&self, id: &str) -> Result<(), RepositoryError>;
+}
+
+/// Acceso a las encuestas (`surveys`)
+#[async_trait]
+pub trait SurveyRepository: Send + Sync {
+    async fn put(&self, survey: &Survey) -> Result<Survey, RepositoryError>;
+    async fn get_all(&self) -> Result<HashMap<String, Survey>, RepositoryError>;
+}
+
+/// Acceso a la relación entre bookings de Cal.com y pagos de Stripe (`relation_cal_stripe`)
+#[async_trait]
+pub trait CalStripeRepository: Send + Sync {
+    async fn put(&self, cal_id: &str, relation: &StripeRelation) -> Result<(), RepositoryError>;
+    async fn get(&self, cal_id: &str) -> Result<Option<StripeRelation>, RepositoryError>;
+    async fn get_all(&self) -> Result<HashMap<String, StripeRelation>, RepositoryError>;
+    async fn delete(&self, cal_id: &str) -> Result<(), RepositoryError>;
+}
+
+/// Acceso a las sesiones de cada usuario (`user_sessions`)
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    /// Documento vacío si el usuario no tiene sesiones registradas
+    async fn get(&self, uid: &str) -> Result<UserSessions, RepositoryError>;
+    async fn put_session(
+        &self,
+        uid: &str,
+        session_id: &str,
+        session: &Session,
+    ) -> Result<(), RepositoryError>;
+    /// Borra todas las sesiones y guarda el instante de revocación
+    async fn revoke_all(&self, uid: &str, revoked_at: i64) -> Result<(), RepositoryError>;
+    async fn delete(&self, uid: &str) -> Result<(), RepositoryError>;
+}
+
+/// Acceso a los planes de suscripción (`subscription_tiers`)
+#[async_trait]
+pub trait SubscriptionTierRepository: Send + Sync {
+    async fn get(&self, id: &str) -> Result<Option<SubscriptionTier>, RepositoryError>;
+    async fn get_all(&self) -> Result<HashMap<String, SubscriptionTier>, RepositoryError>;
+    async fn put(&self, id: &str, tier: &SubscriptionTier) -> Result<(), RepositoryError>;
+    async fn delete(&self, id: &str) -> Result<(), RepositoryError>;
+}
+
+/// Conjunto de repositorios que usan los controladores
+#[derive(Clone)]
+pub struct Repositories {
+    pub users: Arc<dyn UserRepository>,
+    pub comments: Arc<dyn CommentRepository>,
+    pub teachers: Arc<dyn TeacherRepository>,
+    pub surveys: Arc<dyn SurveyRepository>,
+    pub cal_stripe: Arc<dyn CalStripeRepository>,
+    pub sessions: Arc<dyn SessionRepository>,
+    pub subscription_tiers: Arc<dyn SubscriptionTierRepository>,
+}
+
+impl Repositories {
+    /// Repositorios sobre Firebase Realtime Database
+    pub fn firebase(client: HttpClient, database_url: String, database_secret: String) -> Self {
+        Self::from_backend(Arc::new(FirebaseDatabase::new(
+            client,
+            database_url,
+            database_secret,
+        )))
+    }
+
+    /// Repositorios sobre un fichero SQLite local (se crea si no existe)
+    pub fn sqlite(path: impl AsRef<Path>) -> Result<Self, RepositoryError> {
+        Ok(Self::from_backend(Arc::new(SqliteDatabase::open(path)?)))
+    }
+
+    /// Repositorios sobre SQLite en memoria, útil para tests
+    pub fn sqlite_in_memory() -> Result<Self, RepositoryError> {
+        Ok(Self::from_backend(Arc::new(
+            SqliteDatabase::open_in_memory()?,
+        )))
+    }
+
+    fn from_backend<B>(backend: Arc<B>) -> Self
+    where
+        B: UserRepository
+            + CommentRepository
+            + TeacherRepository
+            + SurveyRepository
+            + CalStripeRepository
+            + SessionRepository
+            + SubscriptionTierRepository
+            + 'static,
+    {
+        Self {
+            users: backend.clone(),
+            comments: backend.clone(),
+            teachers: backend.clone(),
+            surveys: backend.clone(),
+            cal_stripe: backend.clone(),
+            sessions: backend.clone(),
+            subscription_tiers: backend,
+        }
+    }
+}
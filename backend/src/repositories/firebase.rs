@@ -0,0 +1,413 @@
+use {
+    crate::{
+        models::{
+            comments::Comment,
+            error::RepositoryError,
+            session::{Session, UserSessions},
+            sourvey::Survey,
+            stripe::StripeRelation,
+            subscription::SubscriptionTier,
+            teacher::Teacher,
+            user::UserDB,
+        },
+        repositories::{
+            CalStripeRepository, CommentRepository, SessionRepository, SubscriptionTierRepository,
+            SurveyRepository, TeacherRepository, UserRepository,
+        },
+    },
+    async_trait::async_trait,
+    axum::http::StatusCode,
+    reqwest::{
+        Client as HttpClient, RequestBuilder,
+        header::{ETAG, HeaderMap, IF_MATCH},
+    },
+    serde::{Serialize, de::DeserializeOwned},
+    serde_json::{Value, json},
+    std::collections::HashMap,
+};
+
+/// Implementación de los repositorios sobre la API REST de Firebase Realtime Database.
+/// Todas las peticiones se autentican con el secreto de la base de datos; la autorización
+/// por usuario (autor, rol...) la hacen los controladores.
+pub struct FirebaseDatabase {
+    client: HttpClient,
+    database_url: String,
+    database_secret: String,
+}
+
+/// Respuesta de Firebase al hacer POST sobre una colección
+#[derive(serde::Deserialize)]
+struct PushResponse {
+    name: String,
+}
+
+impl FirebaseDatabase {
+    pub fn new(client: HttpClient, database_url: String, database_secret: String) -> Self {
+        Self {
+            client,
+            database_url: database_url.trim_end_matches('/').to_string(),
+            database_secret,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}.json", self.database_url, path)
+    }
+
+    fn authed(&self, request: RequestBuilder) -> RequestBuilder {
+        request.query(&[("auth", &self.database_secret)])
+    }
+
+    /// Ejecuta la petición y deserializa la respuesta; `null` se traduce a `None`
+    async fn send<T>(&self, request: RequestBuilder) -> Result<Option<T>, RepositoryError>
+    where
+        T: DeserializeOwned,
+    {
+        let (_, body) = self.execute(request).await?;
+        Self::parse(&body)
+    }
+
+    fn parse<T>(body: &str) -> Result<Option<T>, RepositoryError>
+    where
+        T: DeserializeOwned,
+    {
+        if body.trim().is_empty() {
+            return Ok(None);
+        }
+
+        Ok(serde_json::from_str::<Option<T>>(body)?)
+    }
+
+    /// Ejecuta la petición y devuelve las cabeceras y el cuerpo si el estado es de éxito
+    async fn execute(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<(HeaderMap, String), RepositoryError> {
+        // La URL lleva el secreto en `?auth=`: no puede acabar en el mensaje del error
+        let response = self
+            .authed(request)
+            .send()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().await.map_err(reqwest::Error::without_url)?;
+
+        if !status.is_success() {
+            let status =
+                StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            // Firebase devuelve errores en formato: {"error": "..."}
+            let message = serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|json| json.get("error").and_then(Value::as_str).map(String::from))
+                .unwrap_or(body);
+            return Err(RepositoryError::Status { status, message });
+        }
+
+        Ok((headers, body))
+    }
+
+    async fn get_node<T>(&self, path: &str) -> Result<Option<T>, RepositoryError>
+    where
+        T: DeserializeOwned,
+    {
+        self.send(self.client.get(self.url(path))).await
+    }
+
+    async fn get_collection<T>(&self, path: &str) -> Result<HashMap<String, T>, RepositoryError>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(self.get_node(path).await?.unwrap_or_default())
+    }
+
+    async fn put_node<T>(&self, path: &str, value: &T) -> Result<(), RepositoryError>
+    where
+        T: Serialize + Sync,
+    {
+        self.send::<Value>(self.client.put(self.url(path)).json(value))
+            .await
+            .map(|_| ())
+    }
+
+    async fn push_node<T>(&self, path: &str, value: &T) -> Result<String, RepositoryError>
+    where
+        T: Serialize + Sync,
+    {
+        self.send::<PushResponse>(self.client.post(self.url(path)).json(value))
+            .await?
+            .map(|response| response.name)
+            .ok_or_else(|| RepositoryError::Status {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                message: "Firebase did not return the generated id".to_string(),
+            })
+    }
+
+    async fn delete_node(&self, path: &str) -> Result<(), RepositoryError> {
+        self.send::<Value>(self.client.delete(self.url(path)))
+            .await
+            .map(|_| ())
+    }
+
+    /// Lee el nodo pidiendo su ETag, que identifica la versión leída
+    async fn get_node_versioned<T>(
+        &self,
+        path: &str,
+    ) -> Result<(Option<T>, String), RepositoryError>
+    where
+        T: DeserializeOwned,
+    {
+        let (headers, body) = self
+            .execute(
+                self.client
+                    .get(self.url(path))
+                    .header("X-Firebase-ETag", "true"),
+            )
+            .await?;
+        let etag: String = headers
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| RepositoryError::Status {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                message: "Firebase did not return an ETag".to_string(),
+            })?;
+
+        Ok((Self::parse(&body)?, etag))
+    }
+
+    /// PUT condicional: Firebase responde 412 si el ETag del nodo ya no es `etag`
+    async fn put_node_if_match<T>(
+        &self,
+        path: &str,
+        value: &T,
+        etag: &str,
+    ) -> Result<(), RepositoryError>
+    where
+        T: Serialize + Sync,
+    {
+        match self
+            .execute(
+                self.client
+                    .put(self.url(path))
+                    .header(IF_MATCH, etag)
+                    .json(value),
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(RepositoryError::Status {
+                status: StatusCode::PRECONDITION_FAILED,
+                ..
+            }) => Err(RepositoryError::Conflict),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[async_trait]
+impl UserRepository for FirebaseDatabase {
+    async fn get(&self, uid: &str) -> Result<Option<UserDB>, RepositoryError> {
+        self.get_node(&format!("user_profiles/{}", uid)).await
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, UserDB>, RepositoryError> {
+        self.get_collection("user_profiles").await
+    }
+
+    async fn find_by_email(
+        &self,
+        email: &str,
+    ) -> Result<Option<(String, UserDB)>, RepositoryError> {
+        // Requiere la regla ".indexOn": ["email"] en user_profiles
+        let users: Option<HashMap<String, UserDB>> = self
+            .send(self.client.get(self.url("user_profiles")).query(&[
+                ("orderBy", "\"email\"".to_string()),
+                // Codificado como JSON: comillas o barras en el email no rompen la consulta
+                ("equalTo", serde_json::to_string(email)?),
+            ]))
+            .await?;
+
+        Ok(users.and_then(|users| users.into_iter().next()))
+    }
+
+    async fn put(&self, uid: &str, user: &UserDB) -> Result<UserDB, RepositoryError> {
+        self.put_node(&format!("user_profiles/{}", uid), user)
+            .await?;
+        Ok(user.clone())
+    }
+
+    async fn set_first_free_class(&self, uid: &str, value: bool) -> Result<(), RepositoryError> {
+        // PATCH para actualizar solo el campo específico
+        self.send::<Value>(
+            self.client
+                .patch(self.url(&format!("user_profiles/{}", uid)))
+                .json(&json!({ "first_free_class": value })),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn delete(&self, uid: &str) -> Result<(), RepositoryError> {
+        self.delete_node(&format!("user_profiles/{}", uid)).await
+    }
+}
+
+#[async_trait]
+impl CommentRepository for FirebaseDatabase {
+    async fn create(&self, comment: &Comment) -> Result<String, RepositoryError> {
+        self.push_node("comments", comment).await
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Comment>, RepositoryError> {
+        self.get_node(&format!("comments/{}", id)).await
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, Comment>, RepositoryError> {
+        self.get_collection("comments").await
+    }
+
+    async fn put(&self, id: &str, comment: &Comment) -> Result<Comment, RepositoryError> {
+        self.put_node(&format!("comments/{}", id), comment).await?;
+        Ok(comment.clone())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), RepositoryError> {
+        self.delete_node(&format!("comments/{}", id)).await
+    }
+
+    async fn get_versioned(&self, id: &str) -> Result<Option<(Comment, String)>, RepositoryError> {
+        let (comment, etag) = self
+            .get_node_versioned::<Comment>(&format!("comments/{}", id))
+            .await?;
+        Ok(comment.map(|comment| (comment, etag)))
+    }
+
+    async fn put_if_version(
+        &self,
+        id: &str,
+        comment: &Comment,
+        version: &str,
+    ) -> Result<(), RepositoryError> {
+        self.put_node_if_match(&format!("comments/{}", id), comment, version)
+            .await
+    }
+}
+
+#[async_trait]
+impl TeacherRepository for FirebaseDatabase {
+    async fn create(&self, teacher: &Teacher) -> Result<String, RepositoryError> {
+        self.push_node("teacher_profiles", teacher).await
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Teacher>, RepositoryError> {
+        self.get_node(&format!("teacher_profiles/{}", id)).await
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, Teacher>, RepositoryError> {
+        self.get_collection("teacher_profiles").await
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), RepositoryError> {
+        self.delete_node(&format!("teacher_profiles/{}", id)).await
+    }
+}
+
+#[async_trait]
+impl SurveyRepository for FirebaseDatabase {
+    async fn put(&self, survey: &Survey) -> Result<Survey, RepositoryError> {
+        self.put_node(&format!("surveys/{}", survey.id), survey)
+            .await?;
+        Ok(survey.clone())
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, Survey>, RepositoryError> {
+        self.get_collection("surveys").await
+    }
+}
+
+#[async_trait]
+impl CalStripeRepository for FirebaseDatabase {
+    async fn put(&self, cal_id: &str, relation: &StripeRelation) -> Result<(), RepositoryError> {
+        self.put_node(&format!("relation_cal_stripe/{}", cal_id), relation)
+            .await
+    }
+
+    async fn get(&self, cal_id: &str) -> Result<Option<StripeRelation>, RepositoryError> {
+        self.get_node(&format!("relation_cal_stripe/{}", cal_id))
+            .await
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, StripeRelation>, RepositoryError> {
+        self.get_collection("relation_cal_stripe").await
+    }
+
+    async fn delete(&self, cal_id: &str) -> Result<(), RepositoryError> {
+        self.delete_node(&format!("relation_cal_stripe/{}", cal_id))
+            .await
+    }
+}
+
+#[async_trait]
+impl SessionRepository for FirebaseDatabase {
+    async fn get(&self, uid: &str) -> Result<UserSessions, RepositoryError> {
+        Ok(self
+            .get_node(&format!("user_sessions/{}", uid))
+            .await?
+            .unwrap_or_default())
+    }
+
+    async fn put_session(
+        &self,
+        uid: &str,
+        session_id: &str,
+        session: &Session,
+    ) -> Result<(), RepositoryError> {
+        // Cada sesión es su propio nodo: logins concurrentes no se pisan
+        self.put_node(
+            &format!("user_sessions/{}/sessions/{}", uid, session_id),
+            session,
+        )
+        .await
+    }
+
+    async fn revoke_all(&self, uid: &str, revoked_at: i64) -> Result<(), RepositoryError> {
+        self.put_node(
+            &format!("user_sessions/{}", uid),
+            &UserSessions {
+                sessions: HashMap::new(),
+                revoked_at: Some(revoked_at),
+            },
+        )
+        .await
+    }
+
+    async fn delete(&self, uid: &str) -> Result<(), RepositoryError> {
+        self.delete_node(&format!("user_sessions/{}", uid)).await
+    }
+}
+
+#[async_trait]
+impl SubscriptionTierRepository for FirebaseDatabase {
+    async fn get(&self, id: &str) -> Result<Option<SubscriptionTier>, RepositoryError> {
+        self.get_node(&format!("subscription_tiers/{}", id)).await
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, SubscriptionTier>, RepositoryError> {
+        self.get_collection("subscription_tiers").await
+    }
+
+    async fn put(&self, id: &str, tier: &SubscriptionTier) -> Result<(), RepositoryError> {
+        self.put_node(&format!("subscription_tiers/{}", id), tier)
+            .await
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), RepositoryError> {
+        self.delete_node(&format!("subscription_tiers/{}", id))
+            .await
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/repositories/firebase.rs"]
+mod extended_tests;
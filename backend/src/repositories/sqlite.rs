@@ -0,0 +1,419 @@
+use {
+    crate::{
+        models::{
+            comments::Comment,
+            error::RepositoryError,
+            session::{Session, UserSessions},
+            sourvey::Survey,
+            stripe::StripeRelation,
+            subscription::SubscriptionTier,
+            teacher::Teacher,
+            user::UserDB,
+        },
+        repositories::{
+            CalStripeRepository, CommentRepository, SessionRepository, SubscriptionTierRepository,
+            SurveyRepository, TeacherRepository, UserRepository,
+        },
+    },
+    async_trait::async_trait,
+    rusqlite::{Connection, OptionalExtension, params},
+    serde::{Serialize, de::DeserializeOwned},
+    std::{
+        collections::HashMap,
+        hash::{DefaultHasher, Hash, Hasher},
+        path::Path,
+        sync::{Mutex, MutexGuard},
+    },
+    uuid::Uuid,
+};
+
+/// Colecciones de la base de datos. Cada una se guarda como una tabla de documentos JSON
+/// con la misma forma que los nodos de Firebase Realtime Database.
+const TABLES: [&str; 7] = [
+    "user_profiles",
+    "comments",
+    "teacher_profiles",
+    "surveys",
+    "relation_cal_stripe",
+    "user_sessions",
+    "subscription_tiers",
+];
+
+/// Implementación de los repositorios sobre SQLite embebido.
+/// Pensada para desarrollo local y tests: las operaciones son síncronas y cortas,
+/// y la conexión se comparte detrás de un Mutex.
+pub struct SqliteDatabase {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteDatabase {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RepositoryError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, RepositoryError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> Result<Self, RepositoryError> {
+        for table in TABLES {
+            connection.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {} (id TEXT PRIMARY KEY, data TEXT NOT NULL)",
+                    table
+                ),
+                [],
+            )?;
+        }
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        // Si otro hilo hizo panic con el lock, la conexión sigue siendo utilizable
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn get_document<T>(&self, table: &str, id: &str) -> Result<Option<T>, RepositoryError>
+    where
+        T: DeserializeOwned,
+    {
+        let data: Option<String> = self
+            .connection()
+            .query_row(
+                &format!("SELECT data FROM {} WHERE id = ?1", table),
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+    }
+
+    fn get_collection<T>(&self, table: &str) -> Result<HashMap<String, T>, RepositoryError>
+    where
+        T: DeserializeOwned,
+    {
+        let connection = self.connection();
+        let mut statement = connection.prepare(&format!("SELECT id, data FROM {}", table))?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut documents: HashMap<String, T> = HashMap::new();
+        for row in rows {
+            let (id, data) = row?;
+            documents.insert(id, serde_json::from_str(&data)?);
+        }
+        Ok(documents)
+    }
+
+    fn put_document<T>(&self, table: &str, id: &str, value: &T) -> Result<(), RepositoryError>
+    where
+        T: Serialize,
+    {
+        let data = serde_json::to_string(value)?;
+        self.connection().execute(
+            &format!(
+                "INSERT INTO {} (id, data) VALUES (?1, ?2)
+                 ON CONFLICT(id) DO UPDATE SET data = excluded.data",
+                table
+            ),
+            params![id, data],
+        )?;
+        Ok(())
+    }
+
+    /// Versión de un documento: hash de su JSON, como el ETag de Firebase
+    fn version_of(data: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    fn get_document_versioned<T>(
+        &self,
+        table: &str,
+        id: &str,
+    ) -> Result<Option<(T, String)>, RepositoryError>
+    where
+        T: DeserializeOwned,
+    {
+        let data: Option<String> = self
+            .connection()
+            .query_row(
+                &format!("SELECT data FROM {} WHERE id = ?1", table),
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        data.map(|data| Ok((serde_json::from_str(&data)?, Self::version_of(&data))))
+            .transpose()
+    }
+
+    /// Lectura y escritura bajo el mismo lock: nadie puede escribir entre la comprobación
+    /// de la versión y el UPDATE
+    fn put_document_if_version<T>(
+        &self,
+        table: &str,
+        id: &str,
+        value: &T,
+        version: &str,
+    ) -> Result<(), RepositoryError>
+    where
+        T: Serialize,
+    {
+        let data = serde_json::to_string(value)?;
+        let connection = self.connection();
+        let current: Option<String> = connection
+            .query_row(
+                &format!("SELECT data FROM {} WHERE id = ?1", table),
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if current.as_deref().map(Self::version_of).as_deref() != Some(version) {
+            return Err(RepositoryError::Conflict);
+        }
+
+        connection.execute(
+            &format!("UPDATE {} SET data = ?2 WHERE id = ?1", table),
+            params![id, data],
+        )?;
+        Ok(())
+    }
+
+    fn push_document<T>(&self, table: &str, value: &T) -> Result<String, RepositoryError>
+    where
+        T: Serialize,
+    {
+        let id = Uuid::new_v4().to_string();
+        self.put_document(table, &id, value)?;
+        Ok(id)
+    }
+
+    fn delete_document(&self, table: &str, id: &str) -> Result<(), RepositoryError> {
+        self.connection()
+            .execute(&format!("DELETE FROM {} WHERE id = ?1", table), params![id])?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserRepository for SqliteDatabase {
+    async fn get(&self, uid: &str) -> Result<Option<UserDB>, RepositoryError> {
+        self.get_document("user_profiles", uid)
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, UserDB>, RepositoryError> {
+        self.get_collection("user_profiles")
+    }
+
+    async fn find_by_email(
+        &self,
+        email: &str,
+    ) -> Result<Option<(String, UserDB)>, RepositoryError> {
+        let row: Option<(String, String)> = self
+            .connection()
+            .query_row(
+                "SELECT id, data FROM user_profiles WHERE json_extract(data, '$.email') = ?1",
+                params![email],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        match row {
+            Some((uid, data)) => Ok(Some((uid, serde_json::from_str(&data)?))),
+            None => Ok(None),
+        }
+    }
+
+    async fn put(&self, uid: &str, user: &UserDB) -> Result<UserDB, RepositoryError> {
+        self.put_document("user_profiles", uid, user)?;
+        Ok(user.clone())
+    }
+
+    async fn set_first_free_class(&self, uid: &str, value: bool) -> Result<(), RepositoryError> {
+        self.connection().execute(
+            "UPDATE user_profiles SET data = json_set(data, '$.first_free_class', json(?2))
+             WHERE id = ?1",
+            params![uid, value.to_string()],
+        )?;
+        Ok(())
+    }
+
+    async fn delete(&self, uid: &str) -> Result<(), RepositoryError> {
+        self.delete_document("user_profiles", uid)
+    }
+}
+
+#[async_trait]
+impl CommentRepository for SqliteDatabase {
+    async fn create(&self, comment: &Comment) -> Result<String, RepositoryError> {
+        self.push_document("comments", comment)
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Comment>, RepositoryError> {
+        self.get_document("comments", id)
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, Comment>, RepositoryError> {
+        self.get_collection("comments")
+    }
+
+    async fn put(&self, id: &str, comment: &Comment) -> Result<Comment, RepositoryError> {
+        self.put_document("comments", id, comment)?;
+        Ok(comment.clone())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), RepositoryError> {
+        self.delete_document("comments", id)
+    }
+
+    async fn get_versioned(&self, id: &str) -> Result<Option<(Comment, String)>, RepositoryError> {
+        self.get_document_versioned("comments", id)
+    }
+
+    async fn put_if_version(
+        &self,
+        id: &str,
+        comment: &Comment,
+        version: &str,
+    ) -> Result<(), RepositoryError> {
+        self.put_document_if_version("comments", id, comment, version)
+    }
+}
+
+#[async_trait]
+impl TeacherRepository for SqliteDatabase {
+    async fn create(&self, teacher: &Teacher) -> Result<String, RepositoryError> {
+        self.push_document("teacher_profiles", teacher)
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Teacher>, RepositoryError> {
+        self.get_document("teacher_profiles", id)
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, Teacher>, RepositoryError> {
+        self.get_collection("teacher_profiles")
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), RepositoryError> {
+        self.delete_document("teacher_profiles", id)
+    }
+}
+
+#[async_trait]
+impl SurveyRepository for SqliteDatabase {
+    async fn put(&self, survey: &Survey) -> Result<Survey, RepositoryError> {
+        self.put_document("surveys", &survey.id, survey)?;
+        Ok(survey.clone())
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, Survey>, RepositoryError> {
+        self.get_collection("surveys")
+    }
+}
+
+#[async_trait]
+impl CalStripeRepository for SqliteDatabase {
+    async fn put(&self, cal_id: &str, relation: &StripeRelation) -> Result<(), RepositoryError> {
+        self.put_document("relation_cal_stripe", cal_id, relation)
+    }
+
+    async fn get(&self, cal_id: &str) -> Result<Option<StripeRelation>, RepositoryError> {
+        self.get_document("relation_cal_stripe", cal_id)
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, StripeRelation>, RepositoryError> {
+        self.get_collection("relation_cal_stripe")
+    }
+
+    async fn delete(&self, cal_id: &str) -> Result<(), RepositoryError> {
+        self.delete_document("relation_cal_stripe", cal_id)
+    }
+}
+
+#[async_trait]
+impl SessionRepository for SqliteDatabase {
+    async fn get(&self, uid: &str) -> Result<UserSessions, RepositoryError> {
+        Ok(self.get_document("user_sessions", uid)?.unwrap_or_default())
+    }
+
+    async fn put_session(
+        &self,
+        uid: &str,
+        session_id: &str,
+        session: &Session,
+    ) -> Result<(), RepositoryError> {
+        // Lectura y escritura en una transacción bajo el mismo lock: dos logins a la vez no
+        // se pisan la sesión del otro
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        let data: Option<String> = transaction
+            .query_row(
+                "SELECT data FROM user_sessions WHERE id = ?1",
+                params![uid],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let mut sessions: UserSessions = data
+            .map(|data| serde_json::from_str(&data))
+            .transpose()?
+            .unwrap_or_default();
+        sessions
+            .sessions
+            .insert(session_id.to_string(), session.clone());
+
+        transaction.execute(
+            "INSERT INTO user_sessions (id, data) VALUES (?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET data = excluded.data",
+            params![uid, serde_json::to_string(&sessions)?],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    async fn revoke_all(&self, uid: &str, revoked_at: i64) -> Result<(), RepositoryError> {
+        self.put_document(
+            "user_sessions",
+            uid,
+            &UserSessions {
+                sessions: HashMap::new(),
+                revoked_at: Some(revoked_at),
+            },
+        )
+    }
+
+    async fn delete(&self, uid: &str) -> Result<(), RepositoryError> {
+        self.delete_document("user_sessions", uid)
+    }
+}
+
+#[async_trait]
+impl SubscriptionTierRepository for SqliteDatabase {
+    async fn get(&self, id: &str) -> Result<Option<SubscriptionTier>, RepositoryError> {
+        self.get_document("subscription_tiers", id)
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, SubscriptionTier>, RepositoryError> {
+        self.get_collection("subscription_tiers")
+    }
+
+    async fn put(&self, id: &str, tier: &SubscriptionTier) -> Result<(), RepositoryError> {
+        self.put_document("subscription_tiers", id, tier)
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), RepositoryError> {
+        self.delete_document("subscription_tiers", id)
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/repositories/sqlite.rs"]
+mod extended_tests;
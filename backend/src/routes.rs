@@ -1,10 +1,12 @@
 pub mod cal;
 pub mod comments;
+pub mod docs;
 pub mod email;
 pub mod mailchimp;
 pub mod metrics;
 pub mod payments;
 pub mod sourvey;
+pub mod subscriptions;
 pub mod teachers;
 pub mod users;
 pub mod webhooks;
@@ -4,7 +4,7 @@ use {
             add_booking, add_guests_to_booking, confirm_booking, get_all_bookings,
             get_all_schedules, get_booking, get_schedule,
         },
-        middleware::auth::firebase_auth_middleware,
+        middleware::{auth::firebase_auth_middleware, authorization::require_verified_email},
         models::state::AppState,
     },
     axum::{
@@ -15,11 +15,19 @@ use {
 };
 
 pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    // Reservar exige el email verificado
+    let verified_routes: Router<Arc<AppState>> = Router::new()
+        .route("/bookings", post(add_booking))
+        .route_layer(middleware::from_fn_with_state(
+            "booking",
+            require_verified_email,
+        ));
+
     let protected_routes: Router<Arc<AppState>> = Router::new()
         .route("/bookings/:id/confirm", post(confirm_booking))
         .route("/bookings/:id/guests", post(add_guests_to_booking))
         .route("/bookings/:id", get(get_booking))
-        .route("/bookings", post(add_booking))
+        .merge(verified_routes)
         .layer(middleware::from_fn_with_state(
             state.clone(),
             firebase_auth_middleware,
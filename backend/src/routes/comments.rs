@@ -2,10 +2,14 @@ use {
     crate::{
         controllers::comments::{
             add_comment, add_reply, delete_comment, delete_reply, edit_comment, edit_reply,
-            get_all_comments, get_comment_by_id, get_reply_by_id, toggle_like,
+            get_all_comments, get_comment_by_id, get_comments_summary, get_moderation_queue,
+            get_reply_by_id, moderate_comment, moderate_reply, toggle_like, toggle_reply_like,
         },
-        middleware::auth::firebase_auth_middleware,
-        models::state::AppState,
+        middleware::{
+            auth::firebase_auth_middleware,
+            authorization::{RequireRole, require_authorization},
+        },
+        models::{state::AppState, user::Role},
     },
     axum::{
         Router, middleware,
@@ -15,18 +19,34 @@ use {
 };
 
 pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
-    let public_routes: Router<Arc<AppState>> = Router::new().route("/all", get(get_all_comments));
+    let public_routes: Router<Arc<AppState>> = Router::new()
+        .route("/all", get(get_all_comments))
+        .route("/summary", get(get_comments_summary));
+
+    let admin_routes: Router<Arc<AppState>> = Router::new()
+        .route("/moderation", get(get_moderation_queue))
+        .route("/moderation/:comment_id", put(moderate_comment))
+        .route(
+            "/moderation/:comment_id/reply/:reply_id",
+            put(moderate_reply),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RequireRole(Role::Admin)),
+            require_authorization::<RequireRole>,
+        ));
 
     let protected_routes: Router<Arc<AppState>> = Router::new()
         .route("/add", post(add_comment))
         .route("/:id", get(get_comment_by_id))
         .route("/edit/:comment_id", put(edit_comment))
         .route("/like/:comment_id", put(toggle_like))
+        .route("/like/:comment_id/reply/:reply_id", put(toggle_reply_like))
         .route("/reply/:comment_id", post(add_reply))
         .route("/reply/:comment_id/:reply_id/edit", put(edit_reply))
         .route("/:comment_id/reply/:reply_id", get(get_reply_by_id))
         .route("/del/:comment_id/reply/:reply_id", delete(delete_reply))
         .route("/del/:comment_id", delete(delete_comment))
+        .merge(admin_routes)
         .layer(middleware::from_fn_with_state(
             state.clone(),
             firebase_auth_middleware,
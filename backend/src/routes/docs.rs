@@ -0,0 +1,183 @@
+use {
+    crate::{controllers, models::state::AppState},
+    axum::{
+        Json, Router,
+        response::{Html, IntoResponse},
+        routing::get,
+    },
+    std::sync::Arc,
+    utoipa::{
+        Modify, OpenApi,
+        openapi::{
+            OpenApi as OpenApiDoc,
+            security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+        },
+    },
+};
+
+/// Documento OpenAPI 3 generado a partir de los handlers (`#[utoipa::path]`) y modelos (`ToSchema`).
+/// Los esquemas usados por las rutas se recogen automáticamente al registrar los paths.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Amanah Academia API",
+        description = "API del backend de Amanah Academia. Todas las respuestas JSON usan el envoltorio `ResponseAPI`."
+    ),
+    paths(
+        controllers::users::register_user,
+        controllers::users::login_user,
+        controllers::users::update_user,
+        controllers::users::get_all_users,
+        controllers::users::refresh_token,
+        controllers::users::get_user_me,
+        controllers::users::get_user_admin_check,
+        controllers::users::delete_me,
+        controllers::users::export_me,
+        controllers::users::link_provider,
+        controllers::users::unlink_provider,
+        controllers::users::list_my_sessions,
+        controllers::users::revoke_my_sessions,
+        controllers::users::get_my_learner_profile,
+        controllers::users::update_my_learner_profile,
+        controllers::users::prefill_my_learner_profile,
+        controllers::users::send_verification_email,
+        controllers::users::confirm_verification_email,
+        controllers::users::request_password_reset,
+        controllers::users::confirm_password_reset,
+        controllers::users::admin_set_role,
+        controllers::users::admin_update_permissions,
+        controllers::users::admin_set_subscription_tier,
+        controllers::users::admin_set_account_status,
+        controllers::users::admin_revoke_sessions,
+        controllers::comments::get_all_comments,
+        controllers::comments::get_comments_summary,
+        controllers::comments::add_comment,
+        controllers::comments::get_comment_by_id,
+        controllers::comments::edit_comment,
+        controllers::comments::toggle_like,
+        controllers::comments::toggle_reply_like,
+        controllers::comments::add_reply,
+        controllers::comments::edit_reply,
+        controllers::comments::get_reply_by_id,
+        controllers::comments::get_moderation_queue,
+        controllers::comments::moderate_comment,
+        controllers::comments::moderate_reply,
+        controllers::comments::delete_reply,
+        controllers::comments::delete_comment,
+        controllers::payments::payment_intent,
+        controllers::payments::get_payment_history,
+        controllers::payments::create_product,
+        controllers::payments::get_all_products,
+        controllers::payments::get_all_prices,
+        controllers::payments::archive_product,
+        controllers::payments::delete_price,
+        controllers::payments::archive_cal_connection,
+        controllers::payments::get_all_paid_reservations,
+        controllers::teachers::get_all_teachers,
+        controllers::teachers::get_teacher,
+        controllers::teachers::create_teacher,
+        controllers::teachers::delete_teacher,
+        controllers::sourvey::create_survey,
+        controllers::sourvey::get_survey_results,
+        controllers::sourvey::get_all_survey_results,
+        controllers::subscriptions::get_subscription_tiers,
+        controllers::subscriptions::put_subscription_tier,
+        controllers::subscriptions::delete_subscription_tier,
+        controllers::subscriptions::get_my_entitlement,
+        controllers::email::send_contact_email,
+        controllers::mailchimp::add_contact,
+        controllers::mailchimp::get_all_contacts,
+        controllers::cal::confirm_booking,
+        controllers::cal::get_booking,
+        controllers::cal::add_guests_to_booking,
+        controllers::cal::add_booking,
+        controllers::cal::get_all_bookings,
+        controllers::cal::get_all_schedules,
+        controllers::cal::get_schedule,
+        controllers::metrics::get_user_metrics,
+        controllers::metrics::get_article_metrics,
+        controllers::metrics::get_class_metrics,
+        controllers::webhook::health_check,
+        controllers::webhook::tasks_health,
+        controllers::webhook::handle_cal_webhook,
+    ),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "users", description = "Registro, sesión y cuenta del usuario"),
+        (name = "comments", description = "Comentarios y respuestas"),
+        (name = "payments", description = "Pagos y catálogo de Stripe"),
+        (name = "teachers", description = "Profesores"),
+        (name = "surveys", description = "Encuestas"),
+        (name = "subscriptions", description = "Planes de suscripción y clases incluidas"),
+        (name = "email", description = "Formulario de contacto"),
+        (name = "mailchimp", description = "Newsletter"),
+        (name = "cal", description = "Reservas y horarios de Cal.com"),
+        (name = "metrics", description = "Métricas de Google Analytics"),
+        (name = "webhook", description = "Webhooks y salud del servicio"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registra el esquema `bearer_auth` (idToken de Firebase) que usan las rutas protegidas
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some("idToken de Firebase Auth"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Página de Swagger UI; los assets se cargan desde el CDN para no empaquetarlos en el binario
+const SWAGGER_UI_HTML: &str = r##"<!DOCTYPE html>
+<html lang="es">
+<head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>Amanah Academia API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+    <script>
+        window.onload = () => {
+            window.ui = SwaggerUIBundle({
+                url: window.location.pathname.replace(/\/$/, "") + "/openapi.json",
+                dom_id: "#swagger-ui",
+                persistAuthorization: true,
+            });
+        };
+    </script>
+</body>
+</html>
+"##;
+
+async fn swagger_ui() -> impl IntoResponse {
+    Html(SWAGGER_UI_HTML)
+}
+
+async fn openapi_json() -> impl IntoResponse {
+    Json(ApiDoc::openapi())
+}
+
+pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let public_routes: Router<Arc<AppState>> = Router::new()
+        .route("/", get(swagger_ui))
+        .route("/openapi.json", get(openapi_json));
+
+    Router::new().merge(public_routes).with_state(state)
+}
+
+#[cfg(test)]
+#[path = "../test/routes/docs.rs"]
+mod extended_tests;
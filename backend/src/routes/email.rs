@@ -1,12 +1,20 @@
 use {
-    crate::{controllers::email::send_contact_email, models::state::AppState},
-    axum::{Router, routing::post},
+    crate::{
+        controllers::email::send_contact_email,
+        middleware::rate_limit::rate_limit,
+        models::{rate_limit::RateLimitGroup, state::AppState},
+    },
+    axum::{Router, middleware, routing::post},
     std::sync::Arc,
 };
 
 pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
-    let public_routes: Router<Arc<AppState>> =
-        Router::new().route("/contact", post(send_contact_email));
+    let public_routes: Router<Arc<AppState>> = Router::new()
+        .route("/contact", post(send_contact_email))
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RateLimitGroup::Contact),
+            rate_limit,
+        ));
 
     Router::new().merge(public_routes).with_state(state)
 }
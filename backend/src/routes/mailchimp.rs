@@ -1,18 +1,23 @@
 use {
     crate::{
         controllers::mailchimp::{add_contact, get_all_contacts},
-        models::state::AppState,
+        middleware::rate_limit::rate_limit,
+        models::{rate_limit::RateLimitGroup, state::AppState},
     },
     axum::{
-        Router,
+        Router, middleware,
         routing::{get, post},
     },
     std::sync::Arc,
 };
 
 pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
-    let public_routes: Router<Arc<AppState>> =
-        Router::new().route("/add_contact", post(add_contact));
+    let public_routes: Router<Arc<AppState>> = Router::new()
+        .route("/add_contact", post(add_contact))
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RateLimitGroup::Newsletter),
+            rate_limit,
+        ));
 
     let protected_routes: Router<Arc<AppState>> =
         Router::new().route("/get_all_contacts", get(get_all_contacts));
@@ -5,8 +5,11 @@ use {
             get_all_paid_reservations, get_all_prices, get_all_products, get_payment_history,
             payment_intent,
         },
-        middleware::auth::firebase_auth_middleware,
-        models::state::AppState,
+        middleware::{
+            auth::firebase_auth_middleware,
+            authorization::{RequirePermission, require_authorization},
+        },
+        models::{state::AppState, user::Permission},
     },
     axum::{
         Router, middleware,
@@ -16,6 +19,15 @@ use {
 };
 
 pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let product_admin_routes = Router::new()
+        .route("/del/product/:id", delete(archive_product))
+        .route("/del/price/:id", delete(delete_price))
+        .route("/product", post(create_product))
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RequirePermission(Permission::ManageProducts)),
+            require_authorization::<RequirePermission>,
+        ));
+
     Router::new().merge(
         Router::new()
             .route("/intent", post(payment_intent))
@@ -23,10 +35,8 @@ pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
             .route("/cal/connection/all", get(get_all_paid_reservations))
             .route("/product/all", get(get_all_products))
             .route("/price/all", get(get_all_prices))
-            .route("/del/product/:id", delete(archive_product))
-            .route("/del/price/:id", delete(delete_price))
-            .route("/product", post(create_product))
             .route("/history", get(get_payment_history))
+            .merge(product_admin_routes)
             .layer(middleware::from_fn_with_state(
                 state.clone(),
                 firebase_auth_middleware,
@@ -1,8 +1,11 @@
 use {
     crate::{
         controllers::sourvey::{create_survey, get_all_survey_results, get_survey_results},
-        middleware::auth::firebase_auth_middleware,
-        models::state::AppState,
+        middleware::{
+            auth::firebase_auth_middleware,
+            authorization::{RequirePermission, require_authorization},
+        },
+        models::{state::AppState, user::Permission},
     },
     axum::{
         Router, middleware,
@@ -12,10 +15,17 @@ use {
 };
 
 pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let survey_admin_routes: Router<Arc<AppState>> = Router::new()
+        .route("/results", get(get_all_survey_results))
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RequirePermission(Permission::ReadSurveys)),
+            require_authorization::<RequirePermission>,
+        ));
+
     Router::new()
         .route("/", post(create_survey))
         .route("/:survey_id/results", get(get_survey_results))
-        .route("/results", get(get_all_survey_results))
+        .merge(survey_admin_routes)
         .layer(middleware::from_fn_with_state(
             state.clone(),
             firebase_auth_middleware,
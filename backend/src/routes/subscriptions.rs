@@ -0,0 +1,47 @@
+use {
+    crate::{
+        controllers::subscriptions::{
+            delete_subscription_tier, get_my_entitlement, get_subscription_tiers,
+            put_subscription_tier,
+        },
+        middleware::{
+            auth::firebase_auth_middleware,
+            authorization::{RequirePermission, require_authorization},
+        },
+        models::{state::AppState, user::Permission},
+    },
+    axum::{
+        Router, middleware,
+        routing::{get, put},
+    },
+    std::sync::Arc,
+};
+
+pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let public_routes: Router<Arc<AppState>> =
+        Router::new().route("/tiers", get(get_subscription_tiers)); // GET /subscriptions/tiers
+
+    // Los planes forman parte del catálogo de Stripe
+    let tier_admin_routes: Router<Arc<AppState>> = Router::new()
+        .route(
+            "/tiers/:id",
+            put(put_subscription_tier).delete(delete_subscription_tier),
+        ) // PUT, DELETE /subscriptions/tiers/:id
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RequirePermission(Permission::ManageProducts)),
+            require_authorization::<RequirePermission>,
+        ));
+
+    let protected_routes: Router<Arc<AppState>> = Router::new()
+        .route("/me", get(get_my_entitlement)) // GET /subscriptions/me
+        .merge(tier_admin_routes)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            firebase_auth_middleware,
+        ));
+
+    Router::new()
+        .merge(public_routes)
+        .merge(protected_routes)
+        .with_state(state)
+}
@@ -1,8 +1,11 @@
 use {
     crate::{
         controllers::teachers::{create_teacher, delete_teacher, get_all_teachers, get_teacher},
-        middleware::auth::firebase_auth_middleware,
-        models::state::AppState,
+        middleware::{
+            auth::firebase_auth_middleware,
+            authorization::{RequirePermission, require_authorization},
+        },
+        models::{state::AppState, user::Permission},
     },
     axum::{
         Router, middleware,
@@ -14,10 +17,17 @@ use {
 pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
     let public_routes: Router<Arc<AppState>> = Router::new().route("/all", get(get_all_teachers)); // GET /all
 
-    let protected_routes: Router<Arc<AppState>> = Router::new()
+    let teacher_admin_routes: Router<Arc<AppState>> = Router::new()
         .route("/add", post(create_teacher)) // POST /add
+        .route("/del/:id", delete(delete_teacher)) // DELETE /del/:id
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RequirePermission(Permission::ManageTeachers)),
+            require_authorization::<RequirePermission>,
+        ));
+
+    let protected_routes: Router<Arc<AppState>> = Router::new()
         .route("/:id", get(get_teacher)) // GET /user/:id
-        .route("/del/:id", delete(delete_teacher)) // GET /user/:id
+        .merge(teacher_admin_routes)
         .layer(middleware::from_fn_with_state(
             state.clone(),
             firebase_auth_middleware,
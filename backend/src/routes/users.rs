@@ -1,11 +1,20 @@
 use {
     crate::{
         controllers::users::{
-            delete_me, get_all_users, get_user_admin_check, get_user_me, login_user, refresh_token,
-            register_user, update_user,
+            admin_revoke_sessions, admin_set_account_status, admin_set_role,
+            admin_set_subscription_tier, admin_update_permissions, confirm_password_reset,
+            confirm_verification_email, delete_me, export_me, get_all_users,
+            get_my_learner_profile, get_user_admin_check, get_user_me, link_provider,
+            list_my_sessions, login_user, prefill_my_learner_profile, refresh_token, register_user,
+            request_password_reset, revoke_my_sessions, send_verification_email, unlink_provider,
+            update_my_learner_profile, update_user,
         },
-        middleware::auth::firebase_auth_middleware,
-        models::state::AppState,
+        middleware::{
+            auth::firebase_auth_middleware,
+            authorization::{RequireRole, require_authorization},
+            rate_limit::rate_limit,
+        },
+        models::{rate_limit::RateLimitGroup, state::AppState, user::Role},
     },
     axum::{
         Router, middleware,
@@ -17,15 +26,55 @@ use {
 pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
     let public_routes = Router::new()
         .route("/register", post(register_user)) // POST /user/register
-        .route("/login", post(login_user)); // GET /user/login
+        .route("/login", post(login_user)) // GET /user/login
+        .route("/password-reset/request", post(request_password_reset)) // POST /user/password-reset/request
+        .route("/password-reset/confirm", post(confirm_password_reset)) // POST /user/password-reset/confirm
+        .route("/verify-email/confirm", post(confirm_verification_email)) // POST /user/verify-email/confirm
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RateLimitGroup::Auth),
+            rate_limit,
+        ));
+
+    // Cada petición manda un email con Resend: mismo presupuesto que el restablecimiento
+    let email_routes = Router::new()
+        .route("/verify-email", post(send_verification_email)) // POST /user/verify-email
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RateLimitGroup::Auth),
+            rate_limit,
+        ));
+
+    let admin_routes = Router::new()
+        .route("/all", get(get_all_users)) // GET /user/all
+        .route("/admin/:uid/role", put(admin_set_role)) // PUT /user/admin/:uid/role
+        .route("/admin/:uid/permissions", put(admin_update_permissions)) // PUT /user/admin/:uid/permissions
+        .route("/admin/:uid/tier", put(admin_set_subscription_tier)) // PUT /user/admin/:uid/tier
+        .route("/admin/:uid/status", put(admin_set_account_status)) // PUT /user/admin/:uid/status
+        .route("/admin/:uid/sessions", delete(admin_revoke_sessions)) // DELETE /user/admin/:uid/sessions
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RequireRole(Role::Admin)),
+            require_authorization::<RequireRole>,
+        ));
 
     let protected_routes = Router::new()
         .route("/update/me", put(update_user)) // PUT /user/
         .route("/del/me", delete(delete_me)) // DELETE /user/me
-        .route("/all", get(get_all_users)) // GET /user/all
         .route("/refresh_token", put(refresh_token)) // PUT /user/refresh_token
         .route("/me", get(get_user_me)) // GET /user/me
+        .route("/me/export", get(export_me)) // GET /user/me/export
+        .route(
+            "/me/profile",
+            get(get_my_learner_profile).put(update_my_learner_profile),
+        ) // GET, PUT /user/me/profile
+        .route("/me/profile/prefill", post(prefill_my_learner_profile)) // POST /user/me/profile/prefill
+        .route("/me/providers", post(link_provider)) // POST /user/me/providers
+        .route("/me/providers/:provider", delete(unlink_provider)) // DELETE /user/me/providers/:provider
+        .route(
+            "/me/sessions",
+            get(list_my_sessions).delete(revoke_my_sessions),
+        ) // GET, DELETE /user/me/sessions
         .route("/admin_check", get(get_user_admin_check)) // GET /user/admin_check
+        .merge(email_routes)
+        .merge(admin_routes)
         .layer(middleware::from_fn_with_state(
             state.clone(),
             firebase_auth_middleware,
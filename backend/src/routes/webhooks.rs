@@ -1,18 +1,44 @@
 use {
     crate::{
-        controllers::webhook::{handle_cal_webhook, health_check},
-        models::state::AppState,
+        controllers::webhook::{handle_cal_webhook, health_check, tasks_health},
+        middleware::{
+            auth::firebase_auth_middleware,
+            authorization::{RequireRole, require_authorization},
+            rate_limit::rate_limit,
+        },
+        models::{rate_limit::RateLimitGroup, state::AppState, user::Role},
     },
     axum::{
-        Router,
+        Router, middleware,
         routing::{get, post},
     },
     std::sync::Arc,
 };
 
 pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let webhook_routes = Router::new()
+        .route("/cal", post(handle_cal_webhook))
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RateLimitGroup::Webhook),
+            rate_limit,
+        ));
+
     let public_routes = Router::new()
         .route("/health", get(health_check))
-        .route("/cal", post(handle_cal_webhook));
-    Router::new().merge(public_routes).with_state(state)
+        .merge(webhook_routes);
+    let admin_routes = Router::new()
+        .route("/health/tasks", get(tasks_health))
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RequireRole(Role::Admin)),
+            require_authorization::<RequireRole>,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            firebase_auth_middleware,
+        ));
+
+    Router::new()
+        .merge(public_routes)
+        .merge(admin_routes)
+        .with_state(state)
 }
@@ -1,4 +1,16 @@
+pub mod account_deletion;
+pub mod comments;
+pub mod data_export;
+pub mod email;
 pub mod firebase;
+pub mod firebase_keys;
+pub mod google_oauth;
 pub mod mailchimp;
 pub mod metrics;
+pub mod moderation;
 pub mod payments;
+pub mod reviews;
+pub mod sessions;
+pub mod subscriptions;
+pub mod supervisor;
+pub mod users;
@@ -0,0 +1,412 @@
+use {
+    crate::{
+        models::{
+            cal::CalBookingPayload,
+            comments::Comment,
+            error::ApiError,
+            mailchimp::MembershipStatus,
+            state::AppState,
+            stripe::StripeRelation,
+            user::{AccountDeletionReport, CancelledBooking, FailedDeletionStep},
+            webhook::{CalBookingsResponse, RefundResponse},
+        },
+        services::{
+            comments::update_comment, data_export::fetch_newsletter, firebase::delete_account,
+            payments::refund_payment_intent,
+        },
+    },
+    chrono::{DateTime, Duration as ChronoDuration, Utc},
+    reqwest::StatusCode,
+    std::{collections::HashMap, future::Future, time::Duration},
+    tracing::{info, warn},
+};
+
+/// Nombre que sustituye al del autor en el contenido anonimizado
+pub const DELETED_USER_NAME: &str = "Usuario eliminado";
+/// `author_uid` de las respuestas anonimizadas (el campo es obligatorio)
+pub const DELETED_USER_UID: &str = "deleted-user";
+/// Antigüedad máxima del login para aceptar el borrado de la cuenta
+pub const RECENT_LOGIN_WINDOW: ChronoDuration = ChronoDuration::minutes(5);
+/// Antelación mínima para reembolsar una reserva pagada cancelada por el borrado
+pub const REFUND_MIN_NOTICE: ChronoDuration = ChronoDuration::hours(24);
+
+/// Intentos por paso antes de darlo por fallido
+const MAX_ATTEMPTS: u32 = 3;
+/// Espera antes del segundo intento; se duplica en cada reintento
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// Borra la cuenta en cascada. Cada paso es idempotente y se reintenta con backoff; si alguno
+/// sigue fallando la cuenta de Firebase y el perfil se conservan para que el usuario pueda
+/// repetir la petición y completar el borrado. Encuestas, newsletter y reservas se buscan por
+/// email, así que solo se tocan con el email verificado del token (`verified_email`).
+pub async fn delete_account_cascade(
+    state: &AppState,
+    uid: &str,
+    verified_email: Option<&str>,
+) -> AccountDeletionReport {
+    let mut report: AccountDeletionReport = AccountDeletionReport::default();
+
+    match with_retry("comments", || anonymize_comments(state, uid)).await {
+        Ok((comments, replies, likes)) => {
+            report.comments_anonymized = comments;
+            report.replies_anonymized = replies;
+            report.likes_removed = likes;
+        }
+        Err(failed) => report.failed_steps.push(failed),
+    }
+
+    if let Some(email) = verified_email {
+        match with_retry("surveys", || anonymize_surveys(state, email)).await {
+            Ok(surveys) => report.surveys_anonymized = surveys,
+            Err(failed) => report.failed_steps.push(failed),
+        }
+        match with_retry("newsletter", || archive_newsletter_member(state, email)).await {
+            Ok(archived) => report.newsletter_archived = archived,
+            Err(failed) => report.failed_steps.push(failed),
+        }
+        match with_retry("bookings", || fetch_upcoming_bookings(state, email)).await {
+            Ok(bookings) => {
+                // Cada reserva falla por separado; las demás se cancelan igualmente
+                for booking in &bookings {
+                    let Some(uid) = booking.uid.as_deref() else {
+                        continue;
+                    };
+                    match cancel_booking(state, booking, uid).await {
+                        Ok(cancelled) => report.bookings_cancelled.push(cancelled),
+                        Err(failed) => report.failed_steps.push(failed),
+                    }
+                }
+            }
+            Err(failed) => report.failed_steps.push(failed),
+        }
+    }
+
+    if !report.failed_steps.is_empty() {
+        warn!(
+            "Account deletion for {} incomplete, keeping the account: {:?}",
+            uid, report.failed_steps
+        );
+        return report;
+    }
+
+    // Primero Firebase Auth: si falla el perfil sigue existiendo y el borrado se puede repetir
+    let auth = with_retry("auth_account", || async {
+        delete_account(&state.firebase_options, uid)
+            .await
+            .map(|_| ())
+            .map_err(|(status, message)| format!("{}: {}", status, message))
+    })
+    .await;
+    if let Err(failed) = auth {
+        report.failed_steps.push(failed);
+        return report;
+    }
+
+    match with_retry("profile", || async {
+        state
+            .repositories
+            .users
+            .delete(uid)
+            .await
+            .map_err(|e| e.to_string())?;
+        // Las sesiones guardan el User-Agent de cada dispositivo
+        state
+            .repositories
+            .sessions
+            .delete(uid)
+            .await
+            .map_err(|e| e.to_string())
+    })
+    .await
+    {
+        Ok(()) => report.account_deleted = true,
+        Err(failed) => report.failed_steps.push(failed),
+    }
+
+    info!("Account {} deleted: {:?}", uid, report);
+    report
+}
+
+/// El borrado es irreversible: solo se acepta con un login de hace menos de
+/// `RECENT_LOGIN_WINDOW` (`auth_time` en segundos Unix)
+pub fn is_recent_login(auth_time: i64, now: DateTime<Utc>) -> bool {
+    now.timestamp() - auth_time <= RECENT_LOGIN_WINDOW.num_seconds()
+}
+
+/// Ejecuta un paso hasta `MAX_ATTEMPTS` veces con backoff exponencial
+async fn with_retry<T, F, Fut>(step: &str, mut operation: F) -> Result<T, FailedDeletionStep>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let mut delay: Duration = RETRY_BASE_DELAY;
+    let mut attempt: u32 = 1;
+
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(error) if attempt >= MAX_ATTEMPTS => {
+                return Err(FailedDeletionStep {
+                    step: step.to_string(),
+                    attempts: attempt,
+                    error,
+                });
+            }
+            Err(error) => {
+                warn!(
+                    "Account deletion step '{}' failed (attempt {}): {}",
+                    step, attempt, error
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Anonimiza comentarios y respuestas del usuario y quita sus likes. Cada comentario se
+/// reescribe con `update_comment` para no pisar likes o respuestas que lleguen a la vez.
+/// Devuelve (comentarios, respuestas, likes) modificados.
+async fn anonymize_comments(state: &AppState, uid: &str) -> Result<(usize, usize, usize), String> {
+    let comments: HashMap<String, Comment> = state
+        .repositories
+        .comments
+        .get_all()
+        .await
+        .map_err(|e| e.to_string())?;
+    let (mut comments_count, mut replies_count, mut likes_count) = (0, 0, 0);
+
+    for (id, mut comment) in comments {
+        // Solo se reescriben los comentarios en los que aparece el usuario
+        if anonymize_comment(&mut comment, uid) == (0, 0, 0) {
+            continue;
+        }
+
+        match update_comment(state, &id, |comment| Ok(anonymize_comment(comment, uid))).await {
+            Ok((_, (comments, replies, likes))) => {
+                comments_count += comments;
+                replies_count += replies;
+                likes_count += likes;
+            }
+            // Borrado entre la lectura y la escritura
+            Err(ApiError::NotFound(_)) => {}
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+
+    Ok((comments_count, replies_count, likes_count))
+}
+
+/// Anonimiza un comentario y devuelve (comentario, respuestas, likes) modificados
+fn anonymize_comment(comment: &mut Comment, uid: &str) -> (usize, usize, usize) {
+    let (mut comments_count, mut replies_count, mut likes_count) = (0, 0, 0);
+
+    if comment.author_uid.as_deref() == Some(uid) {
+        comment.author_uid = None;
+        comment.name = DELETED_USER_NAME.to_string();
+        comment.url_img = None;
+        comment.booking_uid = None;
+        comments_count += 1;
+    }
+    if remove_like(&mut comment.users_liked, &mut comment.like, uid) {
+        likes_count += 1;
+    }
+
+    for reply in comment.reply.iter_mut() {
+        if reply.author_uid == uid {
+            reply.author_uid = DELETED_USER_UID.to_string();
+            reply.name = DELETED_USER_NAME.to_string();
+            reply.url_img = None;
+            replies_count += 1;
+        }
+        if remove_like(&mut reply.users_liked, &mut reply.like, uid) {
+            likes_count += 1;
+        }
+    }
+
+    (comments_count, replies_count, likes_count)
+}
+
+/// Quita el like del usuario y ajusta el contador. Devuelve si había like.
+fn remove_like(users_liked: &mut Vec<String>, like: &mut u32, uid: &str) -> bool {
+    let before: usize = users_liked.len();
+    users_liked.retain(|liked| liked != uid);
+    let removed: usize = before - users_liked.len();
+    *like = like.saturating_sub(removed as u32);
+    removed > 0
+}
+
+/// Las respuestas se conservan para las estadísticas, pero sin el email
+async fn anonymize_surveys(state: &AppState, email: &str) -> Result<usize, String> {
+    let surveys = state
+        .repositories
+        .surveys
+        .get_all()
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut count: usize = 0;
+
+    for mut survey in surveys.into_values() {
+        if survey.user_email.eq_ignore_ascii_case(email) {
+            survey.user_email = String::new();
+            state
+                .repositories
+                .surveys
+                .put(&survey)
+                .await
+                .map_err(|e| e.to_string())?;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Archiva el miembro de la audiencia de Mailchimp. Devuelve `false` si no estaba suscrito.
+async fn archive_newsletter_member(state: &AppState, email: &str) -> Result<bool, String> {
+    let Some(member): Option<MembershipStatus> = fetch_newsletter(state, Some(email)).await? else {
+        return Ok(false);
+    };
+    let mailchimp = &state.mailchimp_client;
+
+    // DELETE archiva el contacto (no lo borra de forma permanente)
+    let response: reqwest::Response = mailchimp
+        .client
+        .delete(format!(
+            "{}/lists/{}/members/{}",
+            mailchimp.get_base_url(),
+            mailchimp.list_id,
+            member.id
+        ))
+        .basic_auth("", Some(&mailchimp.api_key))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match response.status() {
+        status if status.is_success() => Ok(true),
+        // Archivado entre la búsqueda y el borrado (p. ej. en un intento anterior)
+        StatusCode::NOT_FOUND => Ok(false),
+        status => Err(format!("Mailchimp responded {}", status)),
+    }
+}
+
+/// Reservas futuras del usuario en Cal.com
+async fn fetch_upcoming_bookings(
+    state: &AppState,
+    email: &str,
+) -> Result<Vec<CalBookingPayload>, String> {
+    let cal = &state.cal_options;
+    let response: reqwest::Response = cal
+        .client
+        .get(format!("{}/bookings", cal.base_url))
+        .header("cal-api-version", "2024-06-11")
+        .header("Authorization", &cal.api_key)
+        .query(&[("attendeeEmail", email), ("status", "upcoming")])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Cal.com responded {}", response.status()));
+    }
+
+    Ok(response
+        .json::<CalBookingsResponse>()
+        .await
+        .map_err(|e| e.to_string())?
+        .data
+        .bookings)
+}
+
+/// Cancela una reserva aplicando las reglas de reembolso. Primero Cal.com: si falla, el pago
+/// y su relación siguen intactos. Si la reserva no se reembolsa la relación se elimina antes,
+/// porque el webhook de cancelación reembolsa sin mirar la antelación mínima; si se reembolsa,
+/// el webhook usa la misma clave de idempotencia y Stripe crea un único reembolso.
+async fn cancel_booking(
+    state: &AppState,
+    booking: &CalBookingPayload,
+    uid: &str,
+) -> Result<CancelledBooking, FailedDeletionStep> {
+    let step: String = format!("booking {}", uid);
+
+    let relation: Option<StripeRelation> = with_retry(&step, || async {
+        state
+            .repositories
+            .cal_stripe
+            .get(uid)
+            .await
+            .map_err(|e| e.to_string())
+    })
+    .await?;
+    let refundable: bool = is_refundable(booking, Utc::now());
+
+    if relation.is_some() && !refundable {
+        info!("Booking {} starts too soon, not refunded", uid);
+        with_retry(&step, || delete_relation(state, uid)).await?;
+    }
+
+    with_retry(&step, || cancel_in_cal(state, uid)).await?;
+
+    let refund: Option<RefundResponse> = match relation.filter(|_| refundable) {
+        Some(relation) => {
+            let refund: RefundResponse =
+                with_retry(&step, || refund_payment_intent(state, &relation.stripe_id)).await?;
+            with_retry(&step, || delete_relation(state, uid)).await?;
+            Some(refund)
+        }
+        None => None,
+    };
+
+    Ok(CancelledBooking {
+        uid: uid.to_string(),
+        start_time: booking.start_time.clone(),
+        refunded: refund.is_some(),
+        refund_id: refund.map(|refund| refund.id),
+    })
+}
+
+/// Cancela la reserva en Cal.com
+async fn cancel_in_cal(state: &AppState, uid: &str) -> Result<(), String> {
+    let cal = &state.cal_options;
+    let response: reqwest::Response = cal
+        .client
+        .post(format!("{}/bookings/{}/cancel", cal.base_url, uid))
+        .header("cal-api-version", "2024-08-13")
+        .header("Authorization", &cal.api_key)
+        .json(&serde_json::json!({ "cancellationReason": "Account deleted" }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Cal.com responded {} cancelling {}",
+            response.status(),
+            uid
+        ));
+    }
+    Ok(())
+}
+
+/// Elimina la relación booking-pago
+async fn delete_relation(state: &AppState, uid: &str) -> Result<(), String> {
+    state
+        .repositories
+        .cal_stripe
+        .delete(uid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Una reserva sin fecha de inicio conocida se reembolsa (a favor del usuario)
+pub fn is_refundable(booking: &CalBookingPayload, now: DateTime<Utc>) -> bool {
+    booking
+        .start_time
+        .as_deref()
+        .and_then(|start| DateTime::parse_from_rfc3339(start).ok())
+        .is_none_or(|start| start.with_timezone(&Utc) - now >= REFUND_MIN_NOTICE)
+}
+
+#[cfg(test)]
+#[path = "../test/services/account_deletion.rs"]
+mod extended_tests;
@@ -0,0 +1,183 @@
+use {
+    crate::{
+        models::{
+            comments::{Comment, CommentListQuery, CommentSort, RatingSummary},
+            error::{ApiError, RepositoryError},
+            state::AppState,
+        },
+        services::reviews::is_review,
+    },
+    chrono::{DateTime, NaiveDateTime},
+    indexmap::IndexMap,
+    std::{
+        cmp::Ordering,
+        collections::{BTreeMap, HashMap, HashSet},
+    },
+    tracing::{debug, warn},
+};
+
+/// Comentarios con más likes que se muestran en el resumen
+pub const MOST_LIKED_LIMIT: usize = 3;
+
+/// Intentos de una escritura condicional antes de responder 409
+pub const MAX_WRITE_ATTEMPTS: usize = 5;
+
+/// Instante de un comentario o respuesta. Los timestamps se guardan como `dd/mm/YYYY HH:MM`;
+/// los que no se pueden leer quedan al final al ordenar por fecha.
+pub fn timestamp_time(timestamp: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(timestamp, "%d/%m/%Y %H:%M")
+        .ok()
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(timestamp)
+                .ok()
+                .map(|time| time.naive_utc())
+        })
+}
+
+fn comment_time(comment: &Comment) -> Option<NaiveDateTime> {
+    timestamp_time(&comment.timestamp)
+}
+
+fn compare(sort: CommentSort, a: &Comment, b: &Comment) -> Ordering {
+    match sort {
+        CommentSort::Newest => comment_time(b).cmp(&comment_time(a)),
+        CommentSort::MostLiked => b.like.cmp(&a.like),
+        CommentSort::HighestStars => b.stars.total_cmp(&a.stars),
+        CommentSort::LowestStars => a.stars.total_cmp(&b.stars),
+    }
+}
+
+/// Filtra y ordena los comentarios. A igualdad se desempata por fecha y después por id,
+/// para que el orden sea estable entre peticiones.
+pub fn list_comments(
+    comments: HashMap<String, Comment>,
+    query: &CommentListQuery,
+) -> IndexMap<String, Comment> {
+    let sort: CommentSort = query.sort.unwrap_or_default();
+    let mut listed: Vec<(String, Comment)> = comments
+        .into_iter()
+        .filter(|(_, comment)| query.min_stars.is_none_or(|min| comment.stars >= min))
+        .filter(|(_, comment)| {
+            query
+                .has_replies
+                .is_none_or(|has_replies| has_replies != comment.reply.is_empty())
+        })
+        .collect();
+
+    listed.sort_by(|(id_a, a), (id_b, b)| {
+        compare(sort, a, b)
+            .then_with(|| compare(CommentSort::Newest, a, b))
+            .then_with(|| id_a.cmp(id_b))
+    });
+    listed.into_iter().collect()
+}
+
+/// Media, histograma y comentarios con más likes. Solo cuentan las reseñas: un comentario
+/// sin estrellas no es una valoración de 1.
+pub fn rating_summary(comments: HashMap<String, Comment>) -> RatingSummary {
+    let comments: HashMap<String, Comment> = comments
+        .into_iter()
+        .filter(|(_, comment)| is_review(comment.stars))
+        .collect();
+    let count: usize = comments.len();
+    let mut histogram: BTreeMap<u8, usize> = (1..=5).map(|stars| (stars, 0)).collect();
+    for comment in comments.values() {
+        *histogram
+            .entry(comment.stars.round().clamp(1.0, 5.0) as u8)
+            .or_default() += 1;
+    }
+    let average: f32 = if count == 0 {
+        0.0
+    } else {
+        let total: f32 = comments.values().map(|comment| comment.stars).sum();
+        (total / count as f32 * 10.0).round() / 10.0
+    };
+
+    let mut most_liked: IndexMap<String, Comment> = list_comments(
+        comments,
+        &CommentListQuery {
+            sort: Some(CommentSort::MostLiked),
+            ..Default::default()
+        },
+    );
+    most_liked.truncate(MOST_LIKED_LIMIT);
+
+    RatingSummary {
+        average,
+        count,
+        histogram,
+        most_liked,
+    }
+}
+
+/// Añade o quita el like de `uid` y devuelve si queda con like. El contador se recalcula a
+/// partir de la lista sin duplicados, así un uid repetido o un contador desfasado no se
+/// cuentan dos veces.
+pub fn toggle_like(users_liked: &mut Vec<String>, like: &mut u32, uid: &str) -> bool {
+    let mut seen: HashSet<String> = HashSet::new();
+    users_liked.retain(|user| seen.insert(user.clone()));
+
+    let liked: bool = !seen.contains(uid);
+    if liked {
+        users_liked.push(uid.to_string());
+    } else {
+        users_liked.retain(|user| user != uid);
+    }
+    *like = users_liked.len() as u32;
+    liked
+}
+
+/// Marca en el comentario y sus respuestas si `uid` les ha dado like
+pub fn mark_liked(comment: &mut Comment, uid: &str) {
+    comment.liked = Some(comment.users_liked.iter().any(|user| user == uid));
+    for reply in &mut comment.reply {
+        reply.liked = Some(reply.users_liked.iter().any(|user| user == uid));
+    }
+}
+
+/// Lee la última versión del comentario, aplica `mutate` y la guarda con una escritura
+/// condicional. Si otro escritor se adelanta se vuelve a leer y se reaplica `mutate` (que no
+/// debe tener efectos fuera del comentario); un error de `mutate` cancela la escritura.
+pub async fn update_comment<T, F>(
+    state: &AppState,
+    id: &str,
+    mut mutate: F,
+) -> Result<(Comment, T), ApiError>
+where
+    F: FnMut(&mut Comment) -> Result<T, ApiError>,
+{
+    for attempt in 1..=MAX_WRITE_ATTEMPTS {
+        let (mut comment, version) = state
+            .repositories
+            .comments
+            .get_versioned(id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Comment not found".to_string()))?;
+        let output: T = mutate(&mut comment)?;
+
+        match state
+            .repositories
+            .comments
+            .put_if_version(id, &comment, &version)
+            .await
+        {
+            Ok(()) => return Ok((comment, output)),
+            Err(RepositoryError::Conflict) => {
+                debug!("Comment {} modified concurrently (attempt {})", id, attempt);
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    warn!(
+        "Comment {} still contended after {} attempts",
+        id, MAX_WRITE_ATTEMPTS
+    );
+    Err(ApiError::Conflict(
+        "Comment is being modified concurrently, try again".to_string(),
+    ))
+}
+
+#[cfg(test)]
+#[path = "../test/services/comments.rs"]
+mod extended_tests;
@@ -0,0 +1,304 @@
+use {
+    crate::{
+        models::{
+            cal::CalBookingPayload,
+            comments::Comment,
+            firebase::{FirebaseUserInfo, UserAuthentication},
+            mailchimp::{MembershipStatus, SearchMembersResponse},
+            sourvey::Survey,
+            state::AppState,
+            stripe::{PAYMENT_USER_METADATA_KEY, PaymentIntentSimplified},
+            user::{ExportedReply, UserDataExport},
+            webhook::CalBookingsResponse,
+        },
+        services::{firebase::lookup_account, mailchimp::handle_mailchimp_response},
+    },
+    chrono::Utc,
+    serde::Serialize,
+    serde_json::Value,
+    std::{
+        collections::{HashMap, HashSet},
+        io::{Cursor, Write},
+    },
+    stripe::{PaymentIntent, PaymentIntentId, SearchList},
+    tracing::warn,
+    zip::{ZipWriter, result::ZipError, write::SimpleFileOptions},
+};
+
+/// Páginas de la búsqueda de pagos que se recorren como máximo en un export
+const MAX_PAYMENT_PAGES: usize = 10;
+
+/// Parámetros de `/payment_intents/search`. `PaymentIntentSearchParams` tipa `page` como
+/// número, pero Stripe pagina con el token opaco que devuelve en `next_page`.
+#[derive(Serialize)]
+struct PaymentSearchPage<'a> {
+    query: &'a str,
+    limit: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<&'a str>,
+}
+
+/// Reúne todo lo que guardamos del usuario. Si una fuente externa falla se registra en
+/// `unavailable_sources` y el resto del export se entrega igualmente.
+pub async fn collect_user_data(
+    state: &AppState,
+    claims: &UserAuthentication,
+    id_token: &str,
+) -> UserDataExport {
+    let uid: &str = &claims.sub;
+    // Reservas, encuestas y newsletter se buscan por email: solo con el email verificado
+    let email: Option<&str> = claims.verified_email();
+    let mut unavailable: Vec<String> = Vec::new();
+
+    let (profile, auth, comments, surveys, bookings, payments, newsletter) = tokio::join!(
+        state.repositories.users.get(uid),
+        lookup_account(&state.firebase_options, id_token),
+        state.repositories.comments.get_all(),
+        state.repositories.surveys.get_all(),
+        fetch_bookings(state, email),
+        search_payments(state, uid),
+        fetch_newsletter(state, email),
+    );
+
+    let profile = unwrap_source("profile", profile, &mut unavailable).flatten();
+    let auth: Option<FirebaseUserInfo> = unwrap_source("auth", auth, &mut unavailable)
+        .and_then(|lookup| lookup.users.into_iter().next())
+        .map(|mut user| {
+            // El hash no aporta nada al usuario y no debe salir del sistema
+            user.password_hash = None;
+            user
+        });
+
+    let all_comments: HashMap<String, Comment> =
+        unwrap_source("comments", comments, &mut unavailable).unwrap_or_default();
+    let replies: Vec<ExportedReply> = all_comments
+        .iter()
+        .flat_map(|(comment_id, comment)| {
+            comment
+                .reply
+                .iter()
+                .filter(|reply| reply.author_uid == uid)
+                .map(|reply| ExportedReply {
+                    comment_id: comment_id.clone(),
+                    reply: reply.clone(),
+                })
+        })
+        .collect();
+    let comments: HashMap<String, Comment> = all_comments
+        .into_iter()
+        .filter(|(_, comment)| comment.author_uid.as_deref() == Some(uid))
+        .collect();
+
+    let surveys: Vec<Survey> = unwrap_source("surveys", surveys, &mut unavailable)
+        .unwrap_or_default()
+        .into_values()
+        .filter(|survey| email.is_some_and(|e| survey.user_email.eq_ignore_ascii_case(e)))
+        .collect();
+
+    let bookings: Vec<CalBookingPayload> =
+        unwrap_source("bookings", bookings, &mut unavailable).unwrap_or_default();
+    let mut payments: Vec<PaymentIntentSimplified> =
+        match unwrap_source("payments", payments, &mut unavailable) {
+            Some((payments, truncated)) => {
+                if truncated {
+                    unavailable.push("payments (truncated)".to_string());
+                }
+                payments
+            }
+            None => Vec::new(),
+        };
+    // Los pagos anteriores a la metadata con el uid solo se encuentran por su reserva
+    let booking_payments = fetch_booking_payments(state, &bookings, &payments).await;
+    payments.extend(
+        unwrap_source("booking_payments", booking_payments, &mut unavailable).unwrap_or_default(),
+    );
+
+    UserDataExport {
+        uid: uid.to_string(),
+        generated_at: Utc::now().to_rfc3339(),
+        profile,
+        auth,
+        comments,
+        replies,
+        surveys,
+        bookings,
+        payments,
+        newsletter: unwrap_source("newsletter", newsletter, &mut unavailable).flatten(),
+        unavailable_sources: unavailable,
+    }
+}
+
+/// Empaqueta el export en un ZIP con un fichero `<sección>.json` por cada campo
+pub fn export_to_zip(export: &UserDataExport) -> Result<Vec<u8>, ZipError> {
+    let sections: Value = serde_json::to_value(export).map_err(std::io::Error::from)?;
+    let mut zip: ZipWriter<Cursor<Vec<u8>>> = ZipWriter::new(Cursor::new(Vec::new()));
+    let options: SimpleFileOptions = SimpleFileOptions::default();
+
+    if let Value::Object(sections) = sections {
+        for (name, value) in sections {
+            zip.start_file(format!("{}.json", name), options)?;
+            let json: Vec<u8> = serde_json::to_vec_pretty(&value).map_err(std::io::Error::from)?;
+            zip.write_all(&json)?;
+        }
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Devuelve el valor de una fuente o la anota como no disponible
+fn unwrap_source<T, E: std::fmt::Debug>(
+    source: &str,
+    result: Result<T, E>,
+    unavailable: &mut Vec<String>,
+) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Data export: source '{}' unavailable: {:?}", source, err);
+            unavailable.push(source.to_string());
+            None
+        }
+    }
+}
+
+/// Reservas de Cal.com en las que el email figura como asistente
+async fn fetch_bookings(
+    state: &AppState,
+    email: Option<&str>,
+) -> Result<Vec<CalBookingPayload>, String> {
+    let Some(email) = email else {
+        return Ok(Vec::new());
+    };
+
+    let response: reqwest::Response = state
+        .cal_options
+        .client
+        .get(format!("{}/bookings", state.cal_options.base_url))
+        .header("cal-api-version", "2024-06-11")
+        .header("Authorization", &state.cal_options.api_key)
+        .query(&[("attendeeEmail", email)])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err(format!("Cal.com responded {}", response.status()));
+    }
+
+    response
+        .json::<CalBookingsResponse>()
+        .await
+        .map(|bookings| bookings.data.bookings)
+        .map_err(|e| e.to_string())
+}
+
+/// Pagos de Stripe etiquetados con el uid en la metadata del PaymentIntent. Indica también
+/// si se alcanzó `MAX_PAYMENT_PAGES` con resultados pendientes.
+async fn search_payments(
+    state: &AppState,
+    uid: &str,
+) -> Result<(Vec<PaymentIntentSimplified>, bool), String> {
+    let query: String = format!(
+        "metadata['{}']:'{}'",
+        PAYMENT_USER_METADATA_KEY,
+        uid.replace('\'', "\\'")
+    );
+    let mut payments: Vec<PaymentIntentSimplified> = Vec::new();
+    let mut next_page: Option<String> = None;
+
+    for _ in 0..MAX_PAYMENT_PAGES {
+        let params: PaymentSearchPage = PaymentSearchPage {
+            query: &query,
+            limit: 100,
+            page: next_page.as_deref(),
+        };
+        let page: SearchList<PaymentIntent> = state
+            .stripe_client
+            .get_query("/payment_intents/search", params)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        payments.extend(page.data.into_iter().map(PaymentIntentSimplified::from));
+        next_page = page.next_page.filter(|_| page.has_more);
+        if next_page.is_none() {
+            return Ok((payments, false));
+        }
+    }
+
+    warn!(
+        "Data export: payments of {} truncated at {} pages",
+        uid, MAX_PAYMENT_PAGES
+    );
+    Ok((payments, true))
+}
+
+/// Pagos enlazados a las reservas del usuario en `relation_cal_stripe` que la búsqueda por
+/// metadata no ha devuelto
+async fn fetch_booking_payments(
+    state: &AppState,
+    bookings: &[CalBookingPayload],
+    known: &[PaymentIntentSimplified],
+) -> Result<Vec<PaymentIntentSimplified>, String> {
+    let mut seen: HashSet<String> = known.iter().map(|payment| payment.id.clone()).collect();
+    let mut payments: Vec<PaymentIntentSimplified> = Vec::new();
+
+    for uid in bookings.iter().filter_map(|booking| booking.uid.as_deref()) {
+        let Some(relation) = state
+            .repositories
+            .cal_stripe
+            .get(uid)
+            .await
+            .map_err(|e| e.to_string())?
+        else {
+            continue;
+        };
+        if !seen.insert(relation.stripe_id.clone()) {
+            continue;
+        }
+
+        let id: PaymentIntentId = relation
+            .stripe_id
+            .parse()
+            .map_err(|e| format!("Invalid PaymentIntent id '{}': {:?}", relation.stripe_id, e))?;
+        let payment: PaymentIntent = PaymentIntent::retrieve(&state.stripe_client, &id, &[])
+            .await
+            .map_err(|e| e.to_string())?;
+        payments.push(PaymentIntentSimplified::from(payment));
+    }
+
+    Ok(payments)
+}
+
+/// Estado del email en la audiencia de Mailchimp
+pub(crate) async fn fetch_newsletter(
+    state: &AppState,
+    email: Option<&str>,
+) -> Result<Option<MembershipStatus>, String> {
+    let Some(email) = email else {
+        return Ok(None);
+    };
+    let mailchimp = &state.mailchimp_client;
+
+    let response: reqwest::Response = mailchimp
+        .client
+        .get(format!("{}/search-members", mailchimp.get_base_url()))
+        .basic_auth("", Some(&mailchimp.api_key))
+        .query(&[("query", email), ("list_id", mailchimp.list_id.as_str())])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let search: SearchMembersResponse = handle_mailchimp_response(response)
+        .await
+        .map_err(|(status, message)| format!("{}: {}", status, message))?;
+
+    Ok(search
+        .exact_matches
+        .members
+        .into_iter()
+        .find(|member| member.list_id == mailchimp.list_id))
+}
+
+#[cfg(test)]
+#[path = "../test/services/data_export.rs"]
+mod extended_tests;
@@ -0,0 +1,131 @@
+use {
+    crate::models::error::ApiError,
+    resend_rs::{Resend, types::CreateEmailBaseOptions},
+    tracing::error,
+};
+
+/// Remitente de los emails transaccionales
+pub const SENDER: &str = "contact@amanahacademia.com";
+
+/// Email con una única llamada a la acción (verificar email, restablecer contraseña...)
+pub struct ActionEmail<'a> {
+    pub subject: &'a str,
+    pub title: &'a str,
+    pub intro: &'a str,
+    pub action_label: &'a str,
+    pub action_url: &'a str,
+    /// Texto final, p. ej. qué hacer si no se pidió la acción
+    pub outro: &'a str,
+}
+
+impl ActionEmail<'_> {
+    /// Renderiza el email con la misma cabecera y pie que el de contacto
+    pub fn html(&self) -> String {
+        format!(
+            r#"
+        <!DOCTYPE html>
+        <html lang="es">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>{title}</title>
+        <style>
+        body {{
+            font-family: "Segoe UI", Tahoma, Geneva, Verdana, sans-serif;
+            line-height: 1.6;
+            color: #333;
+            margin: 0;
+            padding: 0;
+            background-color: #f5f7fa;
+        }}
+        .email-container {{
+            max-width: 600px;
+            margin: 0 auto;
+            background-color: #ffffff;
+            border-radius: 8px;
+            overflow: hidden;
+            box-shadow: 0 4px 6px rgba(0, 0, 0, 0.05);
+        }}
+        .email-header {{
+            background: linear-gradient(to right, #f88a7e, #a32e19);
+            color: white;
+            padding: 30px;
+            text-align: center;
+        }}
+        .email-header h1 {{
+            margin: 0;
+            font-size: 24px;
+        }}
+        .email-body {{
+            padding: 30px;
+        }}
+        .action {{
+            text-align: center;
+            margin: 30px 0;
+        }}
+        .action a {{
+            background-color: #a32e19;
+            color: #ffffff;
+            padding: 12px 28px;
+            border-radius: 6px;
+            text-decoration: none;
+            font-weight: 600;
+        }}
+        .fallback-link {{
+            font-size: 13px;
+            color: #6c757d;
+            word-break: break-all;
+        }}
+        .email-footer {{
+            background-color: #f1f3f4;
+            padding: 20px;
+            text-align: center;
+            font-size: 14px;
+            color: #6c757d;
+        }}
+        </style>
+        </head>
+        <body>
+            <div class="email-container">
+                <div class="email-header">
+                    <h1>Amanah Academia</h1>
+                </div>
+
+                <div class="email-body">
+                    <h2>{title}</h2>
+                    <p>{intro}</p>
+                    <div class="action">
+                        <a href="{url}">{label}</a>
+                    </div>
+                    <p class="fallback-link">Si el botón no funciona, copia este enlace en tu navegador:<br>{url}</p>
+                    <p>{outro}</p>
+                </div>
+
+                <div class="email-footer">
+                    <p>© 2023 Amanah Academia. Todos los derechos reservados.</p>
+                    <p><strong>Contacto:</strong> {sender}</p>
+                </div>
+            </div>
+        </body>
+        </html>
+        "#,
+            title = self.title,
+            intro = self.intro,
+            url = self.action_url,
+            label = self.action_label,
+            outro = self.outro,
+            sender = SENDER,
+        )
+    }
+
+    /// Envía el email a un único destinatario a través de Resend
+    pub async fn send(&self, resend: &Resend, to: &str) -> Result<(), ApiError> {
+        let email: CreateEmailBaseOptions =
+            CreateEmailBaseOptions::new(SENDER, [to], self.subject).with_html(&self.html());
+
+        resend.emails.send(email).await.map(|_| ()).map_err(|e| {
+            error!("Failed to send '{}' email: {:?}", self.subject, e);
+            ApiError::Unavailable("Failed to send email".to_string())
+        })
+    }
+}
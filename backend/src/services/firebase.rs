@@ -1,4 +1,25 @@
-use {axum::http::StatusCode, serde::de::DeserializeOwned, serde_json::Value};
+use {
+    crate::{
+        models::{
+            error::ApiError,
+            firebase::{
+                AccountUpdateResponse, FirebaseAccountsPage, FirebaseAdminLookupResponse,
+                FirebaseUserInfo, GoogleTokenInfo, OobCodeResult, SendOobCodeRequest,
+                SendOobCodeResponse,
+            },
+            metrics::{CachedAccessToken, TokenResponse},
+            state::CustomFirebase,
+        },
+        services::google_oauth::request_service_account_token,
+    },
+    axum::http::StatusCode,
+    serde::{Serialize, de::DeserializeOwned},
+    serde_json::{Value, json},
+    tracing::{error, info},
+};
+
+/// Scopes necesarios para las operaciones de administración de Identity Toolkit
+const ADMIN_SCOPES: &str = "https://www.googleapis.com/auth/cloud-platform https://www.googleapis.com/auth/identitytoolkit";
 
 /// Maneja la respuesta de Firebase, serializandolo
 pub async fn handle_firebase_response<T>(
@@ -47,6 +68,349 @@ where
     }
 }
 
+/// Convierte el error de `handle_firebase_response` en un `ApiError`.
+/// Identity Toolkit responde 400 con mensajes como `INVALID_OOB_CODE` o `WEAK_PASSWORD : ...`.
+pub fn firebase_api_error((status, message): (StatusCode, String)) -> ApiError {
+    match status {
+        StatusCode::BAD_REQUEST => ApiError::BadRequest(message),
+        StatusCode::UNAUTHORIZED => ApiError::Unauthorized(message),
+        StatusCode::FORBIDDEN => ApiError::Forbidden(message),
+        StatusCode::NOT_FOUND => ApiError::NotFound(message),
+        StatusCode::SERVICE_UNAVAILABLE => ApiError::Unavailable(message),
+        _ => ApiError::Internal(format!("Firebase error ({}): {}", status, message)),
+    }
+}
+
+/// POST a un endpoint `accounts:*` de Identity Toolkit con la API key del proyecto
+async fn identity_toolkit_post<B, T>(
+    firebase: &CustomFirebase,
+    endpoint: &str,
+    body: &B,
+) -> Result<T, (StatusCode, String)>
+where
+    B: Serialize + ?Sized,
+    T: DeserializeOwned,
+{
+    let url: String = format!(
+        "{}/accounts:{}?key={}",
+        firebase.identity_toolkit_url, endpoint, firebase.firebase_api_key
+    );
+
+    match firebase.firebase_client.post(&url).json(body).send().await {
+        Ok(response) => handle_firebase_response::<T>(response).await,
+        Err(_) => Err((
+            StatusCode::BAD_GATEWAY,
+            "Error connecting to Firebase".to_string(),
+        )),
+    }
+}
+
+/// Datos de Firebase Auth de la cuenta dueña del idToken
+pub async fn lookup_account(
+    firebase: &CustomFirebase,
+    id_token: &str,
+) -> Result<FirebaseAdminLookupResponse, (StatusCode, String)> {
+    identity_toolkit_post(firebase, "lookup", &json!({ "idToken": id_token })).await
+}
+
+/// Borra la cuenta de Firebase Auth por su uid. Va por la API de administración: con el
+/// idToken Google exige un login reciente y fallaría a mitad del borrado en cascada.
+pub async fn delete_account(
+    firebase: &CustomFirebase,
+    uid: &str,
+) -> Result<Value, (StatusCode, String)> {
+    identity_toolkit_admin_post(firebase, "delete", &json!({ "localId": uid })).await
+}
+
+/// Genera un código de verificación de email o de restablecimiento de contraseña. Va por la API
+/// de administración: con la API key Google ignora `returnOobLink` y manda su propio email.
+pub async fn send_oob_code(
+    firebase: &CustomFirebase,
+    request: &SendOobCodeRequest,
+) -> Result<SendOobCodeResponse, (StatusCode, String)> {
+    identity_toolkit_admin_post(firebase, "sendOobCode", request).await
+}
+
+/// Cambia la contraseña usando el oobCode de un email de restablecimiento
+pub async fn reset_password(
+    firebase: &CustomFirebase,
+    oob_code: &str,
+    new_password: &str,
+) -> Result<OobCodeResult, (StatusCode, String)> {
+    identity_toolkit_post(
+        firebase,
+        "resetPassword",
+        &json!({ "oobCode": oob_code, "newPassword": new_password }),
+    )
+    .await
+}
+
+/// Marca el email como verificado usando el oobCode del email de verificación
+pub async fn confirm_email_verification(
+    firebase: &CustomFirebase,
+    oob_code: &str,
+) -> Result<OobCodeResult, (StatusCode, String)> {
+    identity_toolkit_post(firebase, "update", &json!({ "oobCode": oob_code })).await
+}
+
+/// Añade email/contraseña como método de acceso a la cuenta dueña del idToken
+pub async fn link_password_provider(
+    firebase: &CustomFirebase,
+    id_token: &str,
+    email: &str,
+    password: &str,
+) -> Result<AccountUpdateResponse, (StatusCode, String)> {
+    identity_toolkit_post(
+        firebase,
+        "update",
+        &json!({
+            "idToken": id_token,
+            "email": email,
+            "password": password,
+            "returnSecureToken": true
+        }),
+    )
+    .await
+}
+
+/// Desvincula un proveedor (`password`, `google.com`...) de la cuenta dueña del idToken
+pub async fn unlink_provider(
+    firebase: &CustomFirebase,
+    id_token: &str,
+    provider_id: &str,
+) -> Result<AccountUpdateResponse, (StatusCode, String)> {
+    identity_toolkit_post(
+        firebase,
+        "update",
+        &json!({ "idToken": id_token, "deleteProvider": [provider_id] }),
+    )
+    .await
+}
+
+/// Valida un id_token de Google Sign-In y comprueba que se emitió para nuestro client ID
+pub async fn verify_google_id_token(
+    firebase: &CustomFirebase,
+    google_id_token: &str,
+) -> Result<GoogleTokenInfo, (StatusCode, String)> {
+    let Some(client_id) = &firebase.google_client_id else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Google account linking is not configured".to_string(),
+        ));
+    };
+
+    let response: reqwest::Response = firebase
+        .firebase_client
+        .get(&firebase.google_tokeninfo_url)
+        .query(&[("id_token", google_id_token)])
+        .send()
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::BAD_GATEWAY,
+                "Error connecting to Google".to_string(),
+            )
+        })?;
+
+    // Google responde 400 a los tokens caducados, mal firmados o revocados
+    if !response.status().is_success() {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Invalid Google id_token".to_string(),
+        ));
+    }
+    let info: GoogleTokenInfo = response.json().await.map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error parsing Google tokeninfo response".to_string(),
+        )
+    })?;
+
+    if info.aud != *client_id {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Google id_token was issued for another application".to_string(),
+        ));
+    }
+    Ok(info)
+}
+
+/// Access token OAuth para la API de administración de Identity Toolkit.
+/// El emulador acepta `owner`; en producción se firma con la cuenta de servicio y se cachea
+/// igual que el token de Google Analytics.
+async fn admin_access_token(firebase: &CustomFirebase) -> Result<String, (StatusCode, String)> {
+    if firebase.auth_emulator_host.is_some() {
+        return Ok("owner".to_string());
+    }
+
+    let Some(service_account) = &firebase.service_account else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Firebase admin credentials are not configured".to_string(),
+        ));
+    };
+
+    let mut cache = firebase.admin_token_cache.lock().await;
+    if let Some(cached) = cache.as_ref()
+        && cached.is_usable()
+    {
+        return Ok(cached.access_token.clone());
+    }
+
+    let token: TokenResponse = request_service_account_token(
+        &firebase.firebase_client,
+        service_account,
+        ADMIN_SCOPES,
+        &firebase.token_url,
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to get Firebase admin access token: {}", e);
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Firebase admin API is unavailable".to_string(),
+        )
+    })?;
+    info!(
+        "Firebase admin access token refreshed (expires in {}s)",
+        token.expires_in
+    );
+
+    let access_token: String = token.access_token.clone();
+    *cache = Some(CachedAccessToken::new(token.access_token, token.expires_in));
+    Ok(access_token)
+}
+
+/// POST a un endpoint `projects/{id}/accounts:*` de Identity Toolkit como administrador
+async fn identity_toolkit_admin_post<B, T>(
+    firebase: &CustomFirebase,
+    endpoint: &str,
+    body: &B,
+) -> Result<T, (StatusCode, String)>
+where
+    B: Serialize + ?Sized,
+    T: DeserializeOwned,
+{
+    let access_token: String = admin_access_token(firebase).await?;
+    let url: String = format!(
+        "{}/projects/{}/accounts:{}",
+        firebase.identity_toolkit_url, firebase.firebase_project_id, endpoint
+    );
+
+    match firebase
+        .firebase_client
+        .post(&url)
+        .bearer_auth(access_token)
+        .json(body)
+        .send()
+        .await
+    {
+        Ok(response) => handle_firebase_response::<T>(response).await,
+        Err(_) => Err((
+            StatusCode::BAD_GATEWAY,
+            "Error connecting to Firebase".to_string(),
+        )),
+    }
+}
+
+/// Cuentas por página al listar con `accounts:batchGet` (máximo de la API)
+const ACCOUNTS_PAGE_SIZE: &str = "1000";
+
+/// Lista todas las cuentas de Firebase Auth del proyecto con credenciales admin,
+/// recorriendo las páginas de `accounts:batchGet`
+pub async fn list_accounts(
+    firebase: &CustomFirebase,
+) -> Result<FirebaseAdminLookupResponse, (StatusCode, String)> {
+    let access_token: String = admin_access_token(firebase).await?;
+    let url: String = format!(
+        "{}/projects/{}/accounts:batchGet",
+        firebase.identity_toolkit_url, firebase.firebase_project_id
+    );
+
+    let mut users: Vec<FirebaseUserInfo> = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut request = firebase
+            .firebase_client
+            .get(&url)
+            .bearer_auth(&access_token)
+            .query(&[("maxResults", ACCOUNTS_PAGE_SIZE)]);
+        if let Some(token) = &page_token {
+            request = request.query(&[("nextPageToken", token)]);
+        }
+
+        let page: FirebaseAccountsPage = match request.send().await {
+            Ok(response) => handle_firebase_response::<FirebaseAccountsPage>(response).await?,
+            Err(_) => {
+                return Err((
+                    StatusCode::BAD_GATEWAY,
+                    "Error connecting to Firebase".to_string(),
+                ));
+            }
+        };
+        users.extend(page.users);
+
+        match page.next_page_token.filter(|token| !token.is_empty()) {
+            Some(token) => page_token = Some(token),
+            None => break,
+        }
+    }
+
+    Ok(FirebaseAdminLookupResponse { users })
+}
+
+/// Deshabilita o vuelve a habilitar la cuenta de Firebase Auth de cualquier usuario
+pub async fn set_account_disabled(
+    firebase: &CustomFirebase,
+    uid: &str,
+    disabled: bool,
+) -> Result<Value, (StatusCode, String)> {
+    identity_toolkit_admin_post(
+        firebase,
+        "update",
+        &json!({ "localId": uid, "disableUser": disabled }),
+    )
+    .await
+}
+
+/// Vincula una cuenta de Google ya verificada a cualquier usuario (requiere credenciales admin)
+pub async fn link_google_provider(
+    firebase: &CustomFirebase,
+    uid: &str,
+    google: &GoogleTokenInfo,
+) -> Result<Value, (StatusCode, String)> {
+    identity_toolkit_admin_post(
+        firebase,
+        "update",
+        &json!({
+            "localId": uid,
+            "linkProviderUserInfo": {
+                "providerId": "google.com",
+                "rawId": google.sub,
+                "email": google.email,
+                "displayName": google.name,
+                "photoUrl": google.picture
+            }
+        }),
+    )
+    .await
+}
+
+/// Invalida los tokens emitidos antes de `valid_since` (segundos Unix), incluidos los
+/// refresh tokens: cierra todas las sesiones del usuario
+pub async fn set_valid_since(
+    firebase: &CustomFirebase,
+    uid: &str,
+    valid_since: i64,
+) -> Result<Value, (StatusCode, String)> {
+    identity_toolkit_admin_post(
+        firebase,
+        "update",
+        &json!({ "localId": uid, "validSince": valid_since.to_string() }),
+    )
+    .await
+}
+
 #[cfg(test)]
 #[path = "../test/services/firebase.rs"]
-mod tests;
+mod extended_tests;
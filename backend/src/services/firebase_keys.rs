@@ -0,0 +1,184 @@
+use {
+    crate::{
+        models::{
+            error::FirebaseKeysError,
+            state::{AppState, CustomFirebase, DEFAULT_KEYS_MAX_AGE, KeyCache},
+        },
+        services::supervisor::ShutdownSignal,
+    },
+    axum::http::StatusCode,
+    reqwest::{Client as HttpClient, header::CACHE_CONTROL},
+    serde_json::Value,
+    std::{
+        sync::Arc,
+        time::{Duration, SystemTime},
+    },
+    tracing::{error, info, instrument, warn},
+};
+
+/// Antelación con la que se refrescan las claves antes de que venzan
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+/// Intervalo mínimo entre refrescos programados, por si Google devuelve un `max-age` muy bajo
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// Reintentos tras un fallo: 2s, 4s, 8s... hasta 5 minutos
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// Como mucho un refresco forzado por `kid` desconocido cada minuto
+pub const UNKNOWN_KID_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Claves descargadas junto con su vigencia
+#[derive(Debug)]
+pub struct FetchedKeys {
+    pub keys: Value,
+    pub max_age: Duration,
+}
+
+/// Descarga las claves públicas de Firebase y lee su vigencia de `Cache-Control: max-age`
+#[instrument(skip(client))]
+pub async fn fetch_firebase_keys(
+    client: &HttpClient,
+    public_keys_url: &str,
+) -> Result<FetchedKeys, FirebaseKeysError> {
+    let response = client
+        .get(public_keys_url)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(FirebaseKeysError::Status(
+            StatusCode::from_u16(response.status().as_u16())
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        ));
+    }
+
+    let max_age: Duration = response
+        .headers()
+        .get(CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_max_age)
+        .unwrap_or(DEFAULT_KEYS_MAX_AGE);
+
+    let keys: Value = response.json().await?;
+
+    if keys.as_object().is_none_or(|keys| keys.is_empty()) {
+        return Err(FirebaseKeysError::Empty);
+    }
+
+    Ok(FetchedKeys { keys, max_age })
+}
+
+/// Extrae `max-age` de una cabecera `Cache-Control` (p. ej. `public, max-age=19770, must-revalidate`)
+pub fn parse_max_age(cache_control: &str) -> Option<Duration> {
+    cache_control
+        .split(',')
+        .filter_map(|directive| directive.trim().strip_prefix("max-age="))
+        .find_map(|seconds| seconds.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Descarga las claves y las guarda en la caché
+pub async fn refresh_firebase_keys(firebase: &CustomFirebase) -> Result<Value, FirebaseKeysError> {
+    let fetched: FetchedKeys =
+        fetch_firebase_keys(&firebase.firebase_client, &firebase.public_keys_url).await?;
+
+    let mut cache = firebase.firebase_keys.write().await;
+    let last_forced_refresh: Option<SystemTime> = cache.last_forced_refresh;
+    *cache = KeyCache::new(fetched.keys.clone(), fetched.max_age);
+    cache.last_forced_refresh = last_forced_refresh;
+
+    Ok(fetched.keys)
+}
+
+/// Refresco fuera de programa cuando llega un token firmado con un `kid` que no conocemos
+/// (Google acaba de rotar las claves) o cuando aún no hay claves. Limitado a uno por
+/// `UNKNOWN_KID_REFRESH_INTERVAL` para que tokens inventados no disparen peticiones a Google.
+/// Devuelve `None` si no se ha refrescado por el límite.
+pub async fn force_refresh_firebase_keys(
+    firebase: &CustomFirebase,
+) -> Result<Option<Value>, FirebaseKeysError> {
+    {
+        let mut cache = firebase.firebase_keys.write().await;
+        let now: SystemTime = SystemTime::now();
+        let recently_refreshed: bool = cache.last_forced_refresh.is_some_and(|last| {
+            now.duration_since(last)
+                .is_ok_and(|elapsed| elapsed < UNKNOWN_KID_REFRESH_INTERVAL)
+        });
+        if recently_refreshed {
+            return Ok(None);
+        }
+        cache.last_forced_refresh = Some(now);
+    }
+
+    warn!("Unknown Firebase key id, refreshing keys out of schedule");
+    refresh_firebase_keys(firebase).await.map(Some)
+}
+
+/// Tarea en segundo plano que mantiene las claves al día. Refresca un poco antes de que venzan,
+/// y si falla sigue sirviendo las anteriores y reintenta con backoff exponencial.
+pub async fn key_refresh_task(state: Arc<AppState>, mut shutdown: ShutdownSignal) {
+    let firebase: &CustomFirebase = &state.firebase_options;
+    let mut failures: u32 = 0;
+
+    loop {
+        // Tras un fallo se reintenta en cuanto pasa el backoff, sin esperar al vencimiento
+        if failures == 0 {
+            let wait: Duration = {
+                let cache = firebase.firebase_keys.read().await;
+                if cache.is_empty() {
+                    Duration::ZERO
+                } else {
+                    cache
+                        .expires_in()
+                        .saturating_sub(REFRESH_MARGIN)
+                        .max(MIN_REFRESH_INTERVAL)
+                }
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = shutdown.wait() => return,
+            }
+        }
+
+        match refresh_firebase_keys(firebase).await {
+            Ok(_) => {
+                failures = 0;
+                info!("Firebase public keys refreshed");
+            }
+            Err(err) => {
+                let backoff: Duration = refresh_backoff(failures);
+                failures = failures.saturating_add(1);
+
+                let cache = firebase.firebase_keys.read().await;
+                if cache.is_empty() {
+                    error!(
+                        "Firebase keys unavailable, authentication degraded ({}). Retrying in {:?}",
+                        err, backoff
+                    );
+                } else {
+                    warn!(
+                        "Failed to refresh Firebase keys ({}), serving cached keys. Retrying in {:?}",
+                        err, backoff
+                    );
+                }
+                drop(cache);
+
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    _ = shutdown.wait() => return,
+                }
+            }
+        }
+    }
+}
+
+/// Espera antes del siguiente reintento tras `failures` fallos consecutivos
+pub fn refresh_backoff(failures: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(failures.min(16)))
+        .min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+#[path = "../test/services/firebase_keys.rs"]
+mod extended_tests;
@@ -0,0 +1,58 @@
+use {
+    crate::models::{
+        error::ServiceAccountError,
+        metrics::{ClaimsGA, ServiceAccount, TokenResponse},
+    },
+    axum::http::StatusCode,
+    jsonwebtoken::{Algorithm, EncodingKey, Header, encode},
+    reqwest::Client as HttpClient,
+    std::time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Firma el JWT de una cuenta de servicio y lo intercambia por un access token de Google
+/// (flujo `jwt-bearer`). Lo usan Google Analytics y la API de administración de Firebase Auth.
+pub async fn request_service_account_token(
+    client: &HttpClient,
+    service_account: &ServiceAccount,
+    scope: &str,
+    token_url: &str,
+) -> Result<TokenResponse, ServiceAccountError> {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let claims: ClaimsGA = ClaimsGA {
+        iss: service_account.client_email.clone(),
+        scope: scope.to_string(),
+        aud: token_url.to_string(),
+        exp: now + 3600,
+        iat: now,
+    };
+
+    let encoding_key: EncodingKey =
+        EncodingKey::from_rsa_pem(service_account.private_key.as_bytes())
+            .map_err(|e| ServiceAccountError::Credentials(e.to_string()))?;
+    let jwt: String = encode(&Header::new(Algorithm::RS256), &claims, &encoding_key)
+        .map_err(|e| ServiceAccountError::Credentials(e.to_string()))?;
+
+    let response: reqwest::Response = client
+        .post(token_url)
+        .form(&[
+            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+            ("assertion", &jwt),
+        ])
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status: StatusCode = StatusCode::from_u16(response.status().as_u16())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        // El cuerpo puede incluir detalles de la cuenta de servicio: solo va al log
+        let body: String = response.text().await.unwrap_or_default();
+        tracing::error!(status = %status, body = %body, "Token endpoint rejected the request");
+        return Err(ServiceAccountError::Token { status });
+    }
+
+    Ok(response.json().await?)
+}
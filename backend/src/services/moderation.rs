@@ -0,0 +1,128 @@
+use {
+    crate::{
+        models::{
+            comments::{
+                Comment, ModerationDecision, ModerationQueue, ModerationStatus, QueuedReply,
+            },
+            error::{ApiError, RepositoryError},
+            state::AppState,
+        },
+        services::comments::timestamp_time,
+    },
+    chrono::NaiveDateTime,
+    std::collections::HashMap,
+};
+
+/// Si el usuario ya tiene algún comentario o respuesta aprobados, sin contar `editing` (el id
+/// del comentario o respuesta que se está editando: su aprobación era del texto anterior)
+pub fn is_trusted_author(
+    comments: &HashMap<String, Comment>,
+    uid: &str,
+    editing: Option<&str>,
+) -> bool {
+    comments.iter().any(|(id, comment)| {
+        (comment.author_uid.as_deref() == Some(uid)
+            && comment.moderation_status == ModerationStatus::Approved
+            && editing != Some(id.as_str()))
+            || comment.reply.iter().any(|reply| {
+                reply.author_uid == uid
+                    && reply.moderation_status == ModerationStatus::Approved
+                    && editing != Some(reply.id.as_str())
+            })
+    })
+}
+
+/// Estado con el que entra un comentario o respuesta nuevos (o `editing`, si se edita): los
+/// autores con otro contenido ya aprobado se publican directamente, el resto pasa por la cola.
+pub async fn initial_status(
+    state: &AppState,
+    uid: &str,
+    editing: Option<&str>,
+) -> Result<ModerationStatus, RepositoryError> {
+    let comments: HashMap<String, Comment> = state.repositories.comments.get_all().await?;
+    Ok(if is_trusted_author(&comments, uid, editing) {
+        ModerationStatus::Approved
+    } else {
+        ModerationStatus::Pending
+    })
+}
+
+/// Estado tras editar: lo rechazado u oculto vuelve a la cola, lo demás queda como si fuera
+/// nuevo (`initial`, el resultado de `initial_status` para el autor)
+pub fn edited_status(current: ModerationStatus, initial: ModerationStatus) -> ModerationStatus {
+    match current {
+        ModerationStatus::Rejected | ModerationStatus::Hidden => ModerationStatus::Pending,
+        ModerationStatus::Pending | ModerationStatus::Approved => initial,
+    }
+}
+
+/// Comentarios aprobados, solo con sus respuestas aprobadas y sin el uid del autor ni la
+/// reserva que respalda la reseña
+pub fn public_comments(comments: HashMap<String, Comment>) -> HashMap<String, Comment> {
+    comments
+        .into_iter()
+        .filter(|(_, comment)| comment.moderation_status == ModerationStatus::Approved)
+        .map(|(id, mut comment)| {
+            comment.author_uid = None; // Ocultamos el uid
+            comment.booking_uid = None;
+            comment
+                .reply
+                .retain(|reply| reply.moderation_status == ModerationStatus::Approved);
+            (id, comment)
+        })
+        .collect()
+}
+
+/// Comentarios y respuestas con el estado indicado; las respuestas de la más antigua a la
+/// más reciente
+pub fn moderation_queue(
+    comments: HashMap<String, Comment>,
+    status: ModerationStatus,
+) -> ModerationQueue {
+    let mut queue: ModerationQueue = ModerationQueue::default();
+    for (id, comment) in comments {
+        queue.replies.extend(
+            comment
+                .reply
+                .iter()
+                .filter(|reply| reply.moderation_status == status)
+                .map(|reply| QueuedReply {
+                    comment_id: id.clone(),
+                    reply: reply.clone(),
+                }),
+        );
+        if comment.moderation_status == status {
+            queue.comments.insert(id, comment);
+        }
+    }
+    queue.replies.sort_by_key(|queued| {
+        let time: Option<NaiveDateTime> = timestamp_time(&queued.reply.timestamp);
+        (time.is_none(), time)
+    });
+    queue
+}
+
+/// Valida la decisión y devuelve el estado y motivo a guardar
+pub fn validate_decision(
+    decision: ModerationDecision,
+) -> Result<(ModerationStatus, Option<String>), ApiError> {
+    let reason: Option<String> = decision
+        .reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty());
+
+    match decision.status {
+        ModerationStatus::Pending => Err(ApiError::BadRequest(
+            "A moderation decision must approve, reject or hide".to_string(),
+        )),
+        ModerationStatus::Approved => Ok((ModerationStatus::Approved, None)),
+        _ if reason.is_none() => Err(ApiError::BadRequest(
+            "A reason is required to reject or hide".to_string(),
+        )),
+        status => Ok((status, reason)),
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/services/moderation.rs"]
+mod extended_tests;
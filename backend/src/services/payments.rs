@@ -1,5 +1,57 @@
-use crate::models::stripe::{CurrencyMap, PricePayload};
-use stripe::{CreateProductDefaultPriceDataCurrencyOptions, Currency};
+use crate::models::{
+    state::AppState,
+    stripe::{CurrencyMap, PricePayload},
+    webhook::RefundResponse,
+};
+use stripe::{
+    Client, CreateProductDefaultPriceDataCurrencyOptions, CreateRefund, Currency, PaymentIntentId,
+    Refund, RequestStrategy,
+};
+
+/// Reembolsa por completo un PaymentIntent de Stripe. La clave de idempotencia depende solo del
+/// pago, así que el webhook de cancelación y el borrado de cuenta no lo reembolsan dos veces.
+pub async fn refund_payment_intent(
+    state: &AppState,
+    stripe_id: &str,
+) -> Result<RefundResponse, String> {
+    let pi_id: PaymentIntentId = stripe_id.parse().map_err(|e| {
+        let msg = format!("Invalid PaymentIntent ID: {}", e);
+        tracing::error!("{}", msg);
+        msg
+    })?;
+
+    let client: Client = state
+        .stripe_client
+        .clone()
+        .with_strategy(RequestStrategy::Idempotent(format!("refund-{}", stripe_id)));
+
+    match Refund::create(
+        &client,
+        CreateRefund {
+            payment_intent: Some(pi_id),
+            ..Default::default()
+        },
+    )
+    .await
+    {
+        Ok(refund) => {
+            tracing::info!("Reembolso creado en Stripe: {:?}", refund);
+
+            Ok(RefundResponse {
+                id: refund.id.to_string(),
+                amount: refund.amount,
+                currency: refund.currency.to_string(),
+                status: refund.status.clone(),
+                created: refund.created,
+            })
+        }
+        Err(e) => {
+            let msg = format!("Error creando reembolso: {:?}", e);
+            tracing::error!("{}", msg);
+            Err(msg)
+        }
+    }
+}
 
 // Añadir otras monedas
 pub fn insert_options_by_country(
@@ -37,4 +89,4 @@ pub fn insert_options_by_country(
 
 #[cfg(test)]
 #[path = "../test/services/payments.rs"]
-mod tests;
+mod extended_tests;
@@ -0,0 +1,134 @@
+use {
+    crate::models::{
+        cal::{BookingStatus, CalBookingPayload},
+        comments::Comment,
+        error::ApiError,
+        firebase::UserAuthentication,
+        state::AppState,
+        webhook::CalBookingsResponse,
+    },
+    chrono::{DateTime, Utc},
+    std::{
+        cmp::Reverse,
+        collections::{HashMap, HashSet},
+    },
+    tracing::info,
+};
+
+/// Motivo del rechazo de una reseña sin clase que la respalde
+pub const REVIEW_REQUIRES_CLASS: &str = "Only students with a completed class can leave a review";
+
+/// Los comentarios con estrellas son reseñas
+pub fn is_review(stars: f32) -> bool {
+    stars > 0.0
+}
+
+/// Uids de las clases completadas por `email`: aceptadas, ya terminadas y con él entre los
+/// asistentes. De la más reciente a la más antigua.
+pub fn completed_bookings(
+    bookings: &[CalBookingPayload],
+    email: &str,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let mut completed: Vec<(DateTime<Utc>, String)> = bookings
+        .iter()
+        .filter(|booking| booking.status == BookingStatus::Accepted)
+        .filter(|booking| {
+            booking
+                .attendees
+                .iter()
+                .any(|attendee| attendee.email.eq_ignore_ascii_case(email))
+        })
+        .filter_map(|booking| {
+            let end: DateTime<Utc> = booking
+                .end_time
+                .as_deref()
+                .and_then(|end| DateTime::parse_from_rfc3339(end).ok())?
+                .with_timezone(&Utc);
+            Some((end, booking.uid.clone()?))
+        })
+        .filter(|(end, _)| *end <= now)
+        .collect();
+
+    completed.sort_by_key(|(end, _)| Reverse(*end));
+    completed.into_iter().map(|(_, uid)| uid).collect()
+}
+
+/// Primera de las clases completadas que aún no respalda otra reseña del usuario. En una
+/// clase grupal la reserva es la misma para todos los asistentes, así que solo se comparan
+/// las reseñas de `uid`.
+pub fn unreviewed_booking(
+    completed: Vec<String>,
+    comments: &HashMap<String, Comment>,
+    uid: &str,
+) -> Option<String> {
+    let reviewed: HashSet<&str> = comments
+        .values()
+        .filter(|comment| comment.author_uid.as_deref() == Some(uid))
+        .filter_map(|comment| comment.booking_uid.as_deref())
+        .collect();
+
+    completed
+        .into_iter()
+        .find(|booking| !reviewed.contains(booking.as_str()))
+}
+
+/// Reserva que respalda una reseña nueva del usuario. Sin clase completada que no tenga ya
+/// su reseña se rechaza; si Cal.com no responde tampoco se puede comprobar. Las reservas se
+/// buscan por el email verificado del token: el del perfil lo puede cambiar el usuario.
+pub async fn review_booking(
+    state: &AppState,
+    claims: &UserAuthentication,
+) -> Result<String, ApiError> {
+    let uid: &str = &claims.user_id;
+    let email: &str = match claims.email.as_deref() {
+        Some(email) if claims.email_verified == Some(true) => email,
+        _ => return Err(ApiError::EmailNotVerified("leaving a review")),
+    };
+
+    let bookings: Vec<CalBookingPayload> = fetch_past_bookings(state, email).await?;
+    let completed: Vec<String> = completed_bookings(&bookings, email, Utc::now());
+    if completed.is_empty() {
+        info!("No completed class found for {}", uid);
+        return Err(ApiError::Forbidden(REVIEW_REQUIRES_CLASS.to_string()));
+    }
+
+    let comments: HashMap<String, Comment> = state.repositories.comments.get_all().await?;
+    unreviewed_booking(completed, &comments, uid).ok_or_else(|| {
+        ApiError::Forbidden("Every completed class already has a review".to_string())
+    })
+}
+
+/// Reservas pasadas en las que el email aparece como asistente
+async fn fetch_past_bookings(
+    state: &AppState,
+    email: &str,
+) -> Result<Vec<CalBookingPayload>, ApiError> {
+    let cal = &state.cal_options;
+    let response: reqwest::Response = cal
+        .client
+        .get(format!("{}/bookings", cal.base_url))
+        .header("cal-api-version", "2024-08-13")
+        .header("Authorization", &cal.api_key)
+        .query(&[("attendeeEmail", email), ("status", "past")])
+        .send()
+        .await
+        .map_err(|e| ApiError::Unavailable(format!("Cal.com unreachable: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(ApiError::Unavailable(format!(
+            "Cal.com responded {}",
+            response.status()
+        )));
+    }
+
+    Ok(response
+        .json::<CalBookingsResponse>()
+        .await
+        .map_err(|e| ApiError::Unavailable(format!("Invalid Cal.com response: {}", e)))?
+        .data
+        .bookings)
+}
+
+#[cfg(test)]
+#[path = "../test/services/reviews.rs"]
+mod extended_tests;
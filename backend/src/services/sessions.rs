@@ -0,0 +1,159 @@
+use {
+    crate::{
+        middleware::auth::decode_token_claims,
+        models::{
+            error::{ApiError, RepositoryError},
+            firebase::UserAuthentication,
+            session::{
+                CachedRevocation, RevokedSessions, Session, SessionEvent, SessionView, UserSessions,
+            },
+            state::AppState,
+        },
+        services::firebase::{firebase_api_error, set_valid_since},
+    },
+    chrono::Utc,
+    std::time::{Duration, Instant},
+    tracing::{info, warn},
+    uuid::Uuid,
+};
+
+/// Tiempo que el middleware reutiliza el instante de revocación leído de la base de datos.
+/// Otras instancias tardan como mucho esto en rechazar los tokens revocados.
+pub const REVOCATION_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Registra la sesión del ID token recién emitido por Firebase. Un login abre una sesión
+/// nueva; un refresco actualiza la de su login (mismo `auth_time`, la usada más recientemente
+/// si hubo varios logins en el mismo segundo).
+/// Nunca hace fallar el login: un error solo se registra en el log.
+pub async fn track_session(
+    state: &AppState,
+    id_token: &str,
+    user_agent: Option<&str>,
+    event: SessionEvent,
+) {
+    let claims: UserAuthentication = match decode_token_claims(id_token) {
+        Ok(claims) => claims,
+        Err(err) => {
+            warn!("Session not tracked, unreadable id token: {}", err);
+            return;
+        }
+    };
+    let now: String = Utc::now().to_rfc3339();
+
+    let existing: Option<(String, String)> = match event {
+        SessionEvent::Login => None,
+        SessionEvent::Refresh => match state.repositories.sessions.get(&claims.sub).await {
+            Ok(UserSessions { sessions, .. }) => sessions
+                .into_iter()
+                .filter(|(_, session)| session.auth_time == claims.auth_time)
+                .max_by(|(_, a), (_, b)| a.last_seen_at.cmp(&b.last_seen_at))
+                .map(|(id, session)| (id, session.created_at)),
+            Err(err) => {
+                warn!("Session not tracked: {}", err);
+                return;
+            }
+        },
+    };
+    // Sin sesión previa (login o refresco de un login no registrado) se abre una nueva
+    let (session_id, created_at): (String, String) =
+        existing.unwrap_or_else(|| (Uuid::new_v4().to_string(), now.clone()));
+    let session: Session = Session {
+        auth_time: claims.auth_time,
+        created_at,
+        last_seen_at: now,
+        sign_in_provider: claims
+            .firebase
+            .map(|firebase| firebase.sign_in_provider)
+            .or(claims.provider_id),
+        user_agent: user_agent.map(str::to_string),
+    };
+
+    if let Err(err) = state
+        .repositories
+        .sessions
+        .put_session(&claims.sub, &session_id, &session)
+        .await
+    {
+        warn!("Session not tracked: {}", err);
+    }
+}
+
+/// Sesiones del usuario, de la más reciente a la más antigua
+pub async fn list_sessions(
+    state: &AppState,
+    claims: &UserAuthentication,
+) -> Result<Vec<SessionView>, RepositoryError> {
+    let mut sessions: Vec<SessionView> = state
+        .repositories
+        .sessions
+        .get(&claims.sub)
+        .await?
+        .sessions
+        .into_values()
+        .map(|session| SessionView {
+            current: session.auth_time == claims.auth_time,
+            session,
+        })
+        .collect();
+    sessions.sort_by(|a, b| b.session.last_seen_at.cmp(&a.session.last_seen_at));
+    Ok(sessions)
+}
+
+/// Cierra todas las sesiones: Firebase deja de aceptar los refresh tokens (`validSince`)
+/// y el middleware rechaza los ID tokens aún vigentes
+pub async fn revoke_all_sessions(state: &AppState, uid: &str) -> Result<RevokedSessions, ApiError> {
+    let revoked_at: i64 = Utc::now().timestamp();
+
+    set_valid_since(&state.firebase_options, uid, revoked_at)
+        .await
+        .map_err(firebase_api_error)?;
+
+    let sessions_revoked: usize = state.repositories.sessions.get(uid).await?.sessions.len();
+    state
+        .repositories
+        .sessions
+        .revoke_all(uid, revoked_at)
+        .await?;
+    state.session_revocations.write().await.insert(
+        uid.to_string(),
+        CachedRevocation {
+            revoked_at: Some(revoked_at),
+            fetched_at: Instant::now(),
+        },
+    );
+    info!("Revoked {} sessions of {}", sessions_revoked, uid);
+
+    Ok(RevokedSessions {
+        revoked_at,
+        sessions_revoked,
+    })
+}
+
+/// Instante de revocación del usuario, cacheado durante `REVOCATION_CACHE_TTL`
+pub async fn session_revoked_at(
+    state: &AppState,
+    uid: &str,
+) -> Result<Option<i64>, RepositoryError> {
+    if let Some(cached) = state.session_revocations.read().await.get(uid)
+        && cached.fetched_at.elapsed() < REVOCATION_CACHE_TTL
+    {
+        return Ok(cached.revoked_at);
+    }
+
+    let revoked_at: Option<i64> = state.repositories.sessions.get(uid).await?.revoked_at;
+    let mut cache = state.session_revocations.write().await;
+    // Se aprovecha la escritura para que la caché no crezca con usuarios inactivos
+    cache.retain(|_, cached| cached.fetched_at.elapsed() < REVOCATION_CACHE_TTL);
+    cache.insert(
+        uid.to_string(),
+        CachedRevocation {
+            revoked_at,
+            fetched_at: Instant::now(),
+        },
+    );
+    Ok(revoked_at)
+}
+
+#[cfg(test)]
+#[path = "../test/services/sessions.rs"]
+mod extended_tests;
@@ -0,0 +1,194 @@
+use {
+    crate::models::{
+        cal::CalBookingPayload,
+        error::ApiError,
+        state::AppState,
+        subscription::{Entitlement, SubscriptionTier},
+        user::UserDB,
+        webhook::CalBookingsResponse,
+    },
+    chrono::{DateTime, Datelike, Months, TimeZone, Utc},
+    std::str::FromStr,
+    stripe::{Price, PriceId, StripeError},
+    tracing::{info, warn},
+};
+
+/// Estados de Cal.com que consumen clases del plan (las canceladas y rechazadas no cuentan)
+const COUNTED_BOOKING_STATUSES: &str = "upcoming,unconfirmed,past";
+
+/// Mes natural (UTC) que contiene `at`, como intervalo `[inicio, fin)`
+pub fn billing_period(at: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start: DateTime<Utc> = Utc
+        .with_ymd_and_hms(at.year(), at.month(), 1, 0, 0, 0)
+        .single()
+        .unwrap_or(at);
+    let end: DateTime<Utc> = start + Months::new(1);
+    (start, end)
+}
+
+/// Plan del usuario con su id. Un id sin definición se rechaza: el plan no se puede aplicar.
+pub async fn user_tier(
+    state: &AppState,
+    user: &UserDB,
+) -> Result<Option<(String, SubscriptionTier)>, ApiError> {
+    let Some(tier_id) = user.subscription_tier.as_deref() else {
+        return Ok(None);
+    };
+
+    match state.repositories.subscription_tiers.get(tier_id).await? {
+        Some(tier) => Ok(Some((tier_id.to_string(), tier))),
+        None => {
+            warn!("User has unknown subscription tier {}", tier_id);
+            Err(ApiError::Forbidden(format!(
+                "Subscription tier {} is not available",
+                tier_id
+            )))
+        }
+    }
+}
+
+/// Clases usadas y restantes del plan en el periodo que contiene `at`
+pub async fn entitlement(
+    state: &AppState,
+    tier_id: &str,
+    tier: &SubscriptionTier,
+    email: &str,
+    at: DateTime<Utc>,
+) -> Result<Entitlement, ApiError> {
+    let (period_start, period_end) = billing_period(at);
+    let used: u32 = count_plan_bookings(state, tier, email, period_start, period_end).await?;
+
+    Ok(Entitlement {
+        tier: tier_id.to_string(),
+        period_start: period_start.to_rfc3339(),
+        period_end: period_end.to_rfc3339(),
+        allowance: tier.monthly_class_allowance,
+        used,
+        remaining: tier
+            .monthly_class_allowance
+            .map(|allowance| allowance.saturating_sub(used)),
+        discount_percentage: tier.discount_percentage,
+    })
+}
+
+/// Comprueba que el plan del usuario cubre la reserva: tipo de evento incluido y clases
+/// disponibles en el mes de la clase. Sin plan no hay nada que comprobar (pago por clase).
+pub async fn check_booking_entitlement(
+    state: &AppState,
+    user: &UserDB,
+    booking: &CalBookingPayload,
+) -> Result<Option<Entitlement>, ApiError> {
+    let Some((tier_id, tier)) = user_tier(state, user).await? else {
+        return Ok(None);
+    };
+
+    if !tier.allows_event_type(booking.event_type_slug.as_deref(), booking.event_type_id) {
+        return Err(ApiError::Forbidden(format!(
+            "This class type is not included in the {} plan",
+            tier.name
+        )));
+    }
+    // Las clases del plan se cuentan por el email del asistente
+    if booking
+        .attendees
+        .first()
+        .is_some_and(|attendee| !attendee.email.eq_ignore_ascii_case(&user.email))
+    {
+        return Err(ApiError::Forbidden(
+            "Plan classes must be booked with the account email".to_string(),
+        ));
+    }
+
+    let start: DateTime<Utc> = booking
+        .start_time
+        .as_deref()
+        .and_then(|start| DateTime::parse_from_rfc3339(start).ok())
+        .map(|start| start.with_timezone(&Utc))
+        .ok_or_else(|| ApiError::BadRequest("Invalid start time".to_string()))?;
+
+    let entitlement: Entitlement = entitlement(state, &tier_id, &tier, &user.email, start).await?;
+    if entitlement.remaining == Some(0) {
+        info!("Monthly allowance of {} exhausted", tier_id);
+        return Err(ApiError::Forbidden(format!(
+            "Monthly class allowance of the {} plan exhausted",
+            tier.name
+        )));
+    }
+
+    Ok(Some(entitlement))
+}
+
+/// Reservas del periodo que cuentan para el plan. Si Cal.com no responde no se puede
+/// comprobar el saldo, así que la reserva se rechaza.
+async fn count_plan_bookings(
+    state: &AppState,
+    tier: &SubscriptionTier,
+    email: &str,
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+) -> Result<u32, ApiError> {
+    let cal = &state.cal_options;
+    let response: reqwest::Response = cal
+        .client
+        .get(format!("{}/bookings", cal.base_url))
+        .header("cal-api-version", "2024-08-13")
+        .header("Authorization", &cal.api_key)
+        .query(&[
+            ("attendeeEmail", email),
+            ("afterStart", &period_start.to_rfc3339()),
+            ("beforeEnd", &period_end.to_rfc3339()),
+            ("status", COUNTED_BOOKING_STATUSES),
+        ])
+        .send()
+        .await
+        .map_err(|e| ApiError::Unavailable(format!("Cal.com unreachable: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(ApiError::Unavailable(format!(
+            "Cal.com responded {}",
+            response.status()
+        )));
+    }
+
+    let bookings: Vec<CalBookingPayload> = response
+        .json::<CalBookingsResponse>()
+        .await
+        .map_err(|e| ApiError::Unavailable(format!("Invalid Cal.com response: {}", e)))?
+        .data
+        .bookings;
+
+    Ok(bookings
+        .iter()
+        .filter(|booking| {
+            let event_type = booking.event_type.as_ref();
+            tier.allows_event_type(
+                booking
+                    .event_type_slug
+                    .as_deref()
+                    .or(event_type.and_then(|e| e.slug.as_deref())),
+                booking.event_type_id.or(event_type.and_then(|e| e.id)),
+            )
+        })
+        .count() as u32)
+}
+
+/// El plan tiene que cobrarse con un precio recurrente de Stripe
+pub async fn verify_recurring_price(state: &AppState, price_id: &str) -> Result<(), ApiError> {
+    let id: PriceId = PriceId::from_str(price_id)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid Stripe price id {}", price_id)))?;
+
+    match Price::retrieve(&state.stripe_client, &id, &[]).await {
+        Ok(price) if price.recurring.is_some() => Ok(()),
+        Ok(_) => Err(ApiError::BadRequest(format!(
+            "Stripe price {} is not recurring",
+            price_id
+        ))),
+        Err(StripeError::Stripe(error)) if error.http_status == 404 => Err(ApiError::BadRequest(
+            format!("Stripe price {} not found", price_id),
+        )),
+        Err(e) => Err(ApiError::Unavailable(format!("Stripe unreachable: {}", e))),
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/services/subscriptions.rs"]
+mod extended_tests;
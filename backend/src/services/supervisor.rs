@@ -0,0 +1,281 @@
+use {
+    chrono::{DateTime, Utc},
+    serde::Serialize,
+    std::{
+        collections::BTreeMap,
+        future::Future,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tokio::{sync::watch, task::JoinHandle},
+    tracing::{error, info, warn},
+    utoipa::ToSchema,
+};
+
+/// Backoff por defecto entre reinicios: 1s, 2s, 4s... hasta 1 minuto
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Si una tarea aguanta este tiempo en marcha, el backoff vuelve a empezar desde el principio
+const BACKOFF_RESET_AFTER: Duration = Duration::from_secs(300);
+
+/// Señal de apagado que reciben las tareas supervisadas. Deben terminar su iteración
+/// en curso y volver en cuanto se active.
+#[derive(Clone)]
+pub struct ShutdownSignal(watch::Receiver<bool>);
+
+impl ShutdownSignal {
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Espera hasta que se pida el apagado (vuelve enseguida si ya se pidió)
+    pub async fn wait(&mut self) {
+        // Si el supervisor desaparece también lo tratamos como apagado
+        let _ = self.0.wait_for(|triggered| *triggered).await;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    Running,
+    Restarting,
+    Stopped,
+}
+
+/// Estado de una tarea en segundo plano, tal y como se expone en `/webhook/health/tasks`
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TaskStatus {
+    pub name: String,
+    pub state: TaskState,
+    pub restarts: u32,
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+}
+
+impl TaskStatus {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: TaskState::Running,
+            restarts: 0,
+            started_at: None,
+            last_error: None,
+            last_failure_at: None,
+        }
+    }
+}
+
+/// Supervisor de tareas en segundo plano (polling de Cal.com, refresco de claves...).
+/// Reinicia con backoff las que terminan o hacen panic y coordina el apagado ordenado.
+#[derive(Clone)]
+pub struct TaskSupervisor {
+    statuses: Arc<Mutex<BTreeMap<String, TaskStatus>>>,
+    handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    shutdown: Arc<watch::Sender<bool>>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for TaskSupervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskSupervisor {
+    pub fn new() -> Self {
+        Self::with_backoff(DEFAULT_INITIAL_BACKOFF, DEFAULT_MAX_BACKOFF)
+    }
+
+    pub fn with_backoff(initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            statuses: Arc::new(Mutex::new(BTreeMap::new())),
+            handles: Arc::new(Mutex::new(Vec::new())),
+            shutdown: Arc::new(watch::channel(false).0),
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        ShutdownSignal(self.shutdown.subscribe())
+    }
+
+    /// Lanza una tarea supervisada. `task` se vuelve a llamar cada vez que hay que reiniciarla.
+    pub fn spawn<F, Fut>(&self, name: &str, task: F)
+    where
+        F: Fn(ShutdownSignal) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.update(name, |_| {});
+
+        let supervisor: TaskSupervisor = self.clone();
+        let name: String = name.to_string();
+        let handle: JoinHandle<()> = tokio::spawn(async move {
+            supervisor.supervise(&name, task).await;
+        });
+
+        self.handles
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(handle);
+    }
+
+    async fn supervise<F, Fut>(&self, name: &str, task: F)
+    where
+        F: Fn(ShutdownSignal) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut shutdown: ShutdownSignal = self.shutdown_signal();
+        let mut consecutive_failures: u32 = 0;
+
+        while !shutdown.is_triggered() {
+            self.update(name, |status| {
+                status.state = TaskState::Running;
+                status.started_at = Some(Utc::now());
+            });
+            info!(task = name, "Background task started");
+
+            let started: Instant = Instant::now();
+            // Se ejecuta en su propia tarea para que un panic no tumbe al supervisor
+            let result = tokio::spawn(task(shutdown.clone())).await;
+
+            if shutdown.is_triggered() {
+                break;
+            }
+
+            let reason: String = match result {
+                Ok(()) => "task returned unexpectedly".to_string(),
+                Err(err) if err.is_panic() => {
+                    let payload = err.into_panic();
+                    let message: &str = payload
+                        .downcast_ref::<&str>()
+                        .copied()
+                        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                        .unwrap_or("unknown panic");
+                    format!("task panicked: {}", message)
+                }
+                Err(err) => err.to_string(),
+            };
+
+            if started.elapsed() >= BACKOFF_RESET_AFTER {
+                consecutive_failures = 0;
+            }
+            let backoff: Duration = self.backoff(consecutive_failures);
+            consecutive_failures = consecutive_failures.saturating_add(1);
+
+            error!(
+                task = name,
+                "Background task stopped ({}), restarting in {:?}", reason, backoff
+            );
+            self.update(name, |status| {
+                status.state = TaskState::Restarting;
+                status.restarts += 1;
+                status.last_error = Some(reason);
+                status.last_failure_at = Some(Utc::now());
+            });
+
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = shutdown.wait() => break,
+            }
+        }
+
+        self.update(name, |status| status.state = TaskState::Stopped);
+        info!(task = name, "Background task stopped");
+    }
+
+    fn backoff(&self, failures: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(failures.min(16)))
+            .min(self.max_backoff)
+    }
+
+    fn update(&self, name: &str, change: impl FnOnce(&mut TaskStatus)) {
+        let mut statuses = self
+            .statuses
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        change(
+            statuses
+                .entry(name.to_string())
+                .or_insert_with(|| TaskStatus::new(name)),
+        );
+    }
+
+    /// Estado actual de todas las tareas, ordenadas por nombre
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        self.statuses
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Pide a todas las tareas que terminen. Se puede llamar varias veces.
+    pub fn begin_shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Pide el apagado y espera a que las tareas terminen su trabajo en curso, como mucho `timeout`
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.begin_shutdown();
+
+        let handles: Vec<JoinHandle<()>> = std::mem::take(
+            &mut *self
+                .handles
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        );
+
+        let wait_all = async {
+            for handle in handles {
+                let _ = handle.await;
+            }
+        };
+        if tokio::time::timeout(timeout, wait_all).await.is_err() {
+            warn!(
+                "Background tasks did not stop within {:?}, exiting anyway",
+                timeout
+            );
+        }
+    }
+}
+
+/// Espera a SIGTERM (lo que envían Fly.io y Docker al parar) o Ctrl+C / SIGINT
+pub async fn wait_for_os_shutdown() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                error!("Failed to listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("SIGINT received, shutting down"),
+        _ = terminate => info!("SIGTERM received, shutting down"),
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/services/supervisor.rs"]
+mod extended_tests;
@@ -0,0 +1,75 @@
+use {crate::models::firebase::UserMerged, chrono::DateTime};
+
+/// Cabecera del CSV del directorio de usuarios
+const CSV_HEADER: [&str; 11] = [
+    "uid",
+    "email",
+    "name",
+    "email_verified",
+    "disabled",
+    "role",
+    "subscription_tier",
+    "first_free_class",
+    "permissions",
+    "created_at",
+    "last_login_at",
+];
+
+/// Serializa el directorio de usuarios como CSV (RFC 4180, separador `,` y fin de línea CRLF)
+pub fn users_to_csv(users: &[UserMerged]) -> String {
+    let mut csv: String = CSV_HEADER.join(",");
+    csv.push_str("\r\n");
+
+    for user in users {
+        let mut permissions: Vec<String> = user.permissions.clone().unwrap_or_default();
+        permissions.sort();
+
+        let row: [String; 11] = [
+            user.local_id.clone(),
+            user.email.clone().unwrap_or_default(),
+            user.display_name.clone().unwrap_or_default(),
+            user.email_verified.unwrap_or(false).to_string(),
+            user.disabled.unwrap_or(false).to_string(),
+            user.role.clone().unwrap_or_default(),
+            user.subscription_tier.clone().unwrap_or_default(),
+            user.first_free_class.to_string(),
+            permissions.join(";"),
+            format_millis(user.created_at.as_deref()),
+            format_millis(user.last_login_at.as_deref()),
+        ];
+        let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+/// Firebase guarda las fechas como milisegundos en texto; en el CSV van en RFC 3339
+fn format_millis(value: Option<&str>) -> String {
+    value
+        .and_then(|ms| ms.parse::<i64>().ok())
+        .and_then(DateTime::from_timestamp_millis)
+        .map(|date| date.to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// Escapa un campo CSV. Los valores que empiezan por `=`, `+`, `-` o `@` se prefijan con `'`
+/// para que las hojas de cálculo no los interpreten como fórmulas.
+fn csv_field(value: &str) -> String {
+    let value: String = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/services/users.rs"]
+mod extended_tests;
@@ -0,0 +1,224 @@
+#[cfg(test)]
+mod tests {
+    use {crate::config::Config, std::collections::HashMap};
+
+    /// Entorno mínimo válido con Firebase como base de datos
+    fn valid_env() -> HashMap<String, String> {
+        [
+            ("FIREBASE_PROJECT_ID", "test-project"),
+            ("FIREBASE_API_KEY", "test-api-key"),
+            ("FIREBASE_DATABASE_URL", "https://test.firebaseio.com"),
+            ("FIREBASE_DATABASE_SECRET", "test-secret"),
+            ("STRIPE_API_KEY", "sk_test_key"),
+            ("RESEND_API_KEY", "re_test_key"),
+            ("MAILCHIMP_API_KEY", "test-key-us1"),
+            ("MAILCHIMP_SERVER_PREFIX", "us1"),
+            ("MAILCHIMP_LIST_ID", "test-list-id"),
+            ("CAL_BASE_URL", "https://api.cal.com/v2"),
+            ("CAL_API_KEY", "test-cal-key"),
+            ("GA_CLIENT_EMAIL", "test@test.com"),
+            ("GA_PRIVATE_KEY", "test-key"),
+            ("GA_PROPERTY_ID", "123456"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_env_only_uses_defaults() {
+        let config = Config::from_sources(None, &valid_env()).unwrap();
+
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.server.port, 3000);
+        assert_eq!(config.server.cors_origins.len(), 3);
+        assert_eq!(config.cal.polling_interval_secs, 600);
+        assert_eq!(
+            config.ga.base_url,
+            "https://analyticsdata.googleapis.com/v1beta"
+        );
+        assert_eq!(config.firebase.project_id, "test-project");
+    }
+
+    #[test]
+    fn test_toml_values_are_loaded() {
+        let toml = r#"
+            [server]
+            port = 8080
+            cors_origins = ["https://staging.amanahacademia.com"]
+
+            [cal]
+            polling_interval_secs = 120
+        "#;
+
+        let config = Config::from_sources(Some(toml), &valid_env()).unwrap();
+
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(
+            config.server.cors_origins,
+            vec!["https://staging.amanahacademia.com".to_string()]
+        );
+        assert_eq!(config.cal.polling_interval_secs, 120);
+    }
+
+    #[test]
+    fn test_env_overrides_toml() {
+        let toml = r#"
+            [server]
+            port = 8080
+
+            [cal]
+            base_url = "https://cal.example.com"
+        "#;
+        let mut env = valid_env();
+        env.insert("PORT".to_string(), "9090".to_string());
+        env.insert(
+            "CORS_ORIGINS".to_string(),
+            "http://localhost:4321, https://amanahacademia.com".to_string(),
+        );
+
+        let config = Config::from_sources(Some(toml), &env).unwrap();
+
+        assert_eq!(config.server.port, 9090);
+        assert_eq!(config.cal.base_url, "https://api.cal.com/v2");
+        assert_eq!(
+            config.server.cors_origins,
+            vec![
+                "http://localhost:4321".to_string(),
+                "https://amanahacademia.com".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_every_missing_key() {
+        let err = Config::from_sources(None, &HashMap::new()).unwrap_err();
+
+        // 12 claves obligatorias + URL y secreto de Firebase Realtime Database
+        assert_eq!(err.problems.len(), 14);
+        let report = err.to_string();
+        assert!(report.contains("FIREBASE_PROJECT_ID"));
+        assert!(report.contains("GA_PRIVATE_KEY"));
+        assert!(report.contains("FIREBASE_DATABASE_SECRET"));
+    }
+
+    #[test]
+    fn test_reports_malformed_values() {
+        let mut env = valid_env();
+        env.insert("PORT".to_string(), "not-a-port".to_string());
+        env.insert("CAL_BASE_URL".to_string(), "cal.com".to_string());
+        env.insert("CORS_ORIGINS".to_string(), "localhost:4321".to_string());
+        env.insert("CAL_POLLING_INTERVAL_SECS".to_string(), "0".to_string());
+
+        let err = Config::from_sources(None, &env).unwrap_err();
+
+        assert_eq!(err.problems.len(), 4);
+        assert!(err.problems.iter().any(|p| p.contains("PORT")));
+        assert!(err.problems.iter().any(|p| p.contains("CAL_BASE_URL")));
+        assert!(err.problems.iter().any(|p| p.contains("CORS_ORIGINS")));
+        assert!(
+            err.problems
+                .iter()
+                .any(|p| p.contains("CAL_POLLING_INTERVAL_SECS"))
+        );
+    }
+
+    #[test]
+    fn test_sqlite_backend_does_not_require_firebase_database() {
+        let mut env = valid_env();
+        env.remove("FIREBASE_DATABASE_URL");
+        env.remove("FIREBASE_DATABASE_SECRET");
+        env.insert("DATABASE_BACKEND".to_string(), "sqlite".to_string());
+
+        let config = Config::from_sources(None, &env).unwrap();
+
+        assert_eq!(config.database.backend, "sqlite");
+        assert_eq!(config.database.sqlite_path, "amanahacademia.db");
+    }
+
+    #[test]
+    fn test_invalid_toml_is_reported() {
+        let err = Config::from_sources(Some("[server\nport = "), &valid_env()).unwrap_err();
+
+        assert_eq!(err.problems.len(), 1);
+        assert!(err.problems[0].starts_with("Invalid config file"));
+    }
+
+    #[test]
+    fn test_auth_emulator_host_rewrites_auth_urls() {
+        let mut env = valid_env();
+        env.insert(
+            "FIREBASE_AUTH_EMULATOR_HOST".to_string(),
+            "localhost:9099".to_string(),
+        );
+
+        let config = Config::from_sources(None, &env).unwrap();
+
+        assert_eq!(
+            config.firebase.identity_toolkit_url,
+            "http://localhost:9099/identitytoolkit.googleapis.com/v1"
+        );
+        assert_eq!(
+            config.firebase.secure_token_url,
+            "http://localhost:9099/securetoken.googleapis.com/v1"
+        );
+    }
+
+    #[test]
+    fn test_auth_emulator_host_rejects_scheme() {
+        let mut env = valid_env();
+        env.insert(
+            "FIREBASE_AUTH_EMULATOR_HOST".to_string(),
+            "http://localhost:9099".to_string(),
+        );
+
+        let err = Config::from_sources(None, &env).unwrap_err();
+        assert!(err.problems[0].contains("FIREBASE_AUTH_EMULATOR_HOST"));
+    }
+
+    #[test]
+    fn test_firebase_service_account_requires_both_fields() {
+        let mut env = valid_env();
+        env.insert(
+            "FIREBASE_CLIENT_EMAIL".to_string(),
+            "admin@test.iam.gserviceaccount.com".to_string(),
+        );
+
+        let err = Config::from_sources(None, &env).unwrap_err();
+        assert!(err.problems[0].contains("FIREBASE_PRIVATE_KEY"));
+
+        env.insert("FIREBASE_PRIVATE_KEY".to_string(), "test-key".to_string());
+        let config = Config::from_sources(None, &env).unwrap();
+        assert_eq!(
+            config.firebase.client_email.as_deref(),
+            Some("admin@test.iam.gserviceaccount.com")
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_budgets_are_loaded_and_validated() {
+        let toml = r#"
+            [rate_limit]
+            auth = { capacity = 3, refill_per_minute = 1 }
+            webhook = { capacity = 0, refill_per_minute = 10 }
+        "#;
+        let mut env = valid_env();
+        env.insert(
+            "RATE_LIMIT_CLIENT_IP_HEADER".to_string(),
+            "Fly-Client-IP".to_string(),
+        );
+
+        let err = Config::from_sources(Some(toml), &env).unwrap_err();
+        assert_eq!(err.problems.len(), 1);
+        assert!(err.problems[0].contains("rate_limit.webhook"));
+
+        let toml = toml.replace("capacity = 0", "capacity = 50");
+        let config = Config::from_sources(Some(&toml), &env).unwrap();
+        assert_eq!(config.rate_limit.auth.capacity, 3);
+        assert_eq!(config.rate_limit.contact.capacity, 5);
+        assert_eq!(
+            config.rate_limit.client_ip_header.as_deref(),
+            Some("Fly-Client-IP")
+        );
+    }
+}
@@ -11,20 +11,44 @@ mod tests {
                     AddGuestsPayload, BookingStatus, BookingsQueryParams, CalBookingPayload,
                     GuestInput, SchedulesQuery,
                 },
+                firebase::UserAuthentication,
                 state::AppState,
+                subscription::SubscriptionTier,
+                user::UserDB,
                 webhook::BookingChange,
             },
             test_fixtures::fixtures::{create_mock_app_state, create_test_booking},
         },
         axum::{
+            Extension,
             extract::{Path, Query, State},
             http::StatusCode,
             response::IntoResponse,
         },
+        mockito::Matcher,
         serde_json::json,
         std::{collections::HashMap, sync::Arc},
     };
 
+    fn claims() -> UserAuthentication {
+        UserAuthentication {
+            sub: "student-uid".to_string(),
+            iss: "https://securetoken.google.com/test-project".to_string(),
+            aud: "test-project".to_string(),
+            iat: 0,
+            exp: i64::MAX,
+            email: Some("test@example.com".to_string()),
+            email_verified: Some(true),
+            name: None,
+            picture: None,
+            auth_time: 0,
+            user_id: "student-uid".to_string(),
+            firebase: None,
+            phone_number: None,
+            provider_id: None,
+        }
+    }
+
     /// Test: detect_changes detecta cambio de status
     #[tokio::test]
     async fn test_fetch_and_detect_changes_status_change() {
@@ -209,7 +233,8 @@ mod tests {
         });
 
         // Act
-        let response = add_booking(State(app_state), axum::Json(payload)).await;
+        let response =
+            add_booking(Extension(claims()), State(app_state), axum::Json(payload)).await;
 
         // Assert: Debería retornar BAD_REQUEST
         let resp = response.into_response();
@@ -231,7 +256,8 @@ mod tests {
         });
 
         // Act
-        let response = add_booking(State(app_state), axum::Json(payload)).await;
+        let response =
+            add_booking(Extension(claims()), State(app_state), axum::Json(payload)).await;
 
         // Assert: Debería retornar BAD_REQUEST
         let resp = response.into_response();
@@ -253,7 +279,8 @@ mod tests {
         });
 
         // Act
-        let response = add_booking(State(app_state), axum::Json(payload)).await;
+        let response =
+            add_booking(Extension(claims()), State(app_state), axum::Json(payload)).await;
 
         // Assert: Debería retornar BAD_REQUEST
         let resp = response.into_response();
@@ -282,12 +309,163 @@ mod tests {
         });
 
         // Act
-        let response = add_booking(State(app_state), axum::Json(payload)).await;
+        let response =
+            add_booking(Extension(claims()), State(app_state), axum::Json(payload)).await;
 
         // Assert: No debe hacer panic (fallará por red, pero validación pasa)
         let _result = response.into_response();
     }
 
+    /// Test: add_booking rechaza la reserva si el plan no tiene clases disponibles
+    #[tokio::test]
+    async fn test_add_booking_rejects_exhausted_subscription() {
+        // Arrange: plan de una clase al mes ya consumida
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/bookings")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("attendeeEmail".into(), "test@example.com".into()),
+                Matcher::UrlEncoded("afterStart".into(), "2024-12-01T00:00:00+00:00".into()),
+            ]))
+            .with_status(200)
+            .with_body(
+                json!({"status": "success", "data": {"bookings": [
+                    {"uid": "booking-1", "eventTypeId": 123}
+                ]}})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let create = server
+            .mock("POST", "/bookings")
+            .expect(0)
+            .create_async()
+            .await;
+        let mut state: AppState = create_mock_app_state(HashMap::new()).await;
+        state.cal_options.base_url = server.url();
+        state
+            .repositories
+            .subscription_tiers
+            .put(
+                "monthly-1",
+                &SubscriptionTier {
+                    name: "Mensual".to_string(),
+                    monthly_class_allowance: Some(1),
+                    allowed_event_types: vec![],
+                    discount_percentage: 0,
+                    stripe_price_id: "price_monthly".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        state
+            .repositories
+            .users
+            .put(
+                "student-uid",
+                &UserDB {
+                    email: "test@example.com".to_string(),
+                    first_free_class: false,
+                    role: None,
+                    subscription_tier: Some("monthly-1".to_string()),
+                    permissions: None,
+                    learner_profile: None,
+                },
+            )
+            .await
+            .unwrap();
+        let payload = json!({
+            "eventTypeId": 123,
+            "start": "2024-12-15T10:00:00Z",
+            "attendees": [{
+                "name": "Test User",
+                "email": "test@example.com",
+                "timeZone": "Europe/Madrid"
+            }]
+        });
+
+        // Act
+        let response = add_booking(
+            Extension(claims()),
+            State(Arc::new(state)),
+            axum::Json(payload),
+        )
+        .await;
+
+        // Assert: se rechaza sin llegar a crear la reserva en Cal.com
+        assert_eq!(response.into_response().status(), StatusCode::FORBIDDEN);
+        create.assert_async().await;
+    }
+
+    /// Test: add_booking espera al lock de reservas del usuario antes de comprobar el plan
+    #[tokio::test]
+    async fn test_add_booking_waits_for_user_booking_lock() {
+        // Arrange: otra reserva del mismo usuario tiene el lock
+        let mut server = mockito::Server::new_async().await;
+        let create = server
+            .mock("POST", "/bookings")
+            .with_status(400)
+            .with_body(json!({"status": "error"}).to_string())
+            .create_async()
+            .await;
+        let mut state: AppState = create_mock_app_state(HashMap::new()).await;
+        state.cal_options.base_url = server.url();
+        let state: Arc<AppState> = Arc::new(state);
+        let lock = state.booking_lock("student-uid").await;
+        let guard = lock.lock().await;
+        let payload = json!({
+            "eventTypeId": 123,
+            "start": "2024-12-15T10:00:00Z",
+            "attendees": [{
+                "name": "Test User",
+                "email": "test@example.com",
+                "timeZone": "Europe/Madrid"
+            }]
+        });
+
+        // Act
+        let booking = tokio::spawn(add_booking(
+            Extension(claims()),
+            State(state.clone()),
+            axum::Json(payload),
+        ));
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        // Assert: no llega a Cal.com hasta que se suelta el lock
+        assert!(!booking.is_finished());
+        assert!(!create.matched_async().await);
+        drop(guard);
+        booking.await.unwrap();
+        create.assert_async().await;
+    }
+
+    /// Test: los locks de reservas que ya no usa nadie se liberan del mapa
+    #[tokio::test]
+    async fn test_booking_locks_drop_unused_entries() {
+        // Arrange: un usuario con la reserva en curso y otros que ya terminaron
+        let state: AppState = create_mock_app_state(HashMap::new()).await;
+        let in_progress = state.booking_lock("busy-uid").await;
+        for uid in ["done-1", "done-2", "done-3"] {
+            let lock = state.booking_lock(uid).await;
+            let _guard = lock.lock().await;
+        }
+
+        // Act
+        let same = state.booking_lock("busy-uid").await;
+        drop(same);
+        let _next = state.booking_lock("next-uid").await;
+
+        // Assert: solo quedan los que alguien sigue usando
+        let mut uids: Vec<String> = state.booking_locks.lock().await.keys().cloned().collect();
+        uids.sort();
+        assert_eq!(uids, ["busy-uid", "next-uid"]);
+        // Mientras se usa, todas las peticiones del usuario comparten el mismo mutex
+        assert!(Arc::ptr_eq(
+            &in_progress,
+            &state.booking_lock("busy-uid").await
+        ));
+    }
+
     /// Test: add_booking acepta payload con eventTypeSlug y username
     #[tokio::test]
     async fn test_add_booking_valid_with_slug_and_username() {
@@ -305,7 +483,8 @@ mod tests {
         });
 
         // Act
-        let response = add_booking(State(app_state), axum::Json(payload)).await;
+        let response =
+            add_booking(Extension(claims()), State(app_state), axum::Json(payload)).await;
 
         // Assert: No debe hacer panic
         let _result = response.into_response();
@@ -328,7 +507,8 @@ mod tests {
         });
 
         // Act
-        let response = add_booking(State(app_state), axum::Json(payload)).await;
+        let response =
+            add_booking(Extension(claims()), State(app_state), axum::Json(payload)).await;
 
         // Assert: No debe hacer panic
         let _result = response.into_response();
@@ -345,7 +525,8 @@ mod tests {
         });
 
         // Act
-        let response = add_booking(State(app_state), axum::Json(payload)).await;
+        let response =
+            add_booking(Extension(claims()), State(app_state), axum::Json(payload)).await;
 
         // Assert: Debería retornar BAD_REQUEST
         let resp = response.into_response();
@@ -401,7 +582,8 @@ mod tests {
         });
 
         // Act
-        let response = add_booking(State(app_state), axum::Json(payload)).await;
+        let response =
+            add_booking(Extension(claims()), State(app_state), axum::Json(payload)).await;
 
         // Assert: Debería retornar CREATED
         let resp = response.into_response();
@@ -441,7 +623,8 @@ mod tests {
         });
 
         // Act
-        let response = add_booking(State(app_state), axum::Json(payload)).await;
+        let response =
+            add_booking(Extension(claims()), State(app_state), axum::Json(payload)).await;
 
         // Assert: Debería retornar BAD_REQUEST
         let resp = response.into_response();
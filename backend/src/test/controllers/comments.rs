@@ -0,0 +1,621 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            controllers::comments::{
+                add_comment, edit_comment, get_all_comments, get_comment_by_id,
+                get_comments_summary, moderate_comment, moderate_reply, toggle_like,
+                toggle_reply_like,
+            },
+            models::{
+                comments::{
+                    Comment, CommentListQuery, CommentSort, ModerationDecision, ModerationStatus,
+                    ReplyComment, UpdateComment,
+                },
+                firebase::UserAuthentication,
+                state::AppState,
+                user::UserDB,
+            },
+            test_fixtures::fixtures::create_mock_app_state,
+        },
+        axum::{
+            Extension, Json,
+            body::to_bytes,
+            extract::{Path, Query, State},
+            http::StatusCode,
+            response::{IntoResponse, Response},
+        },
+        mockito::Matcher,
+        serde_json::{Value, json},
+        std::{collections::HashMap, sync::Arc},
+    };
+
+    fn claims_for(uid: &str) -> UserAuthentication {
+        UserAuthentication {
+            sub: uid.to_string(),
+            iss: "https://securetoken.google.com/test-project".to_string(),
+            aud: "test-project".to_string(),
+            iat: 0,
+            exp: i64::MAX,
+            email: Some(format!("{}@test.com", uid)),
+            email_verified: Some(true),
+            name: None,
+            picture: None,
+            auth_time: 0,
+            user_id: uid.to_string(),
+            firebase: None,
+            phone_number: None,
+            provider_id: None,
+        }
+    }
+
+    fn comment(author: &str, status: ModerationStatus) -> Comment {
+        Comment {
+            author_uid: Some(author.to_string()),
+            name: author.to_string(),
+            timestamp: "01/01/2025 10:00".to_string(),
+            content: "Muy buena clase".to_string(),
+            url_img: None,
+            stars: 5.0,
+            like: 0,
+            reply: Vec::new(),
+            users_liked: Vec::new(),
+            moderation_status: status,
+            moderation_reason: None,
+            verified_student: false,
+            booking_uid: None,
+            liked: None,
+        }
+    }
+
+    async fn json_of(response: Response) -> Value {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    async fn state_with(comments: Vec<Comment>) -> (Arc<AppState>, Vec<String>) {
+        let state = create_mock_app_state(HashMap::new()).await;
+        let mut ids = Vec::new();
+        for comment in comments {
+            ids.push(state.repositories.comments.create(&comment).await.unwrap());
+        }
+        (Arc::new(state), ids)
+    }
+
+    #[tokio::test]
+    async fn test_add_comment_queues_new_authors_and_trusts_approved_ones() {
+        let (state, _) = state_with(vec![comment("alice", ModerationStatus::Approved)]).await;
+
+        for (uid, expected) in [("alice", "approved"), ("bob", "pending")] {
+            // Sin estrellas no es una reseña y no hace falta una clase
+            let response = add_comment(
+                Extension(claims_for(uid)),
+                State(state.clone()),
+                Json(Comment {
+                    stars: 0.0,
+                    ..comment(uid, ModerationStatus::Approved)
+                }),
+            )
+            .await
+            .into_response();
+
+            assert_eq!(response.status(), StatusCode::CREATED);
+            assert_eq!(
+                json_of(response).await["data"]["moderation_status"],
+                expected
+            );
+        }
+    }
+
+    // Alumno con perfil y Cal.com simulado devolviendo `bookings` como reservas pasadas
+    async fn student_state(server: &mut mockito::Server, bookings: Value) -> Arc<AppState> {
+        server
+            .mock("GET", "/bookings")
+            .match_query(Matcher::UrlEncoded(
+                "attendeeEmail".into(),
+                "bob@test.com".into(),
+            ))
+            .with_body(json!({"status": "success", "data": {"bookings": bookings}}).to_string())
+            .create_async()
+            .await;
+        let mut state = create_mock_app_state(HashMap::new()).await;
+        state.cal_options.base_url = server.url();
+        state
+            .repositories
+            .users
+            .put(
+                "bob",
+                &UserDB {
+                    email: "bob@test.com".to_string(),
+                    first_free_class: false,
+                    role: None,
+                    subscription_tier: None,
+                    permissions: None,
+                    learner_profile: None,
+                },
+            )
+            .await
+            .unwrap();
+        Arc::new(state)
+    }
+
+    #[tokio::test]
+    async fn test_add_review_without_completed_class_is_forbidden() {
+        let mut server = mockito::Server::new_async().await;
+        let state = student_state(&mut server, json!([])).await;
+
+        let response = add_comment(
+            Extension(claims_for("bob")),
+            State(state.clone()),
+            Json(comment("bob", ModerationStatus::Approved)),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(
+            state
+                .repositories
+                .comments
+                .get_all()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_review_from_student_is_verified() {
+        let mut server = mockito::Server::new_async().await;
+        let state = student_state(
+            &mut server,
+            json!([{
+                "uid": "booking-1",
+                "status": "accepted",
+                "start": "2025-01-10T09:00:00Z",
+                "end": "2025-01-10T10:00:00Z",
+                "attendees": [{"name": "Bob", "email": "bob@test.com", "timeZone": "Europe/Madrid"}]
+            }]),
+        )
+        .await;
+
+        // El cliente no puede marcarse como verificado por su cuenta
+        let response = add_comment(
+            Extension(claims_for("bob")),
+            State(state.clone()),
+            Json(Comment {
+                booking_uid: Some("forged".to_string()),
+                ..comment("bob", ModerationStatus::Approved)
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let id = json_of(response).await["data"]["name"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let stored = state.repositories.comments.get(&id).await.unwrap().unwrap();
+        assert!(stored.verified_student);
+        assert_eq!(stored.booking_uid.as_deref(), Some("booking-1"));
+
+        // La reserva no se publica, la marca sí
+        let response = moderate_comment(
+            Extension(claims_for("admin")),
+            State(state.clone()),
+            Path(id.clone()),
+            Json(ModerationDecision {
+                status: ModerationStatus::Approved,
+                reason: None,
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let listed = json_of(
+            get_all_comments(State(state.clone()), Query(CommentListQuery::default()))
+                .await
+                .into_response(),
+        )
+        .await;
+        assert_eq!(listed["data"][&id]["verified_student"], true);
+        assert!(listed["data"][&id].get("booking_uid").is_none());
+
+        // Cada clase respalda una sola reseña
+        let response = add_comment(
+            Extension(claims_for("bob")),
+            State(state),
+            Json(comment("bob", ModerationStatus::Approved)),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_approving_comment_publishes_it() {
+        let (state, ids) = state_with(vec![comment("bob", ModerationStatus::Pending)]).await;
+
+        let listed = json_of(
+            get_all_comments(State(state.clone()), Query(CommentListQuery::default()))
+                .await
+                .into_response(),
+        )
+        .await;
+        assert!(listed["data"].as_object().unwrap().is_empty());
+
+        let response = moderate_comment(
+            Extension(claims_for("admin")),
+            State(state.clone()),
+            Path(ids[0].clone()),
+            Json(ModerationDecision {
+                status: ModerationStatus::Approved,
+                reason: None,
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let listed = json_of(
+            get_all_comments(State(state), Query(CommentListQuery::default()))
+                .await
+                .into_response(),
+        )
+        .await;
+        assert!(listed["data"].get(&ids[0]).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_editing_only_approved_comment_sends_it_back_to_queue() {
+        let mut approved = comment("alice", ModerationStatus::Approved);
+        approved.stars = 0.0;
+        let mut other = comment("bob", ModerationStatus::Approved);
+        other.stars = 0.0;
+        let (state, ids) = state_with(vec![approved.clone(), approved, other]).await;
+        let edit = |id: &str, uid: &str| {
+            edit_comment(
+                Path(id.to_string()),
+                State(state.clone()),
+                Extension(claims_for(uid)),
+                Json(UpdateComment {
+                    content: "Texto nuevo".to_string(),
+                    stars: 0.0,
+                }),
+            )
+        };
+
+        // Con otro comentario aprobado la edición se sigue publicando
+        let trusted = json_of(edit(&ids[0], "alice").await.into_response()).await;
+        assert_eq!(trusted["data"]["moderation_status"], "approved");
+
+        // bob solo tiene aprobado el comentario que edita: vuelve a la cola
+        let edited = json_of(edit(&ids[2], "bob").await.into_response()).await;
+        assert_eq!(edited["data"]["moderation_status"], "pending");
+    }
+
+    #[tokio::test]
+    async fn test_pending_comment_only_visible_to_author() {
+        let (state, ids) = state_with(vec![comment("bob", ModerationStatus::Pending)]).await;
+
+        let author = get_comment_by_id(
+            Path(ids[0].clone()),
+            State(state.clone()),
+            Extension(claims_for("bob")),
+        )
+        .await
+        .into_response();
+        let other = get_comment_by_id(
+            Path(ids[0].clone()),
+            State(state),
+            Extension(claims_for("alice")),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(author.status(), StatusCode::OK);
+        assert_eq!(other.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_reject_reply_requires_reason() {
+        let mut parent = comment("alice", ModerationStatus::Approved);
+        parent.reply.push(ReplyComment {
+            id: "reply-1".to_string(),
+            author_uid: "bob".to_string(),
+            name: "bob".to_string(),
+            timestamp: "02/01/2025 10:00".to_string(),
+            content: "Spam".to_string(),
+            url_img: None,
+            like: 0,
+            users_liked: Vec::new(),
+            moderation_status: ModerationStatus::Pending,
+            moderation_reason: None,
+            liked: None,
+        });
+        let (state, ids) = state_with(vec![parent]).await;
+        let reject = |reason: Option<&str>| ModerationDecision {
+            status: ModerationStatus::Rejected,
+            reason: reason.map(str::to_string),
+        };
+
+        let missing_reason = moderate_reply(
+            Extension(claims_for("admin")),
+            State(state.clone()),
+            Path((ids[0].clone(), "reply-1".to_string())),
+            Json(reject(None)),
+        )
+        .await
+        .into_response();
+        let rejected = moderate_reply(
+            Extension(claims_for("admin")),
+            State(state.clone()),
+            Path((ids[0].clone(), "reply-1".to_string())),
+            Json(reject(Some("Publicidad"))),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(missing_reason.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(rejected.status(), StatusCode::OK);
+        let stored = state
+            .repositories
+            .comments
+            .get(&ids[0])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            stored.reply[0].moderation_status,
+            ModerationStatus::Rejected
+        );
+        assert_eq!(
+            stored.reply[0].moderation_reason.as_deref(),
+            Some("Publicidad")
+        );
+    }
+
+    fn rated(author: &str, stars: f32, like: u32, timestamp: &str) -> Comment {
+        Comment {
+            stars,
+            like,
+            timestamp: timestamp.to_string(),
+            ..comment(author, ModerationStatus::Approved)
+        }
+    }
+
+    /// Cuerpo sin parsear: `serde_json::Value` reordena las claves de los objetos
+    async fn text_of(response: Response) -> String {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    /// Posiciones en el cuerpo, para comprobar el orden en que se serializan las claves
+    fn in_order(body: &str, needles: &[&str]) -> bool {
+        let positions: Vec<usize> = needles
+            .iter()
+            .map(|needle| body.find(needle).unwrap())
+            .collect();
+        positions.windows(2).all(|pair| pair[0] < pair[1])
+    }
+
+    #[tokio::test]
+    async fn test_get_all_comments_sorts_and_filters() {
+        let (state, ids) = state_with(vec![
+            rated("alice", 3.0, 9, "01/02/2025 10:00"),
+            rated("bob", 5.0, 1, "15/01/2025 10:00"),
+            rated("carol", 4.0, 4, "01/03/2025 10:00"),
+        ])
+        .await;
+
+        let newest = text_of(
+            get_all_comments(State(state.clone()), Query(CommentListQuery::default()))
+                .await
+                .into_response(),
+        )
+        .await;
+        let most_liked = text_of(
+            get_all_comments(
+                State(state.clone()),
+                Query(CommentListQuery {
+                    sort: Some(CommentSort::MostLiked),
+                    min_stars: Some(4.0),
+                    has_replies: None,
+                }),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+
+        assert!(in_order(&newest, &[&ids[2], &ids[0], &ids[1]]));
+        assert!(in_order(&most_liked, &[&ids[2], &ids[1]]));
+        assert!(!most_liked.contains(&ids[0]));
+    }
+
+    #[tokio::test]
+    async fn test_get_all_comments_rejects_invalid_min_stars() {
+        let (state, _) = state_with(vec![]).await;
+
+        let response = get_all_comments(
+            State(state),
+            Query(CommentListQuery {
+                min_stars: Some(6.0),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_get_comments_summary_ignores_unapproved() {
+        let (state, _) = state_with(vec![
+            rated("alice", 4.0, 2, "01/02/2025 10:00"),
+            rated("bob", 5.0, 7, "02/02/2025 10:00"),
+            comment("spammer", ModerationStatus::Pending),
+        ])
+        .await;
+
+        let text = text_of(get_comments_summary(State(state)).await.into_response()).await;
+        let body: Value = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(body["data"]["count"], 2);
+        assert_eq!(body["data"]["average"], 4.5);
+        assert_eq!(body["data"]["histogram"]["5"], 1);
+        assert_eq!(body["data"]["histogram"]["1"], 0);
+        assert!(!text.contains("spammer"));
+        assert!(in_order(&text, &["\"name\":\"bob\"", "\"name\":\"alice\""]));
+    }
+
+    fn reply(id: &str, author: &str, status: ModerationStatus) -> ReplyComment {
+        ReplyComment {
+            id: id.to_string(),
+            author_uid: author.to_string(),
+            name: author.to_string(),
+            timestamp: "02/01/2025 10:00".to_string(),
+            content: "Gracias".to_string(),
+            url_img: None,
+            like: 0,
+            users_liked: Vec::new(),
+            moderation_status: status,
+            moderation_reason: None,
+            liked: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_toggle_reply_like() {
+        let mut parent = comment("alice", ModerationStatus::Approved);
+        parent
+            .reply
+            .push(reply("reply-1", "bob", ModerationStatus::Approved));
+        let (state, ids) = state_with(vec![parent]).await;
+        let toggle = || {
+            toggle_reply_like(
+                Path((ids[0].clone(), "reply-1".to_string())),
+                Extension(claims_for("carol")),
+                State(state.clone()),
+            )
+        };
+
+        let first = json_of(toggle().await.into_response()).await;
+        assert_eq!(first["data"]["liked"], true);
+        assert_eq!(first["data"]["like"], 1);
+
+        let second = json_of(toggle().await.into_response()).await;
+        assert_eq!(second["data"]["liked"], false);
+        assert_eq!(second["data"]["like"], 0);
+
+        let stored = state
+            .repositories
+            .comments
+            .get(&ids[0])
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stored.reply[0].users_liked.is_empty());
+        assert_eq!(stored.reply[0].liked, None);
+    }
+
+    #[tokio::test]
+    async fn test_toggle_reply_like_hides_unapproved_reply() {
+        let mut parent = comment("alice", ModerationStatus::Approved);
+        parent
+            .reply
+            .push(reply("reply-1", "bob", ModerationStatus::Pending));
+        let (state, ids) = state_with(vec![parent]).await;
+
+        let response = toggle_reply_like(
+            Path((ids[0].clone(), "reply-1".to_string())),
+            Extension(claims_for("carol")),
+            State(state),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_toggle_like_hides_unapproved_replies_of_others() {
+        let mut parent = comment("alice", ModerationStatus::Approved);
+        parent
+            .reply
+            .push(reply("reply-1", "bob", ModerationStatus::Approved));
+        parent
+            .reply
+            .push(reply("reply-2", "bob", ModerationStatus::Pending));
+        parent
+            .reply
+            .push(reply("reply-3", "carol", ModerationStatus::Pending));
+        let (state, ids) = state_with(vec![parent]).await;
+
+        let body = json_of(
+            toggle_like(
+                Path(ids[0].clone()),
+                Extension(claims_for("carol")),
+                State(state),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+
+        let replies: Vec<&str> = body["data"]["reply"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|reply| reply["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(replies, vec!["reply-1", "reply-3"]);
+    }
+
+    #[tokio::test]
+    async fn test_toggle_like_hides_booking_of_review() {
+        let mut review = comment("alice", ModerationStatus::Approved);
+        review.verified_student = true;
+        review.booking_uid = Some("booking-1".to_string());
+        let (state, ids) = state_with(vec![review]).await;
+
+        let body = json_of(
+            toggle_like(
+                Path(ids[0].clone()),
+                Extension(claims_for("carol")),
+                State(state),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+
+        assert_eq!(body["data"]["verified_student"], true);
+        assert!(body["data"].get("booking_uid").is_none_or(Value::is_null));
+    }
+
+    #[tokio::test]
+    async fn test_toggle_like_fixes_double_counted_likes() {
+        let mut liked = comment("alice", ModerationStatus::Approved);
+        liked.users_liked = vec!["carol".to_string(), "carol".to_string()];
+        liked.like = 2;
+        let (state, ids) = state_with(vec![liked]).await;
+
+        let body = json_of(
+            toggle_like(
+                Path(ids[0].clone()),
+                Extension(claims_for("dave")),
+                State(state),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+
+        assert_eq!(body["data"]["like"], 2);
+        assert_eq!(body["data"]["liked"], true);
+        assert_eq!(body["data"]["users_liked"].as_array().unwrap().len(), 2);
+    }
+}
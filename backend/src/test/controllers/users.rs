@@ -0,0 +1,1007 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            controllers::users::{
+                admin_set_account_status, admin_set_role, admin_update_permissions,
+                confirm_password_reset, delete_me, get_all_users, get_user_me, link_provider,
+                prefill_my_learner_profile, request_password_reset, send_verification_email,
+                unlink_provider, update_my_learner_profile, update_user,
+            },
+            models::{
+                firebase::UserAuthentication,
+                metrics::{CachedAccessToken, ServiceAccount},
+                sourvey::{Question, QuestionType, Survey},
+                state::AppState,
+                user::{
+                    ExportFormat, LearnerProfile, LearnerProfilePrefillQuery, LinkProviderRequest,
+                    PasswordResetConfirm, PasswordResetRequest, Permission, Provider, Role,
+                    UpdateAccountStatusRequest, UpdatePermissionsRequest, UpdateRoleRequest,
+                    UserDB, UserDirectoryQuery, UserRequest,
+                },
+            },
+            test_fixtures::fixtures::create_mock_app_state,
+            utils::validations::{ValidatedJson, ValidatedQuery},
+        },
+        axum::{
+            Extension,
+            body::to_bytes,
+            extract::{Path, Query, State},
+            http::{StatusCode, header},
+            response::{IntoResponse, Response},
+        },
+        mockito::Matcher,
+        serde_json::Value,
+        std::{
+            collections::{HashMap, HashSet},
+            sync::Arc,
+        },
+    };
+
+    async fn state_with_identity_toolkit(url: String) -> Arc<AppState> {
+        let mut state = create_mock_app_state(HashMap::new()).await;
+        state.firebase_options.identity_toolkit_url = url;
+        // Credenciales de administrador con un token vigente en caché para sendOobCode
+        state.firebase_options.service_account = Some(ServiceAccount {
+            client_email: "admin@test-project.iam.gserviceaccount.com".to_string(),
+            private_key: "unused-while-cached".to_string(),
+        });
+        *state.firebase_options.admin_token_cache.lock().await = Some(CachedAccessToken::new(
+            "cached-admin-token".to_string(),
+            3600,
+        ));
+        Arc::new(state)
+    }
+
+    async fn json_of(response: Response) -> Value {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn claims_for(uid: &str) -> UserAuthentication {
+        UserAuthentication {
+            sub: uid.to_string(),
+            iss: "https://securetoken.google.com/test-project".to_string(),
+            aud: "test-project".to_string(),
+            iat: 0,
+            exp: i64::MAX,
+            email: Some(format!("{}@test.com", uid)),
+            email_verified: Some(true),
+            name: None,
+            picture: None,
+            auth_time: 0,
+            user_id: uid.to_string(),
+            firebase: None,
+            phone_number: None,
+            provider_id: None,
+        }
+    }
+
+    async fn state_with_user(uid: &str, user: UserDB) -> Arc<AppState> {
+        let state = create_mock_app_state(HashMap::new()).await;
+        state.repositories.users.put(uid, &user).await.unwrap();
+        Arc::new(state)
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_request_does_not_reveal_unknown_email() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/projects/test-project/accounts:sendOobCode")
+            .with_status(400)
+            .with_body(r#"{"error": {"code": 400, "message": "EMAIL_NOT_FOUND"}}"#)
+            .create_async()
+            .await;
+        let state = state_with_identity_toolkit(server.url()).await;
+
+        let response = request_password_reset(
+            State(state),
+            ValidatedJson(PasswordResetRequest {
+                email: "ghost@test.com".to_string(),
+            }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            json_of(response).await["message"],
+            "If the email is registered, a password reset link has been sent"
+        );
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_request_without_link_relies_on_firebase_email() {
+        // Si Firebase no devuelve el enlace ya ha enviado él el email: no se llama a Resend
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/projects/test-project/accounts:sendOobCode")
+            .with_status(200)
+            .with_body(r#"{"email": "student@test.com"}"#)
+            .create_async()
+            .await;
+        let state = state_with_identity_toolkit(server.url()).await;
+
+        let response = request_password_reset(
+            State(state),
+            ValidatedJson(PasswordResetRequest {
+                email: "student@test.com".to_string(),
+            }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_confirm_returns_account_email() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/accounts:resetPassword")
+            .match_query(Matcher::Any)
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "oobCode": "code-123",
+                "newPassword": "new-secret"
+            })))
+            .with_status(200)
+            .with_body(r#"{"email": "student@test.com", "requestType": "PASSWORD_RESET"}"#)
+            .create_async()
+            .await;
+        let state = state_with_identity_toolkit(server.url()).await;
+
+        let response = confirm_password_reset(
+            State(state),
+            ValidatedJson(PasswordResetConfirm {
+                oob_code: "code-123".to_string(),
+                new_password: "new-secret".to_string(),
+            }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_of(response).await["data"], "student@test.com");
+    }
+
+    #[tokio::test]
+    async fn test_verification_email_rejected_when_already_verified() {
+        let state = Arc::new(create_mock_app_state(HashMap::new()).await);
+        let claims = claims_for("student-uid");
+
+        let response = send_verification_email(Extension(claims), State(state))
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_delete_me_requires_recent_login_before_touching_data() {
+        let state = state_with_user(
+            "student-uid",
+            UserDB {
+                email: "student-uid@test.com".to_string(),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        // `claims_for` lleva `auth_time: 0`, un login muy antiguo
+        let response = delete_me(Extension(claims_for("student-uid")), State(state.clone()))
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(
+            state
+                .repositories
+                .users
+                .get("student-uid")
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_me_with_unverified_email_leaves_that_email_data_alone() {
+        let mut server = mockito::Server::new_async().await;
+        // Con un email sin verificar no se consultan reservas ni newsletter de ese email
+        let bookings = server
+            .mock("GET", "/bookings")
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let newsletter = server
+            .mock("GET", "/search-members")
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let auth_delete = server
+            .mock("POST", "/projects/test-project/accounts:delete")
+            .match_header("authorization", "Bearer cached-admin-token")
+            .with_status(200)
+            .with_body(r#"{"kind": "identitytoolkit#DeleteAccountResponse"}"#)
+            .create_async()
+            .await;
+        let mut state = create_mock_app_state(HashMap::new()).await;
+        state.firebase_options.identity_toolkit_url = server.url();
+        state.firebase_options.service_account = Some(ServiceAccount {
+            client_email: "admin@test-project.iam.gserviceaccount.com".to_string(),
+            private_key: "unused-while-cached".to_string(),
+        });
+        *state.firebase_options.admin_token_cache.lock().await = Some(CachedAccessToken::new(
+            "cached-admin-token".to_string(),
+            3600,
+        ));
+        state.cal_options.base_url = server.url();
+        state.mailchimp_client.base_url = server.url();
+        state
+            .repositories
+            .users
+            .put(
+                "student-uid",
+                &UserDB {
+                    email: "victim@test.com".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        state
+            .repositories
+            .surveys
+            .put(&Survey {
+                id: "victim-survey".to_string(),
+                title: "Encuesta".to_string(),
+                description: String::new(),
+                user_email: "victim@test.com".to_string(),
+                submitted_at: None,
+                questions: Vec::new(),
+            })
+            .await
+            .unwrap();
+        let state = Arc::new(state);
+
+        let claims = UserAuthentication {
+            email: Some("victim@test.com".to_string()),
+            email_verified: Some(false),
+            auth_time: chrono::Utc::now().timestamp(),
+            ..claims_for("student-uid")
+        };
+        let response = delete_me(Extension(claims), State(state.clone()))
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        auth_delete.assert_async().await;
+        bookings.assert_async().await;
+        newsletter.assert_async().await;
+        let surveys = state.repositories.surveys.get_all().await.unwrap();
+        assert_eq!(surveys["victim-survey"].user_email, "victim@test.com");
+    }
+
+    #[tokio::test]
+    async fn test_update_user_cannot_self_assign_role() {
+        let state = Arc::new(create_mock_app_state(HashMap::new()).await);
+        let request = UserRequest {
+            email: "student-uid@test.com".to_string(),
+            password: "secret123".to_string(),
+            provider: Provider::Email,
+            first_free_class: false,
+            name: None,
+            phone_number: None,
+            id_token: None,
+            role: Some(Role::Admin),
+            permissions: None,
+            subscription_tier: None,
+        };
+
+        let response = update_user(
+            Extension(claims_for("student-uid")),
+            Extension("id-token".to_string()),
+            State(state),
+            axum::Json(request),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_admin_updates_permissions_grant_and_revoke() {
+        let state = state_with_user(
+            "student-uid",
+            UserDB {
+                email: "student-uid@test.com".to_string(),
+                permissions: Some(HashSet::from(["read_surveys".to_string()])),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let response = admin_update_permissions(
+            Extension(claims_for("admin-uid")),
+            State(state.clone()),
+            Path("student-uid".to_string()),
+            axum::Json(UpdatePermissionsRequest {
+                grant: HashSet::from([Permission::ManageTeachers]),
+                revoke: HashSet::from([Permission::ReadSurveys]),
+            }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let stored = state
+            .repositories
+            .users
+            .get("student-uid")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            stored.permissions,
+            Some(HashSet::from(["manage_teachers".to_string()]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_cannot_change_own_role_and_unknown_user_is_404() {
+        let state = state_with_user(
+            "admin-uid",
+            UserDB {
+                email: "admin-uid@test.com".to_string(),
+                role: Some("admin".to_string()),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let own = admin_set_role(
+            Extension(claims_for("admin-uid")),
+            State(state.clone()),
+            Path("admin-uid".to_string()),
+            axum::Json(UpdateRoleRequest {
+                role: Role::Student,
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(own.status(), StatusCode::FORBIDDEN);
+
+        let unknown = admin_set_role(
+            Extension(claims_for("admin-uid")),
+            State(state),
+            Path("ghost-uid".to_string()),
+            axum::Json(UpdateRoleRequest {
+                role: Role::Teacher,
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_admin_disables_account_with_admin_token() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/projects/test-project/accounts:update")
+            .match_header("authorization", "Bearer cached-admin-token")
+            .match_body(Matcher::Json(serde_json::json!({
+                "localId": "student-uid",
+                "disableUser": true
+            })))
+            .with_status(200)
+            .with_body(r#"{"localId": "student-uid"}"#)
+            .create_async()
+            .await;
+        let mut state = create_mock_app_state(HashMap::new()).await;
+        state.firebase_options.identity_toolkit_url = server.url();
+        state.firebase_options.service_account = Some(ServiceAccount {
+            client_email: "admin@test-project.iam.gserviceaccount.com".to_string(),
+            private_key: "unused-while-cached".to_string(),
+        });
+        // Con un token vigente en caché no se firma ningún JWT
+        *state.firebase_options.admin_token_cache.lock().await = Some(CachedAccessToken::new(
+            "cached-admin-token".to_string(),
+            3600,
+        ));
+
+        let response = admin_set_account_status(
+            Extension(claims_for("admin-uid")),
+            State(Arc::new(state)),
+            Path("student-uid".to_string()),
+            axum::Json(UpdateAccountStatusRequest { disabled: true }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_admin_disable_account_without_credentials_is_unavailable() {
+        let state = Arc::new(create_mock_app_state(HashMap::new()).await);
+
+        let response = admin_set_account_status(
+            Extension(claims_for("admin-uid")),
+            State(state),
+            Path("student-uid".to_string()),
+            axum::Json(UpdateAccountStatusRequest { disabled: true }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_get_all_users_exports_filtered_csv() {
+        let mut server = mockito::Server::new_async().await;
+        // Dos páginas de `accounts:batchGet`, con el token de administración
+        let second_page = server
+            .mock("GET", "/projects/test-project/accounts:batchGet")
+            .match_header("authorization", "Bearer cached-admin-token")
+            .match_query(Matcher::UrlEncoded(
+                "nextPageToken".to_string(),
+                "page-2".to_string(),
+            ))
+            .with_status(200)
+            .with_body(
+                r#"{"users": [{"localId": "used-uid", "email": "used@test.com", "emailVerified": true}]}"#,
+            )
+            .create_async()
+            .await;
+        let first_page = server
+            .mock("GET", "/projects/test-project/accounts:batchGet")
+            .match_header("authorization", "Bearer cached-admin-token")
+            .match_query(Matcher::UrlEncoded(
+                "maxResults".to_string(),
+                "1000".to_string(),
+            ))
+            .with_status(200)
+            .with_body(
+                r#"{"users": [{"localId": "new-uid", "email": "new@test.com", "emailVerified": true}],
+                    "nextPageToken": "page-2"}"#,
+            )
+            .create_async()
+            .await;
+        let state = state_with_identity_toolkit(server.url()).await;
+        for (uid, first_free_class) in [("new-uid", false), ("used-uid", true)] {
+            let profile = UserDB {
+                email: format!("{}@test.com", uid),
+                first_free_class,
+                role: Some("student".to_string()),
+                ..Default::default()
+            };
+            state.repositories.users.put(uid, &profile).await.unwrap();
+        }
+
+        let response = get_all_users(
+            Extension(claims_for("admin-uid")),
+            State(state),
+            ValidatedQuery(UserDirectoryQuery {
+                first_free_class: Some(false),
+                format: ExportFormat::Csv,
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+
+        first_page.assert_async().await;
+        second_page.assert_async().await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let csv = String::from_utf8(body.to_vec()).unwrap();
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.contains("new-uid,new@test.com"));
+        assert!(!csv.contains("used-uid"));
+    }
+
+    #[tokio::test]
+    async fn test_get_all_users_lists_every_account_of_the_project() {
+        let mut server = mockito::Server::new_async().await;
+        let batch_get = server
+            .mock("GET", "/projects/test-project/accounts:batchGet")
+            .match_header("authorization", "Bearer cached-admin-token")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(
+                r#"{"users": [
+                    {"localId": "admin-uid", "email": "admin-uid@test.com"},
+                    {"localId": "student-uid", "email": "student-uid@test.com"}
+                ]}"#,
+            )
+            .create_async()
+            .await;
+        // La cuenta del solicitante no se usa para listar el directorio
+        let lookup = server
+            .mock("POST", "/accounts:lookup")
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let state = state_with_identity_toolkit(server.url()).await;
+
+        let response = get_all_users(
+            Extension(claims_for("admin-uid")),
+            State(state),
+            ValidatedQuery(UserDirectoryQuery::default()),
+        )
+        .await
+        .into_response();
+
+        batch_get.assert_async().await;
+        lookup.assert_async().await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_of(response).await;
+        let uids: HashSet<&str> = body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|user| user["local_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(uids, HashSet::from(["admin-uid", "student-uid"]));
+    }
+
+    #[tokio::test]
+    async fn test_get_all_users_uses_admin_view() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/projects/test-project/accounts:batchGet")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(
+                r#"{"users": [{
+                    "localId": "student-uid",
+                    "email": "student@test.com",
+                    "passwordHash": "secret-hash",
+                    "passwordUpdatedAt": 1700000000000,
+                    "validSince": "1700000000",
+                    "providerUserInfo": [{"providerId": "password", "rawId": "student@test.com"}]
+                }]}"#,
+            )
+            .create_async()
+            .await;
+        let state = state_with_identity_toolkit(server.url()).await;
+
+        let response = get_all_users(
+            Extension(claims_for("admin-uid")),
+            State(state),
+            ValidatedQuery(UserDirectoryQuery::default()),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_of(response).await;
+        let user = &body["data"][0];
+        assert_eq!(user["email"], "student@test.com");
+        for hidden in [
+            "password_hash",
+            "password_updated_at",
+            "valid_since",
+            "provider_user_info",
+        ] {
+            assert!(user.get(hidden).is_none(), "{} expuesto", hidden);
+        }
+    }
+
+    /// Estado con Identity Toolkit y `tokeninfo` en el servidor mock, credenciales de
+    /// administración en caché y la cuenta `student-uid` con los proveedores indicados
+    async fn state_with_providers(server: &mut mockito::ServerGuard, providers: &str) -> AppState {
+        server
+            .mock("POST", "/accounts:lookup")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(format!(
+                r#"{{"users": [{{"localId": "student-uid", "email": "student-uid@test.com",
+                    "passwordHash": "secret-hash", "providerUserInfo": {}}}]}}"#,
+                providers
+            ))
+            .create_async()
+            .await;
+
+        let mut state = create_mock_app_state(HashMap::new()).await;
+        state.firebase_options.identity_toolkit_url = server.url();
+        state.firebase_options.google_client_id = Some("web-client.apps.test".to_string());
+        state.firebase_options.google_tokeninfo_url = format!("{}/tokeninfo", server.url());
+        state.firebase_options.service_account = Some(ServiceAccount {
+            client_email: "admin@test-project.iam.gserviceaccount.com".to_string(),
+            private_key: "unused-while-cached".to_string(),
+        });
+        *state.firebase_options.admin_token_cache.lock().await = Some(CachedAccessToken::new(
+            "cached-admin-token".to_string(),
+            3600,
+        ));
+        state
+    }
+
+    #[tokio::test]
+    async fn test_link_google_provider_links_verified_identity_as_admin() {
+        let mut server = mockito::Server::new_async().await;
+        let state = state_with_providers(&mut server, r#"[{"providerId": "password"}]"#).await;
+        server
+            .mock("GET", "/tokeninfo")
+            .match_query(Matcher::UrlEncoded(
+                "id_token".into(),
+                "google-token".into(),
+            ))
+            .with_status(200)
+            .with_body(
+                r#"{"sub": "google-123", "aud": "web-client.apps.test",
+                    "email": "student@gmail.com", "email_verified": "true"}"#,
+            )
+            .create_async()
+            .await;
+        let link = server
+            .mock("POST", "/projects/test-project/accounts:update")
+            .match_header("authorization", "Bearer cached-admin-token")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "localId": "student-uid",
+                "linkProviderUserInfo": {"providerId": "google.com", "rawId": "google-123"}
+            })))
+            .with_status(200)
+            .with_body(r#"{"localId": "student-uid"}"#)
+            .create_async()
+            .await;
+
+        let response = link_provider(
+            Extension(claims_for("student-uid")),
+            Extension("id-token".to_string()),
+            State(Arc::new(state)),
+            axum::Json(LinkProviderRequest::Google {
+                id_token: "google-token".to_string(),
+            }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        link.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_link_google_provider_rejects_token_for_another_client() {
+        let mut server = mockito::Server::new_async().await;
+        let state = state_with_providers(&mut server, r#"[{"providerId": "password"}]"#).await;
+        server
+            .mock("GET", "/tokeninfo")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(r#"{"sub": "google-123", "aud": "other-app.apps.test"}"#)
+            .create_async()
+            .await;
+        let link = server
+            .mock("POST", "/projects/test-project/accounts:update")
+            .expect(0)
+            .create_async()
+            .await;
+
+        let response = link_provider(
+            Extension(claims_for("student-uid")),
+            Extension("id-token".to_string()),
+            State(Arc::new(state)),
+            axum::Json(LinkProviderRequest::Google {
+                id_token: "google-token".to_string(),
+            }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        link.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_link_password_when_already_linked_is_conflict() {
+        let mut server = mockito::Server::new_async().await;
+        let state = state_with_providers(&mut server, r#"[{"providerId": "password"}]"#).await;
+
+        let response = link_provider(
+            Extension(claims_for("student-uid")),
+            Extension("id-token".to_string()),
+            State(Arc::new(state)),
+            axum::Json(LinkProviderRequest::Email {
+                password: "secret123".to_string(),
+            }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_unlink_provider_keeps_at_least_one_sign_in_method() {
+        let mut server = mockito::Server::new_async().await;
+        let state = state_with_providers(&mut server, r#"[{"providerId": "google.com"}]"#).await;
+
+        let response = unlink_provider(
+            Extension(claims_for("student-uid")),
+            Extension("id-token".to_string()),
+            State(Arc::new(state)),
+            Path(Provider::Google),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_unlink_provider_returns_remaining_providers() {
+        let mut server = mockito::Server::new_async().await;
+        let state = state_with_providers(
+            &mut server,
+            r#"[{"providerId": "password"}, {"providerId": "google.com"}]"#,
+        )
+        .await;
+        let update = server
+            .mock("POST", "/accounts:update")
+            .match_query(Matcher::Any)
+            .match_body(Matcher::Json(serde_json::json!({
+                "idToken": "id-token",
+                "deleteProvider": ["google.com"]
+            })))
+            .with_status(200)
+            .with_body(
+                r#"{"localId": "student-uid", "providerUserInfo": [{"providerId": "password"}]}"#,
+            )
+            .create_async()
+            .await;
+
+        let response = unlink_provider(
+            Extension(claims_for("student-uid")),
+            Extension("id-token".to_string()),
+            State(Arc::new(state)),
+            Path(Provider::Google),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        update.assert_async().await;
+        let body = json_of(response).await;
+        assert_eq!(body["data"]["providers"][0]["providerId"], "password");
+        assert_eq!(body["data"]["providers"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_user_me_merges_profile_with_linked_providers() {
+        let mut server = mockito::Server::new_async().await;
+        let state = state_with_providers(
+            &mut server,
+            r#"[{"providerId": "password"}, {"providerId": "google.com"}]"#,
+        )
+        .await;
+        let profile = UserDB {
+            email: "student-uid@test.com".to_string(),
+            role: Some("student".to_string()),
+            ..Default::default()
+        };
+        state
+            .repositories
+            .users
+            .put("student-uid", &profile)
+            .await
+            .unwrap();
+
+        let response = get_user_me(
+            State(Arc::new(state)),
+            Extension(claims_for("student-uid")),
+            Extension("id-token".to_string()),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_of(response).await;
+        assert_eq!(body["data"]["role"], "student");
+        assert_eq!(
+            body["data"]["provider_user_info"][1]["providerId"],
+            "google.com"
+        );
+        assert!(body["data"].get("password_hash").is_none());
+    }
+
+    fn survey_answer(id: &str, answer: &str) -> Question {
+        Question {
+            id: id.to_string(),
+            label: id.to_string(),
+            question_type: QuestionType::Text,
+            options: None,
+            required: false,
+            answer: Some(answer.to_string()),
+        }
+    }
+
+    fn placement_survey(id: &str, email: &str, submitted_at: &str, level: &str) -> Survey {
+        Survey {
+            id: id.to_string(),
+            title: "Group Class Survey".to_string(),
+            description: String::new(),
+            user_email: email.to_string(),
+            submitted_at: Some(submitted_at.to_string()),
+            questions: vec![
+                survey_answer("native-language", "Arabic"),
+                survey_answer("spanish-level", level),
+                survey_answer("focus", "speaking"),
+                survey_answer("extra-notes", "Quiero hablar con fluidez"),
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_learner_profile_keeps_rest_of_user() {
+        let state = state_with_user(
+            "student-uid",
+            UserDB {
+                email: "student-uid@test.com".to_string(),
+                role: Some("student".to_string()),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let response = update_my_learner_profile(
+            Extension(claims_for("student-uid")),
+            State(state.clone()),
+            ValidatedJson(LearnerProfile {
+                spanish_level: Some("A2".to_string()),
+                timezone: Some("America/Mexico_City".to_string()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_of(response).await;
+        assert_eq!(body["data"]["spanish_level"], "A2");
+        assert!(body["data"]["updated_at"].is_string());
+
+        let user = state
+            .repositories
+            .users
+            .get("student-uid")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.role.as_deref(), Some("student"));
+        assert_eq!(
+            user.learner_profile.unwrap().timezone.as_deref(),
+            Some("America/Mexico_City")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prefill_learner_profile_from_latest_survey() {
+        let state = state_with_user(
+            "student-uid",
+            UserDB {
+                email: "student-uid@test.com".to_string(),
+                learner_profile: Some(LearnerProfile {
+                    native_language: Some("French".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        )
+        .await;
+        for survey in [
+            placement_survey("old", "student-uid@test.com", "2025-01-01T00:00:00Z", "A1"),
+            placement_survey("new", "STUDENT-UID@test.com", "2025-06-01T00:00:00Z", "b1"),
+            placement_survey("other", "other@test.com", "2025-09-01T00:00:00Z", "C1"),
+        ] {
+            state.repositories.surveys.put(&survey).await.unwrap();
+        }
+
+        let response = prefill_my_learner_profile(
+            Extension(claims_for("student-uid")),
+            State(state),
+            Query(LearnerProfilePrefillQuery::default()),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let profile = &json_of(response).await["data"];
+        // Lo que el alumno ya había rellenado no se sobrescribe
+        assert_eq!(profile["native_language"], "French");
+        assert_eq!(profile["spanish_level"], "B1");
+        assert_eq!(profile["focus_areas"], serde_json::json!(["speaking"]));
+        assert_eq!(profile["learning_goals"], "Quiero hablar con fluidez");
+    }
+
+    #[tokio::test]
+    async fn test_prefill_learner_profile_ignores_other_users_survey() {
+        let state = state_with_user(
+            "student-uid",
+            UserDB {
+                email: "student-uid@test.com".to_string(),
+                ..Default::default()
+            },
+        )
+        .await;
+        state
+            .repositories
+            .surveys
+            .put(&placement_survey(
+                "other",
+                "other@test.com",
+                "2025-09-01T00:00:00Z",
+                "C1",
+            ))
+            .await
+            .unwrap();
+
+        let response = prefill_my_learner_profile(
+            Extension(claims_for("student-uid")),
+            State(state),
+            Query(LearnerProfilePrefillQuery {
+                survey_id: Some("other".to_string()),
+            }),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_prefill_learner_profile_uses_verified_token_email() {
+        // El email del perfil se ha cambiado al de otra persona con encuesta
+        let state = state_with_user(
+            "student-uid",
+            UserDB {
+                email: "other@test.com".to_string(),
+                ..Default::default()
+            },
+        )
+        .await;
+        for survey in [
+            placement_survey("own", "student-uid@test.com", "2025-01-01T00:00:00Z", "A1"),
+            placement_survey("other", "other@test.com", "2025-09-01T00:00:00Z", "C1"),
+        ] {
+            state.repositories.surveys.put(&survey).await.unwrap();
+        }
+
+        let response = prefill_my_learner_profile(
+            Extension(claims_for("student-uid")),
+            State(state.clone()),
+            Query(LearnerProfilePrefillQuery::default()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_of(response).await["data"]["spanish_level"], "A1");
+
+        // Sin email verificado no hay encuesta que usar
+        let unverified = UserAuthentication {
+            email_verified: Some(false),
+            ..claims_for("student-uid")
+        };
+        let response = prefill_my_learner_profile(
+            Extension(unverified),
+            State(state),
+            Query(LearnerProfilePrefillQuery::default()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -1,14 +1,21 @@
 /// Fixtures: datos de prueba reutilizables
 #[cfg(test)]
 pub mod fixtures {
+    use crate::config::RateLimitConfig;
     use crate::models::{
         cal::{BookingStatus, CalBookingPayload},
         metrics::ServiceAccount,
-        state::{AppState, CalOptions, CustomFirebase, GAOptions, KeyCache, MailchimpOptions},
+        rate_limit::RateLimiter,
+        state::{
+            AppState, CalOptions, CustomFirebase, DEFAULT_KEYS_MAX_AGE, GAOptions, KeyCache,
+            MailchimpOptions,
+        },
         user::{Provider, UserRequest},
     };
+    use crate::repositories::Repositories;
+    use crate::services::supervisor::TaskSupervisor;
     use resend_rs::Resend;
-    use std::{collections::HashMap, sync::Arc, time::SystemTime};
+    use std::{collections::HashMap, sync::Arc};
     use tokio::sync::RwLock;
 
     /// Crea un UserRequest válido para tests
@@ -55,17 +62,25 @@ pub mod fixtures {
                 booking_cache: Arc::new(RwLock::new(initial_cache)),
                 recent_changes: Arc::new(RwLock::new(vec![])),
                 team_id: Some("1234".to_string()),
+                polling_interval_secs: 600,
             },
             firebase_options: CustomFirebase {
-                firebase_keys: Arc::new(RwLock::new(KeyCache {
-                    keys: serde_json::json!({}),
-                    fetched_at: SystemTime::now(),
-                })),
+                firebase_keys: Arc::new(RwLock::new(KeyCache::new(
+                    serde_json::json!({}),
+                    DEFAULT_KEYS_MAX_AGE,
+                ))),
                 firebase_project_id: "test-project".to_string(),
                 firebase_api_key: "test-api-key".to_string(),
-                firebase_database_url: "https://test.firebaseio.com".to_string(),
-                firebase_database_secret: "test-secret".to_string(),
                 firebase_client: reqwest::Client::new(),
+                auth_emulator_host: None,
+                identity_toolkit_url: "https://identitytoolkit.googleapis.com/v1".to_string(),
+                secure_token_url: "https://securetoken.googleapis.com/v1".to_string(),
+                public_keys_url: "https://www.googleapis.com/robot/v1/metadata/x509/securetoken@system.gserviceaccount.com".to_string(),
+                service_account: None,
+                token_url: "https://oauth2.googleapis.com/token".to_string(),
+                admin_token_cache: Default::default(),
+                google_client_id: None,
+                google_tokeninfo_url: "https://oauth2.googleapis.com/tokeninfo".to_string(),
             },
             stripe_client: stripe::Client::new("sk_test_key"),
             resend_client: Resend::new("re_test_key"),
@@ -81,8 +96,15 @@ pub mod fixtures {
                     private_key: "test-key".to_string(),
                 },
                 base_url: "https://analyticsdata.googleapis.com/v1beta".to_string(),
+                token_url: "https://oauth2.googleapis.com/token".to_string(),
                 property_id: "test-property".to_string(),
+                token_cache: Default::default(),
             },
+            repositories: Repositories::sqlite_in_memory().unwrap(),
+            rate_limiter: RateLimiter::new(RateLimitConfig::default()),
+            tasks: TaskSupervisor::new(),
+            session_revocations: Default::default(),
+        booking_locks: Default::default(),
         }
     }
 
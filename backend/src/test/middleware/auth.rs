@@ -4,16 +4,17 @@ mod tests {
 
     use {
         crate::{
-            middleware::auth::{fetch_firebase_keys_internal, get_ga_token, verify_firebase_token},
+            middleware::auth::{get_ga_token, verify_firebase_token},
             models::{
-                error::AuthError,
+                error::{ApiError, AuthError, MetricsError},
                 firebase::UserAuthentication,
                 metrics::{ClaimsGA, ServiceAccount},
-                state::{AppState, KeyCache},
+                state::{AppState, DEFAULT_KEYS_MAX_AGE, KeyCache},
             },
+            services::firebase_keys::fetch_firebase_keys,
         },
         axum::http::StatusCode,
-        jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, encode},
+        jsonwebtoken::{Algorithm, EncodingKey, Header, encode},
         reqwest::Client as HttpClient,
         rsa::rand_core::OsRng,
         serde_json::{Value, json},
@@ -26,7 +27,6 @@ mod tests {
 
     struct TestRsaKeys {
         encoding_key: EncodingKey,
-        decoding_key: DecodingKey,
         kid: String,
         public_pem: String,
     }
@@ -78,9 +78,8 @@ mod tests {
 
         TestRsaKeys {
             encoding_key: EncodingKey::from_rsa_pem(private_pem.as_bytes()).unwrap(),
-            decoding_key: DecodingKey::from_rsa_pem(public_pem.as_bytes()).unwrap(),
             kid: "test-key-id".to_string(),
-            public_pem: public_pem,
+            public_pem,
         }
     }
 
@@ -272,18 +271,18 @@ mod tests {
         let firebase_keys = json!({"test-kid": "test-key"});
 
         // Cache recién creado (no expirado)
-        let fresh_cache = KeyCache {
-            keys: firebase_keys.clone(),
-            fetched_at: SystemTime::now(),
-        };
+        let fresh_cache = KeyCache::new(firebase_keys.clone(), DEFAULT_KEYS_MAX_AGE);
         assert!(!fresh_cache.is_expired());
 
         // Cache expirado (simulado con timestamp antiguo)
-        let expired_cache = KeyCache {
-            keys: firebase_keys,
-            fetched_at: SystemTime::now() - Duration::from_secs(3601),
-        };
+        let mut expired_cache = KeyCache::new(firebase_keys, DEFAULT_KEYS_MAX_AGE);
+        expired_cache.fetched_at = SystemTime::now() - Duration::from_secs(3601);
         assert!(expired_cache.is_expired());
+
+        // Sin claves (arranque degradado) se considera vencido
+        let empty_cache = KeyCache::empty();
+        assert!(empty_cache.is_empty());
+        assert!(empty_cache.is_expired());
     }
 
     // ========== TESTS DE GOOGLE ANALYTICS TOKEN ==========
@@ -333,15 +332,22 @@ mod tests {
         (
             Arc::new(AppState {
                 firebase_options: crate::models::state::CustomFirebase {
-                    firebase_keys: Arc::new(RwLock::new(KeyCache {
-                        keys: json!({"test-kid": "test-key"}),
-                        fetched_at: SystemTime::now(),
-                    })),
+                    firebase_keys: Arc::new(RwLock::new(KeyCache::new(
+                        json!({"test-kid": "test-key"}),
+                        DEFAULT_KEYS_MAX_AGE,
+                    ))),
                     firebase_project_id: "amanahacademia".to_string(),
                     firebase_api_key: "test-api-key".to_string(),
-                    firebase_database_url: "https://test.firebaseio.com".to_string(),
-                    firebase_database_secret: "test-secret".to_string(),
                     firebase_client: HttpClient::new(),
+                    auth_emulator_host: None,
+                    identity_toolkit_url: "https://identitytoolkit.googleapis.com/v1".to_string(),
+                    secure_token_url: "https://securetoken.googleapis.com/v1".to_string(),
+                    public_keys_url: "https://www.googleapis.com/robot/v1/metadata/x509/securetoken@system.gserviceaccount.com".to_string(),
+                    service_account: None,
+                    token_url: "https://oauth2.googleapis.com/token".to_string(),
+                    admin_token_cache: Default::default(),
+                    google_client_id: None,
+                    google_tokeninfo_url: "https://oauth2.googleapis.com/tokeninfo".to_string(),
                 },
                 ga_options: crate::models::state::GAOptions {
                     client: HttpClient::new(),
@@ -350,7 +356,9 @@ mod tests {
                         private_key: token_rsa.public_pem.clone(),
                     },
                     base_url: "https://www.google-analytics.com/".to_string(),
+                    token_url: "https://oauth2.googleapis.com/token".to_string(),
                     property_id: "properties/123456".to_string(),
+                    token_cache: Default::default(),
                 },
                 stripe_client: stripe::Client::new("sk_test_fake"),
                 resend_client: resend_rs::Resend::new("re_test_fake"),
@@ -366,7 +374,15 @@ mod tests {
                     booking_cache: Arc::new(RwLock::new(std::collections::HashMap::new())),
                     recent_changes: Arc::new(RwLock::new(Vec::new())),
                     team_id: Some("1234".to_string()),
+                    polling_interval_secs: 600,
                 },
+                repositories: crate::repositories::Repositories::sqlite_in_memory().unwrap(),
+                rate_limiter: crate::models::rate_limit::RateLimiter::new(
+                    crate::config::RateLimitConfig::default(),
+                ),
+                tasks: crate::services::supervisor::TaskSupervisor::new(),
+                session_revocations: Default::default(),
+                booking_locks: Default::default(),
             }),
             token_rsa,
         )
@@ -390,15 +406,16 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_fetch_firebase_keys_internal_executes() {
+    async fn test_fetch_firebase_keys_executes() {
         let client = HttpClient::new();
 
-        // Este test ejecuta fetch_firebase_keys_internal que cubre:
-        // - Línea 155-159: construcción y envío de request HTTP
-        // - Línea 161-163: validación de respuesta
-        // - Línea 165: parsing de JSON
-        // - Línea 167-169: validación de keys
-        let result = fetch_firebase_keys_internal(&client).await;
+        // Este test ejecuta fetch_firebase_keys contra Google: petición HTTP,
+        // validación de la respuesta, lectura de max-age y validación de keys
+        let result = fetch_firebase_keys(
+            &client,
+            "https://www.googleapis.com/robot/v1/metadata/x509/securetoken@system.gserviceaccount.com",
+        )
+        .await;
 
         // La función se ejecuta correctamente (puede tener éxito o fallar dependiendo de red)
         // Lo importante es que el código se ejecutó
@@ -675,15 +692,15 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
-    // ========== TESTS DE get_or_refresh_keys ==========
+    // ========== TESTS DE current_firebase_keys ==========
 
-    /// Test: get_or_refresh_keys con cache fresco
+    /// Test: current_firebase_keys con cache fresco
     #[tokio::test]
-    async fn test_get_or_refresh_keys_cache_fresh() {
-        use crate::middleware::auth::get_or_refresh_keys;
+    async fn test_current_firebase_keys_cache_fresh() {
+        use crate::middleware::auth::current_firebase_keys;
 
         let (state, _test_rsa) = create_mock_app_state();
-        let result = get_or_refresh_keys(&state).await;
+        let result = current_firebase_keys(&state).await;
 
         assert!(result.is_ok());
         let keys = result.unwrap();
@@ -691,10 +708,10 @@ mod tests {
         assert_eq!(keys.get("test-kid").unwrap(), "test-key");
     }
 
-    /// Test: get_or_refresh_keys con cache expirado
+    /// Test: con el cache vencido se siguen sirviendo las claves sin bloquear la petición
     #[tokio::test]
-    async fn test_get_or_refresh_keys_cache_expired() {
-        use crate::middleware::auth::get_or_refresh_keys;
+    async fn test_current_firebase_keys_serves_stale_keys() {
+        use crate::middleware::auth::current_firebase_keys;
 
         let (state, _) = create_mock_app_state();
 
@@ -705,18 +722,14 @@ mod tests {
             cache.fetched_at = SystemTime::now() - Duration::from_secs(3601);
         }
 
-        // Intentar refrescar (fallará sin servidor real, pero cubre el código)
-        let result = get_or_refresh_keys(&state).await;
-
-        // Puede fallar porque no hay servidor de Firebase real,
-        // pero el código de refresh se ejecutó (líneas 135-148)
-        assert!(result.is_ok() || result.is_err());
+        let keys = current_firebase_keys(&state).await.unwrap();
+        assert_eq!(keys.get("test-kid").unwrap(), "test-key");
     }
 
-    /// Test: get_or_refresh_keys path de refresh exitoso
+    /// Test: current_firebase_keys no modifica un cache fresco
     #[tokio::test]
-    async fn test_get_or_refresh_keys_refresh_updates_cache() {
-        use crate::middleware::auth::get_or_refresh_keys;
+    async fn test_current_firebase_keys_does_not_touch_fresh_cache() {
+        use crate::middleware::auth::current_firebase_keys;
 
         let (state, _) = create_mock_app_state();
 
@@ -727,7 +740,7 @@ mod tests {
         };
 
         // El cache está fresco, no debería refrescar
-        let result = get_or_refresh_keys(&state).await;
+        let result = current_firebase_keys(&state).await;
         assert!(result.is_ok());
 
         // Las keys deben ser las mismas
@@ -774,4 +787,340 @@ mod tests {
             AuthError::InvalidHeaderFormat
         ));
     }
+
+    // ========== TESTS DEL FIREBASE AUTH EMULATOR ==========
+
+    /// Crea un token sin firmar como los que emite el Firebase Auth Emulator
+    fn create_emulator_token(project_id: &str, user_id: &str, exp_offset: i64) -> String {
+        use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+
+        let now: i64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let claims: Value = json!({
+            "sub": user_id,
+            "iss": format!("https://securetoken.google.com/{}", project_id),
+            "aud": project_id,
+            "iat": now,
+            "exp": now + exp_offset,
+            "auth_time": now,
+            "user_id": user_id,
+            "email": "emulator@example.com",
+            "email_verified": false,
+        });
+
+        format!(
+            "{}.{}.",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"none","typ":"JWT"}"#),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        )
+    }
+
+    #[test]
+    fn test_verify_emulator_token_success() {
+        use crate::middleware::auth::verify_emulator_token;
+
+        let token: String = create_emulator_token("demo-project", "emulator-user", 3600);
+
+        let claims = verify_emulator_token(&token, "demo-project").unwrap();
+        assert_eq!(claims.user_id, "emulator-user");
+        assert_eq!(claims.email, Some("emulator@example.com".to_string()));
+    }
+
+    #[test]
+    fn test_verify_emulator_token_wrong_project() {
+        use crate::middleware::auth::verify_emulator_token;
+
+        let token: String = create_emulator_token("other-project", "emulator-user", 3600);
+
+        let result = verify_emulator_token(&token, "demo-project");
+        assert!(matches!(result, Err(AuthError::TokenVerification(_))));
+    }
+
+    #[test]
+    fn test_verify_emulator_token_expired() {
+        use crate::middleware::auth::verify_emulator_token;
+
+        let token: String = create_emulator_token("demo-project", "emulator-user", -60);
+
+        let result = verify_emulator_token(&token, "demo-project");
+        assert!(matches!(result, Err(AuthError::TokenVerification(_))));
+    }
+
+    /// Test: en modo emulador el middleware acepta tokens sin firmar
+    #[tokio::test]
+    async fn test_firebase_auth_middleware_emulator_token() {
+        let (state, _) = create_mock_app_state();
+        let mut state: AppState = Arc::try_unwrap(state).ok().unwrap();
+        state.firebase_options.auth_emulator_host = Some("localhost:9099".to_string());
+        let state: Arc<AppState> = Arc::new(state);
+
+        let token: String = create_emulator_token("amanahacademia", "emulator-user", 3600);
+
+        async fn handler(req: Request<Body>) -> &'static str {
+            if req.extensions().get::<UserAuthentication>().is_some() {
+                "authenticated"
+            } else {
+                "not authenticated"
+            }
+        }
+
+        let app = Router::new()
+            .route("/protected", get(handler))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                crate::middleware::auth::firebase_auth_middleware,
+            ))
+            .with_state(state);
+
+        let request = Request::builder()
+            .uri("/protected")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    /// Test: tras cerrar todas las sesiones se rechazan los tokens emitidos antes
+    #[tokio::test]
+    async fn test_firebase_auth_middleware_rejects_revoked_session() {
+        let (state, _) = create_mock_app_state();
+        let mut state: AppState = Arc::try_unwrap(state).ok().unwrap();
+        state.firebase_options.auth_emulator_host = Some("localhost:9099".to_string());
+        let state: Arc<AppState> = Arc::new(state);
+
+        let now: i64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        state
+            .repositories
+            .sessions
+            .revoke_all("emulator-user", now + 60)
+            .await
+            .unwrap();
+
+        let token: String = create_emulator_token("amanahacademia", "emulator-user", 3600);
+
+        let app = Router::new()
+            .route("/protected", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                crate::middleware::auth::firebase_auth_middleware,
+            ))
+            .with_state(state);
+
+        let request = Request::builder()
+            .uri("/protected")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    /// Test: un kid desconocido fuerza un refresco de claves y el token se acepta
+    #[tokio::test]
+    async fn test_firebase_auth_middleware_refreshes_on_unknown_kid() {
+        let (state, test_rsa) = create_mock_app_state();
+
+        // Google ya ha rotado: el endpoint devuelve la clave con la que se firmó el token
+        let mut server = mockito::Server::new_async().await;
+        let keys_mock = server
+            .mock("GET", "/keys")
+            .with_status(200)
+            .with_header("cache-control", "public, max-age=600")
+            .with_body(json!({ test_rsa.kid.clone(): test_rsa.public_pem.clone() }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut state: AppState = Arc::try_unwrap(state).ok().unwrap();
+        state.firebase_options.public_keys_url = format!("{}/keys", server.url());
+        let state: Arc<AppState> = Arc::new(state);
+
+        let token: String = create_valid_test_token(
+            "amanahacademia",
+            "rotated-user",
+            &test_rsa.encoding_key,
+            &test_rsa.kid,
+        );
+
+        let app = Router::new()
+            .route("/protected", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                crate::middleware::auth::firebase_auth_middleware,
+            ))
+            .with_state(state.clone());
+
+        let request = Request::builder()
+            .uri("/protected")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        keys_mock.assert_async().await;
+
+        let cache = state.firebase_options.firebase_keys.read().await;
+        assert_eq!(cache.max_age, Duration::from_secs(600));
+    }
+
+    /// Test: sin claves (arranque degradado) y sin poder descargarlas se responde 503
+    #[tokio::test]
+    async fn test_firebase_auth_middleware_degraded_without_keys() {
+        let (state, test_rsa) = create_mock_app_state();
+
+        let mut server = mockito::Server::new_async().await;
+        let _keys_mock = server
+            .mock("GET", "/keys")
+            .with_status(503)
+            .create_async()
+            .await;
+
+        let mut state: AppState = Arc::try_unwrap(state).ok().unwrap();
+        state.firebase_options.public_keys_url = format!("{}/keys", server.url());
+        state.firebase_options.firebase_keys = Arc::new(RwLock::new(KeyCache::empty()));
+        let state: Arc<AppState> = Arc::new(state);
+
+        let token: String = create_valid_test_token(
+            "amanahacademia",
+            "some-user",
+            &test_rsa.encoding_key,
+            &test_rsa.kid,
+        );
+
+        let app = Router::new()
+            .route("/protected", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                crate::middleware::auth::firebase_auth_middleware,
+            ))
+            .with_state(state);
+
+        let request = Request::builder()
+            .uri("/protected")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    // ========== TESTS DE CACHÉ DEL TOKEN DE GOOGLE ANALYTICS ==========
+
+    /// AppState con una cuenta de servicio firmable y el endpoint de tokens apuntando al mock
+    fn create_ga_state(token_url: String) -> Arc<AppState> {
+        let (state, _) = create_mock_app_state();
+        let mut state: AppState = Arc::try_unwrap(state).ok().unwrap();
+
+        let private_key = RsaPrivateKey::new(&mut OsRng, 2048).unwrap();
+        state.ga_options.service_account.private_key = private_key
+            .to_pkcs8_pem(Default::default())
+            .unwrap()
+            .to_string();
+        state.ga_options.token_url = token_url;
+        Arc::new(state)
+    }
+
+    async fn token_server(
+        expires_in: u64,
+        expected_hits: usize,
+    ) -> (mockito::ServerGuard, mockito::Mock) {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/token")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "access_token": "ga-access-token",
+                    "expires_in": expires_in,
+                    "token_type": "Bearer"
+                })
+                .to_string(),
+            )
+            .expect(expected_hits)
+            .create_async()
+            .await;
+        (server, mock)
+    }
+
+    #[tokio::test]
+    async fn test_get_ga_token_reuses_cached_token() {
+        let (server, mock) = token_server(3599, 1).await;
+        let state = create_ga_state(format!("{}/token", server.url()));
+
+        let first = get_ga_token(&state).await.unwrap();
+        let second = get_ga_token(&state).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(first, "ga-access-token");
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_get_ga_token_deduplicates_concurrent_refreshes() {
+        let (server, mock) = token_server(3599, 1).await;
+        let state = create_ga_state(format!("{}/token", server.url()));
+
+        let (a, b, c, d) = tokio::join!(
+            get_ga_token(&state),
+            get_ga_token(&state),
+            get_ga_token(&state),
+            get_ga_token(&state)
+        );
+
+        mock.assert_async().await;
+        for token in [a, b, c, d] {
+            assert_eq!(token.unwrap(), "ga-access-token");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_ga_token_refreshes_when_close_to_expiry() {
+        // Un token que caduca dentro del margen de renovación no se reutiliza
+        let (server, mock) = token_server(30, 2).await;
+        let state = create_ga_state(format!("{}/token", server.url()));
+
+        get_ga_token(&state).await.unwrap();
+        get_ga_token(&state).await.unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_ga_token_rejected_is_typed_error() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/token")
+            .with_status(401)
+            .with_body(r#"{"error":"invalid_grant"}"#)
+            .create_async()
+            .await;
+        let state = create_ga_state(format!("{}/token", server.url()));
+
+        let err = get_ga_token(&state).await.unwrap_err();
+        assert!(matches!(
+            err,
+            MetricsError::Token { status } if status == StatusCode::UNAUTHORIZED
+        ));
+
+        // El cuerpo del proveedor no llega al cliente
+        let api_error = ApiError::from(err);
+        assert!(!api_error.public_message().contains("invalid_grant"));
+        assert_eq!(api_error.code(), "metrics_auth_failed");
+        assert_eq!(api_error.status(), StatusCode::BAD_GATEWAY);
+
+        // Un fallo no deja nada en la caché
+        assert!(state.ga_options.token_cache.lock().await.is_none());
+    }
 }
@@ -0,0 +1,235 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            middleware::authorization::{
+                RequirePermission, RequireRole, require_authorization, require_verified_email,
+            },
+            models::{
+                firebase::UserAuthentication,
+                state::AppState,
+                user::{Permission, Role, UserDB},
+            },
+            test_fixtures::fixtures::create_mock_app_state,
+        },
+        axum::{
+            Extension, Router,
+            body::Body,
+            http::{Request, StatusCode},
+            middleware,
+            routing::get,
+        },
+        std::{
+            collections::{HashMap, HashSet},
+            sync::Arc,
+        },
+        tower::ServiceExt,
+    };
+
+    // ========== FIXTURES Y HELPERS ==========
+    fn claims_for(user_id: &str) -> UserAuthentication {
+        UserAuthentication {
+            sub: user_id.to_string(),
+            iss: "https://securetoken.google.com/test-project".to_string(),
+            aud: "test-project".to_string(),
+            iat: 0,
+            exp: i64::MAX,
+            email: None,
+            email_verified: None,
+            name: None,
+            picture: None,
+            auth_time: 0,
+            user_id: user_id.to_string(),
+            firebase: None,
+            phone_number: None,
+            provider_id: None,
+        }
+    }
+
+    fn user_with(role: &str, permissions: &[&str]) -> UserDB {
+        UserDB {
+            email: format!("{}@test.com", role),
+            first_free_class: false,
+            role: Some(role.to_string()),
+            subscription_tier: None,
+            permissions: Some(
+                permissions
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<HashSet<_>>(),
+            ),
+            learner_profile: None,
+        }
+    }
+
+    async fn state_with_user(uid: &str, user: UserDB) -> Arc<AppState> {
+        let state = Arc::new(create_mock_app_state(HashMap::new()).await);
+        state.repositories.users.put(uid, &user).await.unwrap();
+        state
+    }
+
+    /// Router con la capa de autorización y, opcionalmente, los claims que dejaría la autenticación
+    fn guarded_app<A>(
+        state: Arc<AppState>,
+        requirement: A,
+        claims: Option<UserAuthentication>,
+    ) -> Router
+    where
+        A: crate::middleware::authorization::Authorization + std::fmt::Debug,
+    {
+        let router = Router::new()
+            .route("/guarded", get(|| async { "ok" }))
+            .route_layer(middleware::from_fn_with_state(
+                (state, requirement),
+                require_authorization::<A>,
+            ));
+
+        match claims {
+            Some(claims) => router.layer(Extension(claims)),
+            None => router,
+        }
+    }
+
+    async fn status_of(app: Router) -> StatusCode {
+        let request = Request::builder()
+            .uri("/guarded")
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    // ========== TESTS ==========
+    #[tokio::test]
+    async fn test_require_role_admin_allows_admin() {
+        let state = state_with_user("admin-uid", user_with("admin", &[])).await;
+        let app = guarded_app(
+            state,
+            RequireRole(Role::Admin),
+            Some(claims_for("admin-uid")),
+        );
+
+        assert_eq!(status_of(app).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_require_role_admin_rejects_student() {
+        let state = state_with_user("student-uid", user_with("student", &[])).await;
+        let app = guarded_app(
+            state,
+            RequireRole(Role::Admin),
+            Some(claims_for("student-uid")),
+        );
+
+        assert_eq!(status_of(app).await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_require_permission_allows_granted_permission() {
+        let state =
+            state_with_user("teacher-uid", user_with("teacher", &["manage_teachers"])).await;
+        let app = guarded_app(
+            state,
+            RequirePermission(Permission::ManageTeachers),
+            Some(claims_for("teacher-uid")),
+        );
+
+        assert_eq!(status_of(app).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_require_permission_rejects_missing_permission() {
+        let state = state_with_user("teacher-uid", user_with("teacher", &["read_surveys"])).await;
+        let app = guarded_app(
+            state,
+            RequirePermission(Permission::ManageProducts),
+            Some(claims_for("teacher-uid")),
+        );
+
+        assert_eq!(status_of(app).await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_require_permission_allows_admin_implicitly() {
+        let state = state_with_user("admin-uid", user_with("admin", &[])).await;
+        let app = guarded_app(
+            state,
+            RequirePermission(Permission::ReadSurveys),
+            Some(claims_for("admin-uid")),
+        );
+
+        assert_eq!(status_of(app).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_require_authorization_rejects_unknown_user() {
+        let state = Arc::new(create_mock_app_state(HashMap::new()).await);
+        let app = guarded_app(
+            state,
+            RequireRole(Role::Admin),
+            Some(claims_for("ghost-uid")),
+        );
+
+        assert_eq!(status_of(app).await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_require_authorization_without_claims_is_unauthorized() {
+        let state = state_with_user("admin-uid", user_with("admin", &[])).await;
+        let app = guarded_app(state, RequireRole(Role::Admin), None);
+
+        assert_eq!(status_of(app).await, StatusCode::UNAUTHORIZED);
+    }
+
+    fn verified_app(claims: UserAuthentication) -> Router {
+        Router::new()
+            .route("/guarded", get(|| async { "ok" }))
+            .route_layer(middleware::from_fn_with_state(
+                "booking",
+                require_verified_email,
+            ))
+            .layer(Extension(claims))
+    }
+
+    #[tokio::test]
+    async fn test_require_verified_email_allows_verified_user() {
+        let claims = UserAuthentication {
+            email_verified: Some(true),
+            ..claims_for("student-uid")
+        };
+
+        assert_eq!(status_of(verified_app(claims)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_require_verified_email_rejects_unverified_user() {
+        let claims = UserAuthentication {
+            email_verified: Some(false),
+            ..claims_for("student-uid")
+        };
+        let response = verified_app(claims)
+            .oneshot(
+                Request::builder()
+                    .uri("/guarded")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "email_not_verified");
+    }
+
+    #[tokio::test]
+    async fn test_require_verified_email_treats_missing_claim_as_unverified() {
+        // Tokens sin el claim (p. ej. cuentas sin email) no pueden reservar
+        assert_eq!(
+            status_of(verified_app(claims_for("student-uid"))).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+}
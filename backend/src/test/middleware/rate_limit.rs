@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            config::{RateLimitBudget, RateLimitConfig},
+            middleware::rate_limit::rate_limit,
+            models::{
+                firebase::UserAuthentication,
+                rate_limit::{RateLimitGroup, RateLimiter},
+                state::AppState,
+            },
+            test_fixtures::fixtures::create_mock_app_state,
+        },
+        axum::{
+            Extension, Router,
+            body::{Body, to_bytes},
+            http::{Request, StatusCode, header::RETRY_AFTER},
+            middleware,
+            routing::post,
+        },
+        serde_json::Value,
+        std::{collections::HashMap, sync::Arc},
+        tower::ServiceExt,
+    };
+
+    // ========== FIXTURES Y HELPERS ==========
+    async fn state_with_config(config: RateLimitConfig) -> Arc<AppState> {
+        let mut state = create_mock_app_state(HashMap::new()).await;
+        state.rate_limiter = RateLimiter::new(config);
+        Arc::new(state)
+    }
+
+    fn limited_app(state: Arc<AppState>) -> Router {
+        Router::new()
+            .route("/contact", post(|| async { "ok" }))
+            .route_layer(middleware::from_fn_with_state(
+                (state, RateLimitGroup::Contact),
+                rate_limit,
+            ))
+    }
+
+    fn request_from(ip: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/contact")
+            .header("Fly-Client-IP", ip)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn config(capacity: u32) -> RateLimitConfig {
+        RateLimitConfig {
+            client_ip_header: Some("Fly-Client-IP".to_string()),
+            contact: RateLimitBudget::new(capacity, 1),
+            ..RateLimitConfig::default()
+        }
+    }
+
+    // ========== TESTS ==========
+    #[tokio::test]
+    async fn test_rate_limit_returns_429_with_retry_after() {
+        let app = limited_app(state_with_config(config(2)).await);
+
+        for _ in 0..2 {
+            let response = app.clone().oneshot(request_from("1.1.1.1")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app.oneshot(request_from("1.1.1.1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // 1 ficha por minuto: hay que esperar como mucho 60 segundos
+        let retry_after: u64 = response.headers()[RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after > 0 && retry_after <= 60);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "rate_limited");
+        assert_eq!(json["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_is_per_client_ip() {
+        let app = limited_app(state_with_config(config(1)).await);
+
+        let first = app.clone().oneshot(request_from("1.1.1.1")).await.unwrap();
+        let second = app.clone().oneshot(request_from("1.1.1.1")).await.unwrap();
+        let other_ip = app.oneshot(request_from("2.2.2.2")).await.unwrap();
+
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(other_ip.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_ignores_ip_header_when_not_configured() {
+        let app = limited_app(
+            state_with_config(RateLimitConfig {
+                client_ip_header: None,
+                ..config(1)
+            })
+            .await,
+        );
+
+        // Sin cabecera de confianza, cambiarla no sirve para saltarse el límite
+        let first = app.clone().oneshot(request_from("1.1.1.1")).await.unwrap();
+        let spoofed = app.oneshot(request_from("2.2.2.2")).await.unwrap();
+
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(spoofed.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_uses_uid_for_authenticated_requests() {
+        let state = state_with_config(config(1)).await;
+        let claims: UserAuthentication = serde_json::from_value(serde_json::json!({
+            "sub": "user-1",
+            "iss": "https://securetoken.google.com/test-project",
+            "aud": "test-project",
+            "iat": 0,
+            "exp": i64::MAX,
+            "auth_time": 0,
+            "user_id": "user-1",
+        }))
+        .unwrap();
+        let app = limited_app(state).layer(Extension(claims));
+
+        // Mismo uid desde IPs distintas comparte presupuesto
+        let first = app.clone().oneshot(request_from("1.1.1.1")).await.unwrap();
+        let second = app.oneshot(request_from("2.2.2.2")).await.unwrap();
+
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
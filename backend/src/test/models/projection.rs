@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::models::{
+            firebase::{ProviderUserInfo, UserMerged},
+            projection::{UserAdminView, UserOwnerView},
+        },
+        serde_json::Value,
+    };
+
+    /// Usuario con todos los campos informados
+    fn full_user() -> UserMerged {
+        UserMerged {
+            local_id: "student-uid".to_string(),
+            first_free_class: true,
+            email: Some("student@test.com".to_string()),
+            email_verified: Some(true),
+            display_name: Some("Student".to_string()),
+            photo_url: Some("https://img.test/avatar.png".to_string()),
+            phone_number: Some("+34600000000".to_string()),
+            disabled: Some(false),
+            role: Some("student".to_string()),
+            subscription_tier: Some("premium".to_string()),
+            permissions: Some(vec!["comments:write".to_string()]),
+            provider_user_info: Some(vec![
+                serde_json::from_value::<ProviderUserInfo>(
+                    serde_json::json!({"providerId": "password"}),
+                )
+                .unwrap(),
+            ]),
+            password_hash: Some("secret-hash".to_string()),
+            password_updated_at: Some(1.7e12),
+            valid_since: Some("1700000000".to_string()),
+            last_login_at: Some("1700000000000".to_string()),
+            created_at: Some("1700000000000".to_string()),
+            custom_auth: Some(false),
+        }
+    }
+
+    fn keys(value: &Value) -> Vec<String> {
+        let mut keys: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_password_hash_never_serialized() {
+        let user = full_user();
+
+        assert!(
+            serde_json::to_value(&user)
+                .unwrap()
+                .get("password_hash")
+                .is_none()
+        );
+        let owner = serde_json::to_value(UserOwnerView::from(user.clone())).unwrap();
+        assert!(owner.get("password_hash").is_none());
+        let admin = serde_json::to_value(UserAdminView::from(user)).unwrap();
+        assert!(admin.get("password_hash").is_none());
+    }
+
+    #[test]
+    fn test_owner_view_fields() {
+        let owner = serde_json::to_value(UserOwnerView::from(full_user())).unwrap();
+
+        assert_eq!(
+            keys(&owner),
+            vec![
+                "created_at",
+                "display_name",
+                "email",
+                "email_verified",
+                "first_free_class",
+                "last_login_at",
+                "local_id",
+                "permissions",
+                "phone_number",
+                "photo_url",
+                "provider_user_info",
+                "role",
+                "subscription_tier",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_admin_view_fields() {
+        let admin = serde_json::to_value(UserAdminView::from(full_user())).unwrap();
+
+        assert_eq!(admin["disabled"], false);
+        assert_eq!(
+            keys(&admin),
+            vec![
+                "created_at",
+                "disabled",
+                "display_name",
+                "email",
+                "email_verified",
+                "first_free_class",
+                "last_login_at",
+                "local_id",
+                "permissions",
+                "phone_number",
+                "photo_url",
+                "role",
+                "subscription_tier",
+            ]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            config::{RateLimitBudget, RateLimitConfig},
+            models::rate_limit::{
+                InMemoryRateLimitStore, RateLimitDecision, RateLimitGroup, RateLimitStore,
+                RateLimiter, TokenBucket,
+            },
+        },
+        std::time::{Duration, Instant},
+    };
+
+    const BUDGET: RateLimitBudget = RateLimitBudget::new(2, 60);
+
+    #[test]
+    fn test_token_bucket_limits_after_capacity() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::full(BUDGET, now);
+
+        assert_eq!(
+            bucket.take(BUDGET, now),
+            RateLimitDecision::Allowed { remaining: 1 }
+        );
+        assert_eq!(
+            bucket.take(BUDGET, now),
+            RateLimitDecision::Allowed { remaining: 0 }
+        );
+        match bucket.take(BUDGET, now) {
+            RateLimitDecision::Limited { retry_after } => {
+                // 60 fichas/minuto = una ficha por segundo
+                assert_eq!(retry_after.as_secs(), 1);
+            }
+            other => panic!("Expected Limited, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_token_bucket_refills_over_time_up_to_capacity() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::full(BUDGET, start);
+        bucket.take(BUDGET, start);
+        bucket.take(BUDGET, start);
+
+        assert!(matches!(
+            bucket.take(BUDGET, start + Duration::from_secs(1)),
+            RateLimitDecision::Allowed { .. }
+        ));
+
+        // Tras mucho tiempo no se acumulan más fichas que la capacidad
+        let later = start + Duration::from_secs(3600);
+        assert_eq!(
+            bucket.take(BUDGET, later),
+            RateLimitDecision::Allowed { remaining: 1 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_keeps_keys_independent() {
+        let store = InMemoryRateLimitStore::new();
+
+        store.take("auth:ip:1.1.1.1", BUDGET).await;
+        store.take("auth:ip:1.1.1.1", BUDGET).await;
+
+        assert!(matches!(
+            store.take("auth:ip:1.1.1.1", BUDGET).await,
+            RateLimitDecision::Limited { .. }
+        ));
+        assert!(matches!(
+            store.take("auth:ip:2.2.2.2", BUDGET).await,
+            RateLimitDecision::Allowed { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_uses_separate_budgets_per_group() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            contact: RateLimitBudget::new(1, 1),
+            ..RateLimitConfig::default()
+        });
+
+        limiter.check(RateLimitGroup::Contact, "ip:1.1.1.1").await;
+        assert!(matches!(
+            limiter.check(RateLimitGroup::Contact, "ip:1.1.1.1").await,
+            RateLimitDecision::Limited { .. }
+        ));
+        assert!(matches!(
+            limiter.check(RateLimitGroup::Auth, "ip:1.1.1.1").await,
+            RateLimitDecision::Allowed { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_disabled_always_allows() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            enabled: false,
+            contact: RateLimitBudget::new(1, 1),
+            ..RateLimitConfig::default()
+        });
+
+        for _ in 0..5 {
+            assert!(matches!(
+                limiter.check(RateLimitGroup::Contact, "ip:1.1.1.1").await,
+                RateLimitDecision::Allowed { .. }
+            ));
+        }
+    }
+}
@@ -0,0 +1,188 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            models::{
+                comments::Comment,
+                error::{ApiError, RepositoryError},
+                user::UserDB,
+            },
+            repositories::{CommentRepository, UserRepository, firebase::FirebaseDatabase},
+        },
+        axum::{body::to_bytes, http::StatusCode, response::IntoResponse},
+        mockito::Matcher,
+    };
+
+    const COMMENT_JSON: &str =
+        r#"{"name":"Ana","timestamp":"01/01/2025 10:00","content":"Hola","stars":5.0,"like":0}"#;
+
+    fn create_database(url: &str) -> FirebaseDatabase {
+        FirebaseDatabase::new(
+            reqwest::Client::new(),
+            format!("{}/", url),
+            "test-secret".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_get_user_authenticates_with_secret() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/user_profiles/uid-1.json")
+            .match_query(Matcher::UrlEncoded("auth".into(), "test-secret".into()))
+            .with_status(200)
+            .with_body(r#"{"email":"a@example.com","first_free_class":true,"role":"student","subscription_tier":null,"permissions":null}"#)
+            .create_async()
+            .await;
+
+        let db = create_database(&server.url());
+        let user: UserDB = UserRepository::get(&db, "uid-1").await.unwrap().unwrap();
+
+        assert_eq!(user.email, "a@example.com");
+        assert!(user.first_free_class);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_null_node_is_none() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/comments/missing.json")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body("null")
+            .create_async()
+            .await;
+
+        let db = create_database(&server.url());
+        assert!(
+            CommentRepository::get(&db, "missing")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_by_email_builds_valid_query() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/user_profiles.json")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("orderBy".into(), "\"email\"".into()),
+                Matcher::UrlEncoded("equalTo".into(), "\"a@example.com\"".into()),
+            ]))
+            .with_status(200)
+            .with_body(r#"{"uid-1":{"email":"a@example.com","first_free_class":false,"role":null,"subscription_tier":null,"permissions":null}}"#)
+            .create_async()
+            .await;
+
+        let db = create_database(&server.url());
+        let (uid, _) = db.find_by_email("a@example.com").await.unwrap().unwrap();
+
+        assert_eq!(uid, "uid-1");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_find_by_email_escapes_quotes() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/user_profiles.json")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("orderBy".into(), "\"email\"".into()),
+                Matcher::UrlEncoded("equalTo".into(), r#""a\"b\\c@example.com""#.into()),
+            ]))
+            .with_status(200)
+            .with_body("null")
+            .create_async()
+            .await;
+
+        let db = create_database(&server.url());
+        let user = db.find_by_email(r#"a"b\c@example.com"#).await.unwrap();
+
+        assert!(user.is_none());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_error_status_is_propagated() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/user_profiles.json")
+            .match_query(Matcher::Any)
+            .with_status(401)
+            .with_body(r#"{"error":"Permission denied"}"#)
+            .create_async()
+            .await;
+
+        let db = create_database(&server.url());
+        match UserRepository::get_all(&db).await {
+            Err(RepositoryError::Status { status, message }) => {
+                assert_eq!(status, StatusCode::UNAUTHORIZED);
+                assert_eq!(message, "Permission denied");
+            }
+            other => panic!("Expected status error, got {:?}", other.map(|m| m.len())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_versioned_returns_etag() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/comments/c1.json")
+            .match_query(Matcher::Any)
+            .match_header("X-Firebase-ETag", "true")
+            .with_status(200)
+            .with_header("ETag", "etag-1")
+            .with_body(COMMENT_JSON)
+            .create_async()
+            .await;
+
+        let db = create_database(&server.url());
+        let (comment, version) = CommentRepository::get_versioned(&db, "c1")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(comment.name, "Ana");
+        assert_eq!(version, "etag-1");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_put_if_version_precondition_failed_is_conflict() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("PUT", "/comments/c1.json")
+            .match_query(Matcher::Any)
+            .match_header("if-match", "etag-1")
+            .with_status(412)
+            .with_body(r#"{"error":"etag mismatch"}"#)
+            .create_async()
+            .await;
+
+        let db = create_database(&server.url());
+        let comment: Comment = serde_json::from_str(COMMENT_JSON).unwrap();
+        let result = CommentRepository::put_if_version(&db, "c1", &comment, "etag-1").await;
+
+        assert!(matches!(result, Err(RepositoryError::Conflict)));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_network_error_does_not_expose_secret() {
+        // Puerto cerrado: la conexión falla antes de llegar a Firebase
+        let db = create_database("http://127.0.0.1:1");
+        let err = CommentRepository::get(&db, "c1").await.unwrap_err();
+        assert!(matches!(err, RepositoryError::Network(_)));
+        assert!(!err.to_string().contains("test-secret"));
+
+        let response = ApiError::from(err).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(!body.contains("test-secret"));
+        assert!(body.contains("database_error"));
+    }
+}
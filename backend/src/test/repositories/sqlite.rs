@@ -0,0 +1,243 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            models::{
+                comments::{Comment, ModerationStatus},
+                error::RepositoryError,
+                session::Session,
+                stripe::StripeRelation,
+                user::UserDB,
+            },
+            repositories::{
+                CalStripeRepository, CommentRepository, SessionRepository, UserRepository,
+                sqlite::SqliteDatabase,
+            },
+        },
+        std::{collections::HashSet, sync::Arc},
+    };
+
+    fn create_test_user(email: &str) -> UserDB {
+        UserDB {
+            email: email.to_string(),
+            first_free_class: false,
+            role: Some("student".to_string()),
+            subscription_tier: None,
+            permissions: Some(HashSet::new()),
+            learner_profile: None,
+        }
+    }
+
+    fn create_test_comment() -> Comment {
+        Comment {
+            author_uid: Some("user-1".to_string()),
+            name: "Test User".to_string(),
+            timestamp: "01/01/2025 10:00".to_string(),
+            content: "Great class".to_string(),
+            url_img: None,
+            stars: 5.0,
+            like: 0,
+            reply: Vec::new(),
+            users_liked: Vec::new(),
+            moderation_status: ModerationStatus::Approved,
+            moderation_reason: None,
+            verified_student: false,
+            booking_uid: None,
+            liked: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_put_get_and_delete() {
+        let db = SqliteDatabase::open_in_memory().unwrap();
+
+        UserRepository::put(&db, "uid-1", &create_test_user("a@example.com"))
+            .await
+            .unwrap();
+
+        let user = UserRepository::get(&db, "uid-1").await.unwrap().unwrap();
+        assert_eq!(user.email, "a@example.com");
+        assert_eq!(user.role.as_deref(), Some("student"));
+
+        UserRepository::delete(&db, "uid-1").await.unwrap();
+        assert!(UserRepository::get(&db, "uid-1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_user_find_by_email() {
+        let db = SqliteDatabase::open_in_memory().unwrap();
+        UserRepository::put(&db, "uid-1", &create_test_user("a@example.com"))
+            .await
+            .unwrap();
+        UserRepository::put(&db, "uid-2", &create_test_user("b@example.com"))
+            .await
+            .unwrap();
+
+        let (uid, user) = db.find_by_email("b@example.com").await.unwrap().unwrap();
+        assert_eq!(uid, "uid-2");
+        assert_eq!(user.email, "b@example.com");
+
+        assert!(
+            db.find_by_email("none@example.com")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_user_set_first_free_class_only_updates_field() {
+        let db = SqliteDatabase::open_in_memory().unwrap();
+        UserRepository::put(&db, "uid-1", &create_test_user("a@example.com"))
+            .await
+            .unwrap();
+
+        db.set_first_free_class("uid-1", true).await.unwrap();
+
+        let user = UserRepository::get(&db, "uid-1").await.unwrap().unwrap();
+        assert!(user.first_free_class);
+        assert_eq!(user.email, "a@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_comment_create_update_and_list() {
+        let db = SqliteDatabase::open_in_memory().unwrap();
+
+        let id = CommentRepository::create(&db, &create_test_comment())
+            .await
+            .unwrap();
+
+        let mut comment = CommentRepository::get(&db, &id).await.unwrap().unwrap();
+        comment.like = 3;
+        CommentRepository::put(&db, &id, &comment).await.unwrap();
+
+        let comments = CommentRepository::get_all(&db).await.unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[&id].like, 3);
+    }
+
+    #[tokio::test]
+    async fn test_comment_put_if_version_rejects_stale_version() {
+        let db = SqliteDatabase::open_in_memory().unwrap();
+        let id = CommentRepository::create(&db, &create_test_comment())
+            .await
+            .unwrap();
+
+        let (mut comment, version) = db.get_versioned(&id).await.unwrap().unwrap();
+        comment.like = 1;
+        db.put_if_version(&id, &comment, &version).await.unwrap();
+
+        // La versión leída antes de la escritura ya no vale
+        comment.like = 2;
+        let result = db.put_if_version(&id, &comment, &version).await;
+        assert!(matches!(result, Err(RepositoryError::Conflict)));
+
+        let (stored, _) = db.get_versioned(&id).await.unwrap().unwrap();
+        assert_eq!(stored.like, 1);
+    }
+
+    #[tokio::test]
+    async fn test_missing_collection_is_empty() {
+        let db = SqliteDatabase::open_in_memory().unwrap();
+
+        assert!(CommentRepository::get_all(&db).await.unwrap().is_empty());
+        assert!(
+            CalStripeRepository::get(&db, "booking-1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cal_stripe_relation_roundtrip() {
+        let db = SqliteDatabase::open_in_memory().unwrap();
+        let relation = StripeRelation {
+            stripe_id: "pi_123".to_string(),
+        };
+
+        CalStripeRepository::put(&db, "booking-1", &relation)
+            .await
+            .unwrap();
+
+        let stored = CalStripeRepository::get(&db, "booking-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.stripe_id, "pi_123");
+
+        CalStripeRepository::delete(&db, "booking-1").await.unwrap();
+        assert!(
+            CalStripeRepository::get(&db, "booking-1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sessions_revoke_all_keeps_revocation() {
+        let db = SqliteDatabase::open_in_memory().unwrap();
+        let session = Session {
+            auth_time: 1000,
+            created_at: "2025-01-01T00:00:00Z".to_string(),
+            last_seen_at: "2025-01-01T00:00:00Z".to_string(),
+            sign_in_provider: None,
+            user_agent: None,
+        };
+
+        db.put_session("uid-1", "1000", &session).await.unwrap();
+        assert_eq!(
+            SessionRepository::get(&db, "uid-1")
+                .await
+                .unwrap()
+                .sessions
+                .len(),
+            1
+        );
+
+        db.revoke_all("uid-1", 2000).await.unwrap();
+        let sessions = SessionRepository::get(&db, "uid-1").await.unwrap();
+        assert!(sessions.sessions.is_empty());
+        assert_eq!(sessions.revoked_at, Some(2000));
+
+        SessionRepository::delete(&db, "uid-1").await.unwrap();
+        assert!(
+            SessionRepository::get(&db, "uid-1")
+                .await
+                .unwrap()
+                .revoked_at
+                .is_none()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_put_session_keeps_every_session() {
+        let db = Arc::new(SqliteDatabase::open_in_memory().unwrap());
+
+        let writers: Vec<_> = (0..16)
+            .map(|i| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    let session = Session {
+                        auth_time: 1000 + i,
+                        created_at: "2025-01-01T00:00:00Z".to_string(),
+                        last_seen_at: "2025-01-01T00:00:00Z".to_string(),
+                        sign_in_provider: None,
+                        user_agent: None,
+                    };
+                    db.put_session("uid-1", &format!("session-{}", i), &session)
+                        .await
+                        .unwrap();
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.await.unwrap();
+        }
+
+        // Ninguna escritura pisa la sesión añadida por otra
+        let sessions = SessionRepository::get(db.as_ref(), "uid-1").await.unwrap();
+        assert_eq!(sessions.sessions.len(), 16);
+    }
+}
@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            routes::docs::{ApiDoc, router},
+            test_fixtures::fixtures::create_mock_app_state,
+        },
+        axum::{
+            Router,
+            body::{Body, to_bytes},
+            http::{Request, StatusCode},
+        },
+        serde_json::Value,
+        std::{collections::HashMap, sync::Arc},
+        tower::ServiceExt,
+        utoipa::OpenApi,
+    };
+
+    fn document() -> Value {
+        serde_json::to_value(ApiDoc::openapi()).unwrap()
+    }
+
+    #[test]
+    fn test_openapi_documents_main_routes() {
+        let doc = document();
+        let paths = doc["paths"].as_object().unwrap();
+
+        for path in [
+            "/users/register",
+            "/users/me",
+            "/comments/all",
+            "/comments/edit/{comment_id}",
+            "/surveys",
+            "/cal/bookings",
+            "/payment/intent",
+            "/webhook/cal",
+        ] {
+            assert!(paths.contains_key(path), "Missing path {}", path);
+        }
+        assert!(paths["/comments/del/{comment_id}"]["delete"].is_object());
+    }
+
+    #[test]
+    fn test_openapi_includes_model_schemas() {
+        let doc = document();
+        let schemas = doc["components"]["schemas"].as_object().unwrap();
+
+        for schema in [
+            "Comment",
+            "Survey",
+            "CalBookingPayload",
+            "UserRequest",
+            "ResponseAPI_Comment",
+        ] {
+            assert!(schemas.contains_key(schema), "Missing schema {}", schema);
+        }
+    }
+
+    #[test]
+    fn test_openapi_marks_protected_routes_with_bearer_auth() {
+        let doc = document();
+
+        assert_eq!(
+            doc["components"]["securitySchemes"]["bearer_auth"]["scheme"],
+            "bearer"
+        );
+        assert!(doc["paths"]["/users/me"]["get"]["security"].is_array());
+        // Las rutas públicas no piden token
+        assert!(doc["paths"]["/comments/all"]["get"]["security"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_docs_router_serves_ui_and_json() {
+        let state = Arc::new(create_mock_app_state(HashMap::new()).await);
+        let app: Router = Router::new()
+            .nest("/docs", router(state.clone()))
+            .with_state(state);
+
+        let ui = app
+            .clone()
+            .oneshot(Request::get("/docs").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(ui.status(), StatusCode::OK);
+        let html = to_bytes(ui.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&html).contains("swagger-ui"));
+
+        let json = app
+            .oneshot(
+                Request::get("/docs/openapi.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(json.status(), StatusCode::OK);
+        let body = to_bytes(json.into_body(), usize::MAX).await.unwrap();
+        let doc: Value = serde_json::from_slice(&body).unwrap();
+        assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+    }
+}
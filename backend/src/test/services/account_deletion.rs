@@ -0,0 +1,400 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            models::{
+                cal::CalBookingPayload,
+                comments::{Comment, ModerationStatus, ReplyComment},
+                metrics::{CachedAccessToken, ServiceAccount},
+                sourvey::Survey,
+                state::AppState,
+                stripe::StripeRelation,
+                user::UserDB,
+            },
+            services::account_deletion::{
+                DELETED_USER_NAME, DELETED_USER_UID, delete_account_cascade, is_recent_login,
+                is_refundable,
+            },
+            test_fixtures::fixtures::create_mock_app_state,
+        },
+        chrono::{Duration, Utc},
+        mockito::{Matcher, Mock, ServerGuard},
+        serde_json::json,
+        std::collections::HashMap,
+    };
+
+    fn comment(author: &str, users_liked: Vec<&str>, replies: Vec<ReplyComment>) -> Comment {
+        Comment {
+            author_uid: Some(author.to_string()),
+            name: author.to_string(),
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            content: format!("Comentario de {}", author),
+            url_img: Some("https://img.test/avatar.png".to_string()),
+            stars: 5.0,
+            like: users_liked.len() as u32,
+            reply: replies,
+            users_liked: users_liked.into_iter().map(str::to_string).collect(),
+            moderation_status: ModerationStatus::Approved,
+            moderation_reason: None,
+            verified_student: false,
+            booking_uid: None,
+            liked: None,
+        }
+    }
+
+    fn reply(id: &str, author: &str, users_liked: Vec<&str>) -> ReplyComment {
+        ReplyComment {
+            id: id.to_string(),
+            author_uid: author.to_string(),
+            name: author.to_string(),
+            timestamp: "2025-01-02T00:00:00Z".to_string(),
+            content: "Gracias".to_string(),
+            url_img: None,
+            like: users_liked.len() as u32,
+            users_liked: users_liked.into_iter().map(str::to_string).collect(),
+            moderation_status: ModerationStatus::Approved,
+            moderation_reason: None,
+            liked: None,
+        }
+    }
+
+    fn survey(id: &str, email: &str) -> Survey {
+        Survey {
+            id: id.to_string(),
+            title: "Encuesta".to_string(),
+            description: String::new(),
+            user_email: email.to_string(),
+            submitted_at: None,
+            questions: Vec::new(),
+        }
+    }
+
+    fn booking(start: &str) -> CalBookingPayload {
+        serde_json::from_value(json!({ "uid": "booking", "start": start })).unwrap()
+    }
+
+    /// Estado con la DB poblada y las APIs externas apuntando al servidor mock. Cal.com
+    /// responde con `far_cancel_status` al cancelar `booking-far`.
+    /// Devuelve el mock del borrado en Firebase Auth para comprobar si se llamó.
+    async fn deletion_state(
+        server: &mut ServerGuard,
+        mailchimp_status: usize,
+        far_cancel_status: usize,
+    ) -> (AppState, Mock) {
+        let soon: String = (Utc::now() + Duration::hours(2)).to_rfc3339();
+        server
+            .mock("GET", "/bookings")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("attendeeEmail".into(), "student@test.com".into()),
+                Matcher::UrlEncoded("status".into(), "upcoming".into()),
+            ]))
+            .with_status(200)
+            .with_body(
+                json!({"status": "success", "data": {"bookings": [
+                    {"uid": "booking-far", "start": "2099-01-01T10:00:00Z"},
+                    {"uid": "booking-soon", "start": soon},
+                    {"uid": "booking-free", "start": "2099-01-02T10:00:00Z"}
+                ]}})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock(
+                "POST",
+                Matcher::Regex(r"^/bookings/booking-(soon|free)/cancel$".to_string()),
+            )
+            .with_status(200)
+            .with_body(json!({"status": "success"}).to_string())
+            .expect(2)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/bookings/booking-far/cancel")
+            .with_status(far_cancel_status)
+            .with_body(json!({"status": "success"}).to_string())
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/v1/refunds")
+            // La misma clave que usa el webhook de cancelación
+            .match_header("idempotency-key", "refund-pi_far")
+            .match_body(Matcher::UrlEncoded(
+                "payment_intent".into(),
+                "pi_far".into(),
+            ))
+            .with_status(200)
+            .with_body(
+                json!({
+                    "id": "re_far",
+                    "object": "refund",
+                    "amount": 2500,
+                    "created": 1700000000,
+                    "currency": "eur",
+                    "status": "succeeded"
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/search-members")
+            .match_query(Matcher::Any)
+            .with_status(mailchimp_status)
+            .with_body(
+                json!({"exact_matches": {"members": [{
+                    "id": "member-hash",
+                    "email_address": "student@test.com",
+                    "status": "subscribed",
+                    "list_id": "test-list-id"
+                }]}})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("DELETE", "/lists/test-list-id/members/member-hash")
+            .with_status(204)
+            .create_async()
+            .await;
+        let auth_delete: Mock = server
+            .mock("POST", "/projects/test-project/accounts:delete")
+            .match_header("authorization", "Bearer cached-admin-token")
+            .match_body(Matcher::Json(json!({"localId": "student-uid"})))
+            .with_status(200)
+            .with_body(json!({"kind": "identitytoolkit#DeleteAccountResponse"}).to_string())
+            .create_async()
+            .await;
+
+        let mut state = create_mock_app_state(HashMap::new()).await;
+        state.firebase_options.identity_toolkit_url = server.url();
+        // Credenciales de administrador con un token vigente en caché para borrar por uid
+        state.firebase_options.service_account = Some(ServiceAccount {
+            client_email: "admin@test-project.iam.gserviceaccount.com".to_string(),
+            private_key: "unused-while-cached".to_string(),
+        });
+        *state.firebase_options.admin_token_cache.lock().await = Some(CachedAccessToken::new(
+            "cached-admin-token".to_string(),
+            3600,
+        ));
+        state.cal_options.base_url = server.url();
+        state.stripe_client = stripe::Client::from_url(server.url().as_str(), "sk_test_key");
+        state.mailchimp_client.base_url = server.url();
+        state.mailchimp_client.list_id = "test-list-id".to_string();
+
+        let repositories = &state.repositories;
+        repositories
+            .users
+            .put(
+                "student-uid",
+                &UserDB {
+                    email: "student@test.com".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        repositories
+            .comments
+            .create(&comment(
+                "student-uid",
+                vec!["teacher-uid"],
+                vec![reply("r1", "teacher-uid", vec!["student-uid"])],
+            ))
+            .await
+            .unwrap();
+        repositories
+            .comments
+            .create(&comment(
+                "teacher-uid",
+                vec!["student-uid", "other-uid"],
+                vec![reply("r2", "student-uid", Vec::new())],
+            ))
+            .await
+            .unwrap();
+        repositories
+            .surveys
+            .put(&survey("s1", "STUDENT@test.com"))
+            .await
+            .unwrap();
+        repositories
+            .surveys
+            .put(&survey("s2", "other@test.com"))
+            .await
+            .unwrap();
+        for (cal_id, stripe_id) in [("booking-far", "pi_far"), ("booking-soon", "pi_soon")] {
+            repositories
+                .cal_stripe
+                .put(
+                    cal_id,
+                    &StripeRelation {
+                        stripe_id: stripe_id.to_string(),
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        (state, auth_delete)
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_cascade_cleans_every_source() {
+        let mut server = mockito::Server::new_async().await;
+        let (state, auth_delete) = deletion_state(&mut server, 200, 200).await;
+
+        let report = delete_account_cascade(&state, "student-uid", Some("student@test.com")).await;
+
+        assert!(report.failed_steps.is_empty(), "{:?}", report.failed_steps);
+        assert!(report.account_deleted);
+        assert_eq!(report.comments_anonymized, 1);
+        assert_eq!(report.replies_anonymized, 1);
+        assert_eq!(report.likes_removed, 2);
+        assert_eq!(report.surveys_anonymized, 1);
+        assert!(report.newsletter_archived);
+        auth_delete.assert_async().await;
+
+        let comments = state.repositories.comments.get_all().await.unwrap();
+        for comment in comments.values() {
+            assert!(!comment.users_liked.contains(&"student-uid".to_string()));
+            assert_eq!(comment.like as usize, comment.users_liked.len());
+            if comment.author_uid.is_none() {
+                assert_eq!(comment.name, DELETED_USER_NAME);
+                assert!(comment.url_img.is_none());
+                assert_eq!(comment.reply[0].like, 0);
+            } else {
+                assert_eq!(comment.reply[0].author_uid, DELETED_USER_UID);
+                assert_eq!(comment.reply[0].name, DELETED_USER_NAME);
+            }
+        }
+
+        let surveys = state.repositories.surveys.get_all().await.unwrap();
+        assert_eq!(surveys["s1"].user_email, "");
+        assert_eq!(surveys["s2"].user_email, "other@test.com");
+
+        // Solo se reembolsa la reserva pagada con antelación suficiente
+        let bookings: HashMap<String, (bool, Option<String>)> = report
+            .bookings_cancelled
+            .into_iter()
+            .map(|b| (b.uid, (b.refunded, b.refund_id)))
+            .collect();
+        assert_eq!(bookings["booking-far"], (true, Some("re_far".to_string())));
+        assert_eq!(bookings["booking-soon"], (false, None));
+        assert_eq!(bookings["booking-free"], (false, None));
+        assert!(
+            state
+                .repositories
+                .cal_stripe
+                .get_all()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        assert!(
+            state
+                .repositories
+                .users
+                .get("student-uid")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_cascade_keeps_account_when_a_step_fails() {
+        let mut server = mockito::Server::new_async().await;
+        let (state, auth_delete) = deletion_state(&mut server, 500, 200).await;
+
+        let report = delete_account_cascade(&state, "student-uid", Some("student@test.com")).await;
+
+        assert!(!report.account_deleted);
+        assert_eq!(report.failed_steps.len(), 1);
+        assert_eq!(report.failed_steps[0].step, "newsletter");
+        assert_eq!(report.failed_steps[0].attempts, 3);
+        // El resto de pasos se completan y la cuenta se conserva para reintentar
+        assert_eq!(report.comments_anonymized, 1);
+        assert_eq!(report.bookings_cancelled.len(), 3);
+        assert!(!auth_delete.matched_async().await);
+        assert!(
+            state
+                .repositories
+                .users
+                .get("student-uid")
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_cascade_keeps_payment_when_cal_cancel_fails() {
+        let mut server = mockito::Server::new_async().await;
+        let (state, auth_delete) = deletion_state(&mut server, 200, 500).await;
+
+        let report = delete_account_cascade(&state, "student-uid", Some("student@test.com")).await;
+
+        assert!(!report.account_deleted);
+        assert_eq!(report.failed_steps.len(), 1);
+        assert_eq!(report.failed_steps[0].step, "booking booking-far");
+        // Las demás reservas se cancelan igualmente
+        let cancelled: Vec<&str> = report
+            .bookings_cancelled
+            .iter()
+            .map(|b| b.uid.as_str())
+            .collect();
+        assert_eq!(cancelled, vec!["booking-soon", "booking-free"]);
+        // Sin cancelar en Cal.com no se reembolsa ni se pierde la relación con el pago
+        let relation = state
+            .repositories
+            .cal_stripe
+            .get("booking-far")
+            .await
+            .unwrap();
+        assert_eq!(relation.unwrap().stripe_id, "pi_far");
+        assert!(
+            state
+                .repositories
+                .cal_stripe
+                .get("booking-soon")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(!auth_delete.matched_async().await);
+    }
+
+    #[test]
+    fn test_is_refundable_requires_minimum_notice() {
+        let now = Utc::now();
+
+        assert!(is_refundable(
+            &booking(&(now + Duration::hours(25)).to_rfc3339()),
+            now
+        ));
+        assert!(!is_refundable(
+            &booking(&(now + Duration::hours(23)).to_rfc3339()),
+            now
+        ));
+        assert!(is_refundable(
+            &serde_json::from_value(json!({ "uid": "booking" })).unwrap(),
+            now
+        ));
+    }
+
+    #[test]
+    fn test_is_recent_login_within_window() {
+        let now = Utc::now();
+
+        assert!(is_recent_login(
+            (now - Duration::minutes(1)).timestamp(),
+            now
+        ));
+        assert!(!is_recent_login(
+            (now - Duration::minutes(6)).timestamp(),
+            now
+        ));
+    }
+}
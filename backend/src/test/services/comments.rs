@@ -0,0 +1,303 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            models::{
+                comments::{Comment, CommentListQuery, CommentSort, ReplyComment},
+                error::ApiError,
+                state::AppState,
+            },
+            repositories::Repositories,
+            services::comments::{
+                MAX_WRITE_ATTEMPTS, MOST_LIKED_LIMIT, list_comments, mark_liked, rating_summary,
+                toggle_like, update_comment,
+            },
+            test_fixtures::fixtures::create_mock_app_state,
+        },
+        mockito::Matcher,
+        serde_json::json,
+        std::collections::HashMap,
+    };
+
+    fn comment(stars: f32, like: u32, timestamp: &str, replies: usize) -> Comment {
+        Comment {
+            author_uid: None,
+            name: "Estudiante".to_string(),
+            timestamp: timestamp.to_string(),
+            content: "Muy buena clase".to_string(),
+            url_img: None,
+            stars,
+            like,
+            reply: (0..replies)
+                .map(|i| ReplyComment {
+                    id: i.to_string(),
+                    author_uid: "teacher".to_string(),
+                    name: "Profesor".to_string(),
+                    timestamp: timestamp.to_string(),
+                    content: "Gracias".to_string(),
+                    url_img: None,
+                    like: 0,
+                    users_liked: Vec::new(),
+                    moderation_status: Default::default(),
+                    moderation_reason: None,
+                    liked: None,
+                })
+                .collect(),
+            users_liked: Vec::new(),
+            moderation_status: Default::default(),
+            moderation_reason: None,
+            verified_student: false,
+            booking_uid: None,
+            liked: None,
+        }
+    }
+
+    fn comments() -> HashMap<String, Comment> {
+        HashMap::from([
+            ("a".to_string(), comment(2.0, 5, "10/01/2025 09:00", 0)),
+            ("b".to_string(), comment(4.5, 5, "2025-03-01T12:00:00Z", 1)),
+            ("c".to_string(), comment(5.0, 0, "fecha ilegible", 0)),
+            ("d".to_string(), comment(4.0, 8, "20/02/2025 18:30", 2)),
+        ])
+    }
+
+    fn keys(query: CommentListQuery) -> Vec<String> {
+        list_comments(comments(), &query).into_keys().collect()
+    }
+
+    #[test]
+    fn test_list_comments_sorts() {
+        let sorted = |sort| {
+            keys(CommentListQuery {
+                sort: Some(sort),
+                ..Default::default()
+            })
+        };
+
+        // Los timestamps ilegibles van al final
+        assert_eq!(sorted(CommentSort::Newest), ["b", "d", "a", "c"]);
+        // Empate de likes: primero el más reciente
+        assert_eq!(sorted(CommentSort::MostLiked), ["d", "b", "a", "c"]);
+        assert_eq!(sorted(CommentSort::HighestStars), ["c", "b", "d", "a"]);
+        assert_eq!(sorted(CommentSort::LowestStars), ["a", "d", "b", "c"]);
+    }
+
+    #[test]
+    fn test_list_comments_filters() {
+        assert_eq!(
+            keys(CommentListQuery {
+                min_stars: Some(4.5),
+                ..Default::default()
+            }),
+            ["b", "c"]
+        );
+        assert_eq!(
+            keys(CommentListQuery {
+                has_replies: Some(true),
+                ..Default::default()
+            }),
+            ["b", "d"]
+        );
+        assert_eq!(
+            keys(CommentListQuery {
+                has_replies: Some(false),
+                ..Default::default()
+            }),
+            ["a", "c"]
+        );
+    }
+
+    #[test]
+    fn test_rating_summary() {
+        let summary = rating_summary(comments());
+
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.average, 3.9);
+        // 4.5 se redondea a 5 estrellas
+        assert_eq!(summary.histogram[&2], 1);
+        assert_eq!(summary.histogram[&4], 1);
+        assert_eq!(summary.histogram[&5], 2);
+        assert_eq!(summary.histogram[&1], 0);
+        assert_eq!(summary.most_liked.len(), MOST_LIKED_LIMIT);
+        assert_eq!(summary.most_liked.keys().next().unwrap(), "d");
+    }
+
+    #[test]
+    fn test_rating_summary_ignores_comments_without_stars() {
+        let mut comments = comments();
+        comments.insert("e".to_string(), comment(0.0, 20, "01/04/2025 10:00", 0));
+        comments.insert("f".to_string(), comment(0.0, 0, "02/04/2025 10:00", 0));
+
+        let summary = rating_summary(comments);
+
+        // Mismo resumen que solo con las reseñas
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.average, 3.9);
+        assert_eq!(summary.histogram[&1], 0);
+        assert_eq!(summary.histogram.values().sum::<usize>(), 4);
+        assert!(!summary.most_liked.contains_key("e"));
+    }
+
+    #[test]
+    fn test_rating_summary_without_comments() {
+        let summary = rating_summary(HashMap::new());
+
+        assert_eq!(summary.count, 0);
+        assert_eq!(summary.average, 0.0);
+        assert!(summary.histogram.values().all(|count| *count == 0));
+        assert!(summary.most_liked.is_empty());
+    }
+
+    #[test]
+    fn test_toggle_like_recounts_from_unique_users() {
+        // Datos desfasados: uid repetido y contador inflado
+        let mut users_liked = vec!["ana".to_string(), "ana".to_string(), "luis".to_string()];
+        let mut like: u32 = 7;
+
+        assert!(!toggle_like(&mut users_liked, &mut like, "ana"));
+        assert_eq!(users_liked, vec!["luis".to_string()]);
+        assert_eq!(like, 1);
+
+        assert!(toggle_like(&mut users_liked, &mut like, "ana"));
+        assert_eq!(like, 2);
+        // Volver a dar like lo quita: nunca cuenta dos veces
+        assert!(!toggle_like(&mut users_liked, &mut like, "ana"));
+        assert_eq!(like, 1);
+    }
+
+    #[test]
+    fn test_mark_liked_sets_comment_and_replies() {
+        let mut liked = comment(5.0, 1, "10/01/2025 09:00", 2);
+        liked.users_liked = vec!["ana".to_string()];
+        liked.reply[1].users_liked = vec!["ana".to_string()];
+
+        mark_liked(&mut liked, "ana");
+
+        assert_eq!(liked.liked, Some(true));
+        assert_eq!(liked.reply[0].liked, Some(false));
+        assert_eq!(liked.reply[1].liked, Some(true));
+    }
+
+    // Estado con los repositorios sobre un Firebase simulado
+    async fn contended_state(server: &mockito::Server) -> AppState {
+        let mut state = create_mock_app_state(HashMap::new()).await;
+        state.repositories = Repositories::firebase(
+            reqwest::Client::new(),
+            format!("{}/", server.url()),
+            "test-secret".to_string(),
+        );
+        state
+    }
+
+    fn stored(users_liked: &[&str]) -> String {
+        let mut comment = comment(5.0, users_liked.len() as u32, "01/01/2025 10:00", 0);
+        comment.users_liked = users_liked.iter().map(|uid| uid.to_string()).collect();
+        serde_json::to_string(&comment).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_update_comment_retries_after_concurrent_write() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/comments/c1.json")
+            .match_query(Matcher::Any)
+            .with_header("ETag", "e1")
+            .with_body(stored(&[]))
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/comments/c1.json")
+            .match_query(Matcher::Any)
+            .with_header("ETag", "e2")
+            .with_body(stored(&["other"]))
+            .expect(1)
+            .create_async()
+            .await;
+        let stale = server
+            .mock("PUT", "/comments/c1.json")
+            .match_query(Matcher::Any)
+            .match_header("if-match", "e1")
+            .with_status(412)
+            .expect(1)
+            .create_async()
+            .await;
+        let fresh = server
+            .mock("PUT", "/comments/c1.json")
+            .match_query(Matcher::Any)
+            .match_header("if-match", "e2")
+            .match_body(Matcher::PartialJson(
+                json!({ "like": 2, "users_liked": ["other", "uid-1"] }),
+            ))
+            .with_body("{}")
+            .expect(1)
+            .create_async()
+            .await;
+        let state = contended_state(&server).await;
+
+        let (comment, liked) = update_comment(&state, "c1", |comment| {
+            Ok(toggle_like(
+                &mut comment.users_liked,
+                &mut comment.like,
+                "uid-1",
+            ))
+        })
+        .await
+        .unwrap();
+
+        assert!(liked);
+        assert_eq!(comment.like, 2);
+        stale.assert_async().await;
+        fresh.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_update_comment_gives_up_with_conflict() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/comments/c1.json")
+            .match_query(Matcher::Any)
+            .with_header("ETag", "e1")
+            .with_body(stored(&[]))
+            .create_async()
+            .await;
+        let put = server
+            .mock("PUT", "/comments/c1.json")
+            .match_query(Matcher::Any)
+            .with_status(412)
+            .expect(MAX_WRITE_ATTEMPTS)
+            .create_async()
+            .await;
+        let state = contended_state(&server).await;
+
+        let result = update_comment(&state, "c1", |comment| {
+            comment.like += 1;
+            Ok(())
+        })
+        .await;
+
+        assert!(matches!(result, Err(ApiError::Conflict(_))));
+        put.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_update_comment_mutation_error_skips_write() {
+        let state = create_mock_app_state(HashMap::new()).await;
+        let id = state
+            .repositories
+            .comments
+            .create(&comment(5.0, 0, "01/01/2025 10:00", 0))
+            .await
+            .unwrap();
+
+        let result: Result<(Comment, ()), ApiError> = update_comment(&state, &id, |comment| {
+            comment.like = 10;
+            Err(ApiError::Forbidden("nope".to_string()))
+        })
+        .await;
+
+        assert!(matches!(result, Err(ApiError::Forbidden(_))));
+        let comment = state.repositories.comments.get(&id).await.unwrap().unwrap();
+        assert_eq!(comment.like, 0);
+    }
+}
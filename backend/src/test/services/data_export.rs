@@ -0,0 +1,386 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            models::{
+                comments::{Comment, ModerationStatus, ReplyComment},
+                firebase::UserAuthentication,
+                sourvey::Survey,
+                state::AppState,
+                stripe::StripeRelation,
+                user::UserDB,
+            },
+            services::data_export::{collect_user_data, export_to_zip},
+            test_fixtures::fixtures::create_mock_app_state,
+        },
+        mockito::{Matcher, ServerGuard},
+        serde_json::json,
+        std::{collections::HashMap, io::Cursor},
+    };
+
+    fn claims() -> UserAuthentication {
+        UserAuthentication {
+            sub: "student-uid".to_string(),
+            iss: "https://securetoken.google.com/test-project".to_string(),
+            aud: "test-project".to_string(),
+            iat: 0,
+            exp: i64::MAX,
+            email: Some("student@test.com".to_string()),
+            email_verified: Some(true),
+            name: None,
+            picture: None,
+            auth_time: 0,
+            user_id: "student-uid".to_string(),
+            firebase: None,
+            phone_number: None,
+            provider_id: None,
+        }
+    }
+
+    fn comment(author: &str, replies: Vec<ReplyComment>) -> Comment {
+        Comment {
+            author_uid: Some(author.to_string()),
+            name: author.to_string(),
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            content: format!("Comentario de {}", author),
+            url_img: None,
+            stars: 5.0,
+            like: 0,
+            reply: replies,
+            users_liked: Vec::new(),
+            moderation_status: ModerationStatus::Approved,
+            moderation_reason: None,
+            verified_student: false,
+            booking_uid: None,
+            liked: None,
+        }
+    }
+
+    fn reply(id: &str, author: &str) -> ReplyComment {
+        ReplyComment {
+            id: id.to_string(),
+            author_uid: author.to_string(),
+            name: author.to_string(),
+            timestamp: "2025-01-02T00:00:00Z".to_string(),
+            content: "Gracias".to_string(),
+            url_img: None,
+            like: 0,
+            users_liked: Vec::new(),
+            moderation_status: ModerationStatus::Approved,
+            moderation_reason: None,
+            liked: None,
+        }
+    }
+
+    fn survey(id: &str, email: &str) -> Survey {
+        Survey {
+            id: id.to_string(),
+            title: "Encuesta".to_string(),
+            description: String::new(),
+            user_email: email.to_string(),
+            submitted_at: None,
+            questions: Vec::new(),
+        }
+    }
+
+    /// Página de resultados de `/payment_intents/search`
+    fn search_page(data: Vec<serde_json::Value>, next_page: Option<&str>) -> String {
+        json!({
+            "object": "search_result",
+            "url": "/v1/payment_intents/search",
+            "has_more": next_page.is_some(),
+            "data": data,
+            "next_page": next_page
+        })
+        .to_string()
+    }
+
+    fn payment_intent(id: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "object": "payment_intent",
+            "amount": 2500,
+            "amount_capturable": 0,
+            "amount_received": 2500,
+            "capture_method": "automatic",
+            "confirmation_method": "automatic",
+            "created": 1700000000,
+            "currency": "eur",
+            "livemode": false,
+            "metadata": {},
+            "payment_method_types": ["card"],
+            "status": "succeeded"
+        })
+    }
+
+    /// La búsqueda de pagos devuelve una sola página con `data`
+    async fn mock_payment_search(server: &mut ServerGuard, data: Vec<serde_json::Value>) {
+        server
+            .mock("GET", "/v1/payment_intents/search")
+            .match_query(Matcher::UrlEncoded(
+                "query".into(),
+                "metadata['firebase_uid']:'student-uid'".into(),
+            ))
+            .with_status(200)
+            .with_body(search_page(data, None))
+            .create_async()
+            .await;
+    }
+
+    /// Estado con la DB poblada y todas las APIs externas apuntando al servidor mock.
+    /// Cal.com responde con `cal_status`.
+    async fn export_state(server: &mut ServerGuard, cal_status: usize) -> AppState {
+        server
+            .mock("POST", "/accounts:lookup")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(
+                json!({"users": [{
+                    "localId": "student-uid",
+                    "email": "student@test.com",
+                    "passwordHash": "secret-hash",
+                    "createdAt": "1700000000000"
+                }]})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/bookings")
+            .match_query(Matcher::UrlEncoded(
+                "attendeeEmail".into(),
+                "student@test.com".into(),
+            ))
+            .with_status(cal_status)
+            .with_body(
+                json!({"status": "success", "data": {"bookings": [{"uid": "booking-1"}]}})
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/search-members")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(
+                json!({"exact_matches": {"members": [{
+                    "id": "member-hash",
+                    "email_address": "student@test.com",
+                    "status": "subscribed",
+                    "list_id": "test-list-id",
+                    "timestamp_signup": "2025-01-01T00:00:00+00:00"
+                }]}})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let mut state = create_mock_app_state(HashMap::new()).await;
+        state.firebase_options.identity_toolkit_url = server.url();
+        state.cal_options.base_url = server.url();
+        state.stripe_client = stripe::Client::from_url(server.url().as_str(), "sk_test_key");
+        state.mailchimp_client.base_url = server.url();
+        state.mailchimp_client.list_id = "test-list-id".to_string();
+
+        let repositories = &state.repositories;
+        repositories
+            .users
+            .put(
+                "student-uid",
+                &UserDB {
+                    email: "student@test.com".to_string(),
+                    role: Some("student".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        repositories
+            .comments
+            .create(&comment("student-uid", vec![reply("r1", "teacher-uid")]))
+            .await
+            .unwrap();
+        repositories
+            .comments
+            .create(&comment("teacher-uid", vec![reply("r2", "student-uid")]))
+            .await
+            .unwrap();
+        repositories
+            .surveys
+            .put(&survey("s1", "STUDENT@test.com"))
+            .await
+            .unwrap();
+        repositories
+            .surveys
+            .put(&survey("s2", "other@test.com"))
+            .await
+            .unwrap();
+
+        state
+    }
+
+    #[tokio::test]
+    async fn test_collect_user_data_gathers_every_source() {
+        let mut server = mockito::Server::new_async().await;
+        mock_payment_search(&mut server, Vec::new()).await;
+        let state = export_state(&mut server, 200).await;
+
+        let export = collect_user_data(&state, &claims(), "id-token").await;
+
+        assert!(export.unavailable_sources.is_empty());
+        assert_eq!(export.profile.unwrap().role.as_deref(), Some("student"));
+        let auth = export.auth.unwrap();
+        assert_eq!(auth.created_at.as_deref(), Some("1700000000000"));
+        assert!(auth.password_hash.is_none());
+        assert_eq!(export.comments.len(), 1);
+        assert_eq!(export.replies.len(), 1);
+        assert_eq!(export.replies[0].reply.id, "r2");
+        assert_eq!(export.surveys.len(), 1);
+        assert_eq!(export.surveys[0].id, "s1");
+        assert_eq!(export.bookings[0].uid.as_deref(), Some("booking-1"));
+        assert!(export.payments.is_empty());
+        assert_eq!(export.newsletter.unwrap().status, "subscribed");
+    }
+
+    #[tokio::test]
+    async fn test_collect_user_data_reports_unavailable_source() {
+        let mut server = mockito::Server::new_async().await;
+        mock_payment_search(&mut server, Vec::new()).await;
+        let state = export_state(&mut server, 500).await;
+
+        let export = collect_user_data(&state, &claims(), "id-token").await;
+
+        assert_eq!(export.unavailable_sources, vec!["bookings".to_string()]);
+        assert!(export.bookings.is_empty());
+        // El resto de secciones se entregan igualmente
+        assert!(export.profile.is_some());
+        assert_eq!(export.surveys.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_collect_user_data_skips_email_sources_for_unverified_email() {
+        let mut server = mockito::Server::new_async().await;
+        mock_payment_search(&mut server, Vec::new()).await;
+        let state = export_state(&mut server, 200).await;
+        let claims = UserAuthentication {
+            email_verified: Some(false),
+            ..claims()
+        };
+
+        let export = collect_user_data(&state, &claims, "id-token").await;
+
+        // Un email sin verificar puede ser de otra persona: no se exportan sus datos
+        assert!(export.unavailable_sources.is_empty());
+        assert!(export.surveys.is_empty());
+        assert!(export.bookings.is_empty());
+        assert!(export.newsletter.is_none());
+        // Lo que se busca por uid se exporta igualmente
+        assert!(export.profile.is_some());
+        assert_eq!(export.comments.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_export_to_zip_writes_one_file_per_section() {
+        let mut server = mockito::Server::new_async().await;
+        mock_payment_search(&mut server, Vec::new()).await;
+        let state = export_state(&mut server, 200).await;
+        let export = collect_user_data(&state, &claims(), "id-token").await;
+
+        let bytes = export_to_zip(&export).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+        let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert!(names.contains(&"profile.json".to_string()));
+        assert!(names.contains(&"payments.json".to_string()));
+        assert!(names.contains(&"unavailable_sources.json".to_string()));
+
+        let profile: serde_json::Value =
+            serde_json::from_reader(archive.by_name("profile.json").unwrap()).unwrap();
+        assert_eq!(profile["email"], "student@test.com");
+    }
+
+    #[tokio::test]
+    async fn test_collect_user_data_follows_payment_search_pages() {
+        let mut server = mockito::Server::new_async().await;
+        let second = server
+            .mock("GET", "/v1/payment_intents/search")
+            .match_query(Matcher::UrlEncoded("page".into(), "page-2".into()))
+            .with_status(200)
+            .with_body(search_page(vec![payment_intent("pi_second")], None))
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v1/payment_intents/search")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(search_page(
+                vec![payment_intent("pi_first")],
+                Some("page-2"),
+            ))
+            .create_async()
+            .await;
+        let state = export_state(&mut server, 200).await;
+
+        let export = collect_user_data(&state, &claims(), "id-token").await;
+
+        let ids: Vec<&str> = export.payments.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["pi_first", "pi_second"]);
+        assert!(export.unavailable_sources.is_empty());
+        second.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_collect_user_data_reports_truncated_payments() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/v1/payment_intents/search")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(search_page(vec![payment_intent("pi_1")], Some("next")))
+            .expect(10)
+            .create_async()
+            .await;
+        let state = export_state(&mut server, 200).await;
+
+        let export = collect_user_data(&state, &claims(), "id-token").await;
+
+        assert_eq!(
+            export.unavailable_sources,
+            vec!["payments (truncated)".to_string()]
+        );
+        assert_eq!(export.payments.len(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_collect_user_data_resolves_payments_through_bookings() {
+        let mut server = mockito::Server::new_async().await;
+        mock_payment_search(&mut server, vec![payment_intent("pi_tagged")]).await;
+        let legacy = server
+            .mock("GET", "/v1/payment_intents/pi_legacy")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(payment_intent("pi_legacy").to_string())
+            .create_async()
+            .await;
+        let state = export_state(&mut server, 200).await;
+        state
+            .repositories
+            .cal_stripe
+            .put(
+                "booking-1",
+                &StripeRelation {
+                    stripe_id: "pi_legacy".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let export = collect_user_data(&state, &claims(), "id-token").await;
+
+        let ids: Vec<&str> = export.payments.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["pi_tagged", "pi_legacy"]);
+        assert!(export.unavailable_sources.is_empty());
+        legacy.assert_async().await;
+    }
+}
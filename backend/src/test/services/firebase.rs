@@ -1,9 +1,23 @@
 #[cfg(test)]
 mod tests {
     use {
-        crate::services::firebase::handle_firebase_response,
+        crate::{
+            models::{
+                error::ApiError,
+                firebase::{OobRequestType, SendOobCodeRequest},
+                metrics::{CachedAccessToken, ServiceAccount},
+                state::CustomFirebase,
+            },
+            services::firebase::{
+                firebase_api_error, handle_firebase_response, reset_password, send_oob_code,
+            },
+            test_fixtures::fixtures::create_mock_app_state,
+        },
         axum::http::StatusCode,
+        mockito::Matcher,
         serde::{Deserialize, Serialize},
+        serde_json::json,
+        std::collections::HashMap,
     };
 
     // Estructura de prueba para deserializar respuestas exitosas
@@ -293,4 +307,75 @@ mod tests {
 
         mock.assert();
     }
+
+    async fn firebase_pointing_to(url: String) -> CustomFirebase {
+        let mut firebase = create_mock_app_state(HashMap::new()).await.firebase_options;
+        firebase.identity_toolkit_url = url;
+        firebase.service_account = Some(ServiceAccount {
+            client_email: "admin@test-project.iam.gserviceaccount.com".to_string(),
+            private_key: "unused-while-cached".to_string(),
+        });
+        *firebase.admin_token_cache.lock().await = Some(CachedAccessToken::new(
+            "cached-admin-token".to_string(),
+            3600,
+        ));
+        firebase
+    }
+
+    #[tokio::test]
+    async fn test_send_oob_code_requests_link_and_returns_it() {
+        // `returnOobLink` solo se respeta con credenciales de administrador
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/projects/test-project/accounts:sendOobCode")
+            .match_header("authorization", "Bearer cached-admin-token")
+            .match_body(Matcher::PartialJson(json!({
+                "requestType": "PASSWORD_RESET",
+                "email": "student@test.com",
+                "returnOobLink": true
+            })))
+            .with_status(200)
+            .with_body(r#"{"email": "student@test.com", "oobLink": "https://auth.test/reset?oobCode=abc"}"#)
+            .create_async()
+            .await;
+
+        let firebase = firebase_pointing_to(server.url()).await;
+        let response = send_oob_code(
+            &firebase,
+            &SendOobCodeRequest {
+                request_type: OobRequestType::PasswordReset,
+                email: "student@test.com".to_string(),
+                return_oob_link: true,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            response.oob_link.as_deref(),
+            Some("https://auth.test/reset?oobCode=abc")
+        );
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_reset_password_maps_invalid_code_to_bad_request() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/accounts:resetPassword")
+            .match_query(Matcher::Any)
+            .with_status(400)
+            .with_body(r#"{"error": {"code": 400, "message": "INVALID_OOB_CODE"}}"#)
+            .create_async()
+            .await;
+
+        let firebase = firebase_pointing_to(server.url()).await;
+        let err = reset_password(&firebase, "bad-code", "new-password")
+            .await
+            .map_err(firebase_api_error)
+            .unwrap_err();
+
+        assert!(matches!(&err, ApiError::BadRequest(message) if message == "INVALID_OOB_CODE"));
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+    }
 }
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            models::{
+                error::FirebaseKeysError,
+                state::{CustomFirebase, DEFAULT_KEYS_MAX_AGE, KeyCache},
+            },
+            services::firebase_keys::{
+                fetch_firebase_keys, force_refresh_firebase_keys, parse_max_age, refresh_backoff,
+            },
+        },
+        mockito::{Mock, ServerGuard},
+        reqwest::Client as HttpClient,
+        serde_json::json,
+        std::{sync::Arc, time::Duration},
+        tokio::sync::RwLock,
+    };
+
+    // ========== FIXTURES Y HELPERS ==========
+    fn firebase_options(public_keys_url: String, cache: KeyCache) -> CustomFirebase {
+        CustomFirebase {
+            firebase_keys: Arc::new(RwLock::new(cache)),
+            firebase_project_id: "test-project".to_string(),
+            firebase_api_key: "test-api-key".to_string(),
+            firebase_client: HttpClient::new(),
+            auth_emulator_host: None,
+            identity_toolkit_url: "https://identitytoolkit.googleapis.com/v1".to_string(),
+            secure_token_url: "https://securetoken.googleapis.com/v1".to_string(),
+            public_keys_url,
+            service_account: None,
+            token_url: "https://oauth2.googleapis.com/token".to_string(),
+            admin_token_cache: Default::default(),
+            google_client_id: None,
+            google_tokeninfo_url: "https://oauth2.googleapis.com/tokeninfo".to_string(),
+        }
+    }
+
+    async fn keys_server(cache_control: Option<&str>, expected_hits: usize) -> (ServerGuard, Mock) {
+        let mut server: ServerGuard = mockito::Server::new_async().await;
+        let mut mock = server
+            .mock("GET", "/keys")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({"new-kid": "new-pem"}).to_string());
+        if let Some(cache_control) = cache_control {
+            mock = mock.with_header("cache-control", cache_control);
+        }
+        let mock = mock.expect(expected_hits).create_async().await;
+        (server, mock)
+    }
+
+    // ========== TESTS ==========
+    #[test]
+    fn test_parse_max_age() {
+        assert_eq!(
+            parse_max_age("public, max-age=19770, must-revalidate, no-transform"),
+            Some(Duration::from_secs(19770))
+        );
+        assert_eq!(parse_max_age("max-age=60"), Some(Duration::from_secs(60)));
+        assert_eq!(parse_max_age("no-cache"), None);
+        assert_eq!(parse_max_age("max-age=abc"), None);
+    }
+
+    #[test]
+    fn test_refresh_backoff_grows_and_is_capped() {
+        assert_eq!(refresh_backoff(0), Duration::from_secs(2));
+        assert_eq!(refresh_backoff(1), Duration::from_secs(4));
+        assert_eq!(refresh_backoff(3), Duration::from_secs(16));
+        assert_eq!(refresh_backoff(50), Duration::from_secs(300));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_firebase_keys_honors_max_age() {
+        let (server, mock) = keys_server(Some("public, max-age=1234, must-revalidate"), 1).await;
+
+        let fetched = fetch_firebase_keys(&HttpClient::new(), &format!("{}/keys", server.url()))
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(fetched.max_age, Duration::from_secs(1234));
+        assert_eq!(fetched.keys["new-kid"], "new-pem");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_firebase_keys_without_cache_control_uses_default() {
+        let (server, _mock) = keys_server(None, 1).await;
+
+        let fetched = fetch_firebase_keys(&HttpClient::new(), &format!("{}/keys", server.url()))
+            .await
+            .unwrap();
+
+        assert_eq!(fetched.max_age, DEFAULT_KEYS_MAX_AGE);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_firebase_keys_rejects_errors_and_empty_keys() {
+        let mut server: ServerGuard = mockito::Server::new_async().await;
+        let _down = server
+            .mock("GET", "/down")
+            .with_status(503)
+            .create_async()
+            .await;
+        let _empty = server
+            .mock("GET", "/empty")
+            .with_status(200)
+            .with_body("{}")
+            .create_async()
+            .await;
+        let client = HttpClient::new();
+
+        let down = fetch_firebase_keys(&client, &format!("{}/down", server.url())).await;
+        let empty = fetch_firebase_keys(&client, &format!("{}/empty", server.url())).await;
+
+        assert!(matches!(down, Err(FirebaseKeysError::Status(status)) if status == 503));
+        assert!(matches!(empty, Err(FirebaseKeysError::Empty)));
+    }
+
+    #[tokio::test]
+    async fn test_force_refresh_updates_cache_and_is_rate_limited() {
+        // Solo debe llegar una petición a Google aunque se fuerce dos veces seguidas
+        let (server, mock) = keys_server(Some("max-age=600"), 1).await;
+        let firebase = firebase_options(
+            format!("{}/keys", server.url()),
+            KeyCache::new(json!({"old-kid": "old-pem"}), DEFAULT_KEYS_MAX_AGE),
+        );
+
+        let first = force_refresh_firebase_keys(&firebase).await.unwrap();
+        let second = force_refresh_firebase_keys(&firebase).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(first.unwrap()["new-kid"], "new-pem");
+        assert!(second.is_none());
+
+        let cache = firebase.firebase_keys.read().await;
+        assert_eq!(cache.keys["new-kid"], "new-pem");
+        assert_eq!(cache.max_age, Duration::from_secs(600));
+        assert!(cache.last_forced_refresh.is_some());
+    }
+}
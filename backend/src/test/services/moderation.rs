@@ -0,0 +1,164 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            models::{
+                comments::{Comment, ModerationDecision, ModerationStatus, ReplyComment},
+                error::ApiError,
+            },
+            services::moderation::{
+                is_trusted_author, moderation_queue, public_comments, validate_decision,
+            },
+        },
+        std::collections::HashMap,
+    };
+
+    fn comment(author: &str, status: ModerationStatus, replies: Vec<ReplyComment>) -> Comment {
+        Comment {
+            author_uid: Some(author.to_string()),
+            name: author.to_string(),
+            timestamp: "01/01/2025 10:00".to_string(),
+            content: format!("Comentario de {}", author),
+            url_img: None,
+            stars: 5.0,
+            like: 0,
+            reply: replies,
+            users_liked: Vec::new(),
+            moderation_status: status,
+            moderation_reason: None,
+            verified_student: false,
+            booking_uid: None,
+            liked: None,
+        }
+    }
+
+    fn reply(id: &str, author: &str, status: ModerationStatus) -> ReplyComment {
+        ReplyComment {
+            id: id.to_string(),
+            author_uid: author.to_string(),
+            name: author.to_string(),
+            timestamp: format!("02/01/2025 10:0{}", id.len()),
+            content: "Gracias".to_string(),
+            url_img: None,
+            like: 0,
+            users_liked: Vec::new(),
+            moderation_status: status,
+            moderation_reason: None,
+            liked: None,
+        }
+    }
+
+    fn comments() -> HashMap<String, Comment> {
+        HashMap::from([
+            (
+                "c1".to_string(),
+                comment(
+                    "alice",
+                    ModerationStatus::Approved,
+                    vec![
+                        reply("r1", "bob", ModerationStatus::Pending),
+                        reply("r22", "carol", ModerationStatus::Approved),
+                    ],
+                ),
+            ),
+            (
+                "c2".to_string(),
+                comment("bob", ModerationStatus::Pending, vec![]),
+            ),
+            (
+                "c3".to_string(),
+                comment("dave", ModerationStatus::Rejected, vec![]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_is_trusted_author_counts_approved_comments_and_replies() {
+        let comments = comments();
+
+        assert!(is_trusted_author(&comments, "alice", None));
+        assert!(is_trusted_author(&comments, "carol", None));
+        assert!(!is_trusted_author(&comments, "bob", None));
+        assert!(!is_trusted_author(&comments, "dave", None));
+    }
+
+    #[test]
+    fn test_is_trusted_author_ignores_content_being_edited() {
+        let comments = comments();
+
+        // Su único contenido aprobado es el que editan
+        assert!(!is_trusted_author(&comments, "alice", Some("c1")));
+        assert!(!is_trusted_author(&comments, "carol", Some("r22")));
+        assert!(is_trusted_author(&comments, "carol", Some("c1")));
+    }
+
+    #[test]
+    fn test_public_comments_only_serves_approved_content() {
+        let public = public_comments(comments());
+
+        assert_eq!(public.len(), 1);
+        let comment = &public["c1"];
+        assert!(comment.author_uid.is_none());
+        assert_eq!(comment.reply.len(), 1);
+        assert_eq!(comment.reply[0].id, "r22");
+    }
+
+    #[test]
+    fn test_moderation_queue_collects_comments_and_replies() {
+        let queue = moderation_queue(comments(), ModerationStatus::Pending);
+
+        assert_eq!(queue.comments.len(), 1);
+        assert!(queue.comments.contains_key("c2"));
+        assert_eq!(queue.replies.len(), 1);
+        assert_eq!(queue.replies[0].comment_id, "c1");
+        assert_eq!(queue.replies[0].reply.id, "r1");
+    }
+
+    #[test]
+    fn test_moderation_queue_sorts_replies_by_date() {
+        let mut older = reply("older", "bob", ModerationStatus::Pending);
+        older.timestamp = "15/01/2025 10:00".to_string();
+        let mut newer = reply("newer", "bob", ModerationStatus::Pending);
+        newer.timestamp = "02/02/2025 10:00".to_string();
+        let mut unreadable = reply("unreadable", "bob", ModerationStatus::Pending);
+        unreadable.timestamp = "ayer".to_string();
+        let comments = HashMap::from([(
+            "c1".to_string(),
+            comment(
+                "alice",
+                ModerationStatus::Approved,
+                vec![unreadable, newer, older],
+            ),
+        )]);
+
+        let queue = moderation_queue(comments, ModerationStatus::Pending);
+
+        let ids: Vec<&str> = queue.replies.iter().map(|q| q.reply.id.as_str()).collect();
+        assert_eq!(ids, vec!["older", "newer", "unreadable"]);
+    }
+
+    #[test]
+    fn test_validate_decision() {
+        let decision = |status, reason: Option<&str>| ModerationDecision {
+            status,
+            reason: reason.map(str::to_string),
+        };
+
+        assert!(matches!(
+            validate_decision(decision(ModerationStatus::Approved, Some("ok"))),
+            Ok((ModerationStatus::Approved, None))
+        ));
+        assert!(matches!(
+            validate_decision(decision(ModerationStatus::Rejected, Some("  spam "))),
+            Ok((ModerationStatus::Rejected, Some(reason))) if reason == "spam"
+        ));
+        assert!(matches!(
+            validate_decision(decision(ModerationStatus::Hidden, Some("   "))),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            validate_decision(decision(ModerationStatus::Pending, None)),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+}
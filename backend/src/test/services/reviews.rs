@@ -0,0 +1,247 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            models::{
+                cal::CalBookingPayload, comments::Comment, error::ApiError,
+                firebase::UserAuthentication, state::AppState, user::UserDB,
+            },
+            services::reviews::{
+                completed_bookings, is_review, review_booking, unreviewed_booking,
+            },
+            test_fixtures::fixtures::create_mock_app_state,
+        },
+        chrono::{DateTime, Utc},
+        mockito::Matcher,
+        serde_json::{Value, json},
+        std::collections::HashMap,
+    };
+
+    fn booking(uid: &str, status: &str, end: &str, email: &str) -> Value {
+        json!({
+            "uid": uid,
+            "status": status,
+            "start": "2025-01-10T09:00:00Z",
+            "end": end,
+            "attendees": [{"name": "Alumno", "email": email, "timeZone": "Europe/Madrid"}]
+        })
+    }
+
+    fn bookings(values: Vec<Value>) -> Vec<CalBookingPayload> {
+        serde_json::from_value(Value::Array(values)).unwrap()
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-02-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn review(author: &str, booking_uid: Option<&str>) -> Comment {
+        Comment {
+            author_uid: Some(author.to_string()),
+            name: "Alumno".to_string(),
+            timestamp: "01/02/2025 10:00".to_string(),
+            content: "Muy buena clase".to_string(),
+            url_img: None,
+            stars: 5.0,
+            like: 0,
+            reply: Vec::new(),
+            users_liked: Vec::new(),
+            moderation_status: Default::default(),
+            moderation_reason: None,
+            verified_student: booking_uid.is_some(),
+            booking_uid: booking_uid.map(str::to_string),
+            liked: None,
+        }
+    }
+
+    fn claims(email: &str, email_verified: Option<bool>) -> UserAuthentication {
+        UserAuthentication {
+            sub: "student-uid".to_string(),
+            iss: "https://securetoken.google.com/test-project".to_string(),
+            aud: "test-project".to_string(),
+            iat: 0,
+            exp: i64::MAX,
+            email: Some(email.to_string()),
+            email_verified,
+            name: None,
+            picture: None,
+            auth_time: 0,
+            user_id: "student-uid".to_string(),
+            firebase: None,
+            phone_number: None,
+            provider_id: None,
+        }
+    }
+
+    async fn student_state(server: &mockito::Server) -> AppState {
+        let mut state = create_mock_app_state(HashMap::new()).await;
+        state.cal_options.base_url = server.url();
+        state
+            .repositories
+            .users
+            .put(
+                "student-uid",
+                // El email del perfil lo puede cambiar el usuario; no se usa
+                &UserDB {
+                    email: "otro@example.com".to_string(),
+                    first_free_class: false,
+                    role: None,
+                    subscription_tier: None,
+                    permissions: None,
+                    learner_profile: None,
+                },
+            )
+            .await
+            .unwrap();
+        state
+    }
+
+    #[test]
+    fn test_only_starred_comments_are_reviews() {
+        assert!(is_review(4.5));
+        assert!(!is_review(0.0));
+    }
+
+    #[test]
+    fn test_completed_bookings_filters_and_sorts() {
+        let bookings = bookings(vec![
+            booking(
+                "old",
+                "accepted",
+                "2025-01-05T10:00:00Z",
+                "alumno@example.com",
+            ),
+            booking(
+                "cancelled",
+                "cancelled",
+                "2025-01-20T10:00:00Z",
+                "alumno@example.com",
+            ),
+            booking(
+                "future",
+                "accepted",
+                "2025-03-01T10:00:00Z",
+                "alumno@example.com",
+            ),
+            booking(
+                "other",
+                "accepted",
+                "2025-01-20T10:00:00Z",
+                "otro@example.com",
+            ),
+            booking(
+                "recent",
+                "ACCEPTED",
+                "2025-01-25T10:00:00Z",
+                "Alumno@Example.com",
+            ),
+        ]);
+
+        let completed = completed_bookings(&bookings, "alumno@example.com", now());
+
+        assert_eq!(completed, vec!["recent", "old"]);
+    }
+
+    #[test]
+    fn test_unreviewed_booking_skips_own_reviews_only() {
+        let comments = HashMap::from([
+            ("c1".to_string(), review("student-uid", Some("recent"))),
+            // En una clase grupal otro asistente comparte la reserva
+            ("c2".to_string(), review("classmate", Some("old"))),
+        ]);
+        let completed = vec!["recent".to_string(), "old".to_string()];
+
+        assert_eq!(
+            unreviewed_booking(completed.clone(), &comments, "student-uid").as_deref(),
+            Some("old")
+        );
+        assert_eq!(
+            unreviewed_booking(completed, &comments, "classmate").as_deref(),
+            Some("recent")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_review_booking_queries_past_bookings_of_verified_token_email() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/bookings")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("attendeeEmail".into(), "alumno@example.com".into()),
+                Matcher::UrlEncoded("status".into(), "past".into()),
+            ]))
+            .with_body(
+                json!({"status": "success", "data": {"bookings": [
+                    booking("booking-1", "accepted", "2025-01-05T10:00:00Z", "alumno@example.com")
+                ]}})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let state = student_state(&server).await;
+
+        let booking_uid = review_booking(&state, &claims("alumno@example.com", Some(true)))
+            .await
+            .unwrap();
+
+        assert_eq!(booking_uid, "booking-1");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_review_booking_without_completed_class_is_forbidden() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/bookings")
+            .match_query(Matcher::Any)
+            .with_body(
+                json!({"status": "success", "data": {"bookings": [
+                    booking("booking-1", "cancelled", "2025-01-05T10:00:00Z", "alumno@example.com")
+                ]}})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let state = student_state(&server).await;
+
+        let result = review_booking(&state, &claims("alumno@example.com", Some(true))).await;
+
+        assert!(matches!(result, Err(ApiError::Forbidden(_))));
+    }
+
+    #[tokio::test]
+    async fn test_review_booking_requires_verified_email() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/bookings")
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let state = student_state(&server).await;
+
+        for verified in [None, Some(false)] {
+            let result = review_booking(&state, &claims("alumno@example.com", verified)).await;
+            assert!(matches!(result, Err(ApiError::EmailNotVerified(_))));
+        }
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_review_booking_cal_error_is_unavailable() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/bookings")
+            .match_query(Matcher::Any)
+            .with_status(500)
+            .create_async()
+            .await;
+        let state = student_state(&server).await;
+
+        let result = review_booking(&state, &claims("alumno@example.com", Some(true))).await;
+
+        assert!(matches!(result, Err(ApiError::Unavailable(_))));
+    }
+}
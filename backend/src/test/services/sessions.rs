@@ -0,0 +1,299 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            models::{
+                firebase::UserAuthentication,
+                metrics::{CachedAccessToken, ServiceAccount},
+                session::{CachedRevocation, SessionEvent},
+                state::AppState,
+            },
+            services::sessions::{
+                list_sessions, revoke_all_sessions, session_revoked_at, track_session,
+            },
+            test_fixtures::fixtures::create_mock_app_state,
+        },
+        base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD},
+        mockito::Matcher,
+        serde_json::json,
+        std::{collections::HashMap, time::Instant},
+    };
+
+    /// ID token sin firmar; el seguimiento de sesiones solo lee los claims
+    fn id_token(uid: &str, auth_time: i64) -> String {
+        let claims = json!({
+            "sub": uid,
+            "iss": "https://securetoken.google.com/test-project",
+            "aud": "test-project",
+            "iat": auth_time,
+            "exp": auth_time + 3600,
+            "auth_time": auth_time,
+            "user_id": uid,
+            "firebase": { "sign_in_provider": "password", "identities": {} },
+        });
+        format!(
+            "{}.{}.",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"none","typ":"JWT"}"#),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        )
+    }
+
+    fn claims(uid: &str, auth_time: i64) -> UserAuthentication {
+        UserAuthentication {
+            sub: uid.to_string(),
+            iss: "https://securetoken.google.com/test-project".to_string(),
+            aud: "test-project".to_string(),
+            iat: auth_time,
+            exp: i64::MAX,
+            email: None,
+            email_verified: None,
+            name: None,
+            picture: None,
+            auth_time,
+            user_id: uid.to_string(),
+            firebase: None,
+            phone_number: None,
+            provider_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_track_session_keeps_creation_across_refreshes() {
+        let state = create_mock_app_state(HashMap::new()).await;
+
+        track_session(
+            &state,
+            &id_token("student-uid", 1000),
+            Some("Firefox"),
+            SessionEvent::Login,
+        )
+        .await;
+        let first = state
+            .repositories
+            .sessions
+            .get("student-uid")
+            .await
+            .unwrap();
+        let (session_id, created_at) = first
+            .sessions
+            .iter()
+            .map(|(id, session)| (id.clone(), session.created_at.clone()))
+            .next()
+            .unwrap();
+
+        // Un refresco conserva el auth_time: misma sesión, se actualiza el último uso
+        track_session(
+            &state,
+            &id_token("student-uid", 1000),
+            Some("Firefox"),
+            SessionEvent::Refresh,
+        )
+        .await;
+        track_session(
+            &state,
+            &id_token("student-uid", 2000),
+            None,
+            SessionEvent::Login,
+        )
+        .await;
+
+        let sessions = state
+            .repositories
+            .sessions
+            .get("student-uid")
+            .await
+            .unwrap();
+        assert_eq!(sessions.sessions.len(), 2);
+        let session = &sessions.sessions[&session_id];
+        assert_eq!(session.auth_time, 1000);
+        assert_eq!(session.created_at, created_at);
+        assert_eq!(session.user_agent.as_deref(), Some("Firefox"));
+        assert_eq!(session.sign_in_provider.as_deref(), Some("password"));
+    }
+
+    #[tokio::test]
+    async fn test_track_session_keeps_logins_in_the_same_second_apart() {
+        let state = create_mock_app_state(HashMap::new()).await;
+
+        track_session(
+            &state,
+            &id_token("student-uid", 1000),
+            Some("Firefox"),
+            SessionEvent::Login,
+        )
+        .await;
+        track_session(
+            &state,
+            &id_token("student-uid", 1000),
+            Some("Safari"),
+            SessionEvent::Login,
+        )
+        .await;
+        track_session(
+            &state,
+            &id_token("student-uid", 1000),
+            Some("Safari"),
+            SessionEvent::Refresh,
+        )
+        .await;
+
+        // Mismo auth_time, dos dispositivos: ninguno sustituye al otro
+        let sessions = state
+            .repositories
+            .sessions
+            .get("student-uid")
+            .await
+            .unwrap();
+        let mut agents: Vec<&str> = sessions
+            .sessions
+            .values()
+            .filter_map(|session| session.user_agent.as_deref())
+            .collect();
+        agents.sort();
+        assert_eq!(agents, ["Firefox", "Safari"]);
+    }
+
+    #[tokio::test]
+    async fn test_track_session_ignores_unreadable_token() {
+        let state = create_mock_app_state(HashMap::new()).await;
+
+        track_session(&state, "not-a-jwt", None, SessionEvent::Login).await;
+
+        assert!(
+            state
+                .repositories
+                .sessions
+                .get("student-uid")
+                .await
+                .unwrap()
+                .sessions
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_sessions_flags_current_session() {
+        let state = create_mock_app_state(HashMap::new()).await;
+        track_session(
+            &state,
+            &id_token("student-uid", 1000),
+            None,
+            SessionEvent::Login,
+        )
+        .await;
+        track_session(
+            &state,
+            &id_token("student-uid", 2000),
+            None,
+            SessionEvent::Login,
+        )
+        .await;
+
+        let sessions = list_sessions(&state, &claims("student-uid", 1000))
+            .await
+            .unwrap();
+
+        assert_eq!(sessions.len(), 2);
+        // La más reciente primero
+        assert_eq!(sessions[0].session.auth_time, 2000);
+        assert!(!sessions[0].current);
+        assert!(sessions[1].current);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_all_sessions_sets_valid_since_and_clears_sessions() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/projects/test-project/accounts:update")
+            .match_header("authorization", "Bearer cached-admin-token")
+            .match_body(Matcher::PartialJson(json!({ "localId": "student-uid" })))
+            .with_status(200)
+            .with_body(r#"{"localId": "student-uid"}"#)
+            .create_async()
+            .await;
+        let mut state: AppState = create_mock_app_state(HashMap::new()).await;
+        state.firebase_options.identity_toolkit_url = server.url();
+        state.firebase_options.service_account = Some(ServiceAccount {
+            client_email: "admin@test-project.iam.gserviceaccount.com".to_string(),
+            private_key: "unused-while-cached".to_string(),
+        });
+        *state.firebase_options.admin_token_cache.lock().await = Some(CachedAccessToken::new(
+            "cached-admin-token".to_string(),
+            3600,
+        ));
+        track_session(
+            &state,
+            &id_token("student-uid", 1000),
+            None,
+            SessionEvent::Login,
+        )
+        .await;
+        track_session(
+            &state,
+            &id_token("student-uid", 2000),
+            None,
+            SessionEvent::Login,
+        )
+        .await;
+
+        let revoked = revoke_all_sessions(&state, "student-uid").await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(revoked.sessions_revoked, 2);
+        let sessions = state
+            .repositories
+            .sessions
+            .get("student-uid")
+            .await
+            .unwrap();
+        assert!(sessions.sessions.is_empty());
+        assert_eq!(sessions.revoked_at, Some(revoked.revoked_at));
+        // La instancia que revoca no espera a que caduque su caché
+        assert_eq!(
+            session_revoked_at(&state, "student-uid").await.unwrap(),
+            Some(revoked.revoked_at)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoke_all_sessions_keeps_sessions_when_firebase_fails() {
+        // Sin cuenta de servicio no se puede fijar validSince
+        let state = create_mock_app_state(HashMap::new()).await;
+        track_session(
+            &state,
+            &id_token("student-uid", 1000),
+            None,
+            SessionEvent::Login,
+        )
+        .await;
+
+        assert!(revoke_all_sessions(&state, "student-uid").await.is_err());
+        let sessions = state
+            .repositories
+            .sessions
+            .get("student-uid")
+            .await
+            .unwrap();
+        assert_eq!(sessions.sessions.len(), 1);
+        assert!(sessions.revoked_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_revoked_at_uses_cache() {
+        let state = create_mock_app_state(HashMap::new()).await;
+        state.session_revocations.write().await.insert(
+            "student-uid".to_string(),
+            CachedRevocation {
+                revoked_at: Some(1234),
+                fetched_at: Instant::now(),
+            },
+        );
+
+        // La DB no tiene revocación, pero la entrada en caché sigue vigente
+        assert_eq!(
+            session_revoked_at(&state, "student-uid").await.unwrap(),
+            Some(1234)
+        );
+        assert_eq!(session_revoked_at(&state, "other-uid").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,244 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            models::{
+                cal::CalBookingPayload, error::ApiError, state::AppState,
+                subscription::SubscriptionTier, user::UserDB,
+            },
+            services::subscriptions::{
+                billing_period, check_booking_entitlement, verify_recurring_price,
+            },
+            test_fixtures::fixtures::create_mock_app_state,
+        },
+        chrono::{TimeZone, Utc},
+        mockito::Matcher,
+        serde_json::json,
+        std::collections::HashMap,
+    };
+
+    fn tier(allowance: Option<u32>, allowed_event_types: &[&str]) -> SubscriptionTier {
+        SubscriptionTier {
+            name: "Mensual".to_string(),
+            monthly_class_allowance: allowance,
+            allowed_event_types: allowed_event_types.iter().map(|t| t.to_string()).collect(),
+            discount_percentage: 10,
+            stripe_price_id: "price_monthly".to_string(),
+        }
+    }
+
+    fn subscriber(tier_id: Option<&str>) -> UserDB {
+        UserDB {
+            email: "student@test.com".to_string(),
+            first_free_class: false,
+            role: None,
+            subscription_tier: tier_id.map(str::to_string),
+            permissions: None,
+            learner_profile: None,
+        }
+    }
+
+    fn booking(event_type_id: i64, email: &str) -> CalBookingPayload {
+        serde_json::from_value(json!({
+            "eventTypeId": event_type_id,
+            "start": "2025-01-20T10:00:00Z",
+            "attendees": [{ "name": "Student", "email": email, "timeZone": "Europe/Madrid" }]
+        }))
+        .unwrap()
+    }
+
+    async fn state_with_tier(cal_url: String, tier: SubscriptionTier) -> AppState {
+        let mut state: AppState = create_mock_app_state(HashMap::new()).await;
+        state.cal_options.base_url = cal_url;
+        state
+            .repositories
+            .subscription_tiers
+            .put("monthly", &tier)
+            .await
+            .unwrap();
+        state
+    }
+
+    async fn mock_cal_bookings(server: &mut mockito::ServerGuard, bookings: serde_json::Value) {
+        server
+            .mock("GET", "/bookings")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("attendeeEmail".into(), "student@test.com".into()),
+                Matcher::UrlEncoded("afterStart".into(), "2025-01-01T00:00:00+00:00".into()),
+                Matcher::UrlEncoded("beforeEnd".into(), "2025-02-01T00:00:00+00:00".into()),
+            ]))
+            .with_status(200)
+            .with_body(json!({"status": "success", "data": {"bookings": bookings}}).to_string())
+            .create_async()
+            .await;
+    }
+
+    #[test]
+    fn test_billing_period_is_utc_calendar_month() {
+        let (start, end) = billing_period(Utc.with_ymd_and_hms(2024, 12, 31, 23, 30, 0).unwrap());
+
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 12, 1, 0, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_allows_event_type_by_slug_or_id() {
+        let restricted = tier(Some(4), &["standard-class", "42"]);
+
+        assert!(restricted.allows_event_type(Some("standard-class"), None));
+        assert!(restricted.allows_event_type(None, Some(42)));
+        assert!(!restricted.allows_event_type(Some("group-class"), Some(7)));
+        // Sin lista se permite cualquier tipo
+        assert!(tier(None, &[]).allows_event_type(None, None));
+    }
+
+    #[tokio::test]
+    async fn test_check_booking_entitlement_without_tier_skips_checks() {
+        // Sin plan no se consulta Cal.com: la URL del fixture no responde
+        let state = create_mock_app_state(HashMap::new()).await;
+
+        let result =
+            check_booking_entitlement(&state, &subscriber(None), &booking(42, "other@test.com"))
+                .await;
+
+        assert!(matches!(result, Ok(None)));
+    }
+
+    #[tokio::test]
+    async fn test_check_booking_entitlement_counts_only_plan_bookings() {
+        let mut server = mockito::Server::new_async().await;
+        mock_cal_bookings(
+            &mut server,
+            json!([
+                { "uid": "plan-1", "eventTypeId": 42 },
+                { "uid": "other-1", "eventTypeId": 7 }
+            ]),
+        )
+        .await;
+        let state = state_with_tier(server.url(), tier(Some(2), &["42"])).await;
+
+        let entitlement = check_booking_entitlement(
+            &state,
+            &subscriber(Some("monthly")),
+            &booking(42, "Student@Test.com"),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(entitlement.used, 1);
+        assert_eq!(entitlement.remaining, Some(1));
+        assert_eq!(entitlement.discount_percentage, 10);
+    }
+
+    #[tokio::test]
+    async fn test_check_booking_entitlement_rejects_exhausted_allowance() {
+        let mut server = mockito::Server::new_async().await;
+        mock_cal_bookings(
+            &mut server,
+            json!([
+                { "uid": "plan-1", "eventTypeId": 42 },
+                { "uid": "plan-2", "eventTypeId": 42 }
+            ]),
+        )
+        .await;
+        let state = state_with_tier(server.url(), tier(Some(2), &[])).await;
+
+        let result = check_booking_entitlement(
+            &state,
+            &subscriber(Some("monthly")),
+            &booking(42, "student@test.com"),
+        )
+        .await;
+
+        assert!(matches!(result, Err(ApiError::Forbidden(_))));
+    }
+
+    #[tokio::test]
+    async fn test_check_booking_entitlement_rejects_event_type_outside_plan() {
+        let server = mockito::Server::new_async().await;
+        let state = state_with_tier(server.url(), tier(None, &["42"])).await;
+
+        let result = check_booking_entitlement(
+            &state,
+            &subscriber(Some("monthly")),
+            &booking(7, "student@test.com"),
+        )
+        .await;
+
+        assert!(matches!(result, Err(ApiError::Forbidden(_))));
+    }
+
+    #[tokio::test]
+    async fn test_check_booking_entitlement_fails_closed_when_cal_is_down() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/bookings")
+            .match_query(Matcher::Any)
+            .with_status(500)
+            .create_async()
+            .await;
+        let state = state_with_tier(server.url(), tier(Some(4), &[])).await;
+
+        let result = check_booking_entitlement(
+            &state,
+            &subscriber(Some("monthly")),
+            &booking(42, "student@test.com"),
+        )
+        .await;
+
+        assert!(matches!(result, Err(ApiError::Unavailable(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_recurring_price() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/v1/prices/price_monthly")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(
+                json!({
+                    "id": "price_monthly",
+                    "object": "price",
+                    "recurring": { "interval": "month", "interval_count": 1, "usage_type": "licensed" }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v1/prices/price_once")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(json!({ "id": "price_once", "object": "price" }).to_string())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v1/prices/price_missing")
+            .match_query(Matcher::Any)
+            .with_status(404)
+            .with_body(
+                json!({ "error": { "type": "invalid_request_error", "message": "No such price" } })
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let mut state: AppState = create_mock_app_state(HashMap::new()).await;
+        state.stripe_client = stripe::Client::from_url(server.url().as_str(), "sk_test_key");
+
+        assert!(
+            verify_recurring_price(&state, "price_monthly")
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            verify_recurring_price(&state, "price_once").await,
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            verify_recurring_price(&state, "price_missing").await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use {
+        crate::services::supervisor::{ShutdownSignal, TaskState, TaskSupervisor},
+        std::{
+            sync::{
+                Arc,
+                atomic::{AtomicBool, AtomicU32, Ordering},
+            },
+            time::Duration,
+        },
+    };
+
+    fn fast_supervisor() -> TaskSupervisor {
+        TaskSupervisor::with_backoff(Duration::from_millis(10), Duration::from_millis(50))
+    }
+
+    /// Espera (como mucho 2s) a que se cumpla la condición
+    async fn eventually(condition: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("Condition not met in time");
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_restarts_task_that_returns() {
+        let supervisor = fast_supervisor();
+        let runs = Arc::new(AtomicU32::new(0));
+
+        let counter = runs.clone();
+        supervisor.spawn("short_lived", move |_shutdown| {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        eventually(|| runs.load(Ordering::SeqCst) >= 3).await;
+
+        let status = &supervisor.statuses()[0];
+        assert_eq!(status.name, "short_lived");
+        assert!(status.restarts >= 2);
+        assert_eq!(
+            status.last_error.as_deref(),
+            Some("task returned unexpectedly")
+        );
+
+        supervisor.shutdown(Duration::from_secs(1)).await;
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_survives_panics_and_reports_them() {
+        let supervisor = fast_supervisor();
+
+        supervisor.spawn("panicking", |_shutdown| async {
+            panic!("boom");
+        });
+
+        eventually(|| supervisor.statuses()[0].restarts >= 1).await;
+
+        let status = &supervisor.statuses()[0];
+        assert_eq!(status.last_error.as_deref(), Some("task panicked: boom"));
+        assert!(status.last_failure_at.is_some());
+
+        supervisor.shutdown(Duration::from_secs(1)).await;
+        assert_eq!(supervisor.statuses()[0].state, TaskState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_shutdown_waits_for_in_flight_work() {
+        let supervisor = fast_supervisor();
+        let finished = Arc::new(AtomicBool::new(false));
+
+        let flag = finished.clone();
+        supervisor.spawn("worker", move |mut shutdown: ShutdownSignal| {
+            let flag = flag.clone();
+            async move {
+                shutdown.wait().await;
+                // Trabajo que no debe cortarse a la mitad (p. ej. un reembolso)
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                flag.store(true, Ordering::SeqCst);
+            }
+        });
+
+        eventually(|| supervisor.statuses()[0].started_at.is_some()).await;
+        supervisor.shutdown(Duration::from_secs(2)).await;
+
+        assert!(finished.load(Ordering::SeqCst));
+        let status = &supervisor.statuses()[0];
+        assert_eq!(status.state, TaskState::Stopped);
+        assert_eq!(status.restarts, 0);
+    }
+}
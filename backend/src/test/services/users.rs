@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::{
+            firebase::UserMerged,
+            user::{Role, SortOrder, UserDirectoryQuery, UserSortField},
+        },
+        services::users::users_to_csv,
+    };
+
+    fn user(uid: &str, email: &str, created_at: Option<&str>) -> UserMerged {
+        UserMerged {
+            local_id: uid.to_string(),
+            first_free_class: false,
+            email: Some(email.to_string()),
+            email_verified: Some(true),
+            display_name: None,
+            photo_url: None,
+            phone_number: None,
+            disabled: None,
+            role: Some("student".to_string()),
+            subscription_tier: None,
+            permissions: None,
+            provider_user_info: None,
+            password_hash: None,
+            password_updated_at: None,
+            valid_since: None,
+            last_login_at: None,
+            created_at: created_at.map(str::to_string),
+            custom_auth: None,
+        }
+    }
+
+    fn uids(users: &[UserMerged]) -> Vec<&str> {
+        users.iter().map(|u| u.local_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_filters_combine_role_free_class_and_search() {
+        let mut teacher = user("t1", "teacher@test.com", None);
+        teacher.role = Some("teacher".to_string());
+        let mut used_free_class = user("s2", "ana@test.com", None);
+        used_free_class.first_free_class = true;
+        let mut named = user("s3", "other@test.com", None);
+        named.display_name = Some("Ana María".to_string());
+        let users = vec![
+            teacher,
+            used_free_class,
+            named,
+            user("s4", "bob@test.com", None),
+        ];
+
+        let query = UserDirectoryQuery {
+            role: Some(Role::Student),
+            first_free_class: Some(false),
+            search: Some("  ANA ".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(uids(&query.apply(users)), vec!["s3"]);
+    }
+
+    #[test]
+    fn test_sort_by_created_at_keeps_missing_dates_last() {
+        let users = vec![
+            user("old", "old@test.com", Some("1000")),
+            user("none", "none@test.com", None),
+            user("new", "new@test.com", Some("3000")),
+        ];
+
+        let desc = UserDirectoryQuery {
+            sort: Some(UserSortField::CreatedAt),
+            ..Default::default()
+        };
+        let asc = UserDirectoryQuery {
+            sort: Some(UserSortField::CreatedAt),
+            order: SortOrder::Asc,
+            ..Default::default()
+        };
+
+        assert_eq!(uids(&desc.apply(users.clone())), vec!["new", "old", "none"]);
+        assert_eq!(uids(&asc.apply(users)), vec!["old", "new", "none"]);
+    }
+
+    #[test]
+    fn test_csv_escapes_fields_and_neutralises_formulas() {
+        let mut student = user("s1", "student@test.com", Some("0"));
+        student.display_name = Some("Pérez, \"Ana\"".to_string());
+        student.subscription_tier = Some("=HYPERLINK(\"x\")".to_string());
+
+        let csv = users_to_csv(&[student]);
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+
+        assert!(lines[0].starts_with("uid,email,name,"));
+        assert_eq!(
+            lines[1],
+            "s1,student@test.com,\"Pérez, \"\"Ana\"\"\",true,false,student,\"'=HYPERLINK(\"\"x\"\")\",false,,1970-01-01T00:00:00+00:00,"
+        );
+    }
+}
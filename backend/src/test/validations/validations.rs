@@ -1,13 +1,16 @@
 #[cfg(test)]
 mod tests {
     use {
-        crate::utils::validations::{ValidatedJson, validate_non_whitespace},
+        crate::{
+            models::user::{ClassTimeSlot, LearnerProfile, UserDirectoryQuery, Weekday},
+            utils::validations::{ValidatedJson, ValidatedQuery, validate_non_whitespace},
+        },
         axum::{
             Json, Router,
-            body::Body,
+            body::{Body, to_bytes},
             extract::Request,
             http::{Method, StatusCode},
-            routing::post,
+            routing::{get, post},
         },
         serde::{Deserialize, Serialize},
         tower::ServiceExt,
@@ -300,5 +303,143 @@ mod tests {
 
             assert_eq!(response.status(), StatusCode::OK);
         }
+
+        #[tokio::test]
+        async fn test_validated_json_rejection_uses_error_envelope() {
+            let app = Router::new().route("/test", post(test_handler));
+
+            let body = r#"{
+        "name": "John Doe",
+        "age": 200,
+        "email": "john@example.com"
+    }"#;
+
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method(Method::POST)
+                        .uri("/test")
+                        .header("content-type", "application/json")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(json["success"], false);
+            assert_eq!(json["code"], "validation_failed");
+        }
+
+        #[tokio::test]
+        async fn test_validated_json_invalid_json_code() {
+            let app = Router::new().route("/test", post(test_handler));
+
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method(Method::POST)
+                        .uri("/test")
+                        .header("content-type", "application/json")
+                        .body(Body::from("{ invalid json }"))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(json["code"], "invalid_json");
+        }
+    }
+
+    fn slot(start: &str, end: &str) -> ClassTimeSlot {
+        ClassTimeSlot {
+            weekday: Weekday::Monday,
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_learner_profile_valid() {
+        let profile = LearnerProfile {
+            native_language: Some("Arabic".to_string()),
+            spanish_level: Some("B1".to_string()),
+            focus_areas: vec!["speaking".to_string(), "grammar".to_string()],
+            timezone: Some("Europe/Madrid".to_string()),
+            preferred_class_times: vec![slot("18:00", "19:30")],
+            learning_goals: Some("Pass the DELE B2".to_string()),
+            updated_at: None,
+        };
+        assert!(profile.validate().is_ok());
+        assert!(LearnerProfile::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_learner_profile_rejects_invalid_fields() {
+        let invalid = [
+            LearnerProfile {
+                spanish_level: Some("fluent".to_string()),
+                ..Default::default()
+            },
+            LearnerProfile {
+                timezone: Some("Mars/Olympus".to_string()),
+                ..Default::default()
+            },
+            LearnerProfile {
+                focus_areas: vec!["speaking".to_string(), "Speaking".to_string()],
+                ..Default::default()
+            },
+            LearnerProfile {
+                preferred_class_times: vec![slot("19:00", "18:00")],
+                ..Default::default()
+            },
+            LearnerProfile {
+                preferred_class_times: vec![slot("7pm", "8pm")],
+                ..Default::default()
+            },
+            LearnerProfile {
+                native_language: Some("   ".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        for profile in invalid {
+            assert!(profile.validate().is_err(), "{:?}", profile);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validated_query_rejection_uses_response_envelope() {
+        async fn handler(ValidatedQuery(query): ValidatedQuery<UserDirectoryQuery>) -> String {
+            format!("{:?}", query.role)
+        }
+        let app = Router::new().route("/users", get(handler));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/users?role=superuser")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["code"], "bad_request");
     }
 }
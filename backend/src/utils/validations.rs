@@ -1,14 +1,26 @@
 use {
+    crate::models::{
+        error::ApiError,
+        user::{ClassTimeSlot, SPANISH_LEVELS},
+    },
     async_trait::async_trait,
     axum::{
         Json,
-        extract::{FromRequest, Request},
-        http::StatusCode,
-        response::{IntoResponse, Response},
+        extract::{FromRequest, FromRequestParts, Query, Request},
+        http::request::Parts,
     },
+    chrono::NaiveTime,
+    chrono_tz::Tz,
+    serde::de::DeserializeOwned,
+    std::collections::HashSet,
     validator::{Validate, ValidationError},
 };
 
+/// Máximo de áreas de enfoque en el perfil de aprendizaje
+const MAX_FOCUS_AREAS: usize = 10;
+/// Máximo de franjas horarias preferidas (tres por día)
+const MAX_CLASS_TIMES: usize = 21;
+
 /// Valida que una cadena no esté vacía o compuesta solo por espacios en blanco
 pub fn validate_non_whitespace(value: &str) -> Result<(), ValidationError> {
     if value.trim().is_empty() {
@@ -17,6 +29,57 @@ pub fn validate_non_whitespace(value: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Valida que el nivel sea uno de `SPANISH_LEVELS`
+pub fn validate_spanish_level(value: &str) -> Result<(), ValidationError> {
+    if !SPANISH_LEVELS.contains(&value) {
+        return Err(ValidationError::new("unknown_spanish_level"));
+    }
+    Ok(())
+}
+
+/// Valida que la zona horaria exista en la base de datos IANA
+pub fn validate_timezone(value: &str) -> Result<(), ValidationError> {
+    if value.parse::<Tz>().is_err() {
+        return Err(ValidationError::new("unknown_timezone"));
+    }
+    Ok(())
+}
+
+/// Áreas de enfoque no vacías, sin repetir y de como mucho 50 caracteres
+pub fn validate_focus_areas(values: &[String]) -> Result<(), ValidationError> {
+    if values.len() > MAX_FOCUS_AREAS {
+        return Err(ValidationError::new("too_many_focus_areas"));
+    }
+    let mut seen: HashSet<String> = HashSet::new();
+    for value in values {
+        validate_non_whitespace(value)?;
+        if value.len() > 50 {
+            return Err(ValidationError::new("focus_area_too_long"));
+        }
+        if !seen.insert(value.to_lowercase()) {
+            return Err(ValidationError::new("duplicate_focus_area"));
+        }
+    }
+    Ok(())
+}
+
+/// Franjas con horas `HH:MM` y el fin posterior al inicio
+pub fn validate_class_times(slots: &[ClassTimeSlot]) -> Result<(), ValidationError> {
+    if slots.len() > MAX_CLASS_TIMES {
+        return Err(ValidationError::new("too_many_class_times"));
+    }
+    for slot in slots {
+        let start = NaiveTime::parse_from_str(&slot.start, "%H:%M");
+        let end = NaiveTime::parse_from_str(&slot.end, "%H:%M");
+        match (start, end) {
+            (Ok(start), Ok(end)) if start < end => {}
+            (Ok(_), Ok(_)) => return Err(ValidationError::new("class_time_ends_before_start")),
+            _ => return Err(ValidationError::new("invalid_class_time")),
+        }
+    }
+    Ok(())
+}
+
 /// Wrapper que valida automáticamente para Axum
 pub struct ValidatedJson<T>(pub T);
 
@@ -27,21 +90,44 @@ where
     T: for<'de> serde::Deserialize<'de> + Validate,
     S: Send + Sync,
 {
-    type Rejection = Response;
+    type Rejection = ApiError;
 
     async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
-        let Json(data) = Json::<T>::from_request(req, state).await.map_err(|e| {
-            (StatusCode::BAD_REQUEST, format!("Invalid JSON: {}", e)).into_response()
-        })?;
+        let Json(data) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|e| ApiError::InvalidJson(e.body_text()))?;
 
-        data.validate().map_err(|e| {
-            (StatusCode::BAD_REQUEST, format!("Validation error: {}", e)).into_response()
-        })?;
+        data.validate()
+            .map_err(|e| ApiError::Validation(e.to_string()))?;
 
         Ok(ValidatedJson(data))
     }
 }
 
+/// Como `ValidatedJson` pero para la query string: los parámetros mal formados se responden
+/// con el sobre `ResponseAPI` en vez del texto plano de axum
+pub struct ValidatedQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(data) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| ApiError::BadRequest(e.body_text()))?;
+
+        data.validate()
+            .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+        Ok(ValidatedQuery(data))
+    }
+}
+
 #[cfg(test)]
 #[path = "../test/validations/validations.rs"]
-mod tests;
+mod extended_tests;